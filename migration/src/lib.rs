@@ -1,6 +1,25 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_tables;
+mod m20220101_000002_grant_music_folders;
+mod m20220101_000003_create_metadata_cache;
+mod m20220101_000004_create_play_history;
+mod m20220101_000005_create_scrobble_queue;
+mod m20220101_000006_lyrics_sets;
+mod m20220101_000007_create_shares;
+mod m20220101_000008_create_podcasts;
+mod m20220101_000009_create_internet_radio_stations;
+mod m20220101_000010_create_search_index;
+mod m20220101_000011_add_smart_playlist_rules;
+mod m20220101_000012_add_playlist_source_path;
+mod m20220101_000013_create_playlist_users;
+mod m20220101_000014_create_api_keys;
+mod m20220101_000015_add_extended_tags;
+mod m20220101_000016_add_base_gain;
+mod m20220101_000017_add_codec;
+mod m20220101_000018_drop_transcoded_columns;
+mod m20220101_000019_add_episode_downloaded_at;
+mod m20220101_000020_allow_repeated_playlist_songs;
 
 pub struct Migrator;
 
@@ -9,6 +28,25 @@ impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
         vec![
             Box::new(m20220101_000001_create_tables::Migration),
+            Box::new(m20220101_000002_grant_music_folders::Migration),
+            Box::new(m20220101_000003_create_metadata_cache::Migration),
+            Box::new(m20220101_000004_create_play_history::Migration),
+            Box::new(m20220101_000005_create_scrobble_queue::Migration),
+            Box::new(m20220101_000006_lyrics_sets::Migration),
+            Box::new(m20220101_000007_create_shares::Migration),
+            Box::new(m20220101_000008_create_podcasts::Migration),
+            Box::new(m20220101_000009_create_internet_radio_stations::Migration),
+            Box::new(m20220101_000010_create_search_index::Migration),
+            Box::new(m20220101_000011_add_smart_playlist_rules::Migration),
+            Box::new(m20220101_000012_add_playlist_source_path::Migration),
+            Box::new(m20220101_000013_create_playlist_users::Migration),
+            Box::new(m20220101_000014_create_api_keys::Migration),
+            Box::new(m20220101_000015_add_extended_tags::Migration),
+            Box::new(m20220101_000016_add_base_gain::Migration),
+            Box::new(m20220101_000017_add_codec::Migration),
+            Box::new(m20220101_000018_drop_transcoded_columns::Migration),
+            Box::new(m20220101_000019_add_episode_downloaded_at::Migration),
+            Box::new(m20220101_000020_allow_repeated_playlist_songs::Migration),
         ]
     }
 }
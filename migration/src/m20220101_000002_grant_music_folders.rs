@@ -0,0 +1,25 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Folder access is now enforced; keep existing users able to see the whole library.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT OR IGNORE INTO user_music_folders (username, music_folder_id) \
+                 SELECT u.username, mf.id FROM users u CROSS JOIN music_folders mf",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum MetadataCache {
+    #[iden = "metadata_cache"]
+    Table,
+    ItemType,
+    ItemId,
+    Data,
+    UpdatedAt,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MetadataCache::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(MetadataCache::ItemType).string().not_null())
+                    .col(ColumnDef::new(MetadataCache::ItemId).string().not_null())
+                    .col(ColumnDef::new(MetadataCache::Data).text().not_null())
+                    .col(ColumnDef::new(MetadataCache::UpdatedAt).timestamp().not_null())
+                    .primary_key(Index::create().col(MetadataCache::ItemType).col(MetadataCache::ItemId))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(MetadataCache::Table).to_owned()).await?;
+        Ok(())
+    }
+}
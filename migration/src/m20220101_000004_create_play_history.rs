@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Users {
+    #[iden = "users"]
+    Table,
+    Username,
+}
+
+#[derive(Iden)]
+enum PlayHistory {
+    #[iden = "play_history"]
+    Table,
+    Id,
+    Username,
+    SongId,
+    PlayedAt,
+    Client,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // No foreign key to children: history outlives songs removed by a rescan.
+        manager
+            .create_table(
+                Table::create()
+                    .table(PlayHistory::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlayHistory::Id).integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(PlayHistory::Username).string().not_null())
+                    .col(ColumnDef::new(PlayHistory::SongId).string().not_null())
+                    .col(ColumnDef::new(PlayHistory::PlayedAt).date_time().not_null())
+                    .col(ColumnDef::new(PlayHistory::Client).string())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-play_history-username")
+                            .from(PlayHistory::Table, PlayHistory::Username)
+                            .to(Users::Table, Users::Username)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-play_history-username-song_id")
+                    .table(PlayHistory::Table)
+                    .col(PlayHistory::Username)
+                    .col(PlayHistory::SongId)
+                    .col(PlayHistory::PlayedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-play_history-username-played_at")
+                    .table(PlayHistory::Table)
+                    .col(PlayHistory::Username)
+                    .col(PlayHistory::PlayedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(PlayHistory::Table).to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Users {
+    #[iden = "users"]
+    Table,
+    Username,
+}
+
+#[derive(Iden)]
+enum UserConnections {
+    #[iden = "user_connections"]
+    Table,
+    Username,
+    Service,
+    AccountName,
+    Token,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum ScrobbleQueue {
+    #[iden = "scrobble_queue"]
+    Table,
+    Id,
+    Username,
+    Service,
+    Kind,
+    Payload,
+    PlayedAt,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+    CreatedAt,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // UserConnections - linked scrobbling accounts
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserConnections::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(UserConnections::Username).string().not_null())
+                    .col(ColumnDef::new(UserConnections::Service).string().not_null())
+                    .col(ColumnDef::new(UserConnections::AccountName).string())
+                    .col(ColumnDef::new(UserConnections::Token).text().not_null())
+                    .col(ColumnDef::new(UserConnections::CreatedAt).date_time().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(UserConnections::Username)
+                            .col(UserConnections::Service),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_connections-username")
+                            .from(UserConnections::Table, UserConnections::Username)
+                            .to(Users::Table, Users::Username)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // ScrobbleQueue - outbound now-playing/submissions awaiting delivery
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScrobbleQueue::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ScrobbleQueue::Id).integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(ScrobbleQueue::Username).string().not_null())
+                    .col(ColumnDef::new(ScrobbleQueue::Service).string().not_null())
+                    .col(ColumnDef::new(ScrobbleQueue::Kind).string().not_null())
+                    .col(ColumnDef::new(ScrobbleQueue::Payload).text().not_null())
+                    .col(ColumnDef::new(ScrobbleQueue::PlayedAt).date_time().not_null())
+                    .col(ColumnDef::new(ScrobbleQueue::Attempts).integer().not_null().default(0))
+                    .col(ColumnDef::new(ScrobbleQueue::NextAttemptAt).date_time().not_null())
+                    .col(ColumnDef::new(ScrobbleQueue::LastError).text())
+                    .col(ColumnDef::new(ScrobbleQueue::CreatedAt).date_time().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-scrobble_queue-username")
+                            .from(ScrobbleQueue::Table, ScrobbleQueue::Username)
+                            .to(Users::Table, Users::Username)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-scrobble_queue-next_attempt_at")
+                    .table(ScrobbleQueue::Table)
+                    .col(ScrobbleQueue::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(ScrobbleQueue::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(UserConnections::Table).to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Children {
+    #[iden = "children"]
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Lyrics {
+    #[iden = "lyrics"]
+    Table,
+    Id,
+    SongId,
+    Lang,
+    Synced,
+    Content,
+    Source,
+    SourceModified,
+}
+
+#[derive(Iden)]
+enum LyricsOld {
+    #[iden = "lyrics_old"]
+    Table,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A song may now carry several lyric sets (embedded plus sidecar files,
+        // one per language), so `song_id` can no longer be the primary key.
+        manager
+            .rename_table(Table::rename().table(Lyrics::Table, LyricsOld::Table).to_owned())
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Lyrics::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Lyrics::Id).integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(Lyrics::SongId).string().not_null())
+                    .col(ColumnDef::new(Lyrics::Lang).string().not_null().default("xxx"))
+                    .col(ColumnDef::new(Lyrics::Synced).boolean().not_null().default(false))
+                    .col(ColumnDef::new(Lyrics::Content).text().not_null())
+                    // Sidecar file name, or NULL for lyrics embedded in the audio file.
+                    .col(ColumnDef::new(Lyrics::Source).string())
+                    .col(ColumnDef::new(Lyrics::SourceModified).date_time())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-lyrics-song_id")
+                            .from(Lyrics::Table, Lyrics::SongId)
+                            .to(Children::Table, Children::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-lyrics-song_id")
+                    .table(Lyrics::Table)
+                    .col(Lyrics::SongId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing rows all came from embedded tags; treat anything with an
+        // LRC timestamp as synced until the next full scan reparses it.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO lyrics (song_id, lang, synced, content) \
+                 SELECT song_id, 'xxx', content GLOB '*[[][0-9]*:[0-9]*[]]*', content FROM lyrics_old",
+            )
+            .await?;
+
+        manager.drop_table(Table::drop().table(LyricsOld::Table).to_owned()).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .rename_table(Table::rename().table(Lyrics::Table, LyricsOld::Table).to_owned())
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Lyrics::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Lyrics::SongId).string().not_null().primary_key())
+                    .col(ColumnDef::new(Lyrics::Content).text().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-lyrics-song_id")
+                            .from(Lyrics::Table, Lyrics::SongId)
+                            .to(Children::Table, Children::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Only one set per song fits the old schema; keep the first.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO lyrics (song_id, content) \
+                 SELECT song_id, content FROM lyrics_old \
+                 WHERE id IN (SELECT MIN(id) FROM lyrics_old GROUP BY song_id)",
+            )
+            .await?;
+
+        manager.drop_table(Table::drop().table(LyricsOld::Table).to_owned()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Users {
+    #[iden = "users"]
+    Table,
+    Username,
+}
+
+#[derive(Iden)]
+enum Shares {
+    #[iden = "shares"]
+    Table,
+    Id,
+    Token,
+    Username,
+    Description,
+    CreatedAt,
+    ExpiresAt,
+    LastVisitedAt,
+    VisitCount,
+    RevokedAt,
+}
+
+#[derive(Iden)]
+enum ShareEntries {
+    #[iden = "share_entries"]
+    Table,
+    ShareId,
+    Position,
+    Kind,
+    EntryId,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Shares::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Shares::Id).integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(Shares::Token).string().not_null().unique_key())
+                    .col(ColumnDef::new(Shares::Username).string().not_null())
+                    .col(ColumnDef::new(Shares::Description).text())
+                    .col(ColumnDef::new(Shares::CreatedAt).date_time().not_null())
+                    .col(ColumnDef::new(Shares::ExpiresAt).date_time())
+                    .col(ColumnDef::new(Shares::LastVisitedAt).date_time())
+                    .col(ColumnDef::new(Shares::VisitCount).integer().not_null().default(0))
+                    .col(ColumnDef::new(Shares::RevokedAt).date_time())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-shares-username")
+                            .from(Shares::Table, Shares::Username)
+                            .to(Users::Table, Users::Username)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Entries point at songs, albums or playlists by id and are resolved on
+        // every visit, so no foreign keys: a rescan must not drop shared items.
+        manager
+            .create_table(
+                Table::create()
+                    .table(ShareEntries::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ShareEntries::ShareId).integer().not_null())
+                    .col(ColumnDef::new(ShareEntries::Position).integer().not_null())
+                    .col(ColumnDef::new(ShareEntries::Kind).string().not_null())
+                    .col(ColumnDef::new(ShareEntries::EntryId).string().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(ShareEntries::ShareId)
+                            .col(ShareEntries::Position),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-share_entries-share_id")
+                            .from(ShareEntries::Table, ShareEntries::ShareId)
+                            .to(Shares::Table, Shares::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-shares-username")
+                    .table(Shares::Table)
+                    .col(Shares::Username)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(ShareEntries::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(Shares::Table).to_owned()).await?;
+        Ok(())
+    }
+}
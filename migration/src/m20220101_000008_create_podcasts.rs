@@ -0,0 +1,124 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum PodcastChannels {
+    #[iden = "podcast_channels"]
+    Table,
+    Id,
+    Url,
+    Title,
+    Description,
+    ImageUrl,
+    Status,
+    ErrorMessage,
+    CreatedAt,
+    LastRefreshedAt,
+}
+
+#[derive(Iden)]
+enum PodcastEpisodes {
+    #[iden = "podcast_episodes"]
+    Table,
+    Id,
+    ChannelId,
+    Guid,
+    Title,
+    Description,
+    PublishDate,
+    EnclosureUrl,
+    ContentType,
+    Size,
+    Duration,
+    Status,
+    Path,
+    ErrorMessage,
+    CreatedAt,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PodcastChannels::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PodcastChannels::Id).integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(PodcastChannels::Url).string().not_null().unique_key())
+                    .col(ColumnDef::new(PodcastChannels::Title).string())
+                    .col(ColumnDef::new(PodcastChannels::Description).text())
+                    .col(ColumnDef::new(PodcastChannels::ImageUrl).string())
+                    .col(ColumnDef::new(PodcastChannels::Status).string().not_null())
+                    .col(ColumnDef::new(PodcastChannels::ErrorMessage).text())
+                    .col(ColumnDef::new(PodcastChannels::CreatedAt).date_time().not_null())
+                    .col(ColumnDef::new(PodcastChannels::LastRefreshedAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PodcastEpisodes::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PodcastEpisodes::Id).integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(PodcastEpisodes::ChannelId).integer().not_null())
+                    .col(ColumnDef::new(PodcastEpisodes::Guid).string().not_null())
+                    .col(ColumnDef::new(PodcastEpisodes::Title).string().not_null())
+                    .col(ColumnDef::new(PodcastEpisodes::Description).text())
+                    .col(ColumnDef::new(PodcastEpisodes::PublishDate).date_time())
+                    .col(ColumnDef::new(PodcastEpisodes::EnclosureUrl).string().not_null())
+                    .col(ColumnDef::new(PodcastEpisodes::ContentType).string())
+                    .col(ColumnDef::new(PodcastEpisodes::Size).big_integer())
+                    .col(ColumnDef::new(PodcastEpisodes::Duration).integer())
+                    .col(ColumnDef::new(PodcastEpisodes::Status).string().not_null())
+                    .col(ColumnDef::new(PodcastEpisodes::Path).string())
+                    .col(ColumnDef::new(PodcastEpisodes::ErrorMessage).text())
+                    .col(ColumnDef::new(PodcastEpisodes::CreatedAt).date_time().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-podcast_episodes-channel_id")
+                            .from(PodcastEpisodes::Table, PodcastEpisodes::ChannelId)
+                            .to(PodcastChannels::Table, PodcastChannels::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-podcast_episodes-channel_guid")
+                    .table(PodcastEpisodes::Table)
+                    .col(PodcastEpisodes::ChannelId)
+                    .col(PodcastEpisodes::Guid)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-podcast_episodes-publish_date")
+                    .table(PodcastEpisodes::Table)
+                    .col(PodcastEpisodes::PublishDate)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(PodcastEpisodes::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(PodcastChannels::Table).to_owned()).await?;
+        Ok(())
+    }
+}
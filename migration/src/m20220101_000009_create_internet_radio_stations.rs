@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum InternetRadioStations {
+    #[iden = "internet_radio_stations"]
+    Table,
+    Id,
+    Name,
+    StreamUrl,
+    HomePageUrl,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(InternetRadioStations::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(InternetRadioStations::Id).integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(InternetRadioStations::Name).string().not_null())
+                    .col(ColumnDef::new(InternetRadioStations::StreamUrl).string().not_null())
+                    .col(ColumnDef::new(InternetRadioStations::HomePageUrl).string())
+                    .col(ColumnDef::new(InternetRadioStations::CreatedAt).date_time().not_null())
+                    .col(ColumnDef::new(InternetRadioStations::UpdatedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(InternetRadioStations::Table).to_owned()).await?;
+        Ok(())
+    }
+}
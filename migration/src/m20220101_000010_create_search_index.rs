@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // One row per searchable song, album and artist. The text is stored as
+        // indexed (see `scanner::search_index`), so the FTS table can use it as
+        // external content and the triggers keep both in step.
+        db.execute_unprepared(
+            "CREATE TABLE IF NOT EXISTS search_docs (\
+                 id INTEGER PRIMARY KEY, \
+                 kind TEXT NOT NULL, \
+                 item_id TEXT NOT NULL, \
+                 title TEXT NOT NULL DEFAULT '', \
+                 artist TEXT NOT NULL DEFAULT '', \
+                 album TEXT NOT NULL DEFAULT '', \
+                 UNIQUE (kind, item_id))",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(\
+                 title, artist, album, \
+                 content = 'search_docs', content_rowid = 'id', \
+                 tokenize = 'unicode61 remove_diacritics 2')",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS search_docs_ai AFTER INSERT ON search_docs BEGIN \
+                 INSERT INTO search_index (rowid, title, artist, album) \
+                 VALUES (new.id, new.title, new.artist, new.album); \
+             END",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS search_docs_ad AFTER DELETE ON search_docs BEGIN \
+                 INSERT INTO search_index (search_index, rowid, title, artist, album) \
+                 VALUES ('delete', old.id, old.title, old.artist, old.album); \
+             END",
+        )
+        .await?;
+        db.execute_unprepared(
+            "CREATE TRIGGER IF NOT EXISTS search_docs_au AFTER UPDATE ON search_docs BEGIN \
+                 INSERT INTO search_index (search_index, rowid, title, artist, album) \
+                 VALUES ('delete', old.id, old.title, old.artist, old.album); \
+                 INSERT INTO search_index (rowid, title, artist, album) \
+                 VALUES (new.id, new.title, new.artist, new.album); \
+             END",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DROP TABLE IF EXISTS search_index").await?;
+        db.execute_unprepared("DROP TABLE IF EXISTS search_docs").await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Users {
+    #[iden = "users"]
+    Table,
+    Username,
+}
+
+#[derive(Iden)]
+enum Playlists {
+    #[iden = "playlists"]
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum PlaylistUsers {
+    #[iden = "playlist_users"]
+    Table,
+    PlaylistId,
+    Username,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Users other than the owner who may edit a playlist's songs.
+        manager
+            .create_table(
+                Table::create()
+                    .table(PlaylistUsers::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlaylistUsers::PlaylistId).integer().not_null())
+                    .col(ColumnDef::new(PlaylistUsers::Username).string().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(PlaylistUsers::PlaylistId)
+                            .col(PlaylistUsers::Username),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-playlist_users-playlist_id")
+                            .from(PlaylistUsers::Table, PlaylistUsers::PlaylistId)
+                            .to(Playlists::Table, Playlists::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-playlist_users-username")
+                            .from(PlaylistUsers::Table, PlaylistUsers::Username)
+                            .to(Users::Table, Users::Username)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-playlist_users-username")
+                    .table(PlaylistUsers::Table)
+                    .col(PlaylistUsers::Username)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PlaylistUsers::Table).to_owned())
+            .await
+    }
+}
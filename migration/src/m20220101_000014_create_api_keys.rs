@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Users {
+    #[iden = "users"]
+    Table,
+    Username,
+}
+
+#[derive(Iden)]
+enum ApiKeys {
+    #[iden = "api_keys"]
+    Table,
+    Id,
+    Username,
+    Name,
+    KeyHash,
+    CreatedAt,
+    LastUsedAt,
+    LastClient,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Keys for OpenSubsonic `apiKey` authentication. Only a SHA-256 hash of
+        // each key is stored; the key itself is shown once when it's created.
+        manager
+            .create_table(
+                Table::create()
+                    .table(ApiKeys::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ApiKeys::Id).integer().not_null().auto_increment().primary_key())
+                    .col(ColumnDef::new(ApiKeys::Username).string().not_null())
+                    .col(ColumnDef::new(ApiKeys::Name).string().not_null())
+                    .col(ColumnDef::new(ApiKeys::KeyHash).string().not_null().unique_key())
+                    .col(ColumnDef::new(ApiKeys::CreatedAt).date_time().not_null())
+                    .col(ColumnDef::new(ApiKeys::LastUsedAt).date_time())
+                    .col(ColumnDef::new(ApiKeys::LastClient).string())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-api_keys-username")
+                            .from(ApiKeys::Table, ApiKeys::Username)
+                            .to(Users::Table, Users::Username)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-api_keys-username")
+                    .table(ApiKeys::Table)
+                    .col(ApiKeys::Username)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ApiKeys::Table).to_owned())
+            .await
+    }
+}
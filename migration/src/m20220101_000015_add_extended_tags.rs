@@ -0,0 +1,224 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Children {
+    #[iden = "children"]
+    Table,
+    Id,
+    Bpm,
+    Comment,
+    SortName,
+    MusicBrainzId,
+    DisplayArtist,
+    ExplicitStatus,
+    Moods,
+    DiscSubtitle,
+    ReplayGainTrackGain,
+    ReplayGainTrackPeak,
+    ReplayGainAlbumGain,
+    ReplayGainAlbumPeak,
+    ChannelCount,
+    SamplingRate,
+    BitDepth,
+}
+
+#[derive(Iden)]
+enum Albums {
+    #[iden = "albums"]
+    Table,
+    SortName,
+    MusicBrainzId,
+    DisplayArtist,
+    ReleaseTypes,
+    RecordLabels,
+    OriginalReleaseDate,
+    IsCompilation,
+}
+
+#[derive(Iden)]
+enum Artists {
+    #[iden = "artists"]
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum SongContributors {
+    #[iden = "song_contributors"]
+    Table,
+    SongId,
+    Role,
+    SubRole,
+    ArtistId,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Multi-valued tags (moods, release types, labels) are stored "; "-joined,
+        // the way the scanner splits them.
+        let children = [
+            ColumnDef::new(Children::Bpm).integer().to_owned(),
+            ColumnDef::new(Children::Comment).text().to_owned(),
+            ColumnDef::new(Children::SortName).string().to_owned(),
+            ColumnDef::new(Children::MusicBrainzId).string().to_owned(),
+            ColumnDef::new(Children::DisplayArtist).string().to_owned(),
+            ColumnDef::new(Children::ExplicitStatus).string().to_owned(),
+            ColumnDef::new(Children::Moods).string().to_owned(),
+            ColumnDef::new(Children::DiscSubtitle).string().to_owned(),
+            ColumnDef::new(Children::ReplayGainTrackGain).double().to_owned(),
+            ColumnDef::new(Children::ReplayGainTrackPeak).double().to_owned(),
+            ColumnDef::new(Children::ReplayGainAlbumGain).double().to_owned(),
+            ColumnDef::new(Children::ReplayGainAlbumPeak).double().to_owned(),
+            ColumnDef::new(Children::ChannelCount).integer().to_owned(),
+            ColumnDef::new(Children::SamplingRate).integer().to_owned(),
+            ColumnDef::new(Children::BitDepth).integer().to_owned(),
+        ];
+        // SQLite only adds one column per ALTER TABLE.
+        for mut column in children {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Children::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        let albums = [
+            ColumnDef::new(Albums::SortName).string().to_owned(),
+            ColumnDef::new(Albums::MusicBrainzId).string().to_owned(),
+            ColumnDef::new(Albums::DisplayArtist).string().to_owned(),
+            ColumnDef::new(Albums::ReleaseTypes).string().to_owned(),
+            ColumnDef::new(Albums::RecordLabels).string().to_owned(),
+            // "YYYY", "YYYY-MM" or "YYYY-MM-DD", as tagged.
+            ColumnDef::new(Albums::OriginalReleaseDate).string().to_owned(),
+            ColumnDef::new(Albums::IsCompilation)
+                .boolean()
+                .not_null()
+                .default(false)
+                .to_owned(),
+        ];
+        for mut column in albums {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Albums::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Composers, conductors, performers, ... credited on a song. `sub_role`
+        // is the instrument of a performer and empty otherwise.
+        manager
+            .create_table(
+                Table::create()
+                    .table(SongContributors::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(SongContributors::SongId).string().not_null())
+                    .col(ColumnDef::new(SongContributors::Role).string().not_null())
+                    .col(
+                        ColumnDef::new(SongContributors::SubRole)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(ColumnDef::new(SongContributors::ArtistId).string().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(SongContributors::SongId)
+                            .col(SongContributors::Role)
+                            .col(SongContributors::SubRole)
+                            .col(SongContributors::ArtistId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-song_contributors-song_id")
+                            .from(SongContributors::Table, SongContributors::SongId)
+                            .to(Children::Table, Children::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-song_contributors-artist_id")
+                            .from(SongContributors::Table, SongContributors::ArtistId)
+                            .to(Artists::Table, Artists::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-song_contributors-artist_id")
+                    .table(SongContributors::Table)
+                    .col(SongContributors::ArtistId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SongContributors::Table).to_owned())
+            .await?;
+
+        for column in [
+            Albums::SortName,
+            Albums::MusicBrainzId,
+            Albums::DisplayArtist,
+            Albums::ReleaseTypes,
+            Albums::RecordLabels,
+            Albums::OriginalReleaseDate,
+            Albums::IsCompilation,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Albums::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        for column in [
+            Children::Bpm,
+            Children::Comment,
+            Children::SortName,
+            Children::MusicBrainzId,
+            Children::DisplayArtist,
+            Children::ExplicitStatus,
+            Children::Moods,
+            Children::DiscSubtitle,
+            Children::ReplayGainTrackGain,
+            Children::ReplayGainTrackPeak,
+            Children::ReplayGainAlbumGain,
+            Children::ReplayGainAlbumPeak,
+            Children::ChannelCount,
+            Children::SamplingRate,
+            Children::BitDepth,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Children::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Children {
+    #[iden = "children"]
+    Table,
+    ReplayGainBaseGain,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Output gain from the Ogg Opus header, in dB.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Children::Table)
+                    .add_column(ColumnDef::new(Children::ReplayGainBaseGain).double())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Children::Table)
+                    .drop_column(Children::ReplayGainBaseGain)
+                    .to_owned(),
+            )
+            .await
+    }
+}
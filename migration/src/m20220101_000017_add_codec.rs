@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Children {
+    #[iden = "children"]
+    Table,
+    Codec,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Codec of the audio stream, e.g. "aac" or "alac" for an m4a file.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Children::Table)
+                    .add_column(ColumnDef::new(Children::Codec).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Content types used to be "audio/<suffix>"; fix the ones that aren't
+        // real MIME types.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE children SET content_type = CASE LOWER(suffix) \
+                 WHEN 'mp3' THEN 'audio/mpeg' \
+                 WHEN 'm4a' THEN 'audio/mp4' \
+                 WHEN 'opus' THEN 'audio/ogg' \
+                 ELSE content_type END \
+                 WHERE is_dir = 0 AND content_type LIKE 'audio/%'",
+            )
+            .await?;
+
+        // Most suffixes name a single codec. m4a and ogg files can hold
+        // several, so theirs are filled in by the next full scan.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE children SET codec = CASE LOWER(suffix) \
+                 WHEN 'flac' THEN 'flac' \
+                 WHEN 'mp3' THEN 'mp3' \
+                 WHEN 'opus' THEN 'opus' \
+                 WHEN 'aac' THEN 'aac' \
+                 WHEN 'wav' THEN 'pcm' \
+                 WHEN 'aif' THEN 'pcm' \
+                 WHEN 'aiff' THEN 'pcm' \
+                 WHEN 'ape' THEN 'ape' \
+                 WHEN 'wv' THEN 'wavpack' \
+                 WHEN 'mpc' THEN 'mpc' \
+                 END \
+                 WHERE is_dir = 0",
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Children::Table)
+                    .drop_column(Children::Codec)
+                    .to_owned(),
+            )
+            .await
+    }
+}
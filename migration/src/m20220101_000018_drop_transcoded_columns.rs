@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Children {
+    #[iden = "children"]
+    Table,
+    TranscodedContentType,
+    TranscodedSuffix,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Transcoding targets depend on the config and the user, so they are
+        // worked out per response instead of at scan time.
+        for column in [Children::TranscodedContentType, Children::TranscodedSuffix] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Children::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Children::TranscodedContentType, Children::TranscodedSuffix] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Children::Table)
+                        .add_column(ColumnDef::new(column).string())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum PodcastEpisodes {
+    #[iden = "podcast_episodes"]
+    Table,
+    DownloadedAt,
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // When the episode file was last fetched; retention keeps the most
+        // recent downloads.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PodcastEpisodes::Table)
+                    .add_column(ColumnDef::new(PodcastEpisodes::DownloadedAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE podcast_episodes SET downloaded_at = created_at WHERE status = 'completed'",
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PodcastEpisodes::Table)
+                    .drop_column(PodcastEpisodes::DownloadedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
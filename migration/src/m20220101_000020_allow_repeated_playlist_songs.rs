@@ -0,0 +1,162 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::async_trait::async_trait;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Children {
+    #[iden = "children"]
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Playlists {
+    #[iden = "playlists"]
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum PlaylistSongs {
+    #[iden = "playlist_songs"]
+    Table,
+    PlaylistId,
+    SongId,
+    Index,
+}
+
+#[derive(Iden)]
+enum PlaylistSongsOld {
+    #[iden = "playlist_songs_old"]
+    Table,
+}
+
+fn foreign_keys(table: &mut TableCreateStatement) -> &mut TableCreateStatement {
+    table
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk-playlist_songs-playlist_id")
+                .from(PlaylistSongs::Table, PlaylistSongs::PlaylistId)
+                .to(Playlists::Table, Playlists::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk-playlist_songs-song_id")
+                .from(PlaylistSongs::Table, PlaylistSongs::SongId)
+                .to(Children::Table, Children::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A song may appear more than once in a playlist, so entries are keyed
+        // by their position instead of the song.
+        manager
+            .rename_table(
+                Table::rename()
+                    .table(PlaylistSongs::Table, PlaylistSongsOld::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                foreign_keys(
+                    Table::create()
+                        .table(PlaylistSongs::Table)
+                        .if_not_exists()
+                        .col(ColumnDef::new(PlaylistSongs::PlaylistId).integer().not_null())
+                        .col(ColumnDef::new(PlaylistSongs::SongId).string().not_null())
+                        .col(ColumnDef::new(PlaylistSongs::Index).integer().not_null())
+                        .primary_key(
+                            Index::create()
+                                .col(PlaylistSongs::PlaylistId)
+                                .col(PlaylistSongs::Index),
+                        ),
+                )
+                .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO playlist_songs (playlist_id, song_id, \"index\") \
+                 SELECT playlist_id, song_id, \"index\" FROM playlist_songs_old",
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PlaylistSongsOld::Table).to_owned())
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-playlist_songs-song_id")
+                    .table(PlaylistSongs::Table)
+                    .col(PlaylistSongs::SongId)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .rename_table(
+                Table::rename()
+                    .table(PlaylistSongs::Table, PlaylistSongsOld::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                foreign_keys(
+                    Table::create()
+                        .table(PlaylistSongs::Table)
+                        .if_not_exists()
+                        .col(ColumnDef::new(PlaylistSongs::PlaylistId).integer().not_null())
+                        .col(ColumnDef::new(PlaylistSongs::SongId).string().not_null())
+                        .col(ColumnDef::new(PlaylistSongs::Index).integer().not_null())
+                        .primary_key(
+                            Index::create()
+                                .col(PlaylistSongs::PlaylistId)
+                                .col(PlaylistSongs::SongId),
+                        )
+                        .index(
+                            Index::create()
+                                .unique()
+                                .name("idx-playlist-song-index-unique")
+                                .col(PlaylistSongs::PlaylistId)
+                                .col(PlaylistSongs::Index),
+                        ),
+                )
+                .to_owned(),
+            )
+            .await?;
+
+        // Only one entry per song fits the old schema; keep the first.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO playlist_songs (playlist_id, song_id, \"index\") \
+                 SELECT playlist_id, song_id, MIN(\"index\") FROM playlist_songs_old \
+                 GROUP BY playlist_id, song_id",
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PlaylistSongsOld::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
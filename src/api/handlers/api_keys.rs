@@ -0,0 +1,79 @@
+use crate::api::models::{ApiKeyRequest, ApiKeyResponse, CreatedApiKeyResponse, ErrorResponse};
+use crate::models::{api_key, user};
+use crate::service::Service;
+use poem::{
+    handler,
+    http::StatusCode,
+    web::{Data, Json, Path},
+    IntoResponse, Response,
+};
+use std::sync::Arc;
+
+fn error(status: StatusCode, msg: impl Into<String>) -> Response {
+    Json(ErrorResponse { error: msg.into() })
+        .with_status(status)
+        .into_response()
+}
+
+fn internal_error(e: sea_orm::DbErr) -> Response {
+    log::error!("Failed to update API keys: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+impl From<api_key::Model> for ApiKeyResponse {
+    fn from(k: api_key::Model) -> Self {
+        Self {
+            id: k.id,
+            name: k.name,
+            created_at: k.created_at,
+            last_used_at: k.last_used_at,
+            last_client: k.last_client,
+        }
+    }
+}
+
+#[handler]
+pub async fn get_api_keys(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+) -> Result<Json<Vec<ApiKeyResponse>>, poem::Error> {
+    let keys = service.get_api_keys(&user.username).await.map_err(|e| {
+        log::error!("Failed to fetch API keys: {}", e);
+        poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+    })?;
+    Ok(Json(keys.into_iter().map(Into::into).collect()))
+}
+
+#[handler]
+pub async fn create_api_key(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Json(req): Json<ApiKeyRequest>,
+) -> Response {
+    let name = req.name.unwrap_or_default().trim().to_string();
+    if name.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "Name is required");
+    }
+    match service.create_api_key(&user.username, name).await {
+        Ok((api_key, key)) => Json(CreatedApiKeyResponse {
+            api_key: api_key.into(),
+            key,
+        })
+        .with_status(StatusCode::CREATED)
+        .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[handler]
+pub async fn delete_api_key(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Path(id): Path<i32>,
+) -> Response {
+    match service.delete_api_key(id, &user.username).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => internal_error(e),
+    }
+}
@@ -0,0 +1,141 @@
+use crate::api::models::{ConnectionResponse, ErrorResponse, LinkConnectionRequest};
+use crate::models::user;
+use crate::service::scrobble::{ScrobbleForwarder, LASTFM, LISTENBRAINZ, SERVICES};
+use poem::{
+    handler,
+    http::StatusCode,
+    web::{Data, Json, Path},
+    IntoResponse,
+};
+use std::sync::Arc;
+
+fn nonblank(v: &Option<String>) -> Option<&str> {
+    v.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn error(status: StatusCode, msg: impl Into<String>) -> poem::Response {
+    Json(ErrorResponse { error: msg.into() })
+        .with_status(status)
+        .into_response()
+}
+
+#[handler]
+pub async fn get_connections(
+    forwarder: Data<&Arc<ScrobbleForwarder>>,
+    user: Data<&Arc<user::Model>>,
+) -> Result<Json<Vec<ConnectionResponse>>, poem::Error> {
+    let (connections, pending) = tokio::try_join!(
+        forwarder.connections(&user.username),
+        forwarder.pending_counts(&user.username)
+    )
+    .map_err(|e| {
+        log::error!("Failed to load connections for '{}': {}", user.username, e);
+        poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+    })?;
+
+    Ok(Json(
+        SERVICES
+            .iter()
+            .map(|service| {
+                let conn = connections.iter().find(|c| c.service == *service);
+                ConnectionResponse {
+                    service: service.to_string(),
+                    connected: conn.is_some(),
+                    account_name: conn.and_then(|c| c.account_name.clone()),
+                    pending: pending
+                        .iter()
+                        .find(|(s, _)| s == service)
+                        .map(|(_, n)| *n)
+                        .unwrap_or(0),
+                    available: *service != LASTFM || forwarder.client().lastfm_enabled(),
+                }
+            })
+            .collect(),
+    ))
+}
+
+#[handler]
+pub async fn link_connection(
+    forwarder: Data<&Arc<ScrobbleForwarder>>,
+    user: Data<&Arc<user::Model>>,
+    Path(service): Path<String>,
+    req: Json<LinkConnectionRequest>,
+) -> impl IntoResponse {
+    if !user.settings_role {
+        return error(StatusCode::FORBIDDEN, "Not allowed to change settings");
+    }
+
+    let linked = match service.as_str() {
+        LISTENBRAINZ => {
+            let Some(token) = nonblank(&req.token) else {
+                return error(StatusCode::BAD_REQUEST, "token is required");
+            };
+            forwarder
+                .client()
+                .validate_listenbrainz_token(token)
+                .await
+                .map(|name| (name, token.to_string()))
+        }
+        LASTFM => {
+            let (Some(username), Some(password)) =
+                (nonblank(&req.username), req.password.as_deref())
+            else {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    "username and password are required",
+                );
+            };
+            forwarder.client().lastfm_session(username, password).await
+        }
+        _ => return error(StatusCode::NOT_FOUND, "Unknown service"),
+    };
+
+    let (account_name, token) = match linked {
+        Ok(v) => v,
+        Err(e) => {
+            log::warn!("Failed to link {} for '{}': {}", service, user.username, e);
+            return error(StatusCode::BAD_REQUEST, e.to_string());
+        }
+    };
+
+    match forwarder
+        .link(&user.username, &service, Some(account_name), &token)
+        .await
+    {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => {
+            log::error!(
+                "Failed to save {} link for '{}': {}",
+                service,
+                user.username,
+                e
+            );
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+        }
+    }
+}
+
+#[handler]
+pub async fn unlink_connection(
+    forwarder: Data<&Arc<ScrobbleForwarder>>,
+    user: Data<&Arc<user::Model>>,
+    Path(service): Path<String>,
+) -> Result<StatusCode, poem::Error> {
+    if !user.settings_role {
+        return Err(poem::Error::from_status(StatusCode::FORBIDDEN));
+    }
+
+    match forwarder.unlink(&user.username, &service).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(poem::Error::from_status(StatusCode::NOT_FOUND)),
+        Err(e) => {
+            log::error!(
+                "Failed to unlink {} for '{}': {}",
+                service,
+                user.username,
+                e
+            );
+            Err(poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
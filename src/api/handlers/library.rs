@@ -1,4 +1,4 @@
-use crate::models::{child, user};
+use crate::models::{child, queries, user};
 use crate::service::scrape::ScrapeService;
 use crate::service::tag::SongTags;
 use base64::{engine::general_purpose, Engine as _};
@@ -12,7 +12,7 @@ use poem::{
     http::StatusCode,
     web::{Data, Json, Multipart, Path, Query},
 };
-use sea_orm::{DatabaseConnection, EntityTrait};
+use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter};
 use serde::Deserialize;
 use std::sync::Arc;
 
@@ -23,9 +23,11 @@ const MUSICIP_PUID: &str = "MusicIP PUID";
 #[handler]
 pub async fn get_song_tags(
     db: Data<&DatabaseConnection>,
+    user: Data<&std::sync::Arc<user::Model>>,
     Path(id): Path<String>,
 ) -> Result<Json<SongTags>, poem::Error> {
     let song = child::Entity::find_by_id(id)
+        .filter(queries::song_access_filter(&user.username))
         .one(*db)
         .await
         .map_err(|_| poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR))?
@@ -355,7 +357,7 @@ pub async fn update_song_cover(
     Path(id): Path<String>,
     mut multipart: Multipart,
 ) -> Result<StatusCode, poem::Error> {
-    if !user.admin_role {
+    if !user.admin_role || !user.cover_art_role {
         return Err(poem::Error::from_status(StatusCode::FORBIDDEN));
     }
 
@@ -1,4 +1,8 @@
+pub mod api_keys;
 pub mod auth;
+pub mod connections;
 pub mod library;
+pub mod playlists;
+pub mod radio;
 pub mod system;
 pub mod user;
@@ -0,0 +1,295 @@
+use crate::api::models::{
+    ErrorResponse, ExportPlaylistQuery, ImportPlaylistQuery, ImportPlaylistResponse,
+    PlaylistUsersRequest, SmartPlaylistRequest, SmartPlaylistResponse,
+};
+use crate::config::Config;
+use crate::models::user;
+use crate::service::playlist_files::{self, Format, Locations};
+use crate::service::playlists::{UpdatePlaylistOptions, PERMISSION_DENIED, PLAYLIST_NOT_FOUND};
+use crate::service::smart_playlists::SmartRules;
+use crate::service::Service;
+use crate::subsonic::common::public_base_url;
+use poem::{
+    handler,
+    http::{header, StatusCode},
+    web::{Data, Json, Path, Query},
+    IntoResponse, Request, Response,
+};
+use sea_orm::DbErr;
+use std::sync::Arc;
+
+fn error(status: StatusCode, msg: impl Into<String>) -> Response {
+    Json(ErrorResponse { error: msg.into() })
+        .with_status(status)
+        .into_response()
+}
+
+fn internal_error(e: DbErr) -> Response {
+    log::error!("Failed to update playlist: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+fn change_error(e: DbErr) -> Response {
+    match e {
+        DbErr::Custom(msg) if msg == PLAYLIST_NOT_FOUND => StatusCode::NOT_FOUND.into_response(),
+        DbErr::Custom(msg) if msg == PERMISSION_DENIED => StatusCode::FORBIDDEN.into_response(),
+        DbErr::Custom(msg) => error(StatusCode::BAD_REQUEST, msg),
+        e => internal_error(e),
+    }
+}
+
+/// The smart playlist `id` if `username` may read it.
+async fn smart_playlist(
+    service: &Service,
+    id: i32,
+    username: &str,
+) -> Result<Option<SmartPlaylistResponse>, DbErr> {
+    let Some(p) = service.find_visible_playlist(id, username).await? else {
+        return Ok(None);
+    };
+    Ok(p.rules.map(|rules| SmartPlaylistResponse {
+        id: p.id,
+        name: p.name,
+        comment: p.comment,
+        owner: p.owner,
+        public: p.public,
+        rules: serde_json::from_str(&rules).unwrap_or_default(),
+    }))
+}
+
+fn respond(found: Result<Option<SmartPlaylistResponse>, DbErr>, status: StatusCode) -> Response {
+    match found {
+        Ok(Some(p)) => Json(p).with_status(status).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[handler]
+pub async fn get_smart_playlist(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Path(id): Path<i32>,
+) -> Response {
+    respond(
+        smart_playlist(&service, id, &user.username).await,
+        StatusCode::OK,
+    )
+}
+
+#[handler]
+pub async fn create_smart_playlist(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Json(req): Json<SmartPlaylistRequest>,
+) -> Response {
+    if !user.playlist_role {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let Some(name) = req.name.filter(|n| !n.trim().is_empty()) else {
+        return error(StatusCode::BAD_REQUEST, "name is required");
+    };
+    let rules = match SmartRules::parse(&req.rules.unwrap_or_default().to_string()) {
+        Ok(r) => r,
+        Err(msg) => return error(StatusCode::BAD_REQUEST, msg),
+    };
+    let id = match service
+        .create_smart_playlist(
+            name,
+            user.username.clone(),
+            req.comment,
+            req.public.unwrap_or(false),
+            &rules,
+        )
+        .await
+    {
+        Ok(id) => id,
+        Err(e) => return internal_error(e),
+    };
+    respond(
+        smart_playlist(&service, id, &user.username).await,
+        StatusCode::CREATED,
+    )
+}
+
+#[handler]
+pub async fn update_smart_playlist(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Path(id): Path<i32>,
+    Json(req): Json<SmartPlaylistRequest>,
+) -> Response {
+    if !user.playlist_role {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let rules = match req
+        .rules
+        .map(|r| SmartRules::parse(&r.to_string()))
+        .transpose()
+    {
+        Ok(r) => r,
+        Err(msg) => return error(StatusCode::BAD_REQUEST, msg),
+    };
+    let opts = UpdatePlaylistOptions {
+        name: req.name,
+        comment: req.comment,
+        public: req.public,
+        rules,
+        ..Default::default()
+    };
+    match service.update_playlist(id, &user.username, opts).await {
+        Ok(()) => {}
+        Err(e) => return change_error(e),
+    }
+    respond(
+        smart_playlist(&service, id, &user.username).await,
+        StatusCode::OK,
+    )
+}
+
+#[handler]
+pub async fn get_playlist_users(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Path(id): Path<i32>,
+) -> Response {
+    match service.get_playlist_users(id, &user.username).await {
+        Ok(usernames) => Json(usernames).into_response(),
+        Err(e) => change_error(e),
+    }
+}
+
+/// Replaces the users allowed to edit a playlist's songs; only the owner may.
+#[handler]
+pub async fn update_playlist_users(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Path(id): Path<i32>,
+    Json(req): Json<PlaylistUsersRequest>,
+) -> Response {
+    match service
+        .set_playlist_users(id, &user.username, req.usernames)
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => change_error(e),
+    }
+}
+
+#[handler]
+pub async fn export_playlist(
+    service: Data<&Arc<Service>>,
+    config: Data<&Arc<Config>>,
+    user: Data<&Arc<user::Model>>,
+    Path(id): Path<i32>,
+    Query(query): Query<ExportPlaylistQuery>,
+    req: &Request,
+) -> Response {
+    let format = match query.format.as_deref() {
+        None => Format::M3u8,
+        Some(name) => match Format::from_name(name) {
+            Some(f) => f,
+            None => return error(StatusCode::BAD_REQUEST, "format must be m3u8, pls or xspf"),
+        },
+    };
+    let locations = match query.paths.as_deref() {
+        None | Some("relative") => Locations::Relative,
+        // Exports outlive sessions, so they carry a revocable API key rather
+        // than anything derived from the password.
+        Some("url") => {
+            let Some(api_key) = query.api_key.as_deref() else {
+                return error(StatusCode::BAD_REQUEST, "paths=url requires an apiKey");
+            };
+            match service.is_api_key_of(api_key, &user.username).await {
+                Ok(true) => {}
+                Ok(false) => return error(StatusCode::FORBIDDEN, "Invalid API key"),
+                Err(e) => return internal_error(e),
+            }
+            Locations::Stream {
+                base_url: public_base_url(&config, req),
+                query: format!("apiKey={}&v=1.16.1&c=miko", urlencoding::encode(api_key)),
+            }
+        }
+        Some(_) => return error(StatusCode::BAD_REQUEST, "paths must be relative or url"),
+    };
+
+    let (name, content) = match service
+        .export_playlist(id, &user.username, format, &locations)
+        .await
+    {
+        Ok(Some(exported)) => exported,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            log::error!("Failed to export playlist {}: {}", id, e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let filename = format!("{}.{}", name, format.extension());
+    let fallback: String = filename
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() && c != '"' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let disposition = format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback,
+        urlencoding::encode(&filename)
+    );
+    content
+        .with_content_type(format.content_type())
+        .with_header(header::CONTENT_DISPOSITION, disposition)
+        .into_response()
+}
+
+/// Creates a playlist from an M3U8, PLS or XSPF file sent as the request body.
+#[handler]
+pub async fn import_playlist(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Query(query): Query<ImportPlaylistQuery>,
+    body: Vec<u8>,
+) -> Response {
+    if !user.playlist_role {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let content = String::from_utf8_lossy(&body);
+    let format = match query.format.as_deref() {
+        None => Format::detect(&content),
+        Some(name) => match Format::from_name(name) {
+            Some(f) => f,
+            None => return error(StatusCode::BAD_REQUEST, "format must be m3u8, pls or xspf"),
+        },
+    };
+    let file = match playlist_files::parse(format, &content) {
+        Ok(f) => f,
+        Err(msg) => return error(StatusCode::BAD_REQUEST, msg),
+    };
+    let name = query
+        .name
+        .filter(|n| !n.trim().is_empty())
+        .or_else(|| file.name.clone())
+        .unwrap_or_else(|| "Imported playlist".to_string());
+
+    match service
+        .import_playlist(&user.username, name.clone(), &file)
+        .await
+    {
+        Ok((id, resolved)) => Json(ImportPlaylistResponse {
+            id,
+            name,
+            song_count: resolved.song_ids.len(),
+            unresolved: resolved.unresolved,
+        })
+        .with_status(StatusCode::CREATED)
+        .into_response(),
+        Err(e) => {
+            log::error!("Failed to import playlist: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::api::models::{ErrorResponse, RadioStationRequest, RadioStationResponse};
+use crate::models::{internet_radio_station, user};
+use crate::service::radio::StationInput;
+use crate::service::Service;
+use poem::{
+    handler,
+    http::StatusCode,
+    web::{Data, Json, Path},
+    IntoResponse, Response,
+};
+use std::sync::Arc;
+
+fn error(status: StatusCode, msg: impl Into<String>) -> Response {
+    Json(ErrorResponse { error: msg.into() })
+        .with_status(status)
+        .into_response()
+}
+
+fn internal_error(e: sea_orm::DbErr) -> Response {
+    log::error!("Failed to update internet radio stations: {}", e);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+impl From<internet_radio_station::Model> for RadioStationResponse {
+    fn from(s: internet_radio_station::Model) -> Self {
+        Self {
+            id: s.id,
+            name: s.name,
+            stream_url: s.stream_url,
+            home_page_url: s.home_page_url,
+        }
+    }
+}
+
+impl From<RadioStationRequest> for StationInput {
+    fn from(r: RadioStationRequest) -> Self {
+        Self {
+            name: r.name,
+            stream_url: r.stream_url,
+            home_page_url: r.home_page_url,
+        }
+    }
+}
+
+#[handler]
+pub async fn get_stations(
+    service: Data<&Arc<Service>>,
+) -> Result<Json<Vec<RadioStationResponse>>, poem::Error> {
+    let stations = service.get_radio_stations().await.map_err(|e| {
+        log::error!("Failed to fetch internet radio stations: {}", e);
+        poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+    })?;
+    Ok(Json(stations.into_iter().map(Into::into).collect()))
+}
+
+#[handler]
+pub async fn create_station(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Json(req): Json<RadioStationRequest>,
+) -> Response {
+    if !user.admin_role {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let input = match StationInput::from(req).validate(true) {
+        Ok(i) => i,
+        Err(msg) => return error(StatusCode::BAD_REQUEST, msg),
+    };
+    match service.create_radio_station(input).await {
+        Ok(station) => Json(RadioStationResponse::from(station))
+            .with_status(StatusCode::CREATED)
+            .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[handler]
+pub async fn update_station(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Path(id): Path<i32>,
+    Json(req): Json<RadioStationRequest>,
+) -> Response {
+    if !user.admin_role {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let input = match StationInput::from(req).validate(false) {
+        Ok(i) => i,
+        Err(msg) => return error(StatusCode::BAD_REQUEST, msg),
+    };
+    match service.update_radio_station(id, input).await {
+        Ok(Some(station)) => Json(RadioStationResponse::from(station)).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[handler]
+pub async fn delete_station(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    Path(id): Path<i32>,
+) -> Response {
+    if !user.admin_role {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    match service.delete_radio_station(id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => internal_error(e),
+    }
+}
@@ -1,5 +1,6 @@
-use crate::api::models::{CreateFolderRequest, UpdateFolderRequest};
-use crate::models::{album, artist, child, genre, music_folder, user};
+use crate::api::models::{CreateFolderRequest, UpdateFolderRequest, UpdateFolderUsersRequest};
+use crate::models::{album, artist, child, genre, music_folder, queries, user, user_music_folder};
+use crate::scanner::Scanner;
 use once_cell::sync::Lazy;
 use poem::{
     handler,
@@ -8,7 +9,7 @@ use poem::{
 };
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
-    PaginatorTrait, QueryFilter, QuerySelect, Set,
+    PaginatorTrait, QueryFilter, QuerySelect, Set, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 use std::{
@@ -38,6 +39,10 @@ pub struct Stats {
     pub artists: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub genres: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_scan: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_scan: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Serialize)]
@@ -58,6 +63,8 @@ pub struct FolderInfo {
 #[handler]
 pub async fn get_stats(
     db: Data<&DatabaseConnection>,
+    scanner: Data<&std::sync::Arc<Scanner>>,
+    user: Data<&std::sync::Arc<user::Model>>,
     query: Query<StatsQuery>,
 ) -> Result<Json<Stats>, poem::Error> {
     let field_set: HashSet<&str> = query
@@ -72,6 +79,7 @@ pub async fn get_stats(
             if fetch_all || field_set.contains("songs") {
                 child::Entity::find()
                     .filter(child::Column::IsDir.eq(false))
+                    .filter(queries::song_access_filter(&user.username))
                     .count(*db)
                     .await
                     .map(Some)
@@ -81,14 +89,22 @@ pub async fn get_stats(
         },
         async {
             if fetch_all || field_set.contains("albums") {
-                album::Entity::find().count(*db).await.map(Some)
+                album::Entity::find()
+                    .filter(queries::album_access_filter(&user.username))
+                    .count(*db)
+                    .await
+                    .map(Some)
             } else {
                 Ok(None)
             }
         },
         async {
             if fetch_all || field_set.contains("artists") {
-                artist::Entity::find().count(*db).await.map(Some)
+                artist::Entity::find()
+                    .filter(queries::artist_access_filter(&user.username))
+                    .count(*db)
+                    .await
+                    .map(Some)
             } else {
                 Ok(None)
             }
@@ -106,11 +122,19 @@ pub async fn get_stats(
         poem::Error::from_status(poem::http::StatusCode::INTERNAL_SERVER_ERROR)
     })?;
 
+    let (last_scan, next_scan) = if fetch_all || field_set.contains("scan") {
+        (scanner.last_scan_at(), scanner.next_scan_at())
+    } else {
+        (None, None)
+    };
+
     Ok(Json(Stats {
         songs,
         albums,
         artists,
         genres,
+        last_scan,
+        next_scan,
     }))
 }
 
@@ -148,8 +172,14 @@ pub async fn get_system_info() -> Result<Json<SystemInfo>, poem::Error> {
 #[handler]
 pub async fn get_folders(
     db: Data<&DatabaseConnection>,
+    user: Data<&std::sync::Arc<user::Model>>,
 ) -> Result<Json<Vec<FolderInfo>>, poem::Error> {
-    let folders = music_folder::Entity::find().all(*db).await.map_err(|e| {
+    // Admins manage every folder; other users only see the folders granted to them
+    let mut folder_query = music_folder::Entity::find();
+    if !user.admin_role {
+        folder_query = folder_query.filter(queries::folder_access_filter(&user.username));
+    }
+    let folders = folder_query.all(*db).await.map_err(|e| {
         log::error!("Failed to fetch music folders: {}", e);
         poem::Error::from_status(poem::http::StatusCode::INTERNAL_SERVER_ERROR)
     })?;
@@ -203,11 +233,48 @@ pub async fn create_folder(
         ..Default::default()
     };
 
-    folder.insert(*db).await.map_err(|e| {
+    let txn = db.begin().await.map_err(|e| {
+        log::error!("Failed to start transaction: {}", e);
+        poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+    })?;
+
+    let folder = folder.insert(&txn).await.map_err(|e| {
         log::error!("Failed to create music folder: {}", e);
         poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
     })?;
 
+    // New folders are visible to every existing user until an admin narrows the grants
+    let usernames: Vec<String> = user::Entity::find()
+        .select_only()
+        .column(user::Column::Username)
+        .into_tuple()
+        .all(&txn)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to fetch users: {}", e);
+            poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+        })?;
+
+    if !usernames.is_empty() {
+        user_music_folder::Entity::insert_many(usernames.into_iter().map(|username| {
+            user_music_folder::ActiveModel {
+                username: Set(username),
+                music_folder_id: Set(folder.id),
+            }
+        }))
+        .exec_without_returning(&txn)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to grant music folder: {}", e);
+            poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+        })?;
+    }
+
+    txn.commit().await.map_err(|e| {
+        log::error!("Failed to commit transaction: {}", e);
+        poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+    })?;
+
     Ok(StatusCode::CREATED)
 }
 
@@ -267,3 +334,88 @@ pub async fn delete_folder(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+#[handler]
+pub async fn get_folder_users(
+    db: Data<&DatabaseConnection>,
+    user: Data<&std::sync::Arc<user::Model>>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<String>>, poem::Error> {
+    if !user.admin_role {
+        return Err(poem::Error::from_status(StatusCode::FORBIDDEN));
+    }
+
+    let usernames = user_music_folder::Entity::find()
+        .select_only()
+        .column(user_music_folder::Column::Username)
+        .filter(user_music_folder::Column::MusicFolderId.eq(id))
+        .into_tuple::<String>()
+        .all(*db)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to fetch folder grants: {}", e);
+            poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+        })?;
+
+    Ok(Json(usernames))
+}
+
+#[handler]
+pub async fn update_folder_users(
+    db: Data<&DatabaseConnection>,
+    user: Data<&std::sync::Arc<user::Model>>,
+    Path(id): Path<i32>,
+    req: Json<UpdateFolderUsersRequest>,
+) -> Result<StatusCode, poem::Error> {
+    if !user.admin_role {
+        return Err(poem::Error::from_status(StatusCode::FORBIDDEN));
+    }
+
+    music_folder::Entity::find_by_id(id)
+        .one(*db)
+        .await
+        .map_err(|e| {
+            log::error!("Database error finding folder: {}", e);
+            poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+        })?
+        .ok_or_else(|| poem::Error::from_status(StatusCode::NOT_FOUND))?;
+
+    let usernames: HashSet<&String> = req.usernames.iter().collect();
+
+    let txn = db.begin().await.map_err(|e| {
+        log::error!("Failed to start transaction: {}", e);
+        poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+    })?;
+
+    user_music_folder::Entity::delete_many()
+        .filter(user_music_folder::Column::MusicFolderId.eq(id))
+        .exec(&txn)
+        .await
+        .map_err(|e| {
+            log::error!("Failed to clear folder grants: {}", e);
+            poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+        })?;
+
+    if !usernames.is_empty() {
+        user_music_folder::Entity::insert_many(usernames.into_iter().map(|username| {
+            user_music_folder::ActiveModel {
+                username: Set(username.clone()),
+                music_folder_id: Set(id),
+            }
+        }))
+        .exec_without_returning(&txn)
+        .await
+        .map_err(|e| {
+            // Unknown usernames violate the users FK
+            log::error!("Failed to grant music folder: {}", e);
+            poem::Error::from_status(StatusCode::BAD_REQUEST)
+        })?;
+    }
+
+    txn.commit().await.map_err(|e| {
+        log::error!("Failed to commit transaction: {}", e);
+        poem::Error::from_status(StatusCode::INTERNAL_SERVER_ERROR)
+    })?;
+
+    Ok(StatusCode::OK)
+}
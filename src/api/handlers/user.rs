@@ -19,6 +19,14 @@ pub async fn update_profile(
     user: Data<&Arc<user::Model>>,
     req: Json<UpdateProfileRequest>,
 ) -> impl IntoResponse {
+    if !user.settings_role {
+        return Json(ErrorResponse {
+            error: "Not allowed to change settings".into(),
+        })
+        .with_status(StatusCode::FORBIDDEN)
+        .into_response();
+    }
+
     // 1. Verify current password for ANY change
     if !verify_password(
         &user.password,
@@ -1,9 +1,10 @@
 pub mod auth;
 pub mod handlers;
 pub mod models;
+pub mod share;
 pub mod web;
 
-use poem::{get, post, EndpointExt, Route};
+use poem::{delete, get, post, put, EndpointExt, Route};
 
 pub fn create_route(subsonic_routes: Option<Route>) -> Route {
     let mut auth_routes: Route = Route::new()
@@ -17,6 +18,10 @@ pub fn create_route(subsonic_routes: Option<Route>) -> Route {
             "/folders/:id",
             post(handlers::system::update_folder).delete(handlers::system::delete_folder),
         )
+        .at(
+            "/folders/:id/users",
+            get(handlers::system::get_folder_users).put(handlers::system::update_folder_users),
+        )
         .at(
             "/songs/:id/tags",
             get(handlers::library::get_song_tags).post(handlers::library::update_song_tags),
@@ -33,7 +38,48 @@ pub fn create_route(subsonic_routes: Option<Route>) -> Route {
             "/songs/:id/cover",
             post(handlers::library::update_song_cover),
         )
-        .at("/profile", post(handlers::user::update_profile));
+        .at(
+            "/radio-stations",
+            get(handlers::radio::get_stations).post(handlers::radio::create_station),
+        )
+        .at(
+            "/radio-stations/:id",
+            post(handlers::radio::update_station).delete(handlers::radio::delete_station),
+        )
+        .at(
+            "/smart-playlists",
+            post(handlers::playlists::create_smart_playlist),
+        )
+        .at(
+            "/smart-playlists/:id",
+            get(handlers::playlists::get_smart_playlist)
+                .post(handlers::playlists::update_smart_playlist),
+        )
+        .at(
+            "/playlists/import",
+            post(handlers::playlists::import_playlist),
+        )
+        .at(
+            "/playlists/:id/users",
+            get(handlers::playlists::get_playlist_users)
+                .put(handlers::playlists::update_playlist_users),
+        )
+        .at(
+            "/playlists/:id/export",
+            get(handlers::playlists::export_playlist),
+        )
+        .at("/profile", post(handlers::user::update_profile))
+        .at(
+            "/api-keys",
+            get(handlers::api_keys::get_api_keys).post(handlers::api_keys::create_api_key),
+        )
+        .at("/api-keys/:id", delete(handlers::api_keys::delete_api_key))
+        .at("/connections", get(handlers::connections::get_connections))
+        .at(
+            "/connections/:service",
+            put(handlers::connections::link_connection)
+                .delete(handlers::connections::unlink_connection),
+        );
 
     if let Some(subsonic_routes) = subsonic_routes {
         auth_routes = auth_routes.nest("/", subsonic_routes);
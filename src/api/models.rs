@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
@@ -40,3 +41,115 @@ pub struct UpdateFolderRequest {
     pub path: Option<String>,
     pub name: Option<String>,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateFolderUsersRequest {
+    pub usernames: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConnectionResponse {
+    pub service: String,
+    pub connected: bool,
+    pub account_name: Option<String>,
+    pub pending: i64,
+    /// False when the server lacks the configuration needed to link this service.
+    pub available: bool,
+}
+
+/// ListenBrainz links with a user `token`; Last.fm with `username` and `password`.
+#[derive(Debug, Deserialize)]
+pub struct LinkConnectionRequest {
+    pub token: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RadioStationRequest {
+    pub name: Option<String>,
+    pub stream_url: Option<String>,
+    pub home_page_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RadioStationResponse {
+    pub id: i32,
+    pub name: String,
+    pub stream_url: String,
+    pub home_page_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiKeyRequest {
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeyResponse {
+    pub id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub last_client: Option<String>,
+}
+
+/// Returned once on creation; the key can't be looked up again.
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    pub key: String,
+}
+
+/// `rules` is a smart playlist rule set, see `service::smart_playlists::SmartRules`.
+#[derive(Debug, Deserialize)]
+pub struct SmartPlaylistRequest {
+    pub name: Option<String>,
+    pub comment: Option<String>,
+    pub public: Option<bool>,
+    pub rules: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SmartPlaylistResponse {
+    pub id: i32,
+    pub name: String,
+    pub comment: Option<String>,
+    pub owner: String,
+    pub public: bool,
+    pub rules: serde_json::Value,
+}
+
+/// Users besides the owner who may edit a playlist's songs.
+#[derive(Debug, Deserialize)]
+pub struct PlaylistUsersRequest {
+    pub usernames: Vec<String>,
+}
+
+/// `format` is `m3u8` (default), `pls` or `xspf`; `paths` is `relative` (to the
+/// music folder, the default) or `url` for stream URLs, which authenticate
+/// with `apiKey`, one of the user's API keys.
+#[derive(Debug, Deserialize)]
+pub struct ExportPlaylistQuery {
+    pub format: Option<String>,
+    pub paths: Option<String>,
+    #[serde(rename = "apiKey")]
+    pub api_key: Option<String>,
+}
+
+/// The format is detected from the file when `format` is not given.
+#[derive(Debug, Deserialize)]
+pub struct ImportPlaylistQuery {
+    pub format: Option<String>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportPlaylistResponse {
+    pub id: i32,
+    pub name: String,
+    pub song_count: usize,
+    /// Entries of the file that matched no song.
+    pub unresolved: Vec<String>,
+}
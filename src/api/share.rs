@@ -0,0 +1,264 @@
+//! Public, unauthenticated pages for Subsonic shares (`/share/:token`).
+
+use crate::config::Config;
+use crate::models::child::ChildWithMetadata;
+use crate::models::queries::FolderPathInfo;
+use crate::models::{child, music_folder, share};
+use crate::scanner::utils::get_cover_cache_dir;
+use crate::service::Service;
+use chrono::Utc;
+use path_clean::PathClean;
+use poem::{
+    get, handler,
+    http::StatusCode,
+    web::{Data, Html, Path, StaticFileRequest},
+    IntoResponse, Response, Route,
+};
+use sea_orm::{DatabaseConnection, EntityTrait, QuerySelect};
+use std::sync::Arc;
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn message_page(status: StatusCode, message: &str) -> Response {
+    Html(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Miko</title></head>\
+         <body style=\"font-family:sans-serif;text-align:center;margin-top:4em\">\
+         <p>{}</p></body></html>",
+        escape(message)
+    ))
+    .with_status(status)
+    .into_response()
+}
+
+/// The share behind `token` if its link still works, or the page to show instead.
+async fn active_share(service: &Service, token: &str) -> Result<share::Model, Response> {
+    match service.get_share_by_token(token).await {
+        Ok(Some(s)) if s.is_active(Utc::now()) => Ok(s),
+        Ok(Some(_)) => Err(message_page(
+            StatusCode::GONE,
+            "This share has expired or was revoked.",
+        )),
+        Ok(None) => Err(message_page(StatusCode::NOT_FOUND, "Share not found.")),
+        Err(e) => {
+            log::error!("Failed to load share: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+/// A song that belongs to `share`, or the response to send instead.
+async fn shared_song(
+    service: &Service,
+    share: &share::Model,
+    song_id: &str,
+) -> Result<child::Model, Response> {
+    match service.get_share_song(share, song_id).await {
+        Ok(Some(song)) => Ok(song),
+        Ok(None) => Err(StatusCode::NOT_FOUND.into_response()),
+        Err(e) => {
+            log::error!("Failed to load share entries: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+fn format_duration(secs: i32) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+fn render_page(token: &str, share: &share::Model, songs: &[ChildWithMetadata]) -> String {
+    let title = share
+        .description
+        .as_deref()
+        .filter(|d| !d.trim().is_empty())
+        .unwrap_or("Shared music");
+    let cover = songs
+        .iter()
+        .find(|s| s.album_id.is_some())
+        .map(|s| {
+            format!(
+                "<img src=\"/share/{}/cover/{}\" alt=\"\" onerror=\"this.remove()\">",
+                token, s.id
+            )
+        })
+        .unwrap_or_default();
+
+    let rows: String = songs
+        .iter()
+        .map(|s| {
+            let artist = s
+                .artists
+                .iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "<li data-src=\"/share/{}/stream/{}\"><span>{}<small>{}</small></span><time>{}</time></li>",
+                token,
+                s.id,
+                escape(&s.title),
+                escape(&artist),
+                format_duration(s.duration)
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{title}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 40em; margin: 2em auto; padding: 0 1em; color: #111; }}
+img {{ width: 12em; height: 12em; object-fit: cover; border-radius: 1em; }}
+h1 {{ font-size: 1.5em; }}
+audio {{ width: 100%; margin: 1em 0; }}
+ol {{ padding: 0; list-style: none; }}
+li {{ display: flex; justify-content: space-between; padding: .6em .8em; border-radius: .5em; cursor: pointer; }}
+li:hover, li.playing {{ background: #fff1e6; }}
+small {{ display: block; color: #666; }}
+time {{ color: #666; }}
+</style>
+</head>
+<body>
+{cover}
+<h1>{title}</h1>
+<p>Shared by {owner}</p>
+<audio controls preload="none"></audio>
+<ol>{rows}</ol>
+<script>
+const audio = document.querySelector('audio');
+const items = [...document.querySelectorAll('li')];
+let current = -1;
+function play(i) {{
+  if (i < 0 || i >= items.length) return;
+  items.forEach((li, j) => li.classList.toggle('playing', j === i));
+  current = i;
+  audio.src = items[i].dataset.src;
+  audio.play();
+}}
+items.forEach((li, i) => li.addEventListener('click', () => play(i)));
+audio.addEventListener('ended', () => play(current + 1));
+</script>
+</body>
+</html>
+"#,
+        title = escape(title),
+        cover = cover,
+        owner = escape(&share.username),
+        rows = rows,
+    )
+}
+
+#[handler]
+pub async fn share_page(
+    Path(token): Path<String>,
+    service: Data<&Arc<Service>>,
+) -> impl IntoResponse {
+    let share = match active_share(&service, &token).await {
+        Ok(s) => s,
+        Err(r) => return r,
+    };
+
+    let songs = match service.get_share_songs(&share).await {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("Failed to load share entries: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    if let Err(e) = service.record_share_visit(share.id).await {
+        log::warn!("Failed to record share visit: {}", e);
+    }
+
+    Html(render_page(&token, &share, &songs)).into_response()
+}
+
+#[handler]
+pub async fn share_stream(
+    Path((token, song_id)): Path<(String, String)>,
+    service: Data<&Arc<Service>>,
+    db: Data<&DatabaseConnection>,
+    file_req: StaticFileRequest,
+) -> impl IntoResponse {
+    let share = match active_share(&service, &token).await {
+        Ok(s) => s,
+        Err(r) => return r,
+    };
+    let song = match shared_song(&service, &share, &song_id).await {
+        Ok(s) => s,
+        Err(r) => return r,
+    };
+    let folder = music_folder::Entity::find_by_id(song.music_folder_id)
+        .select_only()
+        .column(music_folder::Column::Path)
+        .into_model::<FolderPathInfo>()
+        .one(*db)
+        .await;
+    let root = match folder {
+        Ok(Some(f)) => std::path::Path::new(&f.path).clean(),
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    let path = std::path::Path::new(&song.path).clean();
+    if !path.starts_with(&root) {
+        log::error!(
+            "Security: Blocked shared stream outside root. ID: {}, Path: {:?}, Root: {:?}",
+            song_id,
+            path,
+            root
+        );
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    match file_req.create_response(&path, false, false) {
+        Ok(resp) => resp.into_response(),
+        Err(e) => {
+            log::error!("Static file error: {:?}", e);
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+}
+
+#[handler]
+pub async fn share_cover(
+    Path((token, song_id)): Path<(String, String)>,
+    service: Data<&Arc<Service>>,
+    config: Data<&Arc<Config>>,
+    file_req: StaticFileRequest,
+) -> impl IntoResponse {
+    let share = match active_share(&service, &token).await {
+        Ok(s) => s,
+        Err(r) => return r,
+    };
+    let song = match shared_song(&service, &share, &song_id).await {
+        Ok(s) => s,
+        Err(r) => return r,
+    };
+
+    let Some(album_id) = song.album_id else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    // Album ids are md5 hex digests, so the name cannot escape the cache dir.
+    let path = get_cover_cache_dir(&config).join(format!("al-{}", album_id));
+    match file_req.create_response(&path, false, false) {
+        Ok(resp) => resp.into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+pub fn create_route() -> Route {
+    Route::new()
+        .at("/:token", get(share_page))
+        .at("/:token/stream/:song_id", get(share_stream))
+        .at("/:token/cover/:song_id", get(share_cover))
+}
@@ -8,6 +8,12 @@ pub struct Config {
     pub server: ServerConfig,
     pub database: DatabaseConfig,
     pub subsonic: SubsonicConfig,
+    pub scanner: ScannerConfig,
+    pub transcode: TranscodeConfig,
+    pub metadata: MetadataConfig,
+    pub scrobble: ScrobbleConfig,
+    pub podcast: PodcastConfig,
+    pub jukebox: JukeboxConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -15,6 +21,9 @@ pub struct ServerConfig {
     pub port: u16,
     pub jwt_secret: String,
     pub password_secret: String,
+    /// Public URL of the server (e.g. `https://music.example.com`) used in share
+    /// links; derived from the request when unset.
+    pub base_url: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -26,6 +35,116 @@ pub struct DatabaseConfig {
 pub struct SubsonicConfig {
     pub data_dir: String,
     pub ignored_articles: String,
+    /// Ordered cover art sources: filename globs (e.g. `cover.*`) or `embedded`.
+    pub cover_art_priority: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ScannerConfig {
+    /// Watch music folders for changes and rescan only the affected paths.
+    pub watch: bool,
+    /// Quiet period after the last filesystem event before changes are applied.
+    pub watch_debounce_ms: u64,
+    /// Schedule for incremental scans.
+    pub schedule: Option<CronSchedule>,
+    /// Schedule for full scans.
+    pub full_schedule: Option<CronSchedule>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TranscodeConfig {
+    /// Path to the ffmpeg-compatible encoder binary.
+    pub ffmpeg_path: String,
+    /// Ordered profile table; the first profile matching a source suffix wins.
+    pub profiles: Vec<TranscodeProfile>,
+    /// ReplayGain applied to transcoded streams.
+    pub replay_gain: ReplayGainMode,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayGainMode {
+    #[default]
+    Off,
+    /// Track gain, falling back to album gain.
+    Track,
+    /// Album gain, falling back to track gain.
+    Album,
+}
+
+impl std::str::FromStr for ReplayGainMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "off" => Ok(Self::Off),
+            "track" => Ok(Self::Track),
+            "album" => Ok(Self::Album),
+            other => anyhow::bail!("Invalid ReplayGain mode: {}", other),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TranscodeProfile {
+    /// Source suffix (e.g. `flac`) or `*` to match any file.
+    pub source: String,
+    /// Target suffix (e.g. `opus`, `mp3`).
+    pub target: String,
+    /// Target bit rate in kbps.
+    pub bit_rate: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MetadataConfig {
+    /// Ordered provider names (`local`, `musicbrainz`); earlier providers win per field.
+    pub providers: Vec<String>,
+    /// How long fetched artist/album info is kept before it is looked up again.
+    pub cache_ttl_hours: i64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ScrobbleConfig {
+    /// ListenBrainz API root.
+    pub listenbrainz_url: String,
+    /// Last.fm-compatible API endpoint (Last.fm, Libre.fm, ...).
+    pub lastfm_url: String,
+    /// API account used to sign Last.fm requests; Last.fm linking is disabled when empty.
+    pub lastfm_api_key: String,
+    pub lastfm_api_secret: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PodcastConfig {
+    /// Base that relative feed URLs are resolved against; a `file://` base
+    /// lets feeds and enclosures be read from local files.
+    pub feed_base_url: Option<String>,
+    /// Schedule for refreshing all channels.
+    pub refresh_schedule: Option<CronSchedule>,
+    /// Downloaded episodes kept per channel; `0` keeps everything.
+    pub retention: usize,
+    /// Download new episodes found by a refresh.
+    pub auto_download: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct JukeboxConfig {
+    /// Allow `jukeboxControl` to play audio on the server.
+    pub enabled: bool,
+    /// Command that receives raw PCM (16-bit little-endian, 44.1 kHz, stereo)
+    /// on stdin, e.g. `aplay -q -f cd`. When unset, the encoder plays to `device`.
+    pub command: Option<String>,
+    /// Encoder output as `format:device`, e.g. `alsa:default` or `pulse:default`.
+    pub device: String,
+}
+
+impl TranscodeConfig {
+    pub fn profile_for(&self, suffix: &str) -> Option<&TranscodeProfile> {
+        self.profiles
+            .iter()
+            .find(|p| p.source.eq_ignore_ascii_case(suffix))
+            .or_else(|| self.profiles.iter().find(|p| p.source == "*"))
+    }
 }
 
 impl Config {
@@ -39,11 +158,29 @@ impl Config {
         let read_path =
             |key: &str, default: Option<&str>| -> String { norm_path(&read_val(key, default)) };
 
+        let read_schedule =
+            |key: &str, default: Option<&str>| -> Result<Option<CronSchedule>, anyhow::Error> {
+                let expr = read_val(key, default).trim().to_string();
+                if expr.is_empty() {
+                    return Ok(None);
+                }
+                let schedule = CronSchedule::parse(&expr)
+                    .map_err(|e| anyhow::anyhow!("Invalid {}: {}", key, e))?;
+                Ok(Some(schedule))
+            };
+
         Ok(Config {
             server: ServerConfig {
                 port: read_val("PORT", Some("8081")).parse()?,
                 jwt_secret: read_val("JWT_SECRET", None),
                 password_secret: read_val("PASSWORD_SECRET", None),
+                base_url: Some(
+                    read_val("BASE_URL", None)
+                        .trim()
+                        .trim_end_matches('/')
+                        .to_string(),
+                )
+                .filter(|s| !s.is_empty()),
             },
             database: DatabaseConfig {
                 url: normalize_database_url(&read_val("DATABASE_URL", None)),
@@ -54,6 +191,76 @@ impl Config {
                     "SUBSONIC_IGNORED_ARTICLES",
                     Some("The El La Los Las Le Les"),
                 ),
+                cover_art_priority: read_val(
+                    "SUBSONIC_COVER_ART_PRIORITY",
+                    Some("cover.*,folder.*,front.*,embedded"),
+                )
+                .split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            },
+            scanner: ScannerConfig {
+                watch: matches!(
+                    read_val("SCAN_WATCH", Some("false"))
+                        .to_lowercase()
+                        .as_str(),
+                    "1" | "true" | "yes"
+                ),
+                watch_debounce_ms: read_val("SCAN_WATCH_DEBOUNCE_MS", Some("2000")).parse()?,
+                schedule: read_schedule("SCAN_SCHEDULE", None)?,
+                full_schedule: read_schedule("SCAN_FULL_SCHEDULE", None)?,
+            },
+            transcode: TranscodeConfig {
+                ffmpeg_path: read_val("TRANSCODE_FFMPEG_PATH", Some("ffmpeg")),
+                profiles: parse_transcode_profiles(&read_val(
+                    "TRANSCODE_PROFILES",
+                    Some("flac>opus:128,*>mp3:320"),
+                ))?,
+                replay_gain: read_val("TRANSCODE_REPLAY_GAIN", Some("off")).parse()?,
+            },
+            metadata: MetadataConfig {
+                providers: read_val("METADATA_PROVIDERS", Some("local,musicbrainz"))
+                    .split(',')
+                    .map(|s| s.trim().to_lowercase())
+                    .filter(|s| !s.is_empty())
+                    .collect(),
+                cache_ttl_hours: read_val("METADATA_CACHE_TTL_HOURS", Some("168")).parse()?,
+            },
+            scrobble: ScrobbleConfig {
+                listenbrainz_url: read_val(
+                    "SCROBBLE_LISTENBRAINZ_URL",
+                    Some("https://api.listenbrainz.org"),
+                ),
+                lastfm_url: read_val(
+                    "SCROBBLE_LASTFM_URL",
+                    Some("https://ws.audioscrobbler.com/2.0/"),
+                ),
+                lastfm_api_key: read_val("SCROBBLE_LASTFM_API_KEY", None),
+                lastfm_api_secret: read_val("SCROBBLE_LASTFM_API_SECRET", None),
+            },
+            podcast: PodcastConfig {
+                feed_base_url: Some(read_val("PODCAST_FEED_BASE_URL", None).trim().to_string())
+                    .filter(|s| !s.is_empty()),
+                refresh_schedule: read_schedule("PODCAST_REFRESH_SCHEDULE", Some("0 * * * *"))?,
+                retention: read_val("PODCAST_RETENTION", Some("10")).parse()?,
+                auto_download: matches!(
+                    read_val("PODCAST_AUTO_DOWNLOAD", Some("false"))
+                        .to_lowercase()
+                        .as_str(),
+                    "1" | "true" | "yes"
+                ),
+            },
+            jukebox: JukeboxConfig {
+                enabled: matches!(
+                    read_val("JUKEBOX_ENABLED", Some("false"))
+                        .to_lowercase()
+                        .as_str(),
+                    "1" | "true" | "yes"
+                ),
+                command: Some(read_val("JUKEBOX_COMMAND", None).trim().to_string())
+                    .filter(|s| !s.is_empty()),
+                device: read_val("JUKEBOX_DEVICE", Some("alsa:default")),
             },
         })
     }
@@ -72,6 +279,90 @@ impl Config {
     }
 }
 
+/// A cron schedule parsed from configuration, see [`parse_cron_schedule`].
+#[derive(Debug, Clone)]
+pub struct CronSchedule(cron::Schedule);
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, anyhow::Error> {
+        parse_cron_schedule(expr).map(Self)
+    }
+}
+
+impl std::ops::Deref for CronSchedule {
+    type Target = cron::Schedule;
+
+    fn deref(&self) -> &cron::Schedule {
+        &self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for CronSchedule {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let expr = String::deserialize(deserializer)?;
+        Self::parse(&expr).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Parse a cron expression. Standard five-field expressions (`0 3 * * *`, with
+/// Sunday as `0` or `7`) are accepted as well as the six/seven-field form with
+/// seconds (and years) understood by the `cron` crate.
+pub fn parse_cron_schedule(expr: &str) -> Result<cron::Schedule, anyhow::Error> {
+    let fields: Vec<&str> = expr.split_whitespace().collect();
+    let expr = if fields.len() == 5 {
+        format!("0 {} {}", fields[..4].join(" "), weekday_names(fields[4]))
+    } else {
+        fields.join(" ")
+    };
+    Ok(expr.parse::<cron::Schedule>()?)
+}
+
+/// Rewrites numeric weekdays in a standard cron field to names, since the
+/// `cron` crate numbers them from 1 (Sunday) rather than 0.
+fn weekday_names(field: &str) -> String {
+    const NAMES: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+    let name = |v: &str| match v.parse::<usize>() {
+        Ok(n) if n < NAMES.len() => NAMES[n].to_string(),
+        _ => v.to_string(),
+    };
+    field
+        .split(',')
+        .map(|part| {
+            let (range, step) = match part.split_once('/') {
+                Some((r, s)) => (r, Some(s)),
+                None => (part, None),
+            };
+            let range = range.split('-').map(name).collect::<Vec<_>>().join("-");
+            match step {
+                Some(s) => format!("{}/{}", range, s),
+                None => range,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parse a profile table of the form `flac>opus:128,*>mp3:320`.
+pub fn parse_transcode_profiles(spec: &str) -> Result<Vec<TranscodeProfile>, anyhow::Error> {
+    spec.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let (source, rest) = entry
+                .split_once('>')
+                .ok_or_else(|| anyhow::anyhow!("Invalid transcode profile: {}", entry))?;
+            let (target, bit_rate) = rest
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Invalid transcode profile: {}", entry))?;
+            Ok(TranscodeProfile {
+                source: source.trim().to_lowercase(),
+                target: target.trim().to_lowercase(),
+                bit_rate: bit_rate.trim().parse()?,
+            })
+        })
+        .collect()
+}
+
 /// Normalize a sqlite:// URL so that $HOME/~ expansion produces a valid path
 /// on both Unix (sqlite:///absolute/path) and Windows (sqlite://C:/path).
 fn normalize_database_url(url: &str) -> String {
@@ -5,3 +5,6 @@ pub mod models;
 pub mod scanner;
 pub mod service;
 pub mod subsonic;
+
+#[cfg(test)]
+pub mod test_support;
@@ -7,6 +7,10 @@ use miko::config::Config;
 use miko::crypto;
 use miko::models::user;
 use miko::scanner::Scanner;
+use miko::service::jukebox::JukeboxService;
+use miko::service::metadata::MetadataService;
+use miko::service::podcast::PodcastService;
+use miko::service::scrobble::ScrobbleForwarder;
 use miko::service::Service;
 use miko::{api, subsonic};
 use poem::{
@@ -119,8 +123,29 @@ async fn main() -> Result<(), anyhow::Error> {
         .await
         .expect("Failed to initialize default user");
 
+    miko::scanner::search_index::ensure(&db)
+        .await
+        .expect("Failed to build search index");
+
     let scanner = Arc::new(Scanner::new(db.clone(), config.clone()));
+    miko::scanner::schedule::start(
+        scanner.as_ref().clone(),
+        config.scanner.schedule.clone(),
+        config.scanner.full_schedule.clone(),
+    );
+    if config.scanner.watch {
+        miko::scanner::watcher::start(
+            scanner.as_ref().clone(),
+            std::time::Duration::from_millis(config.scanner.watch_debounce_ms),
+        )?;
+    }
     let service = Arc::new(Service::new(db.clone()));
+    let metadata = Arc::new(MetadataService::new(db.clone(), &config, mb_client.clone()));
+    let scrobble = Arc::new(ScrobbleForwarder::new(db.clone(), &config)?);
+    scrobble.start();
+    let podcasts = Arc::new(PodcastService::new(db.clone(), &config)?);
+    podcasts.start();
+    let jukebox = Arc::new(JukeboxService::new(db.clone(), &config));
     scanner.update_total_count().await;
     let addr = format!("0.0.0.0:{}", config.server.port);
 
@@ -132,12 +157,17 @@ async fn main() -> Result<(), anyhow::Error> {
             "/api",
             api::create_route(Some(subsonic::create_api_route())),
         )
+        .nest("/share", api::share::create_route())
         .nest("/", api::web::create_route())
         .data(db)
         .data(config)
         .data(scanner)
         .data(service)
         .data(mb_client)
+        .data(metadata)
+        .data(scrobble)
+        .data(podcasts)
+        .data(jukebox)
         .with(Tracing)
         .with(
             Cors::new()
@@ -3,6 +3,7 @@ use sea_orm::FromQueryResult;
 use serde::{Deserialize, Serialize};
 
 use super::artist::{parse_artists_field, ArtistIdName};
+use super::child::split_values;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "albums")]
@@ -15,6 +16,19 @@ pub struct Model {
     pub average_rating: f64,
     #[sea_orm(default_value = 0)]
     pub year: i32,
+    pub sort_name: Option<String>,
+    /// MusicBrainz release id.
+    pub music_brainz_id: Option<String>,
+    /// The album artist tag as written.
+    pub display_artist: Option<String>,
+    /// "; "-joined, e.g. "album; soundtrack".
+    pub release_types: Option<String>,
+    /// "; "-joined.
+    pub record_labels: Option<String>,
+    /// "YYYY", "YYYY-MM" or "YYYY-MM-DD".
+    pub original_release_date: Option<String>,
+    #[sea_orm(default_value = false)]
+    pub is_compilation: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -48,7 +62,41 @@ pub struct AlbumWithStats {
     pub song_count: i64,
     pub duration: i64,
     pub play_count: i64,
+    pub last_played: Option<chrono::DateTime<chrono::Utc>>,
     pub artists: Vec<ArtistIdName>,
+    pub sort_name: Option<String>,
+    pub music_brainz_id: Option<String>,
+    pub display_artist: Option<String>,
+    pub release_types: Vec<String>,
+    pub record_labels: Vec<String>,
+    pub original_release_date: Option<String>,
+    pub is_compilation: bool,
+    /// Moods of the album's songs.
+    pub moods: Vec<String>,
+    /// "explicit" if any song is, otherwise "clean" if any song is.
+    pub explicit_status: Option<String>,
+    /// Subtitles by disc number.
+    pub disc_titles: Vec<(i32, String)>,
+}
+
+/// Parses `disc[:]title` entries joined by `[;]`.
+fn parse_disc_titles_field(
+    res: &sea_orm::QueryResult,
+    pre: &str,
+) -> Result<Vec<(i32, String)>, sea_orm::DbErr> {
+    let raw: Option<String> = res.try_get(pre, "disc_titles")?;
+    let mut titles: Vec<(i32, String)> = raw
+        .as_deref()
+        .unwrap_or_default()
+        .split("[;]")
+        .filter_map(|entry| {
+            let (disc, title) = entry.split_once("[:]")?;
+            Some((disc.parse().ok()?, title.to_string()))
+        })
+        .collect();
+    titles.sort();
+    titles.dedup_by_key(|(disc, _)| *disc);
+    Ok(titles)
 }
 
 impl FromQueryResult for AlbumWithStats {
@@ -67,7 +115,30 @@ impl FromQueryResult for AlbumWithStats {
             song_count: res.try_get(pre, "song_count")?,
             duration: res.try_get(pre, "duration")?,
             play_count: res.try_get(pre, "play_count")?,
+            last_played: res.try_get(pre, "last_played")?,
             artists,
+            sort_name: res.try_get(pre, "sort_name")?,
+            music_brainz_id: res.try_get(pre, "music_brainz_id")?,
+            display_artist: res.try_get(pre, "display_artist")?,
+            release_types: split_values(
+                res.try_get::<Option<String>>(pre, "release_types")?
+                    .as_deref(),
+            ),
+            record_labels: split_values(
+                res.try_get::<Option<String>>(pre, "record_labels")?
+                    .as_deref(),
+            ),
+            original_release_date: res.try_get(pre, "original_release_date")?,
+            is_compilation: res.try_get(pre, "is_compilation")?,
+            moods: {
+                let mut moods =
+                    split_values(res.try_get::<Option<String>>(pre, "moods")?.as_deref());
+                moods.sort();
+                moods.dedup();
+                moods
+            },
+            explicit_status: res.try_get(pre, "explicit_status")?,
+            disc_titles: parse_disc_titles_field(res, pre)?,
         })
     }
 }
@@ -23,8 +23,6 @@ pub struct Model {
     pub size: i64,
     pub content_type: Option<String>,
     pub suffix: Option<String>,
-    pub transcoded_content_type: Option<String>,
-    pub transcoded_suffix: Option<String>,
     #[sea_orm(default_value = 0)]
     pub duration: i32,
     #[sea_orm(default_value = 0)]
@@ -47,6 +45,29 @@ pub struct Model {
     pub music_folder_id: i32,
     #[sea_orm(default_value = "music")]
     pub r#type: String,
+    pub bpm: Option<i32>,
+    pub comment: Option<String>,
+    pub sort_name: Option<String>,
+    /// MusicBrainz recording id.
+    pub music_brainz_id: Option<String>,
+    /// The artist tag as written, e.g. "A feat. B".
+    pub display_artist: Option<String>,
+    /// "explicit" or "clean".
+    pub explicit_status: Option<String>,
+    /// "; "-joined.
+    pub moods: Option<String>,
+    pub disc_subtitle: Option<String>,
+    pub replay_gain_track_gain: Option<f64>,
+    pub replay_gain_track_peak: Option<f64>,
+    pub replay_gain_album_gain: Option<f64>,
+    pub replay_gain_album_peak: Option<f64>,
+    /// Ogg Opus header output gain in dB, already applied by decoders.
+    pub replay_gain_base_gain: Option<f64>,
+    pub channel_count: Option<i32>,
+    pub sampling_rate: Option<i32>,
+    pub bit_depth: Option<i32>,
+    /// Codec of the audio stream, e.g. "alac" for an m4a file.
+    pub codec: Option<String>,
     #[sea_orm(ignore)]
     pub bookmark_position: i64,
 }
@@ -57,6 +78,8 @@ pub enum Relation {
     SongArtist,
     #[sea_orm(has_many = "super::song_genre::Entity")]
     SongGenre,
+    #[sea_orm(has_many = "super::song_contributor::Entity")]
+    SongContributor,
     #[sea_orm(
         belongs_to = "super::album::Entity",
         from = "Column::AlbumId",
@@ -121,8 +144,6 @@ pub struct ChildWithMetadata {
     pub size: i64,
     pub content_type: Option<String>,
     pub suffix: Option<String>,
-    pub transcoded_content_type: Option<String>,
-    pub transcoded_suffix: Option<String>,
     pub duration: i32,
     pub bit_rate: i32,
     pub path: String,
@@ -138,6 +159,76 @@ pub struct ChildWithMetadata {
     pub r#type: String,
     pub artists: Vec<ArtistIdName>,
     pub album_artists: Vec<ArtistIdName>,
+    pub bpm: Option<i32>,
+    pub comment: Option<String>,
+    pub sort_name: Option<String>,
+    pub music_brainz_id: Option<String>,
+    pub display_artist: Option<String>,
+    pub explicit_status: Option<String>,
+    pub moods: Vec<String>,
+    pub replay_gain: ReplayGain,
+    pub channel_count: Option<i32>,
+    pub sampling_rate: Option<i32>,
+    pub bit_depth: Option<i32>,
+    pub codec: Option<String>,
+    pub contributors: Vec<Contributor>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplayGain {
+    pub track_gain: Option<f64>,
+    pub track_peak: Option<f64>,
+    pub album_gain: Option<f64>,
+    pub album_peak: Option<f64>,
+    pub base_gain: Option<f64>,
+}
+
+impl ReplayGain {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Contributor {
+    pub role: String,
+    pub sub_role: Option<String>,
+    pub artist: ArtistIdName,
+}
+
+/// Splits a "; "-joined multi-valued tag column.
+pub fn split_values(raw: Option<&str>) -> Vec<String> {
+    raw.unwrap_or_default()
+        .split(';')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses `role[:]sub_role[:]artist_id[:]artist_name` entries joined by `[;]`.
+fn parse_contributors_field(
+    res: &sea_orm::QueryResult,
+    pre: &str,
+) -> Result<Vec<Contributor>, sea_orm::DbErr> {
+    let raw: Option<String> = res.try_get(pre, "contributors")?;
+    Ok(raw
+        .as_deref()
+        .unwrap_or_default()
+        .split("[;]")
+        .filter_map(|entry| {
+            let mut parts = entry.splitn(4, "[:]");
+            let role = parts.next()?.to_string();
+            let sub_role = parts.next()?;
+            let id = parts.next()?.to_string();
+            let name = parts.next()?.to_string();
+            Some(Contributor {
+                role,
+                sub_role: (!sub_role.is_empty()).then(|| sub_role.to_string()),
+                artist: ArtistIdName { id, name },
+            })
+        })
+        .collect())
 }
 
 impl FromQueryResult for ChildWithMetadata {
@@ -159,8 +250,6 @@ impl FromQueryResult for ChildWithMetadata {
             size: res.try_get(pre, "size")?,
             content_type: res.try_get(pre, "content_type")?,
             suffix: res.try_get(pre, "suffix")?,
-            transcoded_content_type: res.try_get(pre, "transcoded_content_type")?,
-            transcoded_suffix: res.try_get(pre, "transcoded_suffix")?,
             duration: res.try_get(pre, "duration")?,
             bit_rate: res.try_get(pre, "bit_rate")?,
             path: res.try_get(pre, "path")?,
@@ -176,6 +265,25 @@ impl FromQueryResult for ChildWithMetadata {
             r#type: res.try_get(pre, "type")?,
             artists,
             album_artists,
+            bpm: res.try_get(pre, "bpm")?,
+            comment: res.try_get(pre, "comment")?,
+            sort_name: res.try_get(pre, "sort_name")?,
+            music_brainz_id: res.try_get(pre, "music_brainz_id")?,
+            display_artist: res.try_get(pre, "display_artist")?,
+            explicit_status: res.try_get(pre, "explicit_status")?,
+            moods: split_values(res.try_get::<Option<String>>(pre, "moods")?.as_deref()),
+            replay_gain: ReplayGain {
+                track_gain: res.try_get(pre, "replay_gain_track_gain")?,
+                track_peak: res.try_get(pre, "replay_gain_track_peak")?,
+                album_gain: res.try_get(pre, "replay_gain_album_gain")?,
+                album_peak: res.try_get(pre, "replay_gain_album_peak")?,
+                base_gain: res.try_get(pre, "replay_gain_base_gain")?,
+            },
+            channel_count: res.try_get(pre, "channel_count")?,
+            sampling_rate: res.try_get(pre, "sampling_rate")?,
+            bit_depth: res.try_get(pre, "bit_depth")?,
+            codec: res.try_get(pre, "codec")?,
+            contributors: parse_contributors_field(res, pre)?,
         })
     }
 }
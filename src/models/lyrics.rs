@@ -4,9 +4,15 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "lyrics")]
 pub struct Model {
-    #[sea_orm(primary_key, auto_increment = false)]
+    #[sea_orm(primary_key)]
+    pub id: i32,
     pub song_id: String,
+    pub lang: String,
+    pub synced: bool,
     pub content: String,
+    /// Sidecar file name, or `None` for lyrics embedded in the audio file.
+    pub source: Option<String>,
+    pub source_modified: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,19 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "metadata_cache")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub item_type: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub item_id: String,
+    /// JSON-encoded `ArtistMetadata` / `AlbumMetadata`.
+    pub data: String,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
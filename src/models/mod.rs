@@ -1,21 +1,33 @@
 pub mod album;
 pub mod album_artist;
 pub mod album_genre;
+pub mod api_key;
 pub mod artist;
 pub mod bookmark;
 pub mod child;
 pub mod genre;
+pub mod internet_radio_station;
 pub mod lyrics;
+pub mod metadata_cache;
 pub mod music_folder;
 pub mod now_playing;
+pub mod play_history;
 pub mod play_queue;
 pub mod play_queue_song;
 pub mod playlist;
 pub mod playlist_song;
+pub mod playlist_user;
+pub mod podcast_channel;
+pub mod podcast_episode;
 pub mod queries;
+pub mod scrobble_queue;
+pub mod share;
+pub mod share_entry;
 pub mod song_artist;
+pub mod song_contributor;
 pub mod song_genre;
 pub mod user;
+pub mod user_connection;
 pub mod user_music_folder;
 pub mod user_rating;
 pub mod user_star;
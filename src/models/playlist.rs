@@ -12,12 +12,24 @@ pub struct Model {
     pub public: bool,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    /// JSON rules of a smart playlist, see `service::smart_playlists`.
+    pub rules: Option<String>,
+    /// Playlist file in a music folder this playlist is synced from by the scanner.
+    pub source_path: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::playlist_song::Entity")]
     PlaylistSong,
+    #[sea_orm(has_many = "super::playlist_user::Entity")]
+    PlaylistUser,
+}
+
+impl Related<super::playlist_user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PlaylistUser.def()
+    }
 }
 
 impl Related<super::playlist_song::Entity> for Entity {
@@ -28,7 +40,7 @@ impl Related<super::playlist_song::Entity> for Entity {
 
 impl ActiveModelBehavior for ActiveModel {}
 
-#[derive(Debug, sea_orm::FromQueryResult, Clone)]
+#[derive(Debug, Clone)]
 pub struct PlaylistWithStats {
     pub id: i32,
     pub name: String,
@@ -37,6 +49,39 @@ pub struct PlaylistWithStats {
     pub public: bool,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
+    pub rules: Option<String>,
+    /// Users besides the owner who may edit the songs.
+    pub allowed_users: Vec<String>,
     pub song_count: i64,
     pub duration: i64,
 }
+
+impl PlaylistWithStats {
+    /// Whether `username` may change the songs: the owner and allowed users
+    /// can, unless it's a smart playlist.
+    pub fn can_edit(&self, username: &str) -> bool {
+        self.rules.is_none()
+            && (self.owner == username || self.allowed_users.iter().any(|u| u == username))
+    }
+}
+
+impl sea_orm::FromQueryResult for PlaylistWithStats {
+    fn from_query_result(res: &sea_orm::QueryResult, pre: &str) -> Result<Self, DbErr> {
+        let allowed_users: Option<String> = res.try_get(pre, "allowed_users")?;
+        Ok(Self {
+            id: res.try_get(pre, "id")?,
+            name: res.try_get(pre, "name")?,
+            comment: res.try_get(pre, "comment")?,
+            owner: res.try_get(pre, "owner")?,
+            public: res.try_get(pre, "public")?,
+            created_at: res.try_get(pre, "created_at")?,
+            updated_at: res.try_get(pre, "updated_at")?,
+            rules: res.try_get(pre, "rules")?,
+            allowed_users: allowed_users
+                .map(|s| s.split(',').map(String::from).collect())
+                .unwrap_or_default(),
+            song_count: res.try_get(pre, "song_count")?,
+            duration: res.try_get(pre, "duration")?,
+        })
+    }
+}
@@ -8,8 +8,9 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub playlist_id: i32,
-    #[sea_orm(primary_key, auto_increment = false)]
     pub song_id: String,
+    /// Position in the playlist; a song may appear more than once.
+    #[sea_orm(primary_key, auto_increment = false)]
     pub index: i32,
 }
 
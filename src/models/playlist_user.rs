@@ -0,0 +1,41 @@
+use sea_orm::entity::prelude::*;
+
+/// A user other than the owner who may edit a playlist's songs.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "playlist_users")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub playlist_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub username: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::playlist::Entity",
+        from = "Column::PlaylistId",
+        to = "super::playlist::Column::Id"
+    )]
+    Playlist,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::Username",
+        to = "super::user::Column::Username"
+    )]
+    User,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Related<super::playlist::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Playlist.def()
+    }
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
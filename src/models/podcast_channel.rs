@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "podcast_channels")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    /// Feed URL as given by the user; may be relative to `PODCAST_FEED_BASE_URL`.
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    /// Subsonic channel status: `new`, `downloading`, `completed` or `error`.
+    pub status: String,
+    pub error_message: Option<String>,
+    pub created_at: DateTimeUtc,
+    pub last_refreshed_at: Option<DateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::podcast_episode::Entity")]
+    PodcastEpisode,
+}
+
+impl Related<super::podcast_episode::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PodcastEpisode.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,45 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "podcast_episodes")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub channel_id: i32,
+    /// Feed-provided identifier used to recognise the episode across refreshes.
+    pub guid: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub publish_date: Option<DateTimeUtc>,
+    pub enclosure_url: String,
+    pub content_type: Option<String>,
+    pub size: Option<i64>,
+    pub duration: Option<i32>,
+    /// Subsonic episode status: `new`, `downloading`, `completed`, `error` or
+    /// `deleted`.
+    pub status: String,
+    /// Downloaded file, set while the status is `completed`.
+    pub path: Option<String>,
+    /// When `path` was downloaded; retention keeps the latest downloads.
+    pub downloaded_at: Option<DateTimeUtc>,
+    pub error_message: Option<String>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::podcast_channel::Entity",
+        from = "Column::ChannelId",
+        to = "super::podcast_channel::Column::Id"
+    )]
+    PodcastChannel,
+}
+
+impl Related<super::podcast_channel::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PodcastChannel.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
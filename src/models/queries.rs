@@ -1,16 +1,18 @@
 use crate::models::{
     album, album_artist, album_genre, artist, child, lyrics, song_artist, user_rating, user_star,
 };
-use sea_orm::sea_query::Expr;
+use sea_orm::sea_query::{Expr, SimpleExpr};
 use sea_orm::{
     ColumnTrait, Condition, EntityTrait, FromQueryResult, Iterable, JoinType, QueryFilter,
-    QuerySelect, RelationTrait,
+    QueryOrder, QuerySelect, RelationTrait,
 };
 
 #[derive(Debug, FromQueryResult, Clone)]
 pub struct LyricsWithMetadata {
     pub title: String,
     pub artist: Option<String>,
+    pub lang: String,
+    pub synced: bool,
     pub content: String,
 }
 
@@ -18,6 +20,24 @@ pub struct LyricsWithMetadata {
 pub struct SongPathInfo {
     pub path: String,
     pub music_folder_id: i32,
+    pub suffix: Option<String>,
+    pub bit_rate: i32,
+    pub replay_gain_track_gain: Option<f64>,
+    pub replay_gain_track_peak: Option<f64>,
+    pub replay_gain_album_gain: Option<f64>,
+    pub replay_gain_album_peak: Option<f64>,
+}
+
+impl SongPathInfo {
+    pub fn replay_gain(&self) -> child::ReplayGain {
+        child::ReplayGain {
+            track_gain: self.replay_gain_track_gain,
+            track_peak: self.replay_gain_track_peak,
+            album_gain: self.replay_gain_album_gain,
+            album_peak: self.replay_gain_album_peak,
+            base_gain: None,
+        }
+    }
 }
 
 #[derive(sea_orm::FromQueryResult)]
@@ -25,6 +45,56 @@ pub struct FolderPathInfo {
     pub path: String,
 }
 
+/// Subquery selecting the music folder ids a user has been granted.
+const GRANTED_FOLDERS: &str = "SELECT music_folder_id FROM user_music_folders WHERE username = ?";
+
+/// Per-user play count of the song in `children`.
+const USER_PLAY_COUNT: &str =
+    "(SELECT COUNT(*) FROM play_history ph WHERE ph.username = ? AND ph.song_id = children.id)";
+
+/// Per-user last play time of the song in `children`.
+const USER_LAST_PLAYED: &str =
+    "(SELECT MAX(ph.played_at) FROM play_history ph WHERE ph.username = ? AND ph.song_id = children.id)";
+
+/// Restricts `music_folders` rows to the folders granted to `username`.
+pub fn folder_access_filter(username: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!("music_folders.id IN ({})", GRANTED_FOLDERS),
+        [username.to_string()],
+    )
+}
+
+/// Restricts `children` rows to the music folders granted to `username`.
+pub fn song_access_filter(username: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!("children.music_folder_id IN ({})", GRANTED_FOLDERS),
+        [username.to_string()],
+    )
+}
+
+/// Restricts `albums` rows to albums with at least one song in a granted folder.
+pub fn album_access_filter(username: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!(
+            "EXISTS (SELECT 1 FROM children c WHERE c.album_id = albums.id AND c.music_folder_id IN ({}))",
+            GRANTED_FOLDERS
+        ),
+        [username.to_string()],
+    )
+}
+
+/// Restricts `artists` rows to artists credited on a song or album in a granted folder.
+pub fn artist_access_filter(username: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!(
+            "(EXISTS (SELECT 1 FROM song_artists sa JOIN children c ON c.id = sa.song_id WHERE sa.artist_id = artists.id AND c.music_folder_id IN ({0})) \
+             OR EXISTS (SELECT 1 FROM album_artists aa JOIN children c ON c.album_id = aa.album_id WHERE aa.artist_id = artists.id AND c.music_folder_id IN ({0})))",
+            GRANTED_FOLDERS
+        ),
+        [username.to_string(), username.to_string()],
+    )
+}
+
 pub fn song_path_info_query() -> sea_orm::Select<child::Entity> {
     child::Entity::find()
         .filter(child::Column::IsDir.eq(false))
@@ -32,6 +102,12 @@ pub fn song_path_info_query() -> sea_orm::Select<child::Entity> {
         .select_only()
         .column(child::Column::Path)
         .column(child::Column::MusicFolderId)
+        .column(child::Column::Suffix)
+        .column(child::Column::BitRate)
+        .column(child::Column::ReplayGainTrackGain)
+        .column(child::Column::ReplayGainTrackPeak)
+        .column(child::Column::ReplayGainAlbumGain)
+        .column(child::Column::ReplayGainAlbumPeak)
 }
 
 pub fn lyrics_with_metadata_query() -> sea_orm::Select<lyrics::Entity> {
@@ -53,7 +129,8 @@ pub fn lyrics_with_metadata_query() -> sea_orm::Select<lyrics::Entity> {
                 .into(),
         )
         .column_as(Expr::cust("GROUP_CONCAT(DISTINCT artists.name)"), "artist")
-        .group_by(lyrics::Column::SongId)
+        .group_by(lyrics::Column::Id)
+        .order_by_asc(lyrics::Column::Id)
 }
 
 pub fn song_with_metadata_query(username: &str) -> sea_orm::Select<child::Entity> {
@@ -61,7 +138,17 @@ pub fn song_with_metadata_query(username: &str) -> sea_orm::Select<child::Entity
     let rating_user = username.to_string();
     child::Entity::find()
         .select_only()
-        .columns(child::Column::iter())
+        .columns(child::Column::iter().filter(|c| {
+            !matches!(c, child::Column::PlayCount | child::Column::LastPlayed)
+        }))
+        .column_as(
+            Expr::cust_with_values(USER_PLAY_COUNT, [username.to_string()]),
+            "play_count",
+        )
+        .column_as(
+            Expr::cust_with_values(USER_LAST_PLAYED, [username.to_string()]),
+            "last_played",
+        )
         .column_as(user_star::Column::StarredAt, "starred")
         .column_as(
             Expr::col((user_rating::Entity, user_rating::Column::Rating)).if_null(0),
@@ -71,6 +158,7 @@ pub fn song_with_metadata_query(username: &str) -> sea_orm::Select<child::Entity
         .column_as(Expr::cust("(SELECT GROUP_CONCAT(a.id || '[:]' || a.name) FROM album_artists aa JOIN artists a ON aa.artist_id = a.id WHERE aa.album_id = children.album_id)"), "album_artists")
         .column_as(Expr::cust("(SELECT GROUP_CONCAT(genre_name) FROM song_genres WHERE song_id = children.id)"), "genre")
         .column_as(Expr::cust("(SELECT name FROM albums WHERE id = children.album_id)"), "album")
+        .column_as(Expr::cust("(SELECT GROUP_CONCAT(sc.role || '[:]' || sc.sub_role || '[:]' || a.id || '[:]' || a.name, '[;]') FROM song_contributors sc JOIN artists a ON sc.artist_id = a.id WHERE sc.song_id = children.id)"), "contributors")
         .join_rev(
             JoinType::LeftJoin,
             user_star::Entity::belongs_to(child::Entity)
@@ -95,6 +183,7 @@ pub fn song_with_metadata_query(username: &str) -> sea_orm::Select<child::Entity
                 })
                 .into(),
         )
+        .filter(song_access_filter(username))
 }
 
 pub fn artist_with_stats_query(username: &str) -> sea_orm::Select<artist::Entity> {
@@ -108,7 +197,17 @@ pub fn artist_with_stats_query(username: &str) -> sea_orm::Select<artist::Entity
             Expr::col((user_rating::Entity, user_rating::Column::Rating)).if_null(0),
             "user_rating",
         )
-        .column_as(Expr::cust("(SELECT COUNT(DISTINCT album_id) FROM (SELECT album_id FROM children JOIN song_artists ON song_artists.song_id = children.id WHERE song_artists.artist_id = artists.id UNION SELECT album_id FROM album_artists WHERE album_artists.artist_id = artists.id))"), "album_count")
+        .column_as(
+            Expr::cust_with_values(
+                format!(
+                    "(SELECT COUNT(DISTINCT album_id) FROM (SELECT album_id FROM children JOIN song_artists ON song_artists.song_id = children.id WHERE song_artists.artist_id = artists.id AND children.music_folder_id IN ({0}) \
+                     UNION SELECT aa.album_id FROM album_artists aa WHERE aa.artist_id = artists.id AND EXISTS (SELECT 1 FROM children c WHERE c.album_id = aa.album_id AND c.music_folder_id IN ({0}))))",
+                    GRANTED_FOLDERS
+                ),
+                [username.to_string(), username.to_string()],
+            ),
+            "album_count",
+        )
         .join_rev(
             JoinType::LeftJoin,
             user_star::Entity::belongs_to(artist::Entity)
@@ -133,6 +232,7 @@ pub fn artist_with_stats_query(username: &str) -> sea_orm::Select<artist::Entity
                 })
                 .into(),
         )
+        .filter(artist_access_filter(username))
 }
 
 pub fn album_with_stats_query(username: &str) -> sea_orm::Select<album::Entity> {
@@ -152,8 +252,20 @@ pub fn album_with_stats_query(username: &str) -> sea_orm::Select<album::Entity>
         )
         .column_as(child::Column::Id.count(), "song_count")
         .column_as(Expr::cust("COALESCE(SUM(duration), 0)"), "duration")
-        .column_as(Expr::cust("COALESCE(SUM(play_count), 0)"), "play_count")
-        .column_as(child::Column::LastPlayed.max(), "last_played")
+        .column_as(
+            Expr::cust_with_values(
+                "(SELECT COUNT(*) FROM play_history ph JOIN children c ON c.id = ph.song_id WHERE ph.username = ? AND c.album_id = albums.id)",
+                [username.to_string()],
+            ),
+            "play_count",
+        )
+        .column_as(
+            Expr::cust_with_values(
+                "(SELECT MAX(ph.played_at) FROM play_history ph JOIN children c ON c.id = ph.song_id WHERE ph.username = ? AND c.album_id = albums.id)",
+                [username.to_string()],
+            ),
+            "last_played",
+        )
         .column_as(
             Expr::cust("GROUP_CONCAT(DISTINCT artists.id || '[:]' || artists.name)"),
             "artists",
@@ -162,6 +274,18 @@ pub fn album_with_stats_query(username: &str) -> sea_orm::Select<album::Entity>
             Expr::cust("GROUP_CONCAT(DISTINCT album_genres.genre_name)"),
             "genre",
         )
+        .column_as(Expr::cust("GROUP_CONCAT(children.moods, '; ')"), "moods")
+        .column_as(
+            Expr::cust(
+                "CASE WHEN MAX(children.explicit_status = 'explicit') THEN 'explicit' \
+                 WHEN MAX(children.explicit_status = 'clean') THEN 'clean' END",
+            ),
+            "explicit_status",
+        )
+        .column_as(
+            Expr::cust("GROUP_CONCAT(children.disc_number || '[:]' || children.disc_subtitle, '[;]')"),
+            "disc_titles",
+        )
         .join_rev(
             JoinType::LeftJoin,
             child::Entity::belongs_to(album::Entity)
@@ -214,5 +338,6 @@ pub fn album_with_stats_query(username: &str) -> sea_orm::Select<album::Entity>
                 })
                 .into(),
         )
+        .filter(song_access_filter(username))
         .group_by(album::Column::Id)
 }
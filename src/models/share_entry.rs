@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "share_entries")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub share_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub position: i32,
+    /// `song`, `album` or `playlist`.
+    pub kind: String,
+    pub entry_id: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::share::Entity",
+        from = "Column::ShareId",
+        to = "super::share::Column::Id"
+    )]
+    Share,
+}
+
+impl Related<super::share::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Share.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "song_contributors")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub song_id: String,
+    #[sea_orm(primary_key)]
+    pub role: String,
+    /// Instrument of a performer, empty for other roles.
+    #[sea_orm(primary_key)]
+    pub sub_role: String,
+    #[sea_orm(primary_key)]
+    pub artist_id: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::child::Entity",
+        from = "Column::SongId",
+        to = "super::child::Column::Id"
+    )]
+    Child,
+    #[sea_orm(
+        belongs_to = "super::artist::Entity",
+        from = "Column::ArtistId",
+        to = "super::artist::Column::Id"
+    )]
+    Artist,
+}
+
+impl Related<super::child::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Child.def()
+    }
+}
+
+impl Related<super::artist::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Artist.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
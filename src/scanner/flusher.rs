@@ -1,8 +1,9 @@
 use crate::models::{
-    album, album_artist, album_genre, artist, child, genre, lyrics, song_artist, song_genre,
+    album, album_artist, album_genre, artist, child, genre, lyrics, song_artist, song_contributor,
+    song_genre,
 };
-use crate::scanner::seen;
 use crate::scanner::types::{AlbumRelations, SongRelations, UpsertMessage};
+use crate::scanner::{search_index, seen};
 use sea_orm::{
     ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
     TransactionTrait,
@@ -10,8 +11,8 @@ use sea_orm::{
 use std::time::{Duration, Instant};
 
 /// Maximum rows per INSERT statement.
-/// SQLite has a 32766 bind-parameter limit. The widest table (children) has ~20 columns,
-/// so 500 rows × 20 cols = 10000 params — well within the limit.
+/// SQLite has a 32766 bind-parameter limit. The widest table (children) has ~40 columns,
+/// so 500 rows × 40 cols = 20000 params — well within the limit.
 const CHUNK_SIZE: usize = 500;
 
 /// Extension trait to split a `Vec<T>` into owned chunks without requiring `Clone`.
@@ -77,7 +78,16 @@ async fn flush_albums<C: ConnectionTrait>(
         album::Entity::insert_many(chunk)
             .on_conflict(
                 sea_orm::sea_query::OnConflict::column(album::Column::Id)
-                    .update_columns([album::Column::Year])
+                    .update_columns([
+                        album::Column::Year,
+                        album::Column::SortName,
+                        album::Column::MusicBrainzId,
+                        album::Column::DisplayArtist,
+                        album::Column::ReleaseTypes,
+                        album::Column::RecordLabels,
+                        album::Column::OriginalReleaseDate,
+                        album::Column::IsCompilation,
+                    ])
                     .to_owned(),
             )
             .exec_without_returning(db)
@@ -124,6 +134,23 @@ async fn flush_songs<C: ConnectionTrait>(
                         child::Column::Duration,
                         child::Column::BitRate,
                         child::Column::AlbumId,
+                        child::Column::Bpm,
+                        child::Column::Comment,
+                        child::Column::SortName,
+                        child::Column::MusicBrainzId,
+                        child::Column::DisplayArtist,
+                        child::Column::ExplicitStatus,
+                        child::Column::Moods,
+                        child::Column::DiscSubtitle,
+                        child::Column::ReplayGainTrackGain,
+                        child::Column::ReplayGainTrackPeak,
+                        child::Column::ReplayGainAlbumGain,
+                        child::Column::ReplayGainAlbumPeak,
+                        child::Column::ReplayGainBaseGain,
+                        child::Column::ChannelCount,
+                        child::Column::SamplingRate,
+                        child::Column::BitDepth,
+                        child::Column::Codec,
                     ])
                     .to_owned(),
             )
@@ -146,6 +173,7 @@ async fn flush_song_relations<C: ConnectionTrait>(
     let mut all_artists = Vec::new();
     let mut all_genres = Vec::new();
     let mut all_lyrics = Vec::new();
+    let mut all_contributors = Vec::new();
 
     for r in relations {
         for a_id in r.artists {
@@ -160,10 +188,23 @@ async fn flush_song_relations<C: ConnectionTrait>(
                 genre_name: Set(g_name),
             });
         }
-        if let Some(content) = r.lyrics {
+        for set in r.lyrics {
             all_lyrics.push(lyrics::ActiveModel {
                 song_id: Set(r.song_id.clone()),
-                content: Set(content),
+                lang: Set(set.lang),
+                synced: Set(set.synced),
+                content: Set(set.content),
+                source: Set(set.source),
+                source_modified: Set(set.source_modified),
+                ..Default::default()
+            });
+        }
+        for c in r.contributors {
+            all_contributors.push(song_contributor::ActiveModel {
+                song_id: Set(r.song_id.clone()),
+                role: Set(c.role),
+                sub_role: Set(c.sub_role),
+                artist_id: Set(c.artist_id),
             });
         }
     }
@@ -180,6 +221,10 @@ async fn flush_song_relations<C: ConnectionTrait>(
         .filter(lyrics::Column::SongId.is_in(&song_ids))
         .exec(db)
         .await?;
+    song_contributor::Entity::delete_many()
+        .filter(song_contributor::Column::SongId.is_in(&song_ids))
+        .exec(db)
+        .await?;
 
     if !all_artists.is_empty() {
         for chunk in all_artists.chunks_into(CHUNK_SIZE) {
@@ -214,10 +259,22 @@ async fn flush_song_relations<C: ConnectionTrait>(
     if !all_lyrics.is_empty() {
         for chunk in all_lyrics.chunks_into(CHUNK_SIZE) {
             lyrics::Entity::insert_many(chunk)
+                .exec_without_returning(db)
+                .await?;
+        }
+    }
+    if !all_contributors.is_empty() {
+        for chunk in all_contributors.chunks_into(CHUNK_SIZE) {
+            song_contributor::Entity::insert_many(chunk)
                 .on_conflict(
-                    sea_orm::sea_query::OnConflict::column(lyrics::Column::SongId)
-                        .do_nothing()
-                        .to_owned(),
+                    sea_orm::sea_query::OnConflict::columns([
+                        song_contributor::Column::SongId,
+                        song_contributor::Column::Role,
+                        song_contributor::Column::SubRole,
+                        song_contributor::Column::ArtistId,
+                    ])
+                    .do_nothing()
+                    .to_owned(),
                 )
                 .exec_without_returning(db)
                 .await?;
@@ -341,6 +398,20 @@ pub(crate) async fn do_flush_cycle(
     let ar = std::mem::take(album_relations);
     let si = std::mem::take(seen_ids);
 
+    // Everything written below is re-indexed for search before the commit.
+    let indexed_songs: Vec<String> = s
+        .iter()
+        .filter(|c| !*c.is_dir.as_ref())
+        .map(|c| c.id.as_ref().clone())
+        .chain(sr.iter().map(|r| r.song_id.clone()))
+        .collect();
+    let indexed_albums: Vec<String> = al
+        .iter()
+        .map(|a| a.id.as_ref().clone())
+        .chain(ar.iter().map(|r| r.album_id.clone()))
+        .collect();
+    let indexed_artists: Vec<String> = a.iter().map(|a| a.id.as_ref().clone()).collect();
+
     let txn = db.begin().await?;
 
     if db.get_database_backend() == sea_orm::DbBackend::Sqlite {
@@ -357,6 +428,7 @@ pub(crate) async fn do_flush_cycle(
     //   4. song_relations    (FK → children.id, artists.id, genres.name)
     //   5. album_relations   (FK → albums.id, artists.id, genres.name)
     //   6. seen ids          (independent)
+    //   7. search index      (reads all of the above)
     flush_artists(&txn, a).await?;
     flush_genres(&txn, g).await?;
     flush_albums(&txn, al).await?;
@@ -364,6 +436,7 @@ pub(crate) async fn do_flush_cycle(
     flush_song_relations(&txn, sr).await?;
     flush_album_relations(&txn, ar).await?;
     flush_seen(&txn, si).await?;
+    search_index::update(&txn, indexed_songs, indexed_albums, indexed_artists).await?;
 
     txn.commit().await?;
     Ok(())
@@ -1,5 +1,6 @@
 use super::*;
 use crate::models::{album, artist, child, genre};
+use crate::scanner::lyrics::{LyricsSet, UNKNOWN_LANG};
 use crate::scanner::types::{AlbumRelations, SongRelations, UpsertMessage};
 use sea_orm::Set;
 
@@ -46,8 +47,6 @@ fn make_song(id: &str, path: &str, is_dir: bool) -> UpsertMessage {
         music_folder_id: Set(1),
         content_type: Set(None),
         suffix: Set(None),
-        transcoded_content_type: Set(None),
-        transcoded_suffix: Set(None),
         album_id: Set(None),
         r#type: Set("music".to_string()),
         track: Set(0),
@@ -68,7 +67,8 @@ fn make_song_relations(song_id: &str) -> UpsertMessage {
         song_id: song_id.to_string(),
         artists: vec!["a1".into()],
         genres: vec!["rock".into()],
-        lyrics: None,
+        lyrics: Vec::new(),
+        contributors: Vec::new(),
     }))
 }
 
@@ -336,8 +336,6 @@ fn make_child_active(id: &str, path: &str, is_dir: bool) -> child::ActiveModel {
         music_folder_id: Set(1),
         content_type: Set(None),
         suffix: Set(None),
-        transcoded_content_type: Set(None),
-        transcoded_suffix: Set(None),
         album_id: Set(None),
         r#type: Set("music".to_string()),
         track: Set(0),
@@ -667,8 +665,6 @@ async fn flush_cycle_song_references_album_in_same_batch() {
         music_folder_id: Set(1),
         content_type: Set(Some("audio/mp3".into())),
         suffix: Set(Some("mp3".into())),
-        transcoded_content_type: Set(None),
-        transcoded_suffix: Set(None),
         album_id: Set(Some("al1".into())), // references album in same batch
         r#type: Set("music".into()),
         track: Set(1),
@@ -686,7 +682,12 @@ async fn flush_cycle_song_references_album_in_same_batch() {
         song_id: "s1".into(),
         artists: vec!["a1".into()],
         genres: vec!["Rock".into()],
-        lyrics: Some("Hello world".into()),
+        lyrics: vec![LyricsSet::new(
+            "Hello world".into(),
+            UNKNOWN_LANG.into(),
+            None,
+        )],
+        contributors: Vec::new(),
     }];
     let mut album_relations = vec![AlbumRelations {
         album_id: "al1".into(),
@@ -745,8 +746,6 @@ async fn flush_cycle_parent_child_directory_in_same_batch() {
             music_folder_id: Set(1),
             content_type: Set(None),
             suffix: Set(None),
-            transcoded_content_type: Set(None),
-            transcoded_suffix: Set(None),
             album_id: Set(None),
             r#type: Set("directory".into()),
             track: Set(0),
@@ -769,8 +768,6 @@ async fn flush_cycle_parent_child_directory_in_same_batch() {
             music_folder_id: Set(1),
             content_type: Set(Some("audio/mp3".into())),
             suffix: Set(Some("mp3".into())),
-            transcoded_content_type: Set(None),
-            transcoded_suffix: Set(None),
             album_id: Set(None),
             r#type: Set("music".into()),
             track: Set(1),
@@ -854,8 +851,6 @@ async fn flush_cycle_relations_reference_entities_in_same_batch() {
         music_folder_id: Set(1),
         content_type: Set(Some("audio/mp3".into())),
         suffix: Set(Some("mp3".into())),
-        transcoded_content_type: Set(None),
-        transcoded_suffix: Set(None),
         album_id: Set(Some("al1".into())),
         r#type: Set("music".into()),
         track: Set(1),
@@ -874,7 +869,8 @@ async fn flush_cycle_relations_reference_entities_in_same_batch() {
         song_id: "s1".into(),
         artists: vec!["a1".into(), "a2".into()],
         genres: vec!["Rock".into(), "Pop".into()],
-        lyrics: None,
+        lyrics: Vec::new(),
+        contributors: Vec::new(),
     }];
     let mut album_relations = vec![AlbumRelations {
         album_id: "al1".into(),
@@ -944,8 +940,6 @@ async fn flush_cycle_two_consecutive_cycles() {
         music_folder_id: Set(1),
         content_type: Set(Some("audio/mp3".into())),
         suffix: Set(Some("mp3".into())),
-        transcoded_content_type: Set(None),
-        transcoded_suffix: Set(None),
         album_id: Set(Some("al1".into())),
         r#type: Set("music".into()),
         track: Set(1),
@@ -963,7 +957,12 @@ async fn flush_cycle_two_consecutive_cycles() {
         song_id: "s1".into(),
         artists: vec!["a1".into()],
         genres: vec!["Rock".into()],
-        lyrics: Some("Old lyrics".into()),
+        lyrics: vec![LyricsSet::new(
+            "Old lyrics".into(),
+            UNKNOWN_LANG.into(),
+            None,
+        )],
+        contributors: Vec::new(),
     }];
     let mut ar = vec![];
     let mut seen = vec!["s1".into()];
@@ -1009,8 +1008,6 @@ async fn flush_cycle_two_consecutive_cycles() {
         music_folder_id: Set(1),
         content_type: Set(Some("audio/mp3".into())),
         suffix: Set(Some("mp3".into())),
-        transcoded_content_type: Set(None),
-        transcoded_suffix: Set(None),
         album_id: Set(Some("al1".into())),
         r#type: Set("music".into()),
         track: Set(1),
@@ -1028,7 +1025,12 @@ async fn flush_cycle_two_consecutive_cycles() {
         song_id: "s1".into(),
         artists: vec!["a1".into()],
         genres: vec!["Rock".into()],
-        lyrics: Some("New lyrics".into()),
+        lyrics: vec![LyricsSet::new(
+            "New lyrics".into(),
+            UNKNOWN_LANG.into(),
+            None,
+        )],
+        contributors: Vec::new(),
     });
 
     do_flush_cycle(
@@ -1065,6 +1067,14 @@ async fn flush_cycle_two_consecutive_cycles() {
         .unwrap()
         .unwrap();
     assert_eq!(alb.year, 2024);
+
+    // Lyrics are replaced, not accumulated
+    let sets = crate::models::lyrics::Entity::find()
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(sets.len(), 1);
+    assert_eq!(sets[0].content, "New lyrics");
 }
 
 /// Empty flush cycle should be a no-op and not error.
@@ -1085,3 +1095,108 @@ async fn flush_cycle_empty_is_noop() {
     .await
     .expect("empty flush should succeed");
 }
+
+/// Rescanning a song replaces its contributors and updates the extended
+/// album tags.
+#[tokio::test]
+async fn flush_cycle_replaces_contributors_and_album_tags() {
+    use crate::models::song_contributor;
+    use crate::scanner::types::ContributorRelation;
+
+    let db = test_db().await;
+    let contributor = |role: &str, sub_role: &str, artist_id: &str| ContributorRelation {
+        role: role.into(),
+        sub_role: sub_role.into(),
+        artist_id: artist_id.into(),
+    };
+
+    for (label, compilation, contributors) in [
+        (
+            "Blue Note",
+            false,
+            vec![
+                contributor("composer", "", "a2"),
+                contributor("performer", "guitar", "a1"),
+            ],
+        ),
+        ("Impulse!", true, vec![contributor("composer", "", "a2")]),
+    ] {
+        let mut artists = vec![
+            artist::ActiveModel {
+                id: Set("a1".into()),
+                name: Set("Artist A".into()),
+                artist_image_url: Set(None),
+                average_rating: Set(0.0),
+                ..Default::default()
+            },
+            artist::ActiveModel {
+                id: Set("a2".into()),
+                name: Set("Composer B".into()),
+                artist_image_url: Set(None),
+                average_rating: Set(0.0),
+                ..Default::default()
+            },
+        ];
+        let mut albums = vec![album::ActiveModel {
+            id: Set("al1".into()),
+            name: Set("Album".into()),
+            created: Set(chrono::Utc::now()),
+            year: Set(2024),
+            average_rating: Set(0.0),
+            record_labels: Set(Some(label.into())),
+            is_compilation: Set(compilation),
+            ..Default::default()
+        }];
+        let mut songs = vec![child::ActiveModel {
+            id: Set("s1".into()),
+            parent: Set(None),
+            is_dir: Set(false),
+            title: Set("Song".into()),
+            path: Set("/music/song.flac".into()),
+            music_folder_id: Set(1),
+            album_id: Set(Some("al1".into())),
+            r#type: Set("music".into()),
+            replay_gain_track_gain: Set(Some(-6.5)),
+            ..Default::default()
+        }];
+        let mut song_relations = vec![SongRelations {
+            song_id: "s1".into(),
+            artists: vec!["a1".into()],
+            genres: Vec::new(),
+            lyrics: Vec::new(),
+            contributors,
+        }];
+
+        do_flush_cycle(
+            &db,
+            &mut artists,
+            &mut Vec::new(),
+            &mut albums,
+            &mut songs,
+            &mut song_relations,
+            &mut Vec::new(),
+            &mut vec!["s1".into()],
+        )
+        .await
+        .expect("flush_cycle should write contributors");
+    }
+
+    let contributors = song_contributor::Entity::find().all(&db).await.unwrap();
+    assert_eq!(contributors.len(), 1, "old contributors should be replaced");
+    assert_eq!(contributors[0].role, "composer");
+    assert_eq!(contributors[0].artist_id, "a2");
+
+    let album = album::Entity::find_by_id("al1")
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(album.record_labels.as_deref(), Some("Impulse!"));
+    assert!(album.is_compilation);
+    let song = child::Entity::find_by_id("s1")
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(song.replay_gain_track_gain, Some(-6.5));
+}
@@ -0,0 +1,194 @@
+use crate::scanner::utils::is_audio_file;
+use crate::service::utils::parse_lrc;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+/// Language reported when neither the file name nor the tag names one.
+pub const UNKNOWN_LANG: &str = "xxx";
+
+static LANG_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z]{2,3}(?:[-_][A-Za-z0-9]{2,8})?$").unwrap());
+
+/// One set of lyrics for a song, as stored in the `lyrics` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricsSet {
+    pub lang: String,
+    pub synced: bool,
+    pub content: String,
+    /// Sidecar file name, `None` for embedded lyrics.
+    pub source: Option<String>,
+    pub source_modified: Option<DateTime<Utc>>,
+}
+
+impl LyricsSet {
+    pub fn new(content: String, lang: String, source: Option<String>) -> Self {
+        let (synced, _) = parse_lrc(&content);
+        Self {
+            lang,
+            synced,
+            content,
+            source,
+            source_modified: None,
+        }
+    }
+}
+
+/// A lyrics file next to an audio file: `song.lrc`, `song.txt`, or the same
+/// with a language before the extension (`song.de.lrc`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sidecar {
+    pub path: PathBuf,
+    pub name: String,
+    pub lang: String,
+    pub modified: DateTime<Utc>,
+}
+
+pub fn is_lyrics_file(ext: &str) -> bool {
+    matches!(ext, "lrc" | "txt")
+}
+
+/// The language of `file_name` if it is a lyrics sidecar for an audio file
+/// with the given stem, [`UNKNOWN_LANG`] if it names none.
+pub fn sidecar_lang(audio_stem: &str, file_name: &str) -> Option<String> {
+    let (base, ext) = file_name.rsplit_once('.')?;
+    if !is_lyrics_file(&ext.to_lowercase()) {
+        return None;
+    }
+    if base == audio_stem {
+        return Some(UNKNOWN_LANG.to_string());
+    }
+    let lang = base.strip_prefix(audio_stem)?.strip_prefix('.')?;
+    LANG_RE
+        .is_match(lang)
+        .then(|| lang.to_lowercase().replace('_', "-"))
+}
+
+fn file_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// A lyrics file found by [`lyrics_files`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricsFile {
+    pub name: String,
+    pub modified: DateTime<Utc>,
+}
+
+/// Lists the lyrics files in `dir`, sorted by file name, so the directory is
+/// read once for all of its audio files.
+pub fn lyrics_files(dir: &Path) -> Vec<LyricsFile> {
+    file_names(dir)
+        .into_iter()
+        .filter_map(|name| {
+            let (_, ext) = name.rsplit_once('.')?;
+            if !is_lyrics_file(&ext.to_lowercase()) {
+                return None;
+            }
+            // Empty files are never stored, so they must not count as sidecars
+            // or every incremental scan would see them as changed.
+            let metadata = std::fs::metadata(dir.join(&name))
+                .ok()
+                .filter(|m| m.is_file() && m.len() > 0)?;
+            let modified = metadata.modified().ok()?.into();
+            Some(LyricsFile { name, modified })
+        })
+        .collect()
+}
+
+/// The sidecars of `audio` among `files`, the lyrics files of its directory.
+pub fn sidecars_in(audio: &Path, files: &[LyricsFile]) -> Vec<Sidecar> {
+    let (Some(dir), Some(stem)) = (audio.parent(), audio.file_stem().and_then(|s| s.to_str()))
+    else {
+        return Vec::new();
+    };
+    files
+        .iter()
+        .filter_map(|file| {
+            Some(Sidecar {
+                lang: sidecar_lang(stem, &file.name)?,
+                path: dir.join(&file.name),
+                name: file.name.clone(),
+                modified: file.modified,
+            })
+        })
+        .collect()
+}
+
+/// Audio files in the same directory that `sidecar` provides lyrics for.
+pub fn audio_files_for(sidecar: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (
+        sidecar.parent(),
+        sidecar.file_name().and_then(|s| s.to_str()),
+    ) else {
+        return Vec::new();
+    };
+    file_names(dir)
+        .into_iter()
+        .filter(|audio| {
+            let path = Path::new(audio);
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            is_audio_file(&ext.to_lowercase()) && sidecar_lang(stem, name).is_some()
+        })
+        .map(|audio| dir.join(audio))
+        .collect()
+}
+
+/// Reads the given sidecars, followed by the embedded lyrics unless a sidecar
+/// already has the same text. Unreadable and empty files are skipped.
+pub fn collect(sidecars: Vec<Sidecar>, embedded: Option<String>) -> Vec<LyricsSet> {
+    let mut sets = Vec::new();
+    for sidecar in sidecars {
+        let bytes = match std::fs::read(&sidecar.path) {
+            Ok(b) => b,
+            Err(e) => {
+                log::warn!("Failed to read lyrics '{}': {}", sidecar.path.display(), e);
+                continue;
+            }
+        };
+        let content = String::from_utf8_lossy(&bytes);
+        let content = content.trim_start_matches('\u{feff}').trim();
+        if content.is_empty() {
+            continue;
+        }
+        let mut set = LyricsSet::new(content.to_string(), sidecar.lang, Some(sidecar.name));
+        set.source_modified = Some(sidecar.modified);
+        sets.push(set);
+    }
+
+    if let Some(embedded) = embedded.filter(|e| !e.trim().is_empty()) {
+        if !sets.iter().any(|s| s.content == embedded.trim()) {
+            sets.push(LyricsSet::new(embedded, UNKNOWN_LANG.to_string(), None));
+        }
+    }
+    sets
+}
+
+/// Whether the sidecars on disk match those recorded at the last scan, given as
+/// `(file name, modification time)` pairs.
+pub fn sidecars_unchanged(
+    sidecars: &[Sidecar],
+    mut stored: Vec<(Option<String>, Option<DateTime<Utc>>)>,
+) -> bool {
+    stored.retain(|(name, _)| name.is_some());
+    stored.sort();
+    stored.len() == sidecars.len()
+        && sidecars.iter().zip(&stored).all(|(s, (name, modified))| {
+            name.as_deref() == Some(s.name.as_str())
+                && modified.map(|m| m.timestamp_millis()) == Some(s.modified.timestamp_millis())
+        })
+}
+
+#[cfg(test)]
+#[path = "lyrics_tests.rs"]
+mod tests;
@@ -0,0 +1,74 @@
+use super::*;
+
+#[test]
+fn sidecar_names_match_stem_and_language() {
+    assert_eq!(
+        sidecar_lang("01 Song", "01 Song.lrc").as_deref(),
+        Some(UNKNOWN_LANG)
+    );
+    assert_eq!(
+        sidecar_lang("01 Song", "01 Song.TXT").as_deref(),
+        Some(UNKNOWN_LANG)
+    );
+    assert_eq!(
+        sidecar_lang("01 Song", "01 Song.de.lrc").as_deref(),
+        Some("de")
+    );
+    assert_eq!(
+        sidecar_lang("01 Song", "01 Song.pt_BR.lrc").as_deref(),
+        Some("pt-br")
+    );
+    assert_eq!(sidecar_lang("01 Song", "01 Song.mp3"), None);
+    assert_eq!(sidecar_lang("01 Song", "01 Song (live).lrc"), None);
+    assert_eq!(sidecar_lang("01 Song", "01 Song.karaoke.lrc"), None);
+    assert_eq!(sidecar_lang("01", "01 Song.lrc"), None);
+}
+
+#[test]
+fn collects_sidecars_before_embedded_lyrics() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_path_buf();
+    std::fs::create_dir_all(&dir).unwrap();
+    let audio = dir.join("01 Song.mp3");
+    std::fs::write(&audio, b"").unwrap();
+    std::fs::write(dir.join("01 Song.lrc"), "\u{feff}[00:01.00]Hello\n").unwrap();
+    std::fs::write(dir.join("01 Song.fr.txt"), "Bonjour\n").unwrap();
+    std::fs::write(dir.join("01 Song.de.lrc"), "").unwrap();
+    std::fs::write(dir.join("01 Song.it.txt"), "  \n").unwrap();
+    std::fs::write(dir.join("02 Other.lrc"), "[00:01.00]Other").unwrap();
+
+    let sidecars = sidecars_in(&audio, &lyrics_files(&dir));
+    let names: Vec<&str> = sidecars.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["01 Song.fr.txt", "01 Song.it.txt", "01 Song.lrc"]
+    );
+    assert_eq!(
+        audio_files_for(&dir.join("01 Song.fr.txt")),
+        vec![audio.clone()]
+    );
+    assert!(audio_files_for(&dir.join("03 Missing.lrc")).is_empty());
+
+    let stored: Vec<_> = sidecars
+        .iter()
+        .rev()
+        .map(|s| (Some(s.name.clone()), Some(s.modified)))
+        .chain([(None, None)])
+        .collect();
+    assert!(sidecars_unchanged(&sidecars, stored.clone()));
+    assert!(!sidecars_unchanged(&sidecars[1..], stored));
+
+    let sets = collect(sidecars, Some("[00:01.00]Hello".to_string()));
+    // Blank files are skipped and the embedded copy is a duplicate.
+    assert_eq!(sets.len(), 2);
+    assert_eq!(sets[0].lang, "fr");
+    assert!(!sets[0].synced);
+    assert_eq!(sets[1].lang, UNKNOWN_LANG);
+    assert!(sets[1].synced);
+    assert_eq!(sets[1].content, "[00:01.00]Hello");
+    assert_eq!(sets[1].source.as_deref(), Some("01 Song.lrc"));
+
+    let sets = collect(Vec::new(), Some("Embedded".to_string()));
+    assert_eq!(sets.len(), 1);
+    assert_eq!(sets[0].source, None);
+}
@@ -1,9 +1,13 @@
 pub mod flusher;
+pub mod lyrics;
 pub mod scanner;
+pub mod schedule;
+pub mod search_index;
 pub mod seen;
 pub mod tags;
 pub mod types;
 pub mod utils;
 pub mod walker;
+pub mod watcher;
 
 pub use scanner::Scanner;
@@ -1,17 +1,24 @@
 use crate::config::Config;
-use crate::models::{album, artist, child, genre, music_folder};
+use crate::models::{album, artist, child, genre, lyrics, music_folder};
 use crate::scanner::flusher;
+use crate::scanner::lyrics as sidecar_lyrics;
+use crate::scanner::search_index;
 use crate::scanner::seen;
 use crate::scanner::tags;
-use crate::scanner::types::{AlbumRelations, SongRelations, UpsertMessage};
+use crate::scanner::types::{AlbumRelations, ContributorRelation, SongRelations, UpsertMessage};
 use crate::scanner::utils;
 use crate::scanner::walker::{WalkTask, Walker};
-use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait, QuerySelect, Set};
-use std::path::Path;
+use crate::service::playlist_files;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect, Set,
+    Statement,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, OnceCell};
 
 struct ScannerInner {
     db: DatabaseConnection,
@@ -20,6 +27,7 @@ struct ScannerInner {
     scan_count: AtomicI64,
     total_count: AtomicI64,
     last_scan_time: AtomicI64,
+    next_scan_time: AtomicI64,
     upsert_tx: mpsc::Sender<UpsertMessage>,
 }
 
@@ -30,12 +38,31 @@ pub struct Scanner {
 
 struct ScanGuard(Arc<ScannerInner>);
 
+/// A sidecar recorded at the last scan: file name and modification time.
+type StoredSidecar = (Option<String>, Option<chrono::DateTime<chrono::Utc>>);
+
+/// Lyrics files of one directory and the sidecars recorded for its songs.
+#[derive(Default)]
+struct DirLyrics {
+    files: Vec<sidecar_lyrics::LyricsFile>,
+    stored: HashMap<String, Vec<StoredSidecar>>,
+}
+
+/// [`DirLyrics`] by directory, for the duration of one scan.
+type DirLyricsCache = Arc<Mutex<HashMap<PathBuf, Arc<OnceCell<Arc<DirLyrics>>>>>>;
+
 impl Drop for ScanGuard {
     fn drop(&mut self) {
         self.0.is_scanning.store(false, Ordering::SeqCst);
     }
 }
 
+fn unix_time(secs: i64) -> Option<chrono::DateTime<chrono::Utc>> {
+    (secs > 0)
+        .then(|| chrono::DateTime::from_timestamp(secs, 0))
+        .flatten()
+}
+
 impl Scanner {
     pub fn new(db: DatabaseConnection, cfg: Arc<Config>) -> Self {
         let (tx, rx) = mpsc::channel(1000);
@@ -52,11 +79,16 @@ impl Scanner {
                 scan_count: AtomicI64::new(0),
                 total_count: AtomicI64::new(0),
                 last_scan_time: AtomicI64::new(0),
+                next_scan_time: AtomicI64::new(0),
                 upsert_tx: tx,
             }),
         }
     }
 
+    pub(crate) fn db(&self) -> &DatabaseConnection {
+        &self.inner.db
+    }
+
     pub fn is_scanning(&self) -> bool {
         self.inner.is_scanning.load(Ordering::SeqCst)
     }
@@ -65,6 +97,24 @@ impl Scanner {
         self.inner.last_scan_time.load(Ordering::SeqCst)
     }
 
+    /// Unix time of the next scheduled scan, or 0 if none is scheduled.
+    pub fn next_scan_time(&self) -> i64 {
+        self.inner.next_scan_time.load(Ordering::SeqCst)
+    }
+
+    /// When the last full or incremental scan completed, if any has since startup.
+    pub fn last_scan_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        unix_time(self.last_scan_time())
+    }
+
+    pub fn next_scan_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        unix_time(self.next_scan_time())
+    }
+
+    pub(crate) fn set_next_scan_time(&self, time: i64) {
+        self.inner.next_scan_time.store(time, Ordering::SeqCst);
+    }
+
     pub fn scan_count(&self) -> i64 {
         self.inner.scan_count.load(Ordering::SeqCst)
     }
@@ -73,6 +123,61 @@ impl Scanner {
         self.inner.total_count.load(Ordering::SeqCst)
     }
 
+    /// The lyrics files of `dir` and, for incremental scans, the sidecars
+    /// recorded for its songs. Loaded by the first song of the directory and
+    /// shared with the others.
+    async fn dir_lyrics(
+        &self,
+        cache: &DirLyricsCache,
+        dir: &Path,
+        task: &WalkTask,
+        parent_id: Option<&str>,
+        incremental: bool,
+    ) -> Result<Arc<DirLyrics>, anyhow::Error> {
+        let cell = cache
+            .lock()
+            .unwrap()
+            .entry(dir.to_path_buf())
+            .or_default()
+            .clone();
+        let lyrics = cell
+            .get_or_try_init(|| async {
+                let dir = dir.to_path_buf();
+                let files =
+                    tokio::task::spawn_blocking(move || sidecar_lyrics::lyrics_files(&dir)).await?;
+                let mut stored: HashMap<String, Vec<StoredSidecar>> = HashMap::new();
+                if incremental {
+                    let songs = match parent_id {
+                        Some(parent) => child::Column::Parent.eq(parent),
+                        None => child::Column::Parent
+                            .is_null()
+                            .and(child::Column::MusicFolderId.eq(task.folder.id)),
+                    };
+                    let rows: Vec<(
+                        String,
+                        Option<String>,
+                        Option<chrono::DateTime<chrono::Utc>>,
+                    )> = lyrics::Entity::find()
+                        .select_only()
+                        .column(lyrics::Column::SongId)
+                        .column(lyrics::Column::Source)
+                        .column(lyrics::Column::SourceModified)
+                        .inner_join(child::Entity)
+                        .filter(songs)
+                        .filter(lyrics::Column::Source.is_not_null())
+                        .into_tuple()
+                        .all(&self.inner.db)
+                        .await?;
+                    for (song_id, source, modified) in rows {
+                        stored.entry(song_id).or_default().push((source, modified));
+                    }
+                }
+                Ok::<_, anyhow::Error>(Arc::new(DirLyrics { files, stored }))
+            })
+            .await?;
+        Ok(lyrics.clone())
+    }
+
     pub async fn update_total_count(&self) {
         let count = child::Entity::count_songs(&self.inner.db).await;
         self.inner.total_count.store(count, Ordering::SeqCst);
@@ -83,6 +188,7 @@ impl Scanner {
         task: WalkTask,
         incremental: bool,
         cache_dir: &Path,
+        dirs: &DirLyricsCache,
     ) -> Result<(), anyhow::Error> {
         let id = utils::generate_id(&task.path, task.folder.id, &task.folder.path);
 
@@ -102,8 +208,6 @@ impl Scanner {
                 music_folder_id: Set(task.folder.id),
                 content_type: Set(None),
                 suffix: Set(None),
-                transcoded_content_type: Set(None),
-                transcoded_suffix: Set(None),
                 album_id: Set(None),
                 r#type: Set("directory".to_string()),
                 track: Set(0),
@@ -125,6 +229,16 @@ impl Scanner {
             return Ok(());
         }
 
+        let audio_path = Path::new(&task.path);
+        let dir_lyrics = match audio_path.parent() {
+            Some(dir) => {
+                self.dir_lyrics(dirs, dir, &task, parent_id.as_deref(), incremental)
+                    .await?
+            }
+            None => Arc::default(),
+        };
+        let sidecars = sidecar_lyrics::sidecars_in(audio_path, &dir_lyrics.files);
+
         if incremental {
             let existing: Option<chrono::DateTime<chrono::Utc>> =
                 child::Entity::find_by_id(id.clone())
@@ -135,7 +249,10 @@ impl Scanner {
                     .await?;
 
             if let Some(created) = existing {
-                if task.mod_time <= created {
+                // Lyrics sidecars can change without touching the audio file.
+                let stored = dir_lyrics.stored.get(&id).cloned().unwrap_or_default();
+                if task.mod_time <= created && sidecar_lyrics::sidecars_unchanged(&sidecars, stored)
+                {
                     self.inner
                         .upsert_tx
                         .send(UpsertMessage::Batch(batch))
@@ -145,7 +262,7 @@ impl Scanner {
             }
         }
         // file must be end with a valid audio suffix, that is ensured by the walker
-        let content_type = format!("audio/{}", task.ext.to_lowercase());
+        let content_type = utils::content_type(&task.ext);
 
         let path_for_tags = Path::new(&task.path).to_path_buf();
         let tag_data = match tokio::task::spawn_blocking(move || tags::read(&path_for_tags)).await?
@@ -161,7 +278,8 @@ impl Scanner {
             song_id: id.clone(),
             artists: Vec::new(),
             genres: Vec::new(),
-            lyrics: None,
+            lyrics: Vec::new(),
+            contributors: Vec::new(),
         };
 
         let mut active_child = child::ActiveModel {
@@ -179,8 +297,6 @@ impl Scanner {
             content_type: Set(Some(content_type)),
             created: Set(Some(task.mod_time)),
             music_folder_id: Set(task.folder.id),
-            transcoded_content_type: Set(None),
-            transcoded_suffix: Set(None),
             album_id: Set(None),
             r#type: Set("music".to_string()),
             track: Set(0),
@@ -194,13 +310,35 @@ impl Scanner {
             ..Default::default()
         };
 
+        let embedded_lyrics = tag_data.as_ref().map(|t| t.lyrics.clone());
+        relations.lyrics =
+            tokio::task::spawn_blocking(move || sidecar_lyrics::collect(sidecars, embedded_lyrics))
+                .await?;
+
+        let mut has_image = false;
         if let Some(t) = tag_data {
             active_child.track = Set(t.track.unwrap_or(0));
             active_child.disc_number = Set(t.disc.unwrap_or(0));
             active_child.year = Set(t.year.unwrap_or(0));
-            relations.lyrics = (!t.lyrics.trim().is_empty()).then(|| t.lyrics.clone());
             active_child.duration = Set(t.duration);
             active_child.bit_rate = Set(t.bitrate);
+            active_child.bpm = Set(t.bpm);
+            active_child.comment = Set(t.comment.clone());
+            active_child.sort_name = Set(t.sort_name.clone());
+            active_child.music_brainz_id = Set(t.music_brainz_id.clone());
+            active_child.display_artist = Set(t.display_artist.clone());
+            active_child.explicit_status = Set(t.explicit_status.clone());
+            active_child.moods = Set((!t.moods.is_empty()).then(|| t.moods.join("; ")));
+            active_child.disc_subtitle = Set(t.disc_subtitle.clone());
+            active_child.replay_gain_track_gain = Set(t.replay_gain_track_gain);
+            active_child.replay_gain_track_peak = Set(t.replay_gain_track_peak);
+            active_child.replay_gain_album_gain = Set(t.replay_gain_album_gain);
+            active_child.replay_gain_album_peak = Set(t.replay_gain_album_peak);
+            active_child.replay_gain_base_gain = Set(t.base_gain);
+            active_child.channel_count = Set(t.channels);
+            active_child.sampling_rate = Set(t.sample_rate);
+            active_child.bit_depth = Set(t.bit_depth);
+            active_child.codec = Set(t.codec.clone());
 
             let filtered_artists: Vec<&str> = t
                 .artists
@@ -223,13 +361,32 @@ impl Scanner {
                 album_artists_list.push("Unknown Artist");
             }
 
+            for c in &t.contributors {
+                let artist_id = self.build_artist(c.name.trim(), &mut batch);
+                relations.contributors.push(ContributorRelation {
+                    role: c.role.to_string(),
+                    sub_role: c.sub_role.clone().unwrap_or_default(),
+                    artist_id,
+                });
+            }
+
             if !t.album.trim().is_empty() {
+                let mut album = new_album(&t.album, t.year.unwrap_or(0), task.mod_time);
+                album.sort_name = Set(t.album_sort_name.clone());
+                album.music_brainz_id = Set(t.album_music_brainz_id.clone());
+                album.display_artist =
+                    Set((!t.album_artist.trim().is_empty())
+                        .then(|| t.album_artist.trim().to_string()));
+                album.release_types =
+                    Set((!t.release_types.is_empty()).then(|| t.release_types.join("; ")));
+                album.record_labels =
+                    Set((!t.record_labels.is_empty()).then(|| t.record_labels.join("; ")));
+                album.original_release_date = Set(t.original_release_date.clone());
+                album.is_compilation = Set(t.is_compilation);
                 let album_id = self.build_album(
-                    &t.album,
+                    album,
                     &album_artists_list,
-                    t.year.unwrap_or(0),
                     &t.genres.iter().map(|g| g.as_str()).collect::<Vec<&str>>(),
-                    task.mod_time,
                     &mut batch,
                 );
                 active_child.album_id = Set(Some(album_id.clone()));
@@ -247,22 +404,27 @@ impl Scanner {
                 let g_name = self.build_genre(g_name, &mut batch);
                 relations.genres.push(g_name);
             }
+            has_image = t.has_image;
+        }
 
-            if t.has_image {
-                let cover_art_id = if let Some(aid) = active_child.album_id.as_ref().as_ref() {
-                    format!("al-{}", aid)
-                } else {
-                    id.clone()
-                };
-                let cover_path = cache_dir.join(&cover_art_id);
-                if !cover_path.exists() {
-                    let path_for_img = Path::new(&task.path).to_path_buf();
-                    if let Ok(Ok(img_data)) =
-                        tokio::task::spawn_blocking(move || tags::read_image(&path_for_img)).await
-                    {
-                        tokio::fs::write(cover_path, img_data).await?;
-                    }
+        let cover_art_id = if let Some(aid) = active_child.album_id.as_ref().as_ref() {
+            format!("al-{}", aid)
+        } else {
+            id.clone()
+        };
+        let cover_path = cache_dir.join(&cover_art_id);
+        if !cover_path.exists() {
+            let song_path = Path::new(&task.path).to_path_buf();
+            let priority = self.inner.cfg.subsonic.cover_art_priority.clone();
+            let img_data = tokio::task::spawn_blocking(move || {
+                match utils::find_cover_source(&song_path, &priority, has_image)? {
+                    utils::CoverSource::Embedded => tags::read_image(&song_path).ok(),
+                    utils::CoverSource::File(p) => std::fs::read(p).ok(),
                 }
+            })
+            .await?;
+            if let Some(img_data) = img_data {
+                tokio::fs::write(cover_path, img_data).await?;
             }
         }
 
@@ -301,27 +463,19 @@ impl Scanner {
         name.to_string()
     }
 
+    /// Queues `album`, with its id derived from its name and artists, and its relations.
     fn build_album(
         &self,
-        name: &str,
+        mut album: album::ActiveModel,
         artist_names: &[&str],
-        year: i32,
         genres: &[&str],
-        created: chrono::DateTime<chrono::Utc>,
         batch: &mut Vec<UpsertMessage>,
     ) -> String {
         let artist_name = artist_names.join("; ");
-        let id = utils::generate_album_id(&artist_name, name);
+        let id = utils::generate_album_id(&artist_name, album.name.as_ref());
 
-        let obj = album::ActiveModel {
-            id: Set(id.clone()),
-            name: Set(name.to_string()),
-            created: Set(created),
-            year: Set(year),
-            average_rating: Set(0.0),
-            ..Default::default()
-        };
-        batch.push(UpsertMessage::Album(Box::new(obj)));
+        album.id = Set(id.clone());
+        batch.push(UpsertMessage::Album(Box::new(album)));
 
         let mut relations = AlbumRelations {
             album_id: id.clone(),
@@ -363,53 +517,30 @@ impl Scanner {
         created: chrono::DateTime<chrono::Utc>,
         batch: &mut Vec<UpsertMessage>,
     ) -> String {
-        self.build_album(name, artist_names, year, genres, created, batch)
+        self.build_album(new_album(name, year, created), artist_names, genres, batch)
     }
 
-    pub async fn scan_all(&self, incremental: bool) -> Result<(), anyhow::Error> {
-        if self
-            .inner
-            .is_scanning
-            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-            .is_err()
-        {
-            return Ok(());
-        }
-
-        let _guard = ScanGuard(self.inner.clone());
-        self.inner.scan_count.store(0, Ordering::SeqCst);
-
-        let (tx, mut rx) = mpsc::channel(100);
-        let folders = music_folder::Entity::find().all(&self.inner.db).await?;
-
-        for folder in folders {
-            Walker::walk_path(Path::new(&folder.path).to_path_buf(), folder, tx.clone());
-        }
-        drop(tx);
-
-        log::info!("Starting scan... incremental: {}", incremental);
-        self.inner.scan_count.store(0, Ordering::SeqCst);
-
-        // create a temporary table to track seen ids
-        seen::SeenTracker::prepare(&self.inner.db).await?;
-
-        let cache_dir = utils::get_cover_cache_dir(&self.inner.cfg);
-        if !cache_dir.exists() {
-            tokio::fs::create_dir_all(&cache_dir).await?;
-        }
-
+    /// Processes walk tasks until the channel closes, 32 at a time.
+    async fn run_tasks(
+        &self,
+        mut rx: mpsc::Receiver<WalkTask>,
+        incremental: bool,
+        cache_dir: &Path,
+    ) {
         let mut join_set = tokio::task::JoinSet::new();
         let semaphore = Arc::new(tokio::sync::Semaphore::new(32));
+        let dirs = DirLyricsCache::default();
 
         while let Some(task) = rx.recv().await {
             let scanner = self.clone();
-            let cache_dir_clone = cache_dir.clone();
+            let cache_dir_clone = cache_dir.to_path_buf();
+            let dirs = dirs.clone();
             let permit = semaphore.clone().acquire_owned().await.unwrap();
 
             join_set.spawn(async move {
                 let _permit = permit;
                 if let Err(e) = scanner
-                    .process_task(task, incremental, &cache_dir_clone)
+                    .process_task(task, incremental, &cache_dir_clone, &dirs)
                     .await
                 {
                     log::error!("Error processing task: {}", e);
@@ -425,18 +556,154 @@ impl Scanner {
                 log::error!("Join error: {}", e);
             }
         }
+    }
 
+    /// Waits until the flusher has written everything sent so far.
+    async fn flush(&self) -> Result<(), anyhow::Error> {
         let (ack_tx, ack_rx) = tokio::sync::oneshot::channel();
         self.inner
             .upsert_tx
             .send(UpsertMessage::Flush(ack_tx))
             .await?;
-        // Wait for the flusher to finish its work before pruning
         let _ = ack_rx.await;
+        Ok(())
+    }
+
+    /// Rescans only `paths` instead of walking every folder. Existing directories
+    /// are walked, existing audio files are re-read, and paths that no longer exist
+    /// are pruned together with everything below them. Returns `false` without
+    /// doing anything while another scan is running.
+    pub async fn scan_paths(&self, paths: Vec<PathBuf>) -> Result<bool, anyhow::Error> {
+        if self
+            .inner
+            .is_scanning
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Ok(false);
+        }
+        let _guard = ScanGuard(self.inner.clone());
+
+        let folders = music_folder::Entity::find().all(&self.inner.db).await?;
+        seen::SeenTracker::ensure(&self.inner.db).await?;
+        let cache_dir = utils::get_cover_cache_dir(&self.inner.cfg);
+        if !cache_dir.exists() {
+            tokio::fs::create_dir_all(&cache_dir).await?;
+        }
+
+        let (tx, rx) = mpsc::channel(100);
+        let producer = tokio::spawn(async move {
+            let mut removed = Vec::new();
+            for path in paths {
+                let Some(folder) = utils::folder_for_path(&folders, &path) else {
+                    continue;
+                };
+                let folder = folder.clone();
+                let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+                if sidecar_lyrics::is_lyrics_file(&ext.to_lowercase()) {
+                    // Added, edited or removed lyrics: rescan the songs they belong to.
+                    for audio in sidecar_lyrics::audio_files_for(&path) {
+                        if let Some(task) = Walker::task_for(&audio, &folder) {
+                            if tx.send(task).await.is_err() {
+                                return removed;
+                            }
+                        }
+                    }
+                    continue;
+                }
+                let metadata = match tokio::fs::metadata(&path).await {
+                    Ok(m) => m,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        removed.push(path.to_string_lossy().replace('\\', "/"));
+                        continue;
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to stat '{}': {}", path.display(), e);
+                        continue;
+                    }
+                };
+                if !metadata.is_dir() {
+                    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+                    if !utils::is_audio_file(&ext.to_lowercase()) {
+                        continue;
+                    }
+                }
+
+                // Parent directories may be new as well (e.g. a copied Artist/Album tree).
+                let root = Path::new(&folder.path);
+                for dir in path.ancestors().skip(1) {
+                    if !dir.starts_with(root) {
+                        break;
+                    }
+                    if let Some(task) = Walker::task_for(dir, &folder) {
+                        if tx.send(task).await.is_err() {
+                            return removed;
+                        }
+                    }
+                }
+
+                if metadata.is_dir() {
+                    Walker::walk_path(path, folder, tx.clone());
+                } else if let Some(task) = Walker::task_for(&path, &folder) {
+                    if tx.send(task).await.is_err() {
+                        return removed;
+                    }
+                }
+            }
+            removed
+        });
+
+        self.run_tasks(rx, true, &cache_dir).await;
+        let removed = producer.await?;
+        self.flush().await?;
+        self.prune_paths(&removed).await?;
+        self.update_total_count().await;
+
+        Ok(true)
+    }
+
+    pub async fn scan_all(&self, incremental: bool) -> Result<(), anyhow::Error> {
+        if self
+            .inner
+            .is_scanning
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Ok(());
+        }
+
+        let _guard = ScanGuard(self.inner.clone());
+        self.inner.scan_count.store(0, Ordering::SeqCst);
+
+        let (tx, rx) = mpsc::channel(100);
+        let folders = music_folder::Entity::find().all(&self.inner.db).await?;
+
+        for folder in folders.iter().cloned() {
+            Walker::walk_path(Path::new(&folder.path).to_path_buf(), folder, tx.clone());
+        }
+        drop(tx);
+
+        log::info!("Starting scan... incremental: {}", incremental);
+        self.inner.scan_count.store(0, Ordering::SeqCst);
+
+        // create a temporary table to track seen ids
+        seen::SeenTracker::prepare(&self.inner.db).await?;
+
+        let cache_dir = utils::get_cover_cache_dir(&self.inner.cfg);
+        if !cache_dir.exists() {
+            tokio::fs::create_dir_all(&cache_dir).await?;
+        }
+
+        self.run_tasks(rx, incremental, &cache_dir).await;
+        self.flush().await?;
 
         log::info!("Scan finished, pruning database...");
         self.prune().await?;
 
+        if let Err(e) = playlist_files::sync_folder_playlists(&self.inner.db, folders).await {
+            log::error!("Failed to import playlist files: {}", e);
+        }
+
         self.update_total_count().await;
 
         let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
@@ -457,34 +724,100 @@ impl Scanner {
         self.inner.db.execute_unprepared("DELETE FROM lyrics WHERE NOT EXISTS (SELECT 1 FROM _scanner_seen WHERE _scanner_seen.id = lyrics.song_id)").await?;
         self.inner.db.execute_unprepared("DELETE FROM song_artists WHERE NOT EXISTS (SELECT 1 FROM _scanner_seen WHERE _scanner_seen.id = song_artists.song_id)").await?;
         self.inner.db.execute_unprepared("DELETE FROM song_genres WHERE NOT EXISTS (SELECT 1 FROM _scanner_seen WHERE _scanner_seen.id = song_genres.song_id)").await?;
+        self.inner.db.execute_unprepared("DELETE FROM song_contributors WHERE NOT EXISTS (SELECT 1 FROM _scanner_seen WHERE _scanner_seen.id = song_contributors.song_id)").await?;
         self.inner.db.execute_unprepared("DELETE FROM playlist_songs WHERE NOT EXISTS (SELECT 1 FROM _scanner_seen WHERE _scanner_seen.id = playlist_songs.song_id)").await?;
 
         // 2. Delete children that are NOT in _scanner_seen
         self.inner.db.execute_unprepared("DELETE FROM children WHERE NOT EXISTS (SELECT 1 FROM _scanner_seen WHERE _scanner_seen.id = children.id)").await?;
 
-        // 3. Prune orphaned albums (no more songs referencing them)
+        self.prune_orphans().await?;
+
+        // Cleanup side table
+        seen::SeenTracker::clear(&self.inner.db).await?;
+
+        Ok(())
+    }
+
+    /// Removes the songs and directories at or below each of `paths`.
+    async fn prune_paths(&self, paths: &[String]) -> Result<(), anyhow::Error> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let backend = self.inner.db.get_database_backend();
+        for path in paths {
+            let path = path.trim_end_matches('/');
+            let prefix = format!("{}/", path);
+            let matching = "SELECT id FROM children WHERE path = ? OR substr(path, 1, ?) = ?";
+            // Dependents first, as in `prune`.
+            for (table, column) in [
+                ("lyrics", "song_id"),
+                ("song_artists", "song_id"),
+                ("song_genres", "song_id"),
+                ("song_contributors", "song_id"),
+                ("playlist_songs", "song_id"),
+                ("children", "id"),
+            ] {
+                self.inner
+                    .db
+                    .execute(Statement::from_sql_and_values(
+                        backend,
+                        format!("DELETE FROM {} WHERE {} IN ({})", table, column, matching),
+                        [
+                            path.into(),
+                            (prefix.chars().count() as i64).into(),
+                            prefix.clone().into(),
+                        ],
+                    ))
+                    .await?;
+            }
+        }
+        self.prune_orphans().await
+    }
+
+    /// Drops albums, artists and genres no longer referenced by any song.
+    async fn prune_orphans(&self) -> Result<(), anyhow::Error> {
+        // Prune orphaned albums (no more songs referencing them)
         // First delete junction records for those albums
         self.inner.db.execute_unprepared("DELETE FROM album_artists WHERE NOT EXISTS (SELECT 1 FROM children WHERE children.album_id = album_artists.album_id)").await?;
         self.inner.db.execute_unprepared("DELETE FROM album_genres WHERE NOT EXISTS (SELECT 1 FROM children WHERE children.album_id = album_genres.album_id)").await?;
         self.inner.db.execute_unprepared("DELETE FROM albums WHERE NOT EXISTS (SELECT 1 FROM children WHERE children.album_id = albums.id)").await?;
 
-        // 4. Prune orphaned artists
+        // Prune orphaned artists
         self.inner.db.execute_unprepared("DELETE FROM artists \
             WHERE NOT EXISTS (SELECT 1 FROM song_artists WHERE song_artists.artist_id = artists.id) \
-            AND NOT EXISTS (SELECT 1 FROM album_artists WHERE album_artists.artist_id = artists.id)").await?;
+            AND NOT EXISTS (SELECT 1 FROM album_artists WHERE album_artists.artist_id = artists.id) \
+            AND NOT EXISTS (SELECT 1 FROM song_contributors WHERE song_contributors.artist_id = artists.id)").await?;
 
-        // 5. Prune orphaned genres
+        // Prune orphaned genres
         self.inner.db.execute_unprepared("DELETE FROM genres \
             WHERE NOT EXISTS (SELECT 1 FROM album_genres WHERE album_genres.genre_name = genres.name) \
             AND NOT EXISTS (SELECT 1 FROM song_genres WHERE song_genres.genre_name = genres.name)").await?;
 
-        // Cleanup side table
-        seen::SeenTracker::clear(&self.inner.db).await?;
+        // Drop search entries of whatever was removed above
+        search_index::prune(&self.inner.db).await?;
 
         Ok(())
     }
 }
 
+/// An album row with only the basic tags set.
+fn new_album(name: &str, year: i32, created: chrono::DateTime<chrono::Utc>) -> album::ActiveModel {
+    album::ActiveModel {
+        name: Set(name.to_string()),
+        created: Set(created),
+        year: Set(year),
+        average_rating: Set(0.0),
+        sort_name: Set(None),
+        music_brainz_id: Set(None),
+        display_artist: Set(None),
+        release_types: Set(None),
+        record_labels: Set(None),
+        original_release_date: Set(None),
+        is_compilation: Set(false),
+        ..Default::default()
+    }
+}
+
 #[cfg(test)]
 #[path = "scanner_tests.rs"]
 mod tests;
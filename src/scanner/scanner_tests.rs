@@ -15,6 +15,7 @@ fn test_config() -> Arc<Config> {
             port: 8081,
             jwt_secret: "test".to_string(),
             password_secret: "test".to_string(),
+            base_url: None,
         },
         database: crate::config::DatabaseConfig {
             url: "sqlite::memory:".to_string(),
@@ -22,6 +23,39 @@ fn test_config() -> Arc<Config> {
         subsonic: crate::config::SubsonicConfig {
             data_dir: "/tmp/miko-test".to_string(),
             ignored_articles: "The".to_string(),
+            cover_art_priority: vec!["cover.*".to_string(), "embedded".to_string()],
+        },
+        scanner: crate::config::ScannerConfig {
+            watch: false,
+            watch_debounce_ms: 50,
+            schedule: None,
+            full_schedule: None,
+        },
+        transcode: crate::config::TranscodeConfig {
+            ffmpeg_path: "ffmpeg".to_string(),
+            profiles: Vec::new(),
+            replay_gain: crate::config::ReplayGainMode::Off,
+        },
+        metadata: crate::config::MetadataConfig {
+            providers: vec!["local".to_string()],
+            cache_ttl_hours: 168,
+        },
+        scrobble: crate::config::ScrobbleConfig {
+            listenbrainz_url: "http://127.0.0.1:1".to_string(),
+            lastfm_url: "http://127.0.0.1:1".to_string(),
+            lastfm_api_key: String::new(),
+            lastfm_api_secret: String::new(),
+        },
+        podcast: crate::config::PodcastConfig {
+            feed_base_url: None,
+            refresh_schedule: None,
+            retention: 10,
+            auto_download: false,
+        },
+        jukebox: crate::config::JukeboxConfig {
+            enabled: false,
+            command: None,
+            device: "alsa:default".into(),
         },
     })
 }
@@ -211,3 +245,119 @@ async fn build_album_dependencies_come_before_relations() {
         .unwrap();
     assert!(genre_pos < rel_pos, "Genre must be before AlbumRelations");
 }
+
+// ─── cover art discovery ─────────────────────────────────────────
+
+#[test]
+fn find_cover_source_follows_priority() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_path_buf();
+    std::fs::create_dir_all(&dir).unwrap();
+    let song = dir.join("01.mp3");
+    std::fs::write(&song, b"").unwrap();
+    std::fs::write(dir.join("Folder.PNG"), b"png").unwrap();
+    std::fs::write(dir.join("front.jpg"), b"jpg").unwrap();
+    std::fs::write(dir.join("cover.txt"), b"not an image").unwrap();
+
+    let priority: Vec<String> = ["cover.*", "folder.*", "front.*", "embedded"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+    assert_eq!(
+        utils::find_cover_source(&song, &priority, true),
+        Some(utils::CoverSource::File(dir.join("Folder.PNG")))
+    );
+
+    let embedded_first = vec!["embedded".to_string(), "front.*".to_string()];
+    assert_eq!(
+        utils::find_cover_source(&song, &embedded_first, true),
+        Some(utils::CoverSource::Embedded)
+    );
+    assert_eq!(
+        utils::find_cover_source(&song, &embedded_first, false),
+        Some(utils::CoverSource::File(dir.join("front.jpg")))
+    );
+
+    let none = vec!["cover.*".to_string()];
+    assert_eq!(utils::find_cover_source(&song, &none, true), None);
+}
+
+// ─── scan_paths ──────────────────────────────────────────────────
+
+#[tokio::test]
+async fn scan_paths_adds_new_trees_and_prunes_removed_ones() {
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ActiveModelTrait, ColumnTrait, QueryFilter};
+
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_path_buf();
+    let music = root.join("music");
+    let album = music.join("Artist").join("Album");
+    std::fs::create_dir_all(&album).unwrap();
+    std::fs::write(album.join("01.mp3"), b"not really an mp3").unwrap();
+    std::fs::write(album.join("cover.txt"), b"ignored").unwrap();
+
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let music_path = music.to_string_lossy().to_string();
+    music_folder::ActiveModel {
+        id: Set(1),
+        path: Set(music_path.clone()),
+        name: Set(None),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    let mut cfg = (*test_config()).clone();
+    cfg.subsonic.data_dir = root.join("data").to_string_lossy().to_string();
+    let scanner = Scanner::new(db.clone(), Arc::new(cfg));
+
+    assert!(scanner
+        .scan_paths(vec![music.join("Artist")])
+        .await
+        .unwrap());
+    let mut paths: Vec<String> = child::Entity::find()
+        .all(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|c| c.path.strip_prefix(&music_path).unwrap().to_string())
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec!["", "/Artist", "/Artist/Album", "/Artist/Album/01.mp3"]
+    );
+    assert_eq!(scanner.total_count(), 1);
+
+    std::fs::remove_dir_all(&album).unwrap();
+    assert!(scanner.scan_paths(vec![album.clone()]).await.unwrap());
+    let remaining = child::Entity::find()
+        .filter(child::Column::Path.starts_with(album.to_string_lossy().to_string()))
+        .all(&db)
+        .await
+        .unwrap();
+    assert!(remaining.is_empty());
+    assert!(child::Entity::find_by_id(utils::generate_id(
+        &music.join("Artist").to_string_lossy(),
+        1,
+        &music_path
+    ))
+    .one(&db)
+    .await
+    .unwrap()
+    .is_some());
+    assert_eq!(scanner.total_count(), 0);
+}
+
+#[test]
+fn content_type_maps_suffixes_to_mime_types() {
+    assert_eq!(utils::content_type("MP3"), "audio/mpeg");
+    assert_eq!(utils::content_type("m4a"), "audio/mp4");
+    assert_eq!(utils::content_type("opus"), "audio/ogg");
+    assert_eq!(utils::content_type("ogg"), "audio/ogg");
+    assert_eq!(utils::content_type("flac"), "audio/flac");
+    assert_eq!(utils::content_type("dsf"), "audio/dsf");
+}
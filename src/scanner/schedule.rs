@@ -0,0 +1,60 @@
+use crate::config::CronSchedule;
+use crate::scanner::Scanner;
+use chrono::{DateTime, Local};
+
+/// Picks the earliest upcoming run. Returns its time and whether it is a full
+/// scan; when both schedules fire at the same moment the full scan wins.
+pub fn next_run(
+    incremental: Option<DateTime<Local>>,
+    full: Option<DateTime<Local>>,
+) -> Option<(DateTime<Local>, bool)> {
+    match (incremental, full) {
+        (Some(inc), Some(full)) if inc < full => Some((inc, false)),
+        (_, Some(full)) => Some((full, true)),
+        (Some(inc), None) => Some((inc, false)),
+        (None, None) => None,
+    }
+}
+
+/// Runs `Scanner::scan_all` on the given schedules, evaluated in local time.
+/// Runs that come due while another scan is in progress are skipped.
+pub fn start(scanner: Scanner, incremental: Option<CronSchedule>, full: Option<CronSchedule>) {
+    if incremental.is_none() && full.is_none() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            let now = Local::now();
+            let Some((at, full_scan)) = next_run(
+                incremental.as_ref().and_then(|s| s.after(&now).next()),
+                full.as_ref().and_then(|s| s.after(&now).next()),
+            ) else {
+                log::info!("No upcoming scheduled scans");
+                scanner.set_next_scan_time(0);
+                return;
+            };
+
+            scanner.set_next_scan_time(at.timestamp());
+            log::info!(
+                "Next scheduled {} scan at {}",
+                if full_scan { "full" } else { "incremental" },
+                at
+            );
+            let wait = (at - Local::now()).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+
+            if scanner.is_scanning() {
+                log::info!("Skipping scheduled scan, another scan is running");
+                continue;
+            }
+            if let Err(e) = scanner.scan_all(!full_scan).await {
+                log::error!("Scheduled scan failed: {:?}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+#[path = "schedule_tests.rs"]
+mod tests;
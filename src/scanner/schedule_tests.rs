@@ -0,0 +1,54 @@
+use super::*;
+use crate::config::parse_cron_schedule;
+use chrono::TimeZone;
+
+fn at(h: u32, m: u32) -> DateTime<Local> {
+    Local.with_ymd_and_hms(2024, 5, 1, h, m, 0).unwrap()
+}
+
+#[test]
+fn next_run_prefers_earliest_and_full_on_ties() {
+    assert_eq!(next_run(None, None), None);
+    assert_eq!(next_run(Some(at(3, 0)), None), Some((at(3, 0), false)));
+    assert_eq!(
+        next_run(Some(at(3, 0)), Some(at(4, 0))),
+        Some((at(3, 0), false))
+    );
+    assert_eq!(
+        next_run(Some(at(5, 0)), Some(at(4, 0))),
+        Some((at(4, 0), true))
+    );
+    assert_eq!(
+        next_run(Some(at(4, 0)), Some(at(4, 0))),
+        Some((at(4, 0), true))
+    );
+}
+
+#[test]
+fn five_field_cron_expressions_are_accepted() {
+    let schedule = parse_cron_schedule("30 3 * * *").unwrap();
+    let next = schedule.after(&at(2, 0)).next().unwrap();
+    assert_eq!(next, at(3, 30));
+
+    let with_seconds = parse_cron_schedule("0 0 */6 * * *").unwrap();
+    assert_eq!(with_seconds.after(&at(2, 0)).next().unwrap(), at(6, 0));
+
+    // 2024-05-01 is a Wednesday; Sunday may be written as 0 or 7.
+    let sunday = Local.with_ymd_and_hms(2024, 5, 5, 4, 0, 0).unwrap();
+    for expr in ["0 4 * * 0", "0 4 * * 7", "0 4 * * SUN"] {
+        let schedule = parse_cron_schedule(expr).unwrap();
+        assert_eq!(
+            schedule.after(&at(2, 0)).next().unwrap(),
+            sunday,
+            "{}",
+            expr
+        );
+    }
+    let weekdays = parse_cron_schedule("0 9 * * 1-5").unwrap();
+    assert_eq!(
+        weekdays.after(&at(10, 0)).next().unwrap(),
+        at(9, 0) + chrono::Duration::days(1)
+    );
+
+    assert!(parse_cron_schedule("every night").is_err());
+}
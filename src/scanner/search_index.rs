@@ -0,0 +1,325 @@
+//! Full-text index over songs, albums and artists, kept in the `search_docs`
+//! table and its FTS5 shadow `search_index`. The flusher re-indexes whatever it
+//! writes, and pruning drops entries whose rows are gone.
+
+use sea_orm::sea_query::{Alias, Expr, Query, SimpleExpr};
+use sea_orm::{
+    ConnectionTrait, DbErr, EntityTrait, FromQueryResult, JoinType, QueryTrait, Select, Statement,
+    Value,
+};
+
+/// Rows per multi-row INSERT (5 bind parameters each).
+const CHUNK_SIZE: usize = 500;
+
+/// bm25 weights for the title, artist and album columns; lower ranks sort first.
+const RANK: &str = "bm25(search_index, 10.0, 5.0, 2.0)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Song,
+    Album,
+    Artist,
+}
+
+impl Kind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::Song => "song",
+            Kind::Album => "album",
+            Kind::Artist => "artist",
+        }
+    }
+
+    /// Table whose `id` the indexed `item_id` refers to.
+    pub fn table(self) -> &'static str {
+        match self {
+            Kind::Song => "children",
+            Kind::Album => "albums",
+            Kind::Artist => "artists",
+        }
+    }
+
+    /// Selects `item_id`, `title`, `artist` and `album` for documents of this kind.
+    fn documents(self) -> &'static str {
+        match self {
+            Kind::Song => {
+                "SELECT c.id AS item_id, c.title AS title, \
+                 (SELECT GROUP_CONCAT(a.name, ' ') FROM song_artists sa JOIN artists a ON a.id = sa.artist_id WHERE sa.song_id = c.id) AS artist, \
+                 (SELECT name FROM albums WHERE id = c.album_id) AS album \
+                 FROM children c WHERE c.is_dir = 0"
+            }
+            Kind::Album => {
+                "SELECT c.id AS item_id, c.name AS title, \
+                 (SELECT GROUP_CONCAT(a.name, ' ') FROM album_artists aa JOIN artists a ON a.id = aa.artist_id WHERE aa.album_id = c.id) AS artist, \
+                 NULL AS album \
+                 FROM albums c WHERE 1 = 1"
+            }
+            Kind::Artist => {
+                "SELECT c.id AS item_id, c.name AS title, NULL AS artist, NULL AS album \
+                 FROM artists c WHERE 1 = 1"
+            }
+        }
+    }
+}
+
+#[derive(FromQueryResult)]
+struct Document {
+    item_id: String,
+    title: String,
+    artist: Option<String>,
+    album: Option<String>,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x11FF     // Hangul Jamo
+        | 0x2E80..=0x2FDF   // CJK radicals
+        | 0x3040..=0x30FF   // Hiragana, Katakana
+        | 0x3130..=0x318F   // Hangul compatibility Jamo
+        | 0x3400..=0x4DBF   // CJK extension A
+        | 0x4E00..=0x9FFF   // CJK unified ideographs
+        | 0xAC00..=0xD7AF   // Hangul syllables
+        | 0xF900..=0xFAFF   // CJK compatibility ideographs
+        | 0x20000..=0x2FA1F // CJK extensions B-F
+    )
+}
+
+/// Prepares text for the index. The tokenizer only splits on spaces and
+/// punctuation, so CJK characters are spaced out to make words inside
+/// unspaced text findable; case and diacritics are folded by the tokenizer.
+pub fn index_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if is_cjk(c) {
+            out.push(' ');
+            out.push(c);
+            out.push(' ');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Turns a user query into an FTS5 MATCH expression in which every word has to
+/// match the start of an indexed word. Returns `None` when the query has
+/// nothing searchable, e.g. the empty query clients use to list everything.
+pub fn match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", index_text(&word).trim()))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Like [`match_expression`], but only matching within one indexed column
+/// (`title`, `artist` or `album`).
+pub fn match_column(column: &str, query: &str) -> Option<String> {
+    match_expression(query).map(|m| format!("{} : ({})", column, m))
+}
+
+/// Restricts rows of `kind`'s table to those matching `expression`.
+pub fn matches(kind: Kind, expression: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        format!(
+            "{}.id IN (SELECT d.item_id FROM search_index JOIN search_docs d ON d.id = search_index.rowid \
+             WHERE search_index MATCH ? AND d.kind = '{}')",
+            kind.table(),
+            kind.as_str()
+        ),
+        [expression.to_string()],
+    )
+}
+
+/// Alias of the matches joined by [`join_ranked`].
+const HITS: &str = "search_hits";
+
+/// Joins the documents of `kind` matching `expression` with their relevance,
+/// so the MATCH runs once per query. An inner join keeps only matching rows;
+/// a left join keeps every row and [`rank`] puts the others last.
+pub fn join_ranked<E: EntityTrait>(
+    mut select: Select<E>,
+    join: JoinType,
+    kind: Kind,
+    expression: &str,
+) -> Select<E> {
+    let hits = Query::select()
+        .expr_as(Expr::cust("d.item_id"), Alias::new("item_id"))
+        .expr_as(Expr::cust(RANK), Alias::new("score"))
+        .from(Alias::new("search_index"))
+        .join_as(
+            JoinType::InnerJoin,
+            Alias::new("search_docs"),
+            Alias::new("d"),
+            Expr::cust("d.id = search_index.rowid"),
+        )
+        .and_where(Expr::cust_with_values(
+            "search_index MATCH ?",
+            [expression.to_string()],
+        ))
+        .and_where(Expr::cust(format!("d.kind = '{}'", kind.as_str())))
+        .to_owned();
+    QueryTrait::query(&mut select).join_subquery(
+        join,
+        hits,
+        Alias::new(HITS),
+        Expr::cust(format!("{}.item_id = {}.id", HITS, kind.table())),
+    );
+    select
+}
+
+/// Relevance of rows joined by [`join_ranked`], to order matches by.
+pub fn rank() -> SimpleExpr {
+    Expr::cust(format!("COALESCE({}.score, 0)", HITS))
+}
+
+fn placeholders(n: usize) -> String {
+    vec!["?"; n].join(", ")
+}
+
+async fn write_documents<C: ConnectionTrait>(
+    db: &C,
+    kind: Kind,
+    documents: Vec<Document>,
+) -> Result<(), DbErr> {
+    let backend = db.get_database_backend();
+    for chunk in documents.chunks(CHUNK_SIZE) {
+        let mut values: Vec<Value> = Vec::with_capacity(chunk.len() * 5);
+        for doc in chunk {
+            values.push(kind.as_str().into());
+            values.push(doc.item_id.clone().into());
+            values.push(index_text(&doc.title).into());
+            values.push(index_text(doc.artist.as_deref().unwrap_or_default()).into());
+            values.push(index_text(doc.album.as_deref().unwrap_or_default()).into());
+        }
+        let rows = vec![format!("({})", placeholders(5)); chunk.len()].join(", ");
+        db.execute(Statement::from_sql_and_values(
+            backend,
+            format!(
+                "INSERT INTO search_docs (kind, item_id, title, artist, album) VALUES {}",
+                rows
+            ),
+            values,
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Re-indexes the given rows of `kind`, or all of them when `ids` is `None`.
+async fn reindex<C: ConnectionTrait>(
+    db: &C,
+    kind: Kind,
+    ids: Option<&[String]>,
+) -> Result<(), DbErr> {
+    let backend = db.get_database_backend();
+    let Some(ids) = ids else {
+        db.execute(Statement::from_sql_and_values(
+            backend,
+            "DELETE FROM search_docs WHERE kind = ?",
+            [kind.as_str().into()],
+        ))
+        .await?;
+        let documents =
+            Document::find_by_statement(Statement::from_string(backend, kind.documents()))
+                .all(db)
+                .await?;
+        return write_documents(db, kind, documents).await;
+    };
+
+    for chunk in ids.chunks(CHUNK_SIZE) {
+        let mut values: Vec<Value> = vec![kind.as_str().into()];
+        values.extend(chunk.iter().map(|id| Value::from(id.clone())));
+        db.execute(Statement::from_sql_and_values(
+            backend,
+            format!(
+                "DELETE FROM search_docs WHERE kind = ? AND item_id IN ({})",
+                placeholders(chunk.len())
+            ),
+            values,
+        ))
+        .await?;
+
+        let documents = Document::find_by_statement(Statement::from_sql_and_values(
+            backend,
+            format!(
+                "{} AND c.id IN ({})",
+                kind.documents(),
+                placeholders(chunk.len())
+            ),
+            chunk.iter().map(|id| Value::from(id.clone())),
+        ))
+        .all(db)
+        .await?;
+        write_documents(db, kind, documents).await?;
+    }
+    Ok(())
+}
+
+fn dedup(mut ids: Vec<String>) -> Vec<String> {
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+/// Re-indexes the given songs, albums and artists after they were written.
+pub async fn update<C: ConnectionTrait>(
+    db: &C,
+    songs: Vec<String>,
+    albums: Vec<String>,
+    artists: Vec<String>,
+) -> Result<(), DbErr> {
+    reindex(db, Kind::Song, Some(&dedup(songs))).await?;
+    reindex(db, Kind::Album, Some(&dedup(albums))).await?;
+    reindex(db, Kind::Artist, Some(&dedup(artists))).await
+}
+
+/// Rebuilds the whole index from the library tables.
+pub async fn rebuild<C: ConnectionTrait>(db: &C) -> Result<(), DbErr> {
+    for kind in [Kind::Song, Kind::Album, Kind::Artist] {
+        reindex(db, kind, None).await?;
+    }
+    Ok(())
+}
+
+/// Builds the index for a library scanned before it existed.
+pub async fn ensure<C: ConnectionTrait>(db: &C) -> Result<(), DbErr> {
+    let backend = db.get_database_backend();
+    let row = db
+        .query_one(Statement::from_string(
+            backend,
+            "SELECT NOT EXISTS (SELECT 1 FROM search_docs) \
+             AND EXISTS (SELECT 1 FROM children WHERE is_dir = 0) AS missing",
+        ))
+        .await?;
+    let missing = match row {
+        Some(row) => row.try_get::<bool>("", "missing")?,
+        None => false,
+    };
+    if missing {
+        log::info!("Building the search index...");
+        rebuild(db).await?;
+    }
+    Ok(())
+}
+
+/// Drops entries for songs, albums and artists that no longer exist.
+pub async fn prune<C: ConnectionTrait>(db: &C) -> Result<(), DbErr> {
+    for kind in [Kind::Song, Kind::Album, Kind::Artist] {
+        db.execute_unprepared(&format!(
+            "DELETE FROM search_docs WHERE kind = '{}' \
+             AND NOT EXISTS (SELECT 1 FROM {} WHERE {}.id = search_docs.item_id)",
+            kind.as_str(),
+            kind.table(),
+            kind.table()
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "search_index_tests.rs"]
+mod tests;
@@ -0,0 +1,178 @@
+use super::*;
+use crate::models::{album, artist, child, song_artist};
+use crate::test_support::{insert_folder, memory_db, song};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, EntityTrait, JoinType, Order, QueryFilter, QueryOrder,
+    Set,
+};
+
+async fn setup_db() -> DatabaseConnection {
+    let db = memory_db().await;
+    insert_folder(&db, 1, "/music1", &[]).await;
+
+    for (id, name) in [
+        ("ar1", "The Beatles"),
+        ("ar2", "Sigur Rós"),
+        ("ar3", "坂本龍一"),
+    ] {
+        artist::ActiveModel {
+            id: Set(id.to_string()),
+            name: Set(name.to_string()),
+            artist_image_url: Set(None),
+            average_rating: Set(0.0),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+    for (id, name) in [
+        ("al1", "Abbey Road"),
+        ("al2", "Ágætis byrjun"),
+        ("al3", "Here Comes"),
+    ] {
+        album::ActiveModel {
+            id: Set(id.to_string()),
+            name: Set(name.to_string()),
+            created: Set(Utc::now()),
+            average_rating: Set(0.0),
+            year: Set(0),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    // "Here Comes the Sun" has the words of album al3 in its title, and s4
+    // only matches them through its album name.
+    for (id, title, album_id, artist_id) in [
+        ("s1", "Here Comes the Sun", "al1", "ar1"),
+        ("s2", "Svefn-g-englar", "al2", "ar2"),
+        ("s3", "戦場のメリークリスマス", "al1", "ar3"),
+        ("s4", "Opening", "al3", "ar3"),
+    ] {
+        child::ActiveModel {
+            title: Set(title.to_string()),
+            album_id: Set(Some(album_id.to_string())),
+            ..song(id, 1)
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        song_artist::ActiveModel {
+            song_id: Set(id.to_string()),
+            artist_id: Set(artist_id.to_string()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    db
+}
+
+async fn find_songs(db: &DatabaseConnection, query: &str) -> Vec<String> {
+    let expression = match_expression(query).unwrap();
+    join_ranked(
+        child::Entity::find(),
+        JoinType::InnerJoin,
+        Kind::Song,
+        &expression,
+    )
+    .order_by(rank(), Order::Asc)
+    .all(db)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|c| c.id)
+    .collect()
+}
+
+async fn find_albums(db: &DatabaseConnection, query: &str) -> Vec<String> {
+    let expression = match_expression(query).unwrap();
+    album::Entity::find()
+        .filter(matches(Kind::Album, &expression))
+        .all(db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|a| a.id)
+        .collect()
+}
+
+#[test]
+fn match_expression_prefixes_every_word() {
+    assert_eq!(match_expression(""), None);
+    assert_eq!(match_expression("  \" - "), None);
+    assert_eq!(
+        match_expression("beatles abbey").as_deref(),
+        Some("\"beatles\"* \"abbey\"*")
+    );
+    assert_eq!(
+        match_expression("\"abbey\" OR").as_deref(),
+        Some("\"abbey\"* \"OR\"*")
+    );
+    assert_eq!(match_expression("龍一").as_deref(), Some("\"龍  一\"*"));
+}
+
+#[test]
+fn index_text_spaces_out_cjk() {
+    assert_eq!(index_text("Sigur Rós"), "Sigur Rós");
+    assert_eq!(index_text("坂本 Ryuichi"), " 坂  本  Ryuichi");
+}
+
+#[tokio::test]
+async fn matches_prefixes_accents_cjk_and_multiple_words() {
+    let db = setup_db().await;
+    rebuild(&db).await.unwrap();
+
+    assert_eq!(find_songs(&db, "beatles abbey").await, vec!["s1"]);
+    assert_eq!(find_songs(&db, "beat").await, vec!["s1"]);
+    assert_eq!(find_songs(&db, "sigur ros").await, vec!["s2"]);
+    assert_eq!(find_songs(&db, "AGÆTIS").await, vec!["s2"]);
+    assert_eq!(find_albums(&db, "agæ").await, vec!["al2"]);
+    assert_eq!(find_songs(&db, "メリー").await, vec!["s3"]);
+    let mut by_artist = find_songs(&db, "龍一").await;
+    by_artist.sort();
+    assert_eq!(by_artist, vec!["s3", "s4"]);
+    assert_eq!(
+        find_songs(&db, "beatles nothing").await,
+        Vec::<String>::new()
+    );
+}
+
+#[tokio::test]
+async fn ranks_title_matches_above_album_matches() {
+    let db = setup_db().await;
+    rebuild(&db).await.unwrap();
+
+    assert_eq!(find_songs(&db, "here comes").await, vec!["s1", "s4"]);
+}
+
+#[tokio::test]
+async fn update_and_prune_follow_library_changes() {
+    let db = setup_db().await;
+    ensure(&db).await.unwrap();
+    assert_eq!(find_songs(&db, "sun").await, vec!["s1"]);
+
+    let mut song: child::ActiveModel = child::Entity::find_by_id("s1")
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap()
+        .into();
+    song.title = Set("Something".to_string());
+    song.update(&db).await.unwrap();
+    update(&db, vec!["s1".into(), "s1".into()], vec![], vec![])
+        .await
+        .unwrap();
+    assert_eq!(find_songs(&db, "sun").await, Vec::<String>::new());
+    assert_eq!(find_songs(&db, "something").await, vec!["s1"]);
+
+    song_artist::Entity::delete_many().exec(&db).await.unwrap();
+    child::Entity::delete_by_id("s2").exec(&db).await.unwrap();
+    prune(&db).await.unwrap();
+    assert_eq!(find_songs(&db, "svefn").await, Vec::<String>::new());
+    assert_eq!(find_albums(&db, "agætis").await, vec!["al2"]);
+}
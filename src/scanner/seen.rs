@@ -17,9 +17,16 @@ pub struct SeenTracker;
 
 impl SeenTracker {
     pub async fn prepare(db: &DatabaseConnection) -> Result<(), anyhow::Error> {
+        Self::ensure(db).await?;
+        db.execute_unprepared("DELETE FROM _scanner_seen").await?;
+        Ok(())
+    }
+
+    /// Creates the side table without clearing it, for partial scans that only
+    /// need the flusher's `Seen` inserts to succeed.
+    pub async fn ensure(db: &DatabaseConnection) -> Result<(), anyhow::Error> {
         db.execute_unprepared("CREATE TABLE IF NOT EXISTS _scanner_seen (id TEXT PRIMARY KEY)")
             .await?;
-        db.execute_unprepared("DELETE FROM _scanner_seen").await?;
         Ok(())
     }
 
@@ -1,6 +1,10 @@
+use lofty::config::ParseOptions;
+use lofty::file::{FileType, TaggedFile};
+use lofty::mp4::{Mp4Codec, Mp4File};
 use lofty::prelude::*;
 use lofty::probe::Probe;
-use lofty::tag::Accessor;
+use lofty::tag::{Accessor, ItemKey};
+use std::io::Read;
 use std::path::Path;
 
 #[derive(Debug, Default)]
@@ -20,8 +24,71 @@ pub struct Tags {
     pub duration: i32,
     pub bitrate: i32,
     pub has_image: bool,
+    /// The artist tag as written, before splitting.
+    pub display_artist: Option<String>,
+    pub bpm: Option<i32>,
+    pub comment: Option<String>,
+    pub sort_name: Option<String>,
+    pub album_sort_name: Option<String>,
+    /// MusicBrainz recording id.
+    pub music_brainz_id: Option<String>,
+    /// MusicBrainz release id.
+    pub album_music_brainz_id: Option<String>,
+    pub explicit_status: Option<String>,
+    pub moods: Vec<String>,
+    pub disc_subtitle: Option<String>,
+    pub release_types: Vec<String>,
+    pub record_labels: Vec<String>,
+    pub original_release_date: Option<String>,
+    pub is_compilation: bool,
+    pub replay_gain_track_gain: Option<f64>,
+    pub replay_gain_track_peak: Option<f64>,
+    pub replay_gain_album_gain: Option<f64>,
+    pub replay_gain_album_peak: Option<f64>,
+    /// Ogg Opus header output gain in dB.
+    pub base_gain: Option<f64>,
+    pub channels: Option<i32>,
+    pub sample_rate: Option<i32>,
+    pub bit_depth: Option<i32>,
+    /// Codec of the audio stream, e.g. "alac" or "opus".
+    pub codec: Option<String>,
+    pub contributors: Vec<Contributor>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contributor {
+    /// OpenSubsonic role name, e.g. "composer".
+    pub role: &'static str,
+    /// Instrument of a performer.
+    pub sub_role: Option<String>,
+    pub name: String,
+}
+
+/// Credit tags and the OpenSubsonic roles they map to.
+const CONTRIBUTOR_ROLES: [(ItemKey, &str); 10] = [
+    (ItemKey::Composer, "composer"),
+    (ItemKey::Lyricist, "lyricist"),
+    (ItemKey::Conductor, "conductor"),
+    (ItemKey::Arranger, "arranger"),
+    (ItemKey::Producer, "producer"),
+    (ItemKey::Director, "director"),
+    (ItemKey::Engineer, "engineer"),
+    (ItemKey::MixEngineer, "mixer"),
+    (ItemKey::Remixer, "remixer"),
+    (ItemKey::MixDj, "djmixer"),
+];
+
+/// Codecs that decode to the original samples.
+pub const LOSSLESS_CODECS: [&str; 5] = ["flac", "alac", "pcm", "ape", "wavpack"];
+
+/// Keys MusicBrainz Picard writes the release type under, per tag format.
+const RELEASE_TYPE_KEYS: [&str; 4] = [
+    "RELEASETYPE",
+    "MUSICBRAINZ_ALBUMTYPE",
+    "MusicBrainz Album Type",
+    "----:com.apple.iTunes:MusicBrainz Album Type",
+];
+
 pub fn read(path: &Path) -> Result<Tags, anyhow::Error> {
     let tagged_file = Probe::open(path)?.read()?;
 
@@ -32,6 +99,13 @@ pub fn read(path: &Path) -> Result<Tags, anyhow::Error> {
     let mut tags = Tags {
         duration,
         bitrate,
+        channels: properties.channels().map(i32::from),
+        sample_rate: properties.sample_rate().map(|v| v as i32),
+        bit_depth: properties.bit_depth().map(i32::from),
+        codec: codec(&tagged_file, path),
+        base_gain: (tagged_file.file_type() == FileType::Opus)
+            .then(|| opus_output_gain(path))
+            .flatten(),
         ..Default::default()
     };
 
@@ -47,19 +121,19 @@ pub fn read(path: &Path) -> Result<Tags, anyhow::Error> {
         tags.year = tag.year().map(|v| v as i32);
 
         // Lyrics
-        if let Some(lyrics) = tag.get_string(&lofty::tag::ItemKey::Lyrics) {
+        if let Some(lyrics) = tag.get_string(&ItemKey::Lyrics) {
             tags.lyrics = lyrics.to_string();
         }
 
         // Try to get multiple artists from ARTISTS tag first
         let mut artists: Vec<String> = tag
-            .get_strings(&lofty::tag::ItemKey::TrackArtists)
+            .get_strings(&ItemKey::TrackArtists)
             .flat_map(split_tag)
             .collect();
         // Then fall back to ARTIST tag
         if artists.is_empty() {
             artists = tag
-                .get_strings(&lofty::tag::ItemKey::TrackArtist)
+                .get_strings(&ItemKey::TrackArtist)
                 .flat_map(split_tag)
                 .collect();
         }
@@ -69,7 +143,7 @@ pub fn read(path: &Path) -> Result<Tags, anyhow::Error> {
 
         tags.genres = split_tag(&tags.genre);
 
-        if let Some(item) = tag.get_string(&lofty::tag::ItemKey::AlbumArtist) {
+        if let Some(item) = tag.get_string(&ItemKey::AlbumArtist) {
             tags.album_artist = item.to_string();
         }
 
@@ -80,6 +154,81 @@ pub fn read(path: &Path) -> Result<Tags, anyhow::Error> {
         tags.album_artists = split_tag(&tags.album_artist);
 
         tags.has_image = !tag.pictures().is_empty();
+
+        tags.display_artist = first_string(tag, &ItemKey::TrackArtist);
+        tags.bpm = first_string(tag, &ItemKey::Bpm)
+            .or_else(|| first_string(tag, &ItemKey::IntegerBpm))
+            .and_then(|v| v.parse::<f64>().ok())
+            .map(|v| v.round() as i32)
+            .filter(|&v| v > 0);
+        tags.comment = tag
+            .comment()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        tags.sort_name = first_string(tag, &ItemKey::TrackTitleSortOrder);
+        tags.album_sort_name = first_string(tag, &ItemKey::AlbumTitleSortOrder);
+        tags.music_brainz_id = first_string(tag, &ItemKey::MusicBrainzRecordingId);
+        tags.album_music_brainz_id = first_string(tag, &ItemKey::MusicBrainzReleaseId);
+        tags.explicit_status =
+            first_string(tag, &ItemKey::ParentalAdvisory).and_then(|v| explicit_status(&v));
+        tags.moods = tag
+            .get_strings(&ItemKey::Mood)
+            .flat_map(split_tag)
+            .collect();
+        tags.disc_subtitle = first_string(tag, &ItemKey::SetSubtitle);
+        for key in RELEASE_TYPE_KEYS {
+            for value in tag.get_strings(&ItemKey::Unknown(key.to_string())) {
+                for release_type in split_tag(value) {
+                    if !tags.release_types.contains(&release_type) {
+                        tags.release_types.push(release_type);
+                    }
+                }
+            }
+        }
+        tags.record_labels = tag
+            .get_strings(&ItemKey::Label)
+            .flat_map(split_tag)
+            .collect();
+        tags.original_release_date =
+            first_string(tag, &ItemKey::OriginalReleaseDate).and_then(|v| release_date(&v));
+        tags.is_compilation = first_string(tag, &ItemKey::FlagCompilation)
+            .is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+
+        tags.replay_gain_track_gain =
+            first_string(tag, &ItemKey::ReplayGainTrackGain).and_then(|v| parse_gain(&v));
+        tags.replay_gain_track_peak =
+            first_string(tag, &ItemKey::ReplayGainTrackPeak).and_then(|v| parse_gain(&v));
+        tags.replay_gain_album_gain =
+            first_string(tag, &ItemKey::ReplayGainAlbumGain).and_then(|v| parse_gain(&v));
+        tags.replay_gain_album_peak =
+            first_string(tag, &ItemKey::ReplayGainAlbumPeak).and_then(|v| parse_gain(&v));
+        // Opus files carry R128 gains instead; they have no peak counterpart.
+        tags.replay_gain_track_gain = tags.replay_gain_track_gain.or_else(|| {
+            first_string(tag, &ItemKey::Unknown("R128_TRACK_GAIN".into()))
+                .and_then(|v| r128_gain(&v))
+        });
+        tags.replay_gain_album_gain = tags.replay_gain_album_gain.or_else(|| {
+            first_string(tag, &ItemKey::Unknown("R128_ALBUM_GAIN".into()))
+                .and_then(|v| r128_gain(&v))
+        });
+
+        for (key, role) in &CONTRIBUTOR_ROLES {
+            for name in tag.get_strings(key).flat_map(split_tag) {
+                tags.contributors.push(Contributor {
+                    role,
+                    sub_role: None,
+                    name,
+                });
+            }
+        }
+        for value in tag.get_strings(&ItemKey::Performer).flat_map(split_tag) {
+            let (name, instrument) = performer(&value);
+            tags.contributors.push(Contributor {
+                role: "performer",
+                sub_role: instrument,
+                name,
+            });
+        }
     }
 
     Ok(tags)
@@ -95,6 +244,120 @@ fn split_tag(s: &str) -> Vec<String> {
         .collect()
 }
 
+fn first_string(tag: &lofty::tag::Tag, key: &ItemKey) -> Option<String> {
+    tag.get_string(key)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+/// Parses a ReplayGain value such as "-6.54 dB" or "0.988831".
+pub fn parse_gain(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let value = value
+        .strip_suffix("dB")
+        .or_else(|| value.strip_suffix("db"))
+        .unwrap_or(value);
+    value.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// The codec of a file's audio stream. MP4 files are read again for it, as
+/// the suffix doesn't tell AAC from ALAC.
+pub fn codec(tagged_file: &TaggedFile, path: &Path) -> Option<String> {
+    let codec = match tagged_file.file_type() {
+        FileType::Aac => "aac",
+        FileType::Aiff | FileType::Wav => "pcm",
+        FileType::Ape => "ape",
+        FileType::Flac => "flac",
+        FileType::Mpeg => "mp3",
+        FileType::Mp4 => return mp4_codec(path),
+        FileType::Mpc => "mpc",
+        FileType::Opus => "opus",
+        FileType::Vorbis => "vorbis",
+        FileType::Speex => "speex",
+        FileType::WavPack => "wavpack",
+        _ => return None,
+    };
+    Some(codec.to_string())
+}
+
+fn mp4_codec(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let options = ParseOptions::new().read_tags(false).read_cover_art(false);
+    let mp4 = Mp4File::read_from(&mut file, options).ok()?;
+    let codec = match mp4.properties().codec() {
+        Mp4Codec::AAC => "aac",
+        Mp4Codec::ALAC => "alac",
+        Mp4Codec::MP3 => "mp3",
+        Mp4Codec::FLAC => "flac",
+        _ => return None,
+    };
+    Some(codec.to_string())
+}
+
+/// Converts an R128 gain (a Q7.8 integer relative to -23 LUFS) to a
+/// ReplayGain value, which is relative to -18 LUFS.
+pub fn r128_gain(value: &str) -> Option<f64> {
+    let gain = value.trim().parse::<i16>().ok()?;
+    Some(f64::from(gain) / 256.0 + 5.0)
+}
+
+/// Reads the output gain from the identification header of an Ogg Opus file.
+fn opus_output_gain(path: &Path) -> Option<f64> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)
+        .ok()?
+        .take(512)
+        .read_to_end(&mut head)
+        .ok()?;
+    opus_header_gain(&head)
+}
+
+/// Extracts the output gain (a Q7.8 integer in dB) from the `OpusHead` packet
+/// found in the first Ogg page.
+pub fn opus_header_gain(page: &[u8]) -> Option<f64> {
+    let start = page.windows(8).position(|w| w == b"OpusHead")?;
+    // magic (8), version (1), channels (1), pre-skip (2), input sample rate (4)
+    let gain = page.get(start + 16..start + 18)?;
+    let gain = i16::from_le_bytes([gain[0], gain[1]]);
+    Some(f64::from(gain) / 256.0)
+}
+
+/// Maps iTunes advisory ratings ("1"/"4" explicit, "2" clean) and their
+/// spelled-out forms to OpenSubsonic's explicit status.
+pub fn explicit_status(value: &str) -> Option<String> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "4" | "explicit" => Some("explicit".into()),
+        "2" | "clean" => Some("clean".into()),
+        _ => None,
+    }
+}
+
+/// Keeps the "YYYY", "YYYY-MM" or "YYYY-MM-DD" prefix of a tagged date.
+pub fn release_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    [10, 7, 4].into_iter().find_map(|len| {
+        let date = value.get(..len)?;
+        let valid = date.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        });
+        valid.then(|| date.to_string())
+    })
+}
+
+/// Splits a "Name (instrument)" performer credit.
+pub fn performer(value: &str) -> (String, Option<String>) {
+    if let Some(rest) = value.strip_suffix(')') {
+        if let Some((name, instrument)) = rest.rsplit_once(" (") {
+            if !name.trim().is_empty() && !instrument.trim().is_empty() {
+                return (name.trim().to_string(), Some(instrument.trim().to_string()));
+            }
+        }
+    }
+    (value.trim().to_string(), None)
+}
+
 pub fn read_image(path: &Path) -> Result<Vec<u8>, anyhow::Error> {
     let tagged_file = Probe::open(path)?.read()?;
     if let Some(tag) = tagged_file
@@ -107,3 +370,7 @@ pub fn read_image(path: &Path) -> Result<Vec<u8>, anyhow::Error> {
     }
     anyhow::bail!("No image found")
 }
+
+#[cfg(test)]
+#[path = "tags_tests.rs"]
+mod tests;
@@ -0,0 +1,65 @@
+use super::*;
+
+#[test]
+fn parse_gain_accepts_db_suffix() {
+    assert_eq!(parse_gain("-6.54 dB"), Some(-6.54));
+    assert_eq!(parse_gain("+1.20 db"), Some(1.2));
+    assert_eq!(parse_gain(" 0.988831 "), Some(0.988831));
+    assert_eq!(parse_gain("loud"), None);
+    assert_eq!(parse_gain("NaN dB"), None);
+}
+
+#[test]
+fn explicit_status_maps_advisory_ratings() {
+    assert_eq!(explicit_status("1").as_deref(), Some("explicit"));
+    assert_eq!(explicit_status("4").as_deref(), Some("explicit"));
+    assert_eq!(explicit_status("Explicit").as_deref(), Some("explicit"));
+    assert_eq!(explicit_status("2").as_deref(), Some("clean"));
+    assert_eq!(explicit_status("0"), None);
+}
+
+#[test]
+fn release_date_keeps_the_date_part() {
+    assert_eq!(release_date("1969").as_deref(), Some("1969"));
+    assert_eq!(release_date("1969-09").as_deref(), Some("1969-09"));
+    assert_eq!(release_date("1969-09-26").as_deref(), Some("1969-09-26"));
+    assert_eq!(
+        release_date("1969-09-26T00:00:00").as_deref(),
+        Some("1969-09-26")
+    );
+    assert_eq!(release_date("1969/09/26").as_deref(), Some("1969"));
+    assert_eq!(release_date("late 60s"), None);
+}
+
+#[test]
+fn performer_splits_instrument() {
+    assert_eq!(
+        performer("Jaco Pastorius (bass guitar)"),
+        (
+            "Jaco Pastorius".to_string(),
+            Some("bass guitar".to_string())
+        )
+    );
+    assert_eq!(performer("Björk"), ("Björk".to_string(), None));
+    assert_eq!(performer("(untitled)"), ("(untitled)".to_string(), None));
+}
+
+#[test]
+fn r128_gain_converts_to_replay_gain_reference() {
+    assert_eq!(r128_gain("0"), Some(5.0));
+    assert_eq!(r128_gain("-2560"), Some(-5.0));
+    assert_eq!(r128_gain(" 384 "), Some(6.5));
+    assert_eq!(r128_gain("-6.5 dB"), None);
+}
+
+#[test]
+fn opus_header_gain_reads_output_gain() {
+    let mut page = b"OggS\0\x02".to_vec();
+    page.extend_from_slice(&[0; 21]);
+    page.extend_from_slice(b"OpusHead\x01\x02\x38\x01\x80\xbb\0\0");
+    page.extend_from_slice(&(-768i16).to_le_bytes());
+    page.push(0);
+    assert_eq!(opus_header_gain(&page), Some(-3.0));
+    assert_eq!(opus_header_gain(b"OggS\0\x02OpusTags"), None);
+    assert_eq!(opus_header_gain(b"OpusHead\x01\x02"), None);
+}
@@ -1,10 +1,19 @@
 use crate::models::{album, artist, child, genre};
+use crate::scanner::lyrics::LyricsSet;
 
 pub struct SongRelations {
     pub song_id: String,
     pub artists: Vec<String>,
     pub genres: Vec<String>,
-    pub lyrics: Option<String>,
+    pub lyrics: Vec<LyricsSet>,
+    pub contributors: Vec<ContributorRelation>,
+}
+
+pub struct ContributorRelation {
+    pub role: String,
+    /// Empty unless the role has one, e.g. a performer's instrument.
+    pub sub_role: String,
+    pub artist_id: String,
 }
 
 pub struct AlbumRelations {
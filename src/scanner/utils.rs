@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::models::music_folder;
 use md5;
 use std::path::{Path, PathBuf};
 
@@ -46,3 +47,105 @@ pub fn get_parent_id(path: &str, folder_id: i32, folder_path: &str) -> Option<St
 pub fn is_audio_file(ext: &str) -> bool {
     matches!(ext, "mp3" | "flac" | "m4a" | "wav" | "ogg" | "opus")
 }
+
+/// The MIME type of an audio file by its suffix. Ogg and MP4 containers have
+/// one type whatever the codec inside.
+pub fn content_type(ext: &str) -> String {
+    let ext = ext.to_lowercase();
+    match ext.as_str() {
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "m4a" | "m4b" | "mp4" => "audio/mp4",
+        "aac" => "audio/aac",
+        "wav" => "audio/wav",
+        "aif" | "aiff" => "audio/aiff",
+        "ogg" | "oga" | "opus" => "audio/ogg",
+        _ => return format!("audio/{}", ext),
+    }
+    .to_string()
+}
+
+/// The music folder containing `path`; the innermost one if folders are nested.
+pub fn folder_for_path<'a>(
+    folders: &'a [music_folder::Model],
+    path: &Path,
+) -> Option<&'a music_folder::Model> {
+    folders
+        .iter()
+        .filter(|f| path.starts_with(&f.path))
+        .max_by_key(|f| f.path.len())
+}
+
+/// Where the cover art for a song should be read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoverSource {
+    Embedded,
+    File(PathBuf),
+}
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "gif", "bmp"];
+
+/// Case-insensitive glob match supporting `*` wildcards.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    fn matches(p: &[u8], n: &[u8]) -> bool {
+        match p.split_first() {
+            None => n.is_empty(),
+            Some((b'*', rest)) => (0..=n.len()).any(|i| matches(rest, &n[i..])),
+            Some((c, rest)) => n
+                .split_first()
+                .is_some_and(|(d, n_rest)| c == d && matches(rest, n_rest)),
+        }
+    }
+    matches(
+        pattern.to_lowercase().as_bytes(),
+        name.to_lowercase().as_bytes(),
+    )
+}
+
+/// Picks the cover art source for the audio file at `song_path` by walking `priority`
+/// in order. Entries are either `embedded` or filename globs matched against image
+/// files in the song's directory (e.g. `cover.*`, `folder.png`).
+pub fn find_cover_source(
+    song_path: &Path,
+    priority: &[String],
+    has_embedded: bool,
+) -> Option<CoverSource> {
+    let images: Vec<PathBuf> = song_path
+        .parent()
+        .and_then(|dir| std::fs::read_dir(dir).ok())
+        .map(|entries| {
+            let mut files: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_file())
+                .filter(|p| {
+                    p.extension()
+                        .and_then(|e| e.to_str())
+                        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+                })
+                .collect();
+            // read_dir order is unspecified; keep the choice stable across scans
+            files.sort();
+            files
+        })
+        .unwrap_or_default();
+
+    for source in priority {
+        if source == "embedded" {
+            if has_embedded {
+                return Some(CoverSource::Embedded);
+            }
+            continue;
+        }
+        let found = images.iter().find(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| glob_match(source, n))
+        });
+        if let Some(path) = found {
+            return Some(CoverSource::File(path.clone()));
+        }
+    }
+
+    None
+}
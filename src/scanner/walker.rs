@@ -1,5 +1,5 @@
 use crate::{models::music_folder, scanner::utils::is_audio_file};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::sync::mpsc;
 use walkdir::WalkDir;
 
@@ -17,6 +17,50 @@ pub struct WalkTask {
 pub struct Walker;
 
 impl Walker {
+    /// Builds the task for a single path, or `None` if it is neither a directory
+    /// nor an audio file.
+    pub fn task_for(path: &Path, folder: &music_folder::Model) -> Option<WalkTask> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Self::make_task(path, &metadata, folder)
+    }
+
+    fn make_task(
+        p: &Path,
+        metadata: &std::fs::Metadata,
+        folder: &music_folder::Model,
+    ) -> Option<WalkTask> {
+        // get ext and name
+        let ext = p
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        // filter only dir or audio files
+        if !metadata.is_dir() && !is_audio_file(ext.as_str()) {
+            return None;
+        }
+        let name = p
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+
+        let mod_time: chrono::DateTime<chrono::Utc> = metadata
+            .modified()
+            .unwrap_or_else(|_| std::time::SystemTime::now())
+            .into();
+
+        Some(WalkTask {
+            path: p.to_string_lossy().replace('\\', "/"),
+            is_dir: metadata.is_dir(),
+            name,
+            ext,
+            size: metadata.len(),
+            mod_time,
+            folder: folder.clone(),
+        })
+    }
+
     pub fn walk_path(path: PathBuf, folder: music_folder::Model, tx: mpsc::Sender<WalkTask>) {
         tokio::task::spawn_blocking(move || {
             for entry in WalkDir::new(&path).into_iter().filter_map(|e| e.ok()) {
@@ -25,36 +69,8 @@ impl Walker {
                     Err(_) => continue,
                 };
 
-                let p = entry.path();
-                // get ext and name
-                let ext = p
-                    .extension()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("")
-                    .to_lowercase();
-                // filter only dir or audio files
-                if !metadata.is_dir() && !is_audio_file(ext.as_str()) {
+                let Some(task) = Self::make_task(entry.path(), &metadata, &folder) else {
                     continue;
-                }
-                let name = p
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                let mod_time: chrono::DateTime<chrono::Utc> = metadata
-                    .modified()
-                    .unwrap_or_else(|_| std::time::SystemTime::now())
-                    .into();
-
-                let task = WalkTask {
-                    path: entry.path().to_string_lossy().replace('\\', "/"),
-                    is_dir: entry.file_type().is_dir(),
-                    name: name,
-                    ext: ext,
-                    size: metadata.len(),
-                    mod_time,
-                    folder: folder.clone(),
                 };
 
                 if tx.blocking_send(task).is_err() {
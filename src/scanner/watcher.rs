@@ -0,0 +1,146 @@
+use crate::models::music_folder;
+use crate::scanner::Scanner;
+use notify::event::{AccessKind, AccessMode, ModifyKind};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+use sea_orm::EntityTrait;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// How often the set of watched folders is reconciled with `music_folders`.
+const FOLDER_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A steady stream of events (e.g. a long copy) is applied at least this many
+/// debounce periods after it started.
+const MAX_DEBOUNCE_FACTOR: u32 = 10;
+
+/// Whether an event can change what the scanner would record for its paths.
+/// Plain reads and attribute changes (atime, permissions) are ignored.
+pub fn is_relevant(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Create(_) | EventKind::Remove(_) => true,
+        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+        EventKind::Modify(_) => true,
+        EventKind::Access(AccessKind::Close(AccessMode::Write)) => true,
+        _ => false,
+    }
+}
+
+/// Sorts and dedupes `paths`, dropping any path below another one in the set,
+/// since rescanning a directory already covers everything inside it.
+pub fn collapse_paths(paths: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = paths.into_iter().collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut collapsed: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if collapsed.last().is_some_and(|prev| path.starts_with(prev)) {
+            continue;
+        }
+        collapsed.push(path);
+    }
+    collapsed
+}
+
+/// Watches every music folder and feeds changed paths through
+/// [`Scanner::scan_paths`] once events have been quiet for `debounce`.
+pub fn start(scanner: Scanner, debounce: Duration) -> Result<(), anyhow::Error> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let watcher = notify::recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) if is_relevant(&event.kind) => {
+            for path in event.paths {
+                let _ = tx.send(path);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Filesystem watch error: {}", e),
+    })?;
+
+    tokio::spawn(run(scanner, watcher, rx, debounce));
+    Ok(())
+}
+
+async fn run(
+    scanner: Scanner,
+    mut watcher: RecommendedWatcher,
+    mut rx: mpsc::UnboundedReceiver<PathBuf>,
+    debounce: Duration,
+) {
+    let mut watched: HashSet<PathBuf> = HashSet::new();
+    let mut pending: HashSet<PathBuf> = HashSet::new();
+    let mut first_event = Instant::now();
+    let mut refresh = tokio::time::interval(FOLDER_REFRESH_INTERVAL);
+
+    loop {
+        let overdue =
+            !pending.is_empty() && first_event.elapsed() >= debounce * MAX_DEBOUNCE_FACTOR;
+        tokio::select! {
+            _ = refresh.tick() => sync_folders(&scanner, &mut watcher, &mut watched).await,
+            path = rx.recv(), if !overdue => {
+                let Some(path) = path else { break };
+                if pending.is_empty() {
+                    first_event = Instant::now();
+                }
+                pending.insert(path);
+            }
+            _ = tokio::time::sleep(if overdue { Duration::ZERO } else { debounce }), if !pending.is_empty() => {
+                let paths = collapse_paths(pending.drain());
+                log::info!("Detected changes in {} path(s), rescanning", paths.len());
+                match scanner.scan_paths(paths.clone()).await {
+                    Ok(true) => {}
+                    // Another scan is running; try again later.
+                    Ok(false) => {
+                        pending.extend(paths);
+                        first_event = Instant::now();
+                    }
+                    Err(e) => log::error!("Incremental rescan failed: {}", e),
+                }
+            }
+        }
+    }
+}
+
+/// Starts watching newly added folders and stops watching removed ones.
+async fn sync_folders(
+    scanner: &Scanner,
+    watcher: &mut RecommendedWatcher,
+    watched: &mut HashSet<PathBuf>,
+) {
+    let folders = match music_folder::Entity::find().all(scanner.db()).await {
+        Ok(f) => f,
+        Err(e) => {
+            log::error!("Failed to load music folders for watching: {}", e);
+            return;
+        }
+    };
+    let current: HashSet<PathBuf> = folders.into_iter().map(|f| PathBuf::from(f.path)).collect();
+
+    for path in watched.difference(&current) {
+        if let Err(e) = watcher.unwatch(path) {
+            log::warn!("Failed to stop watching '{}': {}", path.display(), e);
+        }
+    }
+    watched.retain(|p| current.contains(p));
+
+    for path in current {
+        if watched.contains(&path) {
+            continue;
+        }
+        match watcher.watch(&path, RecursiveMode::Recursive) {
+            Ok(()) => {
+                log::info!("Watching '{}' for changes", path.display());
+                watched.insert(path);
+            }
+            // Retried on the next refresh; large libraries may need a higher
+            // fs.inotify.max_user_watches.
+            Err(e) => log::warn!("Failed to watch '{}': {}", path.display(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "watcher_tests.rs"]
+mod tests;
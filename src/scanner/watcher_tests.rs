@@ -0,0 +1,39 @@
+use super::*;
+use notify::event::{CreateKind, DataChange, MetadataKind, RemoveKind};
+
+#[test]
+fn ignores_reads_and_attribute_changes() {
+    assert!(is_relevant(&EventKind::Create(CreateKind::File)));
+    assert!(is_relevant(&EventKind::Remove(RemoveKind::Folder)));
+    assert!(is_relevant(&EventKind::Modify(ModifyKind::Data(
+        DataChange::Content
+    ))));
+    assert!(is_relevant(&EventKind::Access(AccessKind::Close(
+        AccessMode::Write
+    ))));
+    assert!(!is_relevant(&EventKind::Modify(ModifyKind::Metadata(
+        MetadataKind::AccessTime
+    ))));
+    assert!(!is_relevant(&EventKind::Access(AccessKind::Open(
+        AccessMode::Read
+    ))));
+}
+
+#[test]
+fn collapse_keeps_only_outermost_paths() {
+    let paths = collapse_paths(
+        [
+            "/music/Artist/Album/02.flac",
+            "/music/Artist",
+            "/music/Artist/Album/01.flac",
+            "/music/Artist2/01.mp3",
+            "/music/Artist2/01.mp3",
+            "/music/Artist 3",
+        ]
+        .map(PathBuf::from),
+    );
+    assert_eq!(
+        paths,
+        ["/music/Artist", "/music/Artist 3", "/music/Artist2/01.mp3"].map(PathBuf::from)
+    );
+}
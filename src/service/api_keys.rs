@@ -0,0 +1,114 @@
+//! API keys for OpenSubsonic `apiKey` authentication. Keys are random strings
+//! handed out once; only their SHA-256 is stored, so unlike passwords they
+//! never need to be recoverable.
+
+use crate::models::{api_key, user};
+use crate::service::Service;
+use chrono::{Duration, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set,
+};
+use sha2::{Digest, Sha256};
+
+const KEY_LEN: usize = 40;
+
+/// How stale `last_used_at` may get before a request records the use again.
+const LAST_USED_INTERVAL: Duration = Duration::minutes(1);
+
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// The user `key` belongs to, recording the use and the client it came from.
+/// The use is written at most once per [`LAST_USED_INTERVAL`] unless the
+/// client changed, so busy clients don't write on every request.
+pub async fn authenticate<C: ConnectionTrait>(
+    db: &C,
+    key: &str,
+    client: Option<&str>,
+) -> Result<Option<user::Model>, DbErr> {
+    let Some((key, Some(user))) = api_key::Entity::find()
+        .filter(api_key::Column::KeyHash.eq(hash_key(key)))
+        .find_also_related(user::Entity)
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let now = Utc::now();
+    let stale = key
+        .last_used_at
+        .is_none_or(|at| now - at >= LAST_USED_INTERVAL);
+    let new_client = client.is_some_and(|c| key.last_client.as_deref() != Some(c));
+    if stale || new_client {
+        let mut key: api_key::ActiveModel = key.into();
+        key.last_used_at = Set(Some(now));
+        if let Some(client) = client {
+            key.last_client = Set(Some(client.to_string()));
+        }
+        key.update(db).await?;
+    }
+    Ok(Some(user))
+}
+
+impl Service {
+    /// Creates a key for `username`, returning it with the only copy of the key.
+    pub async fn create_api_key(
+        &self,
+        username: &str,
+        name: String,
+    ) -> Result<(api_key::Model, String), DbErr> {
+        let key: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(KEY_LEN)
+            .map(char::from)
+            .collect();
+        let model = api_key::ActiveModel {
+            username: Set(username.to_string()),
+            name: Set(name),
+            key_hash: Set(hash_key(&key)),
+            created_at: Set(Utc::now()),
+            last_used_at: Set(None),
+            last_client: Set(None),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+        Ok((model, key))
+    }
+
+    pub async fn get_api_keys(&self, username: &str) -> Result<Vec<api_key::Model>, DbErr> {
+        api_key::Entity::find()
+            .filter(api_key::Column::Username.eq(username))
+            .order_by_desc(api_key::Column::CreatedAt)
+            .all(&self.db)
+            .await
+    }
+
+    /// Whether `key` is one of `username`'s keys.
+    pub async fn is_api_key_of(&self, key: &str, username: &str) -> Result<bool, DbErr> {
+        let count = api_key::Entity::find()
+            .filter(api_key::Column::KeyHash.eq(hash_key(key)))
+            .filter(api_key::Column::Username.eq(username))
+            .count(&self.db)
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// Revokes key `id` of `username`; false if they have no such key.
+    pub async fn delete_api_key(&self, id: i32, username: &str) -> Result<bool, DbErr> {
+        let result = api_key::Entity::delete_many()
+            .filter(api_key::Column::Id.eq(id))
+            .filter(api_key::Column::Username.eq(username))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+#[cfg(test)]
+#[path = "api_keys_tests.rs"]
+mod tests;
@@ -0,0 +1,107 @@
+use super::*;
+use crate::test_support::{insert_user, memory_db};
+use sea_orm::DatabaseConnection;
+
+async fn setup_db() -> DatabaseConnection {
+    let db = memory_db().await;
+    for username in ["alice", "bob"] {
+        insert_user(&db, username).await;
+    }
+    db
+}
+
+#[tokio::test]
+async fn authenticates_by_key_and_records_use() {
+    let service = Service::new(setup_db().await);
+    let (created, key) = service
+        .create_api_key("alice", "Phone".to_string())
+        .await
+        .unwrap();
+    assert_eq!(key.len(), KEY_LEN);
+    assert_ne!(created.key_hash, key);
+    assert_eq!(created.last_used_at, None);
+
+    let user = authenticate(&service.db, &key, Some("DSub"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.username, "alice");
+    assert!(authenticate(&service.db, "wrong", Some("DSub"))
+        .await
+        .unwrap()
+        .is_none());
+
+    let keys = service.get_api_keys("alice").await.unwrap();
+    assert_eq!(keys.len(), 1);
+    assert!(keys[0].last_used_at.is_some());
+    assert_eq!(keys[0].last_client.as_deref(), Some("DSub"));
+}
+
+#[tokio::test]
+async fn only_owners_list_and_revoke_keys() {
+    let service = Service::new(setup_db().await);
+    let (alice_key, key) = service
+        .create_api_key("alice", "Phone".to_string())
+        .await
+        .unwrap();
+    service
+        .create_api_key("bob", "Laptop".to_string())
+        .await
+        .unwrap();
+
+    let names: Vec<String> = service
+        .get_api_keys("bob")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|k| k.name)
+        .collect();
+    assert_eq!(names, vec!["Laptop"]);
+
+    assert!(!service.delete_api_key(alice_key.id, "bob").await.unwrap());
+    assert!(service.is_api_key_of(&key, "alice").await.unwrap());
+    assert!(!service.is_api_key_of(&key, "bob").await.unwrap());
+    assert!(authenticate(&service.db, &key, None)
+        .await
+        .unwrap()
+        .is_some());
+    assert!(service.delete_api_key(alice_key.id, "alice").await.unwrap());
+    assert!(!service.is_api_key_of(&key, "alice").await.unwrap());
+    assert!(authenticate(&service.db, &key, None)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn records_use_once_a_minute_or_on_new_client() {
+    let service = Service::new(setup_db().await);
+    let (_, key) = service
+        .create_api_key("alice", "Phone".to_string())
+        .await
+        .unwrap();
+    let last_use = || async {
+        let key = &service.get_api_keys("alice").await.unwrap()[0];
+        (key.last_used_at.unwrap(), key.last_client.clone().unwrap())
+    };
+
+    authenticate(&service.db, &key, Some("DSub")).await.unwrap();
+    let (first, _) = last_use().await;
+    authenticate(&service.db, &key, Some("DSub")).await.unwrap();
+    assert_eq!(last_use().await, (first, "DSub".to_string()));
+
+    authenticate(&service.db, &key, Some("Feishin"))
+        .await
+        .unwrap();
+    let (second, client) = last_use().await;
+    assert!(second > first);
+    assert_eq!(client, "Feishin");
+
+    let mut stale: api_key::ActiveModel = service.get_api_keys("alice").await.unwrap()[0]
+        .clone()
+        .into();
+    stale.last_used_at = Set(Some(second - LAST_USED_INTERVAL));
+    stale.update(&service.db).await.unwrap();
+    authenticate(&service.db, &key, None).await.unwrap();
+    assert!(last_use().await.0 > second);
+}
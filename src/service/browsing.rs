@@ -2,7 +2,7 @@ use crate::models::{album_genre, artist, child, genre, queries, song_artist, son
 use crate::service::Service;
 use sea_orm::sea_query::Expr;
 use sea_orm::{
-    ColumnTrait, DbErr, EntityTrait, JoinType, PaginatorTrait, QueryFilter, QueryOrder,
+    ColumnTrait, Condition, DbErr, EntityTrait, JoinType, PaginatorTrait, QueryFilter, QueryOrder,
     QuerySelect, QueryTrait, RelationTrait,
 };
 
@@ -17,10 +17,12 @@ impl Service {
         &self,
         folder_id: Option<i32>,
         ignored_articles: &str,
+        username: &str,
     ) -> Result<Vec<(String, Vec<artist::Model>)>, DbErr> {
         let mut query = child::Entity::find()
             .filter(child::Column::IsDir.eq(true))
-            .filter(child::Column::Parent.is_null());
+            .filter(child::Column::Parent.is_null())
+            .filter(queries::song_access_filter(username));
 
         if let Some(f_id) = folder_id {
             query = query.filter(child::Column::MusicFolderId.eq(f_id));
@@ -54,6 +56,7 @@ impl Service {
     ) -> Result<DirectoryWithChildren, DbErr> {
         let dir = child::Entity::find_by_id(id)
             .filter(child::Column::IsDir.eq(true))
+            .filter(queries::song_access_filter(username))
             .one(&self.db)
             .await?
             .ok_or(DbErr::RecordNotFound("Directory not found".to_string()))?;
@@ -84,7 +87,9 @@ impl Service {
         })
     }
 
-    pub async fn get_genres(&self) -> Result<Vec<genre::GenreWithStats>, DbErr> {
+    pub async fn get_genres(&self, username: &str) -> Result<Vec<genre::GenreWithStats>, DbErr> {
+        let song_user = username.to_string();
+        let album_user = username.to_string();
         genre::Entity::find()
             .select_only()
             .column_as(genre::Column::Name, "value")
@@ -101,6 +106,12 @@ impl Service {
                 song_genre::Entity::belongs_to(genre::Entity)
                     .from(song_genre::Column::GenreName)
                     .to(genre::Column::Name)
+                    .on_condition(move |_left, _right| {
+                        Condition::all().add(Expr::cust_with_values(
+                            "song_genres.song_id IN (SELECT id FROM children WHERE music_folder_id IN (SELECT music_folder_id FROM user_music_folders WHERE username = ?))",
+                            [song_user.clone()],
+                        ))
+                    })
                     .into(),
             )
             .join_rev(
@@ -108,9 +119,16 @@ impl Service {
                 album_genre::Entity::belongs_to(genre::Entity)
                     .from(album_genre::Column::GenreName)
                     .to(genre::Column::Name)
+                    .on_condition(move |_left, _right| {
+                        Condition::all().add(Expr::cust_with_values(
+                            "album_genres.album_id IN (SELECT album_id FROM children WHERE music_folder_id IN (SELECT music_folder_id FROM user_music_folders WHERE username = ?))",
+                            [album_user.clone()],
+                        ))
+                    })
                     .into(),
             )
             .group_by(genre::Column::Name)
+            .having(Expr::cust("COUNT(DISTINCT song_genres.song_id) > 0"))
             .order_by_asc(genre::Column::Name)
             .into_model::<genre::GenreWithStats>()
             .all(&self.db)
@@ -134,7 +152,7 @@ impl Service {
                         .into_query(),
                 ),
             )
-            .order_by_desc(child::Column::PlayCount)
+            .order_by_desc(Expr::cust("play_count"))
             .limit(count)
             .into_model::<child::ChildWithMetadata>()
             .all(&self.db)
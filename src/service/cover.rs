@@ -0,0 +1,55 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::ImageReader;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+const THUMBNAIL_QUALITY: u8 = 85;
+
+/// Thumbnail sizes that are generated and cached; requests are rounded up to one.
+const THUMBNAIL_SIZES: [u32; 6] = [32, 64, 128, 256, 512, 1024];
+
+/// Rounds a requested size up to a cached thumbnail size. `None` means the
+/// request is larger than any thumbnail and the original should be served.
+pub fn thumbnail_size(requested: u32) -> Option<u32> {
+    THUMBNAIL_SIZES.into_iter().find(|s| *s >= requested)
+}
+
+/// Location of the cached `size`px thumbnail for a cover art id.
+pub fn thumbnail_path(cache_dir: &Path, cover_art_id: &str, size: u32) -> PathBuf {
+    cache_dir
+        .join("thumbs")
+        .join(format!("{}_{}.jpg", cover_art_id, size))
+}
+
+/// Resizes `src` so that its longest edge is at most `size` pixels and writes it
+/// to `dst` as JPEG. Returns `false` without writing anything when the original is
+/// already small enough to be served as-is.
+pub fn create_thumbnail(src: &Path, dst: &Path, size: u32) -> Result<bool, anyhow::Error> {
+    let img = ImageReader::open(src)?.with_guessed_format()?.decode()?;
+    if img.width() <= size && img.height() <= size {
+        return Ok(false);
+    }
+
+    let thumb = img.resize(size, size, FilterType::Lanczos3).into_rgb8();
+
+    let parent = dst
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("Invalid thumbnail path"))?;
+    std::fs::create_dir_all(parent)?;
+    // Write to a temp file of our own first so concurrent requests never see,
+    // or clobber, a partial image
+    let tmp = NamedTempFile::new_in(parent)?;
+    {
+        let mut writer = BufWriter::new(tmp.as_file());
+        JpegEncoder::new_with_quality(&mut writer, THUMBNAIL_QUALITY).encode_image(&thumb)?;
+    }
+    tmp.persist(dst)?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+#[path = "cover_tests.rs"]
+mod tests;
@@ -0,0 +1,30 @@
+use super::*;
+
+#[test]
+fn thumbnail_size_rounds_up_to_cached_sizes() {
+    assert_eq!(thumbnail_size(1), Some(32));
+    assert_eq!(thumbnail_size(64), Some(64));
+    assert_eq!(thumbnail_size(300), Some(512));
+    assert_eq!(thumbnail_size(1024), Some(1024));
+    assert_eq!(thumbnail_size(5000), None);
+}
+
+#[test]
+fn create_thumbnail_writes_jpeg_without_leftovers() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_path_buf();
+    std::fs::create_dir_all(&dir).unwrap();
+    let src = dir.join("cover.png");
+    image::RgbImage::new(300, 200).save(&src).unwrap();
+
+    let dst = thumbnail_path(&dir, "al-1", 64);
+    assert!(create_thumbnail(&src, &dst, 64).unwrap());
+    let thumb = image::open(&dst).unwrap();
+    assert_eq!((thumb.width(), thumb.height()), (64, 43));
+    assert_eq!(std::fs::read_dir(dst.parent().unwrap()).unwrap().count(), 1);
+
+    // Small enough already: nothing is written
+    let dst = thumbnail_path(&dir, "al-1", 512);
+    assert!(!create_thumbnail(&src, &dst, 512).unwrap());
+    assert!(!dst.exists());
+}
@@ -0,0 +1,502 @@
+use crate::config::{Config, JukeboxConfig};
+use crate::models::child::{self, ChildWithMetadata};
+use crate::models::{music_folder, queries};
+use path_clean::PathClean;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult, QueryFilter, QuerySelect,
+};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::task::JoinHandle;
+
+/// PCM passed from the decoder to the output: 16-bit little-endian, 44.1 kHz, stereo.
+const SAMPLE_RATE: u32 = 44_100;
+const CHANNELS: u32 = 2;
+const BYTES_PER_SECOND: f64 = (SAMPLE_RATE * CHANNELS * 2) as f64;
+const CHUNK_SIZE: usize = 16 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+/// What `jukeboxControl` reports back to clients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Status {
+    /// `-1` while the playlist is empty.
+    pub current_index: i32,
+    pub playing: bool,
+    pub gain: f64,
+    /// Whole seconds into the current track.
+    pub position: u32,
+}
+
+/// The jukebox playlist and play head. Operations return `true` when the track
+/// being played changed, so playback has to be restarted.
+#[derive(Debug)]
+pub struct Queue {
+    pub tracks: Vec<Track>,
+    pub current: usize,
+    /// Seconds into the current track.
+    pub position: f64,
+    pub playing: bool,
+    pub gain: f64,
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Self {
+            tracks: Vec::new(),
+            current: 0,
+            position: 0.0,
+            playing: false,
+            gain: 1.0,
+        }
+    }
+}
+
+impl Queue {
+    pub fn status(&self) -> Status {
+        Status {
+            current_index: if self.tracks.is_empty() {
+                -1
+            } else {
+                self.current as i32
+            },
+            playing: self.playing,
+            gain: self.gain,
+            position: self.position as u32,
+        }
+    }
+
+    pub fn set(&mut self, tracks: Vec<Track>) -> bool {
+        self.tracks = tracks;
+        self.current = 0;
+        self.position = 0.0;
+        if self.tracks.is_empty() {
+            self.playing = false;
+        }
+        true
+    }
+
+    pub fn add(&mut self, tracks: Vec<Track>) -> bool {
+        self.tracks.extend(tracks);
+        false
+    }
+
+    pub fn clear(&mut self) -> bool {
+        self.set(Vec::new())
+    }
+
+    /// Returns `None` if `index` is out of range.
+    pub fn remove(&mut self, index: usize) -> Option<bool> {
+        if index >= self.tracks.len() {
+            return None;
+        }
+        self.tracks.remove(index);
+        if index < self.current {
+            self.current -= 1;
+            return Some(false);
+        }
+        if index > self.current {
+            return Some(false);
+        }
+        self.position = 0.0;
+        if self.current >= self.tracks.len() {
+            self.current = 0;
+            self.playing = false;
+        }
+        Some(true)
+    }
+
+    /// Jumps to `index`, `offset` seconds in, and starts playing. Returns
+    /// `None` if `index` is out of range.
+    pub fn skip(&mut self, index: usize, offset: u32) -> Option<bool> {
+        if index >= self.tracks.len() {
+            return None;
+        }
+        self.current = index;
+        self.position = offset as f64;
+        self.playing = true;
+        Some(true)
+    }
+
+    /// Shuffles the playlist; the current track keeps playing at its new index.
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) -> bool {
+        let mut order: Vec<usize> = (0..self.tracks.len()).collect();
+        order.shuffle(rng);
+        self.current = order.iter().position(|&i| i == self.current).unwrap_or(0);
+        self.tracks = order.iter().map(|&i| self.tracks[i].clone()).collect();
+        false
+    }
+
+    /// Moves on to the next track, stopping (and rewinding) at the end.
+    pub fn advance(&mut self) {
+        self.current += 1;
+        self.position = 0.0;
+        if self.current >= self.tracks.len() {
+            self.current = 0;
+            self.playing = false;
+        }
+    }
+}
+
+/// Scales 16-bit little-endian samples in place.
+pub fn apply_gain(pcm: &mut [u8], gain: f64) {
+    if (gain - 1.0).abs() < f64::EPSILON {
+        return;
+    }
+    for sample in pcm.chunks_exact_mut(2) {
+        let value = i16::from_le_bytes([sample[0], sample[1]]) as f64 * gain;
+        let value = value.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+        sample.copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+#[derive(FromQueryResult)]
+struct TrackRow {
+    id: String,
+    path: String,
+    music_folder_id: i32,
+}
+
+struct State {
+    queue: Queue,
+    /// Bumped whenever playback restarts; a player whose generation is stale
+    /// must not touch the queue any more.
+    generation: u64,
+    player: Option<JoinHandle<()>>,
+}
+
+/// Plays a server-side queue to a local output for `jukeboxControl`.
+pub struct JukeboxService {
+    db: DatabaseConnection,
+    config: JukeboxConfig,
+    ffmpeg_path: String,
+    state: Mutex<State>,
+}
+
+impl JukeboxService {
+    pub fn new(db: DatabaseConnection, config: &Config) -> Self {
+        Self {
+            db,
+            config: config.jukebox.clone(),
+            ffmpeg_path: config.transcode.ffmpeg_path.clone(),
+            state: Mutex::new(State {
+                queue: Queue::default(),
+                generation: 0,
+                player: None,
+            }),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn status(&self) -> Status {
+        self.state.lock().unwrap().queue.status()
+    }
+
+    /// Applies `op` to the queue and restarts playback if it asks for it.
+    /// Returns `None` (leaving playback alone) if `op` rejected its arguments.
+    pub fn update(self: &Arc<Self>, op: impl FnOnce(&mut Queue) -> Option<bool>) -> Option<Status> {
+        let mut state = self.state.lock().unwrap();
+        let restart = op(&mut state.queue)?;
+        if restart {
+            self.restart(&mut state);
+        }
+        Some(state.queue.status())
+    }
+
+    pub fn start(self: &Arc<Self>) -> Status {
+        let mut state = self.state.lock().unwrap();
+        if !state.queue.playing && !state.queue.tracks.is_empty() {
+            state.queue.playing = true;
+            self.restart(&mut state);
+        }
+        state.queue.status()
+    }
+
+    /// Pauses playback, keeping the position so `start` resumes from it.
+    pub fn stop(self: &Arc<Self>) -> Status {
+        let mut state = self.state.lock().unwrap();
+        if state.queue.playing {
+            state.queue.playing = false;
+            self.restart(&mut state);
+        }
+        state.queue.status()
+    }
+
+    pub fn set_gain(&self, gain: f64) -> Status {
+        let mut state = self.state.lock().unwrap();
+        state.queue.gain = gain.clamp(0.0, 1.0);
+        state.queue.status()
+    }
+
+    fn restart(self: &Arc<Self>, state: &mut State) {
+        state.generation += 1;
+        if let Some(player) = state.player.take() {
+            player.abort();
+        }
+        if state.queue.playing && state.queue.current < state.queue.tracks.len() {
+            let this = self.clone();
+            let generation = state.generation;
+            state.player = Some(tokio::spawn(async move { this.run(generation).await }));
+        }
+    }
+
+    /// Runs `f` on the queue unless playback was restarted since `generation`.
+    fn with_queue<T>(&self, generation: u64, f: impl FnOnce(&mut Queue) -> T) -> Option<T> {
+        let mut state = self.state.lock().unwrap();
+        (state.generation == generation).then(|| f(&mut state.queue))
+    }
+
+    async fn run(self: Arc<Self>, generation: u64) {
+        let (mut output, mut stdin) = match self.spawn_output() {
+            Ok(output) => output,
+            Err(e) => {
+                log::error!("Failed to start jukebox output: {}", e);
+                self.with_queue(generation, |q| q.playing = false);
+                return;
+            }
+        };
+
+        loop {
+            let next = self.with_queue(generation, |q| {
+                q.tracks
+                    .get(q.current)
+                    .map(|t| (t.path.clone(), q.position))
+            });
+            let Some(Some((path, offset))) = next else {
+                break;
+            };
+            if let Err(e) = self.play_track(&mut stdin, &path, offset, generation).await {
+                log::error!("Jukebox output failed: {}", e);
+                self.with_queue(generation, |q| q.playing = false);
+                return;
+            }
+            if self.with_queue(generation, |q| {
+                q.advance();
+                q.playing
+            }) != Some(true)
+            {
+                break;
+            }
+        }
+
+        // Let the output drain what it has buffered.
+        drop(stdin);
+        let _ = output.wait().await;
+    }
+
+    /// Decodes `path` into the output. Decoder problems only skip the track;
+    /// an error means the output is gone.
+    async fn play_track(
+        &self,
+        output: &mut ChildStdin,
+        path: &Path,
+        offset: f64,
+        generation: u64,
+    ) -> io::Result<()> {
+        let mut cmd = Command::new(&self.ffmpeg_path);
+        cmd.args(["-v", "error", "-nostdin"]);
+        if offset > 0.0 {
+            cmd.args(["-ss", &format!("{:.3}", offset)]);
+        }
+        cmd.arg("-i")
+            .arg(path)
+            .args(["-map", "0:a:0", "-vn", "-f", "s16le"])
+            .args([
+                "-ac",
+                &CHANNELS.to_string(),
+                "-ar",
+                &SAMPLE_RATE.to_string(),
+            ])
+            .arg("pipe:1")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+
+        let mut decoder = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                log::warn!("Jukebox failed to decode {}: {}", path.display(), e);
+                return Ok(());
+            }
+        };
+        let Some(mut pcm) = decoder.stdout.take() else {
+            return Ok(());
+        };
+
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut pending = 0;
+        loop {
+            let n = match pcm.read(&mut buf[pending..]).await {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) => {
+                    log::warn!("Jukebox failed to decode {}: {}", path.display(), e);
+                    break;
+                }
+            };
+            let len = pending + n;
+            let whole = len - len % 2;
+            let Some(gain) = self.with_queue(generation, |q| q.gain) else {
+                return Ok(());
+            };
+            apply_gain(&mut buf[..whole], gain);
+            output.write_all(&buf[..whole]).await?;
+            if self
+                .with_queue(generation, |q| {
+                    q.position += whole as f64 / BYTES_PER_SECOND
+                })
+                .is_none()
+            {
+                return Ok(());
+            }
+            buf.copy_within(whole..len, 0);
+            pending = len - whole;
+        }
+        let _ = decoder.wait().await;
+        Ok(())
+    }
+
+    /// Starts the configured command, or the encoder writing to the output device.
+    fn spawn_output(&self) -> io::Result<(Child, ChildStdin)> {
+        let mut cmd = match &self.config.command {
+            Some(command) => {
+                let mut parts = command.split_whitespace();
+                let program = parts
+                    .next()
+                    .ok_or_else(|| io::Error::other("JUKEBOX_COMMAND is empty"))?;
+                let mut cmd = Command::new(program);
+                cmd.args(parts);
+                cmd
+            }
+            None => {
+                let (format, device) = self
+                    .config
+                    .device
+                    .split_once(':')
+                    .unwrap_or((self.config.device.as_str(), "default"));
+                let mut cmd = Command::new(&self.ffmpeg_path);
+                cmd.args(["-v", "error", "-nostdin", "-f", "s16le"])
+                    .args([
+                        "-ac",
+                        &CHANNELS.to_string(),
+                        "-ar",
+                        &SAMPLE_RATE.to_string(),
+                    ])
+                    .args(["-i", "pipe:0", "-f", format, device]);
+                cmd
+            }
+        };
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+
+        let mut child = cmd.spawn()?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| io::Error::other("Jukebox output stdin is not available"))?;
+        Ok((child, stdin))
+    }
+
+    /// Looks up playable files for `ids` in the given order. Returns `None` if
+    /// any of them is missing or outside the user's music folders.
+    pub async fn resolve_tracks(
+        &self,
+        ids: &[String],
+        username: &str,
+    ) -> Result<Option<Vec<Track>>, DbErr> {
+        if ids.is_empty() {
+            return Ok(Some(Vec::new()));
+        }
+        let rows: HashMap<String, TrackRow> = queries::song_path_info_query()
+            .column(child::Column::Id)
+            .filter(child::Column::Id.is_in(ids.iter().cloned()))
+            .filter(queries::song_access_filter(username))
+            .into_model::<TrackRow>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|r| (r.id.clone(), r))
+            .collect();
+        let roots: HashMap<i32, PathBuf> = music_folder::Entity::find()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|f| (f.id, Path::new(&f.path).clean()))
+            .collect();
+
+        let mut tracks = Vec::with_capacity(ids.len());
+        for id in ids {
+            let Some(row) = rows.get(id) else {
+                return Ok(None);
+            };
+            let path = Path::new(&row.path).clean();
+            if !roots
+                .get(&row.music_folder_id)
+                .is_some_and(|root| path.starts_with(root))
+            {
+                log::error!(
+                    "Security: Blocked jukebox access to {:?} outside its music folder",
+                    path
+                );
+                return Ok(None);
+            }
+            tracks.push(Track {
+                id: id.clone(),
+                path,
+            });
+        }
+        Ok(Some(tracks))
+    }
+
+    /// Song details for the current playlist, in playlist order. The queue is
+    /// shared, so songs outside `username`'s folders come back as `None`,
+    /// keeping entries aligned with the current index.
+    pub async fn get_entries(
+        &self,
+        username: &str,
+    ) -> Result<Vec<(String, Option<ChildWithMetadata>)>, DbErr> {
+        let ids: Vec<String> = {
+            let state = self.state.lock().unwrap();
+            state.queue.tracks.iter().map(|t| t.id.clone()).collect()
+        };
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let songs: HashMap<String, ChildWithMetadata> = queries::song_with_metadata_query(username)
+            .filter(child::Column::Id.is_in(ids.iter().cloned()))
+            .into_model::<ChildWithMetadata>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|s| (s.id.clone(), s))
+            .collect();
+        Ok(ids
+            .into_iter()
+            .map(|id| {
+                let song = songs.get(&id).cloned();
+                (id, song)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+#[path = "jukebox_tests.rs"]
+mod tests;
@@ -0,0 +1,93 @@
+use super::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn tracks(ids: &[&str]) -> Vec<Track> {
+    ids.iter()
+        .map(|id| Track {
+            id: id.to_string(),
+            path: PathBuf::from(format!("/music/{}.flac", id)),
+        })
+        .collect()
+}
+
+fn ids(queue: &Queue) -> Vec<&str> {
+    queue.tracks.iter().map(|t| t.id.as_str()).collect()
+}
+
+#[test]
+fn empty_queue_reports_no_current_index() {
+    let mut queue = Queue::default();
+    assert_eq!(queue.status().current_index, -1);
+    assert_eq!(queue.skip(0, 0), None);
+    assert_eq!(queue.remove(0), None);
+
+    queue.add(tracks(&["a"]));
+    assert_eq!(queue.status().current_index, 0);
+    assert!(!queue.status().playing);
+}
+
+#[test]
+fn skip_and_advance_move_the_play_head() {
+    let mut queue = Queue::default();
+    queue.set(tracks(&["a", "b", "c"]));
+    assert_eq!(queue.skip(1, 30), Some(true));
+    assert!(queue.playing);
+    assert_eq!(queue.status().position, 30);
+
+    queue.advance();
+    assert_eq!((queue.current, queue.position), (2, 0.0));
+    queue.advance();
+    // Running off the end stops and rewinds.
+    assert_eq!(queue.current, 0);
+    assert!(!queue.playing);
+}
+
+#[test]
+fn remove_only_restarts_for_the_current_track() {
+    let mut queue = Queue::default();
+    queue.set(tracks(&["a", "b", "c", "d"]));
+    queue.skip(2, 10);
+
+    assert_eq!(queue.remove(0), Some(false));
+    assert_eq!((ids(&queue), queue.current), (vec!["b", "c", "d"], 1));
+    assert_eq!(queue.remove(2), Some(false));
+    assert_eq!(queue.current, 1);
+
+    assert_eq!(queue.remove(1), Some(true));
+    assert_eq!(queue.position, 0.0);
+    // The last track went away, so playback ends.
+    assert_eq!((ids(&queue), queue.current), (vec!["b"], 0));
+    assert!(!queue.playing);
+}
+
+#[test]
+fn shuffle_keeps_the_current_track() {
+    let mut queue = Queue::default();
+    queue.set(tracks(&["a", "b", "c", "d", "e"]));
+    queue.skip(3, 0);
+
+    assert!(!queue.shuffle(&mut StdRng::seed_from_u64(7)));
+    assert_eq!(queue.tracks[queue.current].id, "d");
+    let mut sorted = ids(&queue);
+    sorted.sort();
+    assert_eq!(sorted, vec!["a", "b", "c", "d", "e"]);
+}
+
+#[test]
+fn gain_scales_and_clamps_samples() {
+    let mut pcm: Vec<u8> = [1000i16, -1000, i16::MAX]
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect();
+    apply_gain(&mut pcm, 0.5);
+    let samples: Vec<i16> = pcm
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    assert_eq!(samples, vec![500, -500, 16384]);
+
+    let mut loud = i16::MIN.to_le_bytes().to_vec();
+    apply_gain(&mut loud, 2.0);
+    assert_eq!(i16::from_le_bytes([loud[0], loud[1]]), i16::MIN);
+}
@@ -4,7 +4,7 @@ use crate::models::child::ChildWithMetadata;
 use crate::models::queries::{self};
 use crate::models::{album, album_artist, album_genre, artist, child, song_artist, song_genre};
 use crate::service::Service;
-use sea_orm::sea_query::{Expr, ExprTrait, Query};
+use sea_orm::sea_query::{Expr, Query};
 use sea_orm::{ColumnTrait, DbErr, JoinType, Order, QueryFilter, QueryOrder, QuerySelect};
 use serde::Deserialize;
 
@@ -59,7 +59,7 @@ impl Service {
                 );
             }
             "recent" => {
-                query = query.having(child::Column::LastPlayed.max().is_not_null());
+                query = query.having(Expr::cust("last_played IS NOT NULL"));
             }
             _ => {}
         }
@@ -0,0 +1,776 @@
+use crate::config::Config;
+use crate::models::{album, artist, child, metadata_cache, queries};
+use crate::scanner::utils::{generate_artist_id, get_cover_cache_dir};
+use crate::service::musicbrainz::{MBRelation, MusicBrainzClient};
+use crate::service::scrape::escape_lucene;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use log::{debug, warn};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, FromQueryResult, QueryFilter,
+    Set, Statement,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Failed lookups are cached for this long so an unreachable provider is not
+/// hit again on every request, while still being retried well before the TTL.
+const FAILURE_RETRY_HOURS: i64 = 1;
+
+/// Minimum MusicBrainz search score accepted as a match for a library item.
+const MIN_MATCH_SCORE: u32 = 90;
+
+/// Image widths used for the small/medium/large image URLs.
+const IMAGE_SIZES: [u32; 3] = [250, 500, 1200];
+
+const ARTIST_IMAGE_NAMES: [&str; 3] = ["artist.jpg", "artist.jpeg", "artist.png"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArtistMetadata {
+    pub biography: Option<String>,
+    pub music_brainz_id: Option<String>,
+    pub last_fm_url: Option<String>,
+    pub small_image_url: Option<String>,
+    pub medium_image_url: Option<String>,
+    pub large_image_url: Option<String>,
+    /// Names of related artists; only those present in the library are returned.
+    #[serde(default)]
+    pub similar_artists: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlbumMetadata {
+    pub notes: Option<String>,
+    pub music_brainz_id: Option<String>,
+    pub last_fm_url: Option<String>,
+    pub small_image_url: Option<String>,
+    pub medium_image_url: Option<String>,
+    pub large_image_url: Option<String>,
+}
+
+fn fill<T>(dst: &mut Option<T>, src: Option<T>) {
+    if dst.is_none() {
+        *dst = src;
+    }
+}
+
+impl ArtistMetadata {
+    /// Fills fields that are still missing from `other`; earlier providers win.
+    pub fn merge(&mut self, other: ArtistMetadata) {
+        fill(&mut self.biography, other.biography);
+        fill(&mut self.music_brainz_id, other.music_brainz_id);
+        fill(&mut self.last_fm_url, other.last_fm_url);
+        fill(&mut self.small_image_url, other.small_image_url);
+        fill(&mut self.medium_image_url, other.medium_image_url);
+        fill(&mut self.large_image_url, other.large_image_url);
+        for name in other.similar_artists {
+            if !self
+                .similar_artists
+                .iter()
+                .any(|n| n.eq_ignore_ascii_case(&name))
+            {
+                self.similar_artists.push(name);
+            }
+        }
+    }
+}
+
+impl AlbumMetadata {
+    /// Fills fields that are still missing from `other`; earlier providers win.
+    pub fn merge(&mut self, other: AlbumMetadata) {
+        fill(&mut self.notes, other.notes);
+        fill(&mut self.music_brainz_id, other.music_brainz_id);
+        fill(&mut self.last_fm_url, other.last_fm_url);
+        fill(&mut self.small_image_url, other.small_image_url);
+        fill(&mut self.medium_image_url, other.medium_image_url);
+        fill(&mut self.large_image_url, other.large_image_url);
+    }
+}
+
+/// An artist as known to the library, passed to each provider in turn.
+#[derive(Debug, Clone, Default)]
+pub struct ArtistRef {
+    pub id: String,
+    pub name: String,
+    /// Directories that may hold `artist.nfo`/`artist.jpg`, most specific first.
+    pub dirs: Vec<PathBuf>,
+    /// MBID found by an earlier provider, if any.
+    pub music_brainz_id: Option<String>,
+}
+
+/// An album as known to the library, passed to each provider in turn.
+#[derive(Debug, Clone, Default)]
+pub struct AlbumRef {
+    pub id: String,
+    pub name: String,
+    pub artist: Option<String>,
+    /// Directories holding the album's songs.
+    pub dirs: Vec<PathBuf>,
+    /// MBID found by an earlier provider, if any.
+    pub music_brainz_id: Option<String>,
+}
+
+/// A source of artist and album information.
+#[async_trait]
+pub trait MetadataProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn artist_info(&self, artist: &ArtistRef) -> Result<ArtistMetadata>;
+
+    async fn album_info(&self, album: &AlbumRef) -> Result<AlbumMetadata>;
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct NfoThumb {
+    #[serde(rename = "$text")]
+    url: Option<String>,
+}
+
+/// The subset of a Kodi-style `artist.nfo` we understand.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ArtistNfo {
+    #[serde(rename = "musicBrainzArtistID", alias = "musicbrainzartistid")]
+    music_brainz_id: Option<String>,
+    biography: Option<String>,
+    thumb: Vec<NfoThumb>,
+}
+
+/// The subset of a Kodi-style `album.nfo` we understand.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AlbumNfo {
+    #[serde(
+        rename = "musicBrainzReleaseGroupID",
+        alias = "musicbrainzreleasegroupid"
+    )]
+    release_group_id: Option<String>,
+    #[serde(rename = "musicBrainzAlbumID", alias = "musicbrainzalbumid")]
+    album_id: Option<String>,
+    review: Option<String>,
+    thumb: Vec<NfoThumb>,
+}
+
+fn non_empty(s: Option<String>) -> Option<String> {
+    s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn first_remote_thumb(thumbs: Vec<NfoThumb>) -> Option<String> {
+    thumbs
+        .into_iter()
+        .filter_map(|t| non_empty(t.url))
+        .find(|u| u.starts_with("http://") || u.starts_with("https://"))
+}
+
+pub fn parse_artist_nfo(xml: &str) -> Result<ArtistMetadata> {
+    let nfo: ArtistNfo = quick_xml::de::from_str(xml)?;
+    Ok(ArtistMetadata {
+        biography: non_empty(nfo.biography),
+        music_brainz_id: non_empty(nfo.music_brainz_id),
+        large_image_url: first_remote_thumb(nfo.thumb),
+        ..Default::default()
+    })
+}
+
+pub fn parse_album_nfo(xml: &str) -> Result<AlbumMetadata> {
+    let nfo: AlbumNfo = quick_xml::de::from_str(xml)?;
+    Ok(AlbumMetadata {
+        notes: non_empty(nfo.review),
+        music_brainz_id: non_empty(nfo.release_group_id).or(non_empty(nfo.album_id)),
+        large_image_url: first_remote_thumb(nfo.thumb),
+        ..Default::default()
+    })
+}
+
+/// Reads `artist.nfo`/`album.nfo` sidecars and copies `artist.jpg` into the
+/// cover cache so it is served as the artist's `coverArt`.
+pub struct LocalProvider {
+    cover_cache_dir: PathBuf,
+}
+
+impl LocalProvider {
+    pub fn new(cover_cache_dir: PathBuf) -> Self {
+        Self { cover_cache_dir }
+    }
+
+    async fn cache_artist_image(&self, artist: &ArtistRef) -> Result<()> {
+        let Some(src) = artist
+            .dirs
+            .iter()
+            .flat_map(|d| ARTIST_IMAGE_NAMES.iter().map(move |n| d.join(n)))
+            .find(|p| p.is_file())
+        else {
+            return Ok(());
+        };
+
+        let dst = self.cover_cache_dir.join(format!("ar-{}", artist.id));
+        let src_modified = tokio::fs::metadata(&src).await?.modified()?;
+        if let Ok(meta) = tokio::fs::metadata(&dst).await {
+            if meta.modified()? >= src_modified {
+                return Ok(());
+            }
+        }
+
+        tokio::fs::create_dir_all(&self.cover_cache_dir).await?;
+        tokio::fs::copy(&src, &dst).await?;
+        debug!("Cached artist image {} for {}", src.display(), artist.name);
+        Ok(())
+    }
+}
+
+async fn read_first(dirs: &[PathBuf], file_name: &str) -> Option<(PathBuf, String)> {
+    for dir in dirs {
+        let path = dir.join(file_name);
+        if let Ok(content) = tokio::fs::read_to_string(&path).await {
+            return Some((path, content));
+        }
+    }
+    None
+}
+
+#[async_trait]
+impl MetadataProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn artist_info(&self, artist: &ArtistRef) -> Result<ArtistMetadata> {
+        if let Err(e) = self.cache_artist_image(artist).await {
+            warn!("Failed to cache artist image for {}: {}", artist.name, e);
+        }
+
+        match read_first(&artist.dirs, "artist.nfo").await {
+            Some((path, xml)) => parse_artist_nfo(&xml)
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", path.display(), e)),
+            None => Ok(ArtistMetadata::default()),
+        }
+    }
+
+    async fn album_info(&self, album: &AlbumRef) -> Result<AlbumMetadata> {
+        match read_first(&album.dirs, "album.nfo").await {
+            Some((path, xml)) => parse_album_nfo(&xml)
+                .map_err(|e| anyhow::anyhow!("Invalid {}: {}", path.display(), e)),
+            None => Ok(AlbumMetadata::default()),
+        }
+    }
+}
+
+/// Looks artists up by name (or MBID) on MusicBrainz and albums by release
+/// group, using Wikimedia Commons and the Cover Art Archive for images.
+pub struct MusicBrainzProvider {
+    client: Arc<MusicBrainzClient>,
+}
+
+impl MusicBrainzProvider {
+    pub fn new(client: Arc<MusicBrainzClient>) -> Self {
+        Self { client }
+    }
+}
+
+fn url_relation(relations: &[MBRelation], relation_type: &str) -> Option<String> {
+    relations
+        .iter()
+        .filter(|r| r.relation_type == relation_type)
+        .find_map(|r| r.url.as_ref().map(|u| u.resource.clone()))
+}
+
+/// Turns a Commons `File:` page into direct, resized image URLs.
+pub fn commons_image_urls(page_url: &str) -> Option<[String; 3]> {
+    let file = page_url.split("/wiki/File:").nth(1)?;
+    Some(IMAGE_SIZES.map(|w| {
+        format!(
+            "https://commons.wikimedia.org/wiki/Special:FilePath/{}?width={}",
+            file, w
+        )
+    }))
+}
+
+fn cover_art_archive_urls(release_group_mbid: &str) -> [String; 3] {
+    IMAGE_SIZES.map(|w| {
+        format!(
+            "https://coverartarchive.org/release-group/{}/front-{}",
+            release_group_mbid, w
+        )
+    })
+}
+
+#[async_trait]
+impl MetadataProvider for MusicBrainzProvider {
+    fn name(&self) -> &'static str {
+        "musicbrainz"
+    }
+
+    async fn artist_info(&self, artist: &ArtistRef) -> Result<ArtistMetadata> {
+        let mbid = match &artist.music_brainz_id {
+            Some(id) => id.clone(),
+            None => {
+                let query = format!("artist:\"{}\"", escape_lucene(&artist.name));
+                let found = self
+                    .client
+                    .search_artist(&query)
+                    .await?
+                    .into_iter()
+                    .find(|a| a.score.unwrap_or(0) >= MIN_MATCH_SCORE);
+                match found {
+                    Some(a) => a.id,
+                    None => return Ok(ArtistMetadata::default()),
+                }
+            }
+        };
+
+        let detail = self.client.fetch_artist(&mbid).await?;
+        let relations = detail.relations.unwrap_or_default();
+        let images = url_relation(&relations, "image").and_then(|u| commons_image_urls(&u));
+        let [small, medium, large] = images.map(|i| i.map(Some)).unwrap_or_default();
+
+        Ok(ArtistMetadata {
+            biography: non_empty(detail.annotation),
+            music_brainz_id: Some(detail.id),
+            last_fm_url: url_relation(&relations, "last.fm"),
+            small_image_url: small,
+            medium_image_url: medium,
+            large_image_url: large,
+            // MusicBrainz relations are members, collaborations and the like,
+            // not similarity; it has no similar-artist data.
+            similar_artists: Vec::new(),
+        })
+    }
+
+    async fn album_info(&self, album: &AlbumRef) -> Result<AlbumMetadata> {
+        let mbid = match &album.music_brainz_id {
+            Some(id) => id.clone(),
+            None => {
+                let mut query = format!("releasegroup:\"{}\"", escape_lucene(&album.name));
+                if let Some(artist) = &album.artist {
+                    query.push_str(&format!(" AND artist:\"{}\"", escape_lucene(artist)));
+                }
+                let found = self
+                    .client
+                    .search_release_group(&query)
+                    .await?
+                    .into_iter()
+                    .find(|rg| rg.score.unwrap_or(0) >= MIN_MATCH_SCORE);
+                match found {
+                    Some(rg) => rg.id,
+                    None => return Ok(AlbumMetadata::default()),
+                }
+            }
+        };
+
+        let detail = self.client.fetch_release_group(&mbid).await?;
+        let relations = detail.relations.unwrap_or_default();
+        let [small, medium, large] = cover_art_archive_urls(&detail.id);
+
+        Ok(AlbumMetadata {
+            notes: non_empty(detail.annotation),
+            music_brainz_id: Some(detail.id),
+            last_fm_url: url_relation(&relations, "last.fm"),
+            small_image_url: Some(small),
+            medium_image_url: Some(medium),
+            large_image_url: Some(large),
+        })
+    }
+}
+
+/// Artist info together with the similar artists found in the library.
+pub struct ArtistInfoResult {
+    pub info: ArtistMetadata,
+    pub similar: Vec<artist::ArtistWithStats>,
+}
+
+#[derive(FromQueryResult)]
+struct PathRow {
+    path: String,
+    folder_path: String,
+}
+
+#[derive(FromQueryResult)]
+struct SimilarRow {
+    artist_id: String,
+}
+
+/// Resolves artist/album info through the configured providers and caches the
+/// merged result in `metadata_cache`.
+pub struct MetadataService {
+    db: DatabaseConnection,
+    providers: Vec<Box<dyn MetadataProvider>>,
+    ttl: Duration,
+}
+
+impl MetadataService {
+    pub fn new(db: DatabaseConnection, config: &Config, mb_client: Arc<MusicBrainzClient>) -> Self {
+        let providers = config
+            .metadata
+            .providers
+            .iter()
+            .filter_map(|name| -> Option<Box<dyn MetadataProvider>> {
+                match name.as_str() {
+                    "local" => Some(Box::new(LocalProvider::new(get_cover_cache_dir(config)))),
+                    "musicbrainz" => Some(Box::new(MusicBrainzProvider::new(mb_client.clone()))),
+                    other => {
+                        warn!("Unknown metadata provider '{}', ignoring", other);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self::with_providers(db, providers, config.metadata.cache_ttl_hours)
+    }
+
+    pub fn with_providers(
+        db: DatabaseConnection,
+        providers: Vec<Box<dyn MetadataProvider>>,
+        cache_ttl_hours: i64,
+    ) -> Self {
+        Self {
+            db,
+            providers,
+            ttl: Duration::hours(cache_ttl_hours),
+        }
+    }
+
+    async fn cached<T: for<'de> Deserialize<'de>>(
+        &self,
+        item_type: &str,
+        item_id: &str,
+    ) -> Result<Option<T>> {
+        let entry =
+            metadata_cache::Entity::find_by_id((item_type.to_string(), item_id.to_string()))
+                .one(&self.db)
+                .await?;
+
+        Ok(entry
+            .filter(|e| Utc::now() - e.updated_at < self.ttl)
+            .and_then(|e| serde_json::from_str(&e.data).ok()))
+    }
+
+    async fn store<T: Serialize>(
+        &self,
+        item_type: &str,
+        item_id: &str,
+        data: &T,
+        complete: bool,
+    ) -> Result<()> {
+        // Incomplete lookups expire early so the failing provider is retried soon.
+        let updated_at = if complete {
+            Utc::now()
+        } else {
+            Utc::now() - self.ttl + Duration::hours(FAILURE_RETRY_HOURS)
+        };
+
+        metadata_cache::Entity::insert(metadata_cache::ActiveModel {
+            item_type: Set(item_type.to_string()),
+            item_id: Set(item_id.to_string()),
+            data: Set(serde_json::to_string(data)?),
+            updated_at: Set(updated_at),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                metadata_cache::Column::ItemType,
+                metadata_cache::Column::ItemId,
+            ])
+            .update_columns([
+                metadata_cache::Column::Data,
+                metadata_cache::Column::UpdatedAt,
+            ])
+            .to_owned(),
+        )
+        .exec(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Distinct song directories (and their music folder roots) matching `condition`.
+    async fn song_dirs(&self, condition: &str, id: &str) -> Result<Vec<(PathBuf, PathBuf)>> {
+        let rows = PathRow::find_by_statement(Statement::from_sql_and_values(
+            self.db.get_database_backend(),
+            format!(
+                "SELECT c.path AS path, mf.path AS folder_path FROM children c \
+                 JOIN music_folders mf ON mf.id = c.music_folder_id \
+                 WHERE c.is_dir = 0 AND {} LIMIT 200",
+                condition
+            ),
+            [id.into()],
+        ))
+        .all(&self.db)
+        .await?;
+
+        let mut dirs = Vec::new();
+        for row in rows {
+            if let Some(parent) = Path::new(&row.path).parent() {
+                let entry = (parent.to_path_buf(), PathBuf::from(row.folder_path));
+                if !dirs.contains(&entry) {
+                    dirs.push(entry);
+                }
+            }
+        }
+        Ok(dirs)
+    }
+
+    async fn artist_ref(&self, id: &str, username: &str) -> Result<Option<ArtistRef>> {
+        // getArtistInfo may be called with a top-level directory id from getIndexes.
+        let dir = child::Entity::find_by_id(id.to_string())
+            .filter(child::Column::IsDir.eq(true))
+            .filter(queries::song_access_filter(username))
+            .one(&self.db)
+            .await?;
+
+        let artist_id = match &dir {
+            Some(d) => generate_artist_id(&d.title),
+            None => id.to_string(),
+        };
+        let artist = artist::Entity::find_by_id(artist_id)
+            .filter(queries::artist_access_filter(username))
+            .one(&self.db)
+            .await?;
+
+        let mut dirs: Vec<PathBuf> = dir
+            .as_ref()
+            .map(|d| PathBuf::from(&d.path))
+            .into_iter()
+            .collect();
+        let name = match (&artist, &dir) {
+            (Some(a), _) => {
+                let album_dirs = self
+                    .song_dirs(
+                        "c.id IN (SELECT song_id FROM song_artists WHERE artist_id = ?)",
+                        &a.id,
+                    )
+                    .await?;
+                // The artist folder is the parent of an album folder, unless that is the library root.
+                for (album_dir, root) in &album_dirs {
+                    if let Some(parent) = album_dir.parent() {
+                        if parent.starts_with(root)
+                            && parent != root
+                            && !dirs.iter().any(|d| d == parent)
+                        {
+                            dirs.push(parent.to_path_buf());
+                        }
+                    }
+                }
+                for (album_dir, _) in album_dirs {
+                    if !dirs.contains(&album_dir) {
+                        dirs.push(album_dir);
+                    }
+                }
+                a.name.clone()
+            }
+            (None, Some(d)) => d.title.clone(),
+            (None, None) => return Ok(None),
+        };
+
+        Ok(Some(ArtistRef {
+            id: artist.map(|a| a.id).unwrap_or_else(|| id.to_string()),
+            name,
+            dirs,
+            music_brainz_id: None,
+        }))
+    }
+
+    async fn album_ref(&self, id: &str, username: &str) -> Result<Option<AlbumRef>> {
+        // getAlbumInfo may be called with an album directory id.
+        let album_id = match child::Entity::find_by_id(id.to_string())
+            .filter(child::Column::IsDir.eq(true))
+            .filter(queries::song_access_filter(username))
+            .one(&self.db)
+            .await?
+        {
+            Some(dir) => child::Entity::find()
+                .filter(child::Column::Parent.eq(dir.id))
+                .filter(child::Column::AlbumId.is_not_null())
+                .one(&self.db)
+                .await?
+                .and_then(|c| c.album_id),
+            None => Some(id.to_string()),
+        };
+        let Some(album_id) = album_id else {
+            return Ok(None);
+        };
+
+        let Some(album) = queries::album_with_stats_query(username)
+            .filter(album::Column::Id.eq(&album_id))
+            .into_model::<album::AlbumWithStats>()
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let dirs = self
+            .song_dirs("c.album_id = ?", &album.id)
+            .await?
+            .into_iter()
+            .map(|(dir, _)| dir)
+            .collect();
+
+        Ok(Some(AlbumRef {
+            id: album.id,
+            name: album.name,
+            artist: album.artists.into_iter().next().map(|a| a.name),
+            dirs,
+            music_brainz_id: None,
+        }))
+    }
+
+    async fn fetch_artist(&self, mut artist: ArtistRef) -> (ArtistMetadata, bool) {
+        let mut info = ArtistMetadata::default();
+        let mut complete = true;
+        for provider in &self.providers {
+            artist.music_brainz_id = info.music_brainz_id.clone();
+            match provider.artist_info(&artist).await {
+                Ok(found) => info.merge(found),
+                Err(e) => {
+                    warn!(
+                        "{} artist lookup failed for {}: {}",
+                        provider.name(),
+                        artist.name,
+                        e
+                    );
+                    complete = false;
+                }
+            }
+        }
+        (info, complete)
+    }
+
+    async fn fetch_album(&self, mut album: AlbumRef) -> (AlbumMetadata, bool) {
+        let mut info = AlbumMetadata::default();
+        let mut complete = true;
+        for provider in &self.providers {
+            album.music_brainz_id = info.music_brainz_id.clone();
+            match provider.album_info(&album).await {
+                Ok(found) => info.merge(found),
+                Err(e) => {
+                    warn!(
+                        "{} album lookup failed for {}: {}",
+                        provider.name(),
+                        album.name,
+                        e
+                    );
+                    complete = false;
+                }
+            }
+        }
+        (info, complete)
+    }
+
+    /// Returns info for an artist (or top-level artist directory), or `None` if
+    /// the user cannot see it. At most `count` similar library artists are included.
+    pub async fn get_artist_info(
+        &self,
+        id: &str,
+        username: &str,
+        count: usize,
+    ) -> Result<Option<ArtistInfoResult>> {
+        let Some(artist) = self.artist_ref(id, username).await? else {
+            return Ok(None);
+        };
+
+        let info = match self.cached::<ArtistMetadata>("artist", &artist.id).await? {
+            Some(info) => info,
+            None => {
+                let (info, complete) = self.fetch_artist(artist.clone()).await;
+                self.store("artist", &artist.id, &info, complete).await?;
+                if info.large_image_url.is_some() {
+                    artist::Entity::update_many()
+                        .col_expr(
+                            artist::Column::ArtistImageUrl,
+                            Expr::value(info.large_image_url.clone()),
+                        )
+                        .filter(artist::Column::Id.eq(&artist.id))
+                        .exec(&self.db)
+                        .await?;
+                }
+                info
+            }
+        };
+
+        let similar = self
+            .similar_artists(&artist.id, &info.similar_artists, username, count)
+            .await?;
+
+        Ok(Some(ArtistInfoResult { info, similar }))
+    }
+
+    /// Returns info for an album (or album directory), or `None` if the user cannot see it.
+    pub async fn get_album_info(&self, id: &str, username: &str) -> Result<Option<AlbumMetadata>> {
+        let Some(album) = self.album_ref(id, username).await? else {
+            return Ok(None);
+        };
+
+        if let Some(info) = self.cached::<AlbumMetadata>("album", &album.id).await? {
+            return Ok(Some(info));
+        }
+
+        let album_id = album.id.clone();
+        let (info, complete) = self.fetch_album(album).await;
+        self.store("album", &album_id, &info, complete).await?;
+        Ok(Some(info))
+    }
+
+    /// Library artists related to `artist_id`: those named by providers first,
+    /// then artists sharing the most genres with it.
+    async fn similar_artists(
+        &self,
+        artist_id: &str,
+        names: &[String],
+        username: &str,
+        count: usize,
+    ) -> Result<Vec<artist::ArtistWithStats>> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut ids: Vec<String> = names
+            .iter()
+            .map(|n| generate_artist_id(n))
+            .filter(|id| id != artist_id)
+            .collect();
+
+        let by_genre = SimilarRow::find_by_statement(Statement::from_sql_and_values(
+            self.db.get_database_backend(),
+            "SELECT sa.artist_id AS artist_id FROM song_genres sg \
+             JOIN song_artists sa ON sa.song_id = sg.song_id \
+             WHERE sg.genre_name IN (SELECT g.genre_name FROM song_genres g JOIN song_artists s ON s.song_id = g.song_id WHERE s.artist_id = ?) \
+             AND sa.artist_id != ? \
+             GROUP BY sa.artist_id \
+             ORDER BY COUNT(DISTINCT sg.genre_name) DESC, COUNT(*) DESC \
+             LIMIT ?",
+            [
+                artist_id.into(),
+                artist_id.into(),
+                ((names.len() + count) as i64).into(),
+            ],
+        ))
+        .all(&self.db)
+        .await?;
+        ids.extend(by_genre.into_iter().map(|r| r.artist_id));
+
+        let mut seen = HashSet::new();
+        ids.retain(|id| seen.insert(id.clone()));
+
+        let found = queries::artist_with_stats_query(username)
+            .filter(artist::Column::Id.is_in(ids.clone()))
+            .into_model::<artist::ArtistWithStats>()
+            .all(&self.db)
+            .await?;
+
+        let mut similar: Vec<_> = ids
+            .iter()
+            .filter_map(|id| found.iter().find(|a| &a.id == id).cloned())
+            .collect();
+        similar.truncate(count);
+        Ok(similar)
+    }
+}
+
+#[cfg(test)]
+#[path = "metadata_tests.rs"]
+mod tests;
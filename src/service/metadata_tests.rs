@@ -0,0 +1,102 @@
+use super::*;
+
+#[test]
+fn parses_kodi_artist_nfo() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<artist>
+    <name>Radiohead</name>
+    <musicBrainzArtistID>a74b1b7f-71a5-4011-9441-d0b5e4122711</musicBrainzArtistID>
+    <genre>Rock</genre>
+    <biography>  English rock band formed in Abingdon.  </biography>
+    <thumb preview="https://example.com/p.jpg" aspect="thumb">https://example.com/radiohead.jpg</thumb>
+</artist>"#;
+
+    let info = parse_artist_nfo(xml).unwrap();
+    assert_eq!(
+        info.music_brainz_id.as_deref(),
+        Some("a74b1b7f-71a5-4011-9441-d0b5e4122711")
+    );
+    assert_eq!(
+        info.biography.as_deref(),
+        Some("English rock band formed in Abingdon.")
+    );
+    assert_eq!(
+        info.large_image_url.as_deref(),
+        Some("https://example.com/radiohead.jpg")
+    );
+}
+
+#[test]
+fn parses_kodi_album_nfo_preferring_release_group() {
+    let xml = r#"<album>
+    <title>OK Computer</title>
+    <musicbrainzalbumid>release-id</musicbrainzalbumid>
+    <musicbrainzreleasegroupid>group-id</musicbrainzreleasegroupid>
+    <review>Third studio album.</review>
+    <thumb>folder.jpg</thumb>
+</album>"#;
+
+    let info = parse_album_nfo(xml).unwrap();
+    assert_eq!(info.music_brainz_id.as_deref(), Some("group-id"));
+    assert_eq!(info.notes.as_deref(), Some("Third studio album."));
+    assert_eq!(info.large_image_url, None);
+}
+
+#[test]
+fn merge_keeps_earlier_fields_and_dedupes_similar() {
+    let mut info = ArtistMetadata {
+        biography: Some("local".into()),
+        similar_artists: vec!["Muse".into()],
+        ..Default::default()
+    };
+    info.merge(ArtistMetadata {
+        biography: Some("remote".into()),
+        music_brainz_id: Some("mbid".into()),
+        similar_artists: vec!["muse".into(), "Blur".into()],
+        ..Default::default()
+    });
+
+    assert_eq!(info.biography.as_deref(), Some("local"));
+    assert_eq!(info.music_brainz_id.as_deref(), Some("mbid"));
+    assert_eq!(info.similar_artists, vec!["Muse", "Blur"]);
+}
+
+#[test]
+fn commons_file_pages_become_resized_image_urls() {
+    let urls = commons_image_urls("https://commons.wikimedia.org/wiki/File:Radiohead.jpg").unwrap();
+    assert_eq!(
+        urls[0],
+        "https://commons.wikimedia.org/wiki/Special:FilePath/Radiohead.jpg?width=250"
+    );
+    assert!(urls[2].ends_with("width=1200"));
+    assert!(commons_image_urls("https://example.com/image.jpg").is_none());
+}
+
+#[tokio::test]
+async fn local_provider_reads_nfo_and_caches_artist_image() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_path_buf();
+    let artist_dir = root.join("music").join("Radiohead");
+    let cache_dir = root.join("covers");
+    std::fs::create_dir_all(&artist_dir).unwrap();
+    std::fs::write(
+        artist_dir.join("artist.nfo"),
+        "<artist><biography>From Oxfordshire.</biography></artist>",
+    )
+    .unwrap();
+    std::fs::write(artist_dir.join("artist.jpg"), b"not really a jpeg").unwrap();
+
+    let provider = LocalProvider::new(cache_dir.clone());
+    let info = provider
+        .artist_info(&ArtistRef {
+            id: "abc".into(),
+            name: "Radiohead".into(),
+            dirs: vec![artist_dir.join("OK Computer"), artist_dir.clone()],
+            music_brainz_id: None,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(info.biography.as_deref(), Some("From Oxfordshire."));
+    assert!(cache_dir.join("ar-abc").is_file());
+}
@@ -1,13 +1,25 @@
 use sea_orm::DatabaseConnection;
 
+pub mod api_keys;
 pub mod bookmarks;
 pub mod browsing;
+pub mod cover;
+pub mod jukebox;
 pub mod library;
+pub mod metadata;
 pub mod musicbrainz;
+pub mod playlist_files;
 pub mod playlists;
+pub mod podcast;
+pub mod radio;
 pub mod scrape;
+pub mod scrobble;
 pub mod search;
+pub mod shares;
+pub mod similarity;
+pub mod smart_playlists;
 pub mod tag;
+pub mod transcode;
 pub mod utils;
 
 pub struct Service {
@@ -9,6 +9,7 @@ use std::time::Duration;
 #[derive(Debug, Clone, Deserialize)]
 pub struct MBArtist {
     pub id: String,
+    pub name: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -96,6 +97,49 @@ pub struct MBSearchResponse {
     pub recordings: Vec<MBRecording>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct MBUrl {
+    pub resource: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MBRelation {
+    #[serde(rename = "type")]
+    pub relation_type: String,
+    pub url: Option<MBUrl>,
+}
+
+/// Artist as returned by artist search (with `score`) and lookup (with
+/// `annotation` and url relations).
+#[derive(Debug, Clone, Deserialize)]
+pub struct MBArtistDetail {
+    pub id: String,
+    pub name: String,
+    pub score: Option<u32>,
+    pub annotation: Option<String>,
+    pub relations: Option<Vec<MBRelation>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MBArtistSearchResponse {
+    artists: Vec<MBArtistDetail>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MBReleaseGroupDetail {
+    pub id: String,
+    pub title: String,
+    pub score: Option<u32>,
+    pub annotation: Option<String>,
+    pub relations: Option<Vec<MBRelation>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MBReleaseGroupSearchResponse {
+    #[serde(rename = "release-groups")]
+    release_groups: Vec<MBReleaseGroupDetail>,
+}
+
 pub struct MusicBrainzClient {
     client: reqwest::Client,
     user_agent: String,
@@ -199,6 +243,47 @@ impl MusicBrainzClient {
         self.request_with_retry(&url).await
     }
 
+    pub async fn search_artist(&self, lucene_query: &str) -> Result<Vec<MBArtistDetail>> {
+        let url = format!(
+            "https://musicbrainz.org/ws/2/artist?query={}&limit=5&fmt=json",
+            urlencoding::encode(lucene_query)
+        );
+
+        let result: MBArtistSearchResponse = self.request_with_retry(&url).await?;
+        Ok(result.artists)
+    }
+
+    pub async fn fetch_artist(&self, mbid: &str) -> Result<MBArtistDetail> {
+        let url = format!(
+            "https://musicbrainz.org/ws/2/artist/{}?inc=url-rels+annotation&fmt=json",
+            urlencoding::encode(mbid)
+        );
+
+        self.request_with_retry(&url).await
+    }
+
+    pub async fn search_release_group(
+        &self,
+        lucene_query: &str,
+    ) -> Result<Vec<MBReleaseGroupDetail>> {
+        let url = format!(
+            "https://musicbrainz.org/ws/2/release-group?query={}&limit=5&fmt=json",
+            urlencoding::encode(lucene_query)
+        );
+
+        let result: MBReleaseGroupSearchResponse = self.request_with_retry(&url).await?;
+        Ok(result.release_groups)
+    }
+
+    pub async fn fetch_release_group(&self, mbid: &str) -> Result<MBReleaseGroupDetail> {
+        let url = format!(
+            "https://musicbrainz.org/ws/2/release-group/{}?inc=url-rels+annotation&fmt=json",
+            urlencoding::encode(mbid)
+        );
+
+        self.request_with_retry(&url).await
+    }
+
     pub async fn fetch_cover_art(&self, release_mbid: &str) -> Result<Option<String>> {
         let url = format!(
             "https://coverartarchive.org/release/{}/front",
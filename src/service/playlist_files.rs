@@ -0,0 +1,737 @@
+//! Playlist files: rendering playlists as M3U8, PLS or XSPF and importing such
+//! files by matching their entries against the library. The scanner also keeps
+//! playlists in sync with `.m3u`/`.m3u8` files found in the music folders.
+
+use crate::models::child::ChildWithMetadata;
+use crate::models::playlist_song::PlaylistWithSongs;
+use crate::models::queries;
+use crate::models::{child, music_folder, playlist, playlist_song, user};
+use crate::service::Service;
+use chrono::{DateTime, Utc};
+use path_clean::PathClean;
+use quick_xml::escape::escape;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    M3u8,
+    Pls,
+    Xspf,
+}
+
+impl Format {
+    /// Parses a format name or file extension.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "m3u" | "m3u8" => Some(Format::M3u8),
+            "pls" => Some(Format::Pls),
+            "xspf" => Some(Format::Xspf),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a playlist file from its content.
+    pub fn detect(content: &str) -> Self {
+        let head = content.trim_start_matches('\u{feff}').trim_start();
+        if head
+            .get(..10)
+            .is_some_and(|h| h.eq_ignore_ascii_case("[playlist]"))
+        {
+            Format::Pls
+        } else if head.starts_with('<') {
+            Format::Xspf
+        } else {
+            Format::M3u8
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::M3u8 => "m3u8",
+            Format::Pls => "pls",
+            Format::Xspf => "xspf",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::M3u8 => "audio/x-mpegurl; charset=utf-8",
+            Format::Pls => "audio/x-scpls; charset=utf-8",
+            Format::Xspf => "application/xspf+xml; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entry {
+    /// Path or URL of the song as written in the file.
+    pub location: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// Seconds.
+    pub duration: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaylistFile {
+    pub name: Option<String>,
+    pub entries: Vec<Entry>,
+}
+
+/// Where exported entries point to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Locations {
+    /// Paths relative to the song's music folder.
+    Relative,
+    /// Stream URLs under `base_url`, with `query` appended for authentication.
+    Stream { base_url: String, query: String },
+}
+
+/// Splits the `Artist - Title` display text of M3U and PLS entries.
+fn split_display(text: &str) -> (Option<String>, Option<String>) {
+    let text = text.trim();
+    match text.split_once(" - ") {
+        Some((artist, title)) if !artist.trim().is_empty() && !title.trim().is_empty() => (
+            Some(artist.trim().to_string()),
+            Some(title.trim().to_string()),
+        ),
+        _ if text.is_empty() => (None, None),
+        _ => (None, Some(text.to_string())),
+    }
+}
+
+fn parse_duration(value: &str) -> Option<i32> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|d| *d >= 0.0)
+        .map(|d| d.round() as i32)
+}
+
+fn parse_m3u(content: &str) -> PlaylistFile {
+    let mut file = PlaylistFile::default();
+    let mut info: Option<Entry> = None;
+    for line in content.lines() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            // `#EXTINF:<seconds>[ key="value"...],<artist> - <title>`
+            let (head, display) = extinf.split_once(',').unwrap_or((extinf, ""));
+            let (artist, title) = split_display(display);
+            info = Some(Entry {
+                artist,
+                title,
+                duration: head.split_whitespace().next().and_then(parse_duration),
+                ..Default::default()
+            });
+        } else if let Some(name) = line.strip_prefix("#PLAYLIST:") {
+            file.name = Some(name.trim().to_string()).filter(|n| !n.is_empty());
+        } else if !line.is_empty() && !line.starts_with('#') {
+            file.entries.push(Entry {
+                location: line.to_string(),
+                ..info.take().unwrap_or_default()
+            });
+        }
+    }
+    file
+}
+
+fn parse_pls(content: &str) -> PlaylistFile {
+    let mut entries: BTreeMap<u32, Entry> = BTreeMap::new();
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+        let field = ["file", "title", "length"]
+            .into_iter()
+            .find_map(|f| Some((f, key.strip_prefix(f)?.parse::<u32>().ok()?)));
+        let Some((field, n)) = field else {
+            continue;
+        };
+        let entry = entries.entry(n).or_default();
+        match field {
+            "file" => entry.location = value.to_string(),
+            "title" => (entry.artist, entry.title) = split_display(value),
+            _ => entry.duration = parse_duration(value),
+        }
+    }
+    PlaylistFile {
+        name: None,
+        entries: entries
+            .into_values()
+            .filter(|e| !e.location.is_empty())
+            .collect(),
+    }
+}
+
+#[derive(Deserialize)]
+struct Xspf {
+    title: Option<String>,
+    #[serde(rename = "trackList", default)]
+    track_list: XspfTrackList,
+}
+
+#[derive(Deserialize, Default)]
+struct XspfTrackList {
+    #[serde(default)]
+    track: Vec<XspfTrack>,
+}
+
+#[derive(Deserialize)]
+struct XspfTrack {
+    #[serde(default)]
+    location: Vec<String>,
+    title: Option<String>,
+    creator: Option<String>,
+    /// Milliseconds.
+    duration: Option<String>,
+}
+
+fn parse_xspf(content: &str) -> Result<PlaylistFile, String> {
+    let xspf: Xspf = quick_xml::de::from_str(content.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("Invalid XSPF: {}", e))?;
+    let entries = xspf
+        .track_list
+        .track
+        .into_iter()
+        .filter_map(|t| {
+            let location = t.location.into_iter().find(|l| !l.trim().is_empty())?;
+            let location = location.trim();
+            // Locations are URIs; relative ones are percent-encoded paths.
+            let location = if location.contains("://") {
+                location.to_string()
+            } else {
+                urlencoding::decode(location)
+                    .map(|l| l.into_owned())
+                    .unwrap_or_else(|_| location.to_string())
+            };
+            Some(Entry {
+                location,
+                title: t.title.filter(|s| !s.trim().is_empty()),
+                artist: t.creator.filter(|s| !s.trim().is_empty()),
+                duration: t
+                    .duration
+                    .and_then(|d| d.trim().parse::<i64>().ok())
+                    .map(|ms| ((ms + 500) / 1000) as i32),
+            })
+        })
+        .collect();
+    Ok(PlaylistFile {
+        name: xspf.title.filter(|s| !s.trim().is_empty()),
+        entries,
+    })
+}
+
+pub fn parse(format: Format, content: &str) -> Result<PlaylistFile, String> {
+    match format {
+        Format::M3u8 => Ok(parse_m3u(content)),
+        Format::Pls => Ok(parse_pls(content)),
+        Format::Xspf => parse_xspf(content),
+    }
+}
+
+fn display(entry: &Entry) -> String {
+    match (&entry.artist, &entry.title) {
+        (Some(artist), Some(title)) => format!("{} - {}", artist, title),
+        (None, Some(title)) => title.clone(),
+        (Some(artist), None) => artist.clone(),
+        (None, None) => String::new(),
+    }
+}
+
+fn xspf_location(location: &str) -> String {
+    if location.contains("://") {
+        location.to_string()
+    } else {
+        location
+            .split('/')
+            .map(|segment| urlencoding::encode(segment).into_owned())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+pub fn render(format: Format, name: &str, entries: &[Entry]) -> String {
+    let mut out = String::new();
+    match format {
+        Format::M3u8 => {
+            out.push_str("#EXTM3U\n");
+            out.push_str(&format!("#PLAYLIST:{}\n", name.replace('\n', " ")));
+            for entry in entries {
+                out.push_str(&format!(
+                    "#EXTINF:{},{}\n{}\n",
+                    entry.duration.unwrap_or(-1),
+                    display(entry),
+                    entry.location
+                ));
+            }
+        }
+        Format::Pls => {
+            out.push_str("[playlist]\n");
+            for (i, entry) in entries.iter().enumerate() {
+                let n = i + 1;
+                out.push_str(&format!("File{}={}\n", n, entry.location));
+                out.push_str(&format!("Title{}={}\n", n, display(entry)));
+                out.push_str(&format!("Length{}={}\n", n, entry.duration.unwrap_or(-1)));
+            }
+            out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", entries.len()));
+        }
+        Format::Xspf => {
+            out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+            out.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n");
+            out.push_str(&format!("  <title>{}</title>\n", escape(name)));
+            out.push_str("  <trackList>\n");
+            for entry in entries {
+                out.push_str("    <track>\n");
+                out.push_str(&format!(
+                    "      <location>{}</location>\n",
+                    escape(xspf_location(&entry.location).as_str())
+                ));
+                if let Some(title) = &entry.title {
+                    out.push_str(&format!(
+                        "      <title>{}</title>\n",
+                        escape(title.as_str())
+                    ));
+                }
+                if let Some(artist) = &entry.artist {
+                    out.push_str(&format!(
+                        "      <creator>{}</creator>\n",
+                        escape(artist.as_str())
+                    ));
+                }
+                if let Some(duration) = entry.duration {
+                    out.push_str(&format!(
+                        "      <duration>{}</duration>\n",
+                        duration as i64 * 1000
+                    ));
+                }
+                out.push_str("    </track>\n");
+            }
+            out.push_str("  </trackList>\n</playlist>\n");
+        }
+    }
+    out
+}
+
+/// Turns playlist songs into entries pointing at `locations`.
+pub fn entries(
+    songs: &[ChildWithMetadata],
+    folders: &[music_folder::Model],
+    locations: &Locations,
+) -> Vec<Entry> {
+    songs
+        .iter()
+        .map(|song| {
+            let location = match locations {
+                Locations::Relative => folders
+                    .iter()
+                    .filter_map(|f| Path::new(&song.path).strip_prefix(&f.path).ok())
+                    .min_by_key(|p| p.as_os_str().len())
+                    .map(|p| p.to_string_lossy().replace('\\', "/"))
+                    .unwrap_or_else(|| song.path.clone()),
+                Locations::Stream { base_url, query } => format!(
+                    "{}/rest/stream?id={}&{}",
+                    base_url.trim_end_matches('/'),
+                    urlencoding::encode(&song.id),
+                    query
+                ),
+            };
+            let artist = song
+                .artists
+                .iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            Entry {
+                location,
+                title: Some(song.title.clone()),
+                artist: Some(artist).filter(|a| !a.is_empty()),
+                duration: Some(song.duration),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Resolved {
+    /// Matched songs in playlist order, without duplicates.
+    pub song_ids: Vec<String>,
+    /// Locations of entries that matched no song.
+    pub unresolved: Vec<String>,
+}
+
+/// Paths an entry's location may refer to, most specific first. Relative
+/// paths are tried against `base_dir` (the playlist file's directory) and then
+/// each music folder.
+fn candidate_paths(location: &str, base_dir: Option<&Path>, roots: &[PathBuf]) -> Vec<String> {
+    let location = match location.strip_prefix("file://") {
+        Some(path) => {
+            let path = path.strip_prefix("localhost").unwrap_or(path);
+            urlencoding::decode(path)
+                .map(|p| p.into_owned())
+                .unwrap_or_else(|_| path.to_string())
+        }
+        None => location.replace('\\', "/"),
+    };
+    let path = Path::new(&location);
+    let paths: Vec<PathBuf> = if path.is_absolute() {
+        vec![path.to_path_buf()]
+    } else {
+        base_dir
+            .into_iter()
+            .chain(roots.iter().map(PathBuf::as_path))
+            .map(|dir| dir.join(path))
+            .collect()
+    };
+    paths
+        .into_iter()
+        .map(|p| p.clean().to_string_lossy().into_owned())
+        .collect()
+}
+
+/// The `id` parameter of a stream or download URL.
+fn stream_id(location: &str) -> Option<String> {
+    let (_, query) = location.split_once('?')?;
+    serde_urlencoded::from_str::<Vec<(String, String)>>(query)
+        .ok()?
+        .into_iter()
+        .find(|(key, _)| key == "id")
+        .map(|(_, id)| id)
+}
+
+async fn find_song<C: ConnectionTrait>(
+    db: &C,
+    filter: sea_orm::Condition,
+    username: Option<&str>,
+) -> Result<Option<String>, DbErr> {
+    let mut query = child::Entity::find()
+        .filter(child::Column::IsDir.eq(false))
+        .filter(filter);
+    if let Some(username) = username {
+        query = query.filter(queries::song_access_filter(username));
+    }
+    Ok(query
+        .order_by_asc(child::Column::Path)
+        .one(db)
+        .await?
+        .map(|c| c.id))
+}
+
+async fn resolve_entry<C: ConnectionTrait>(
+    db: &C,
+    entry: &Entry,
+    base_dir: Option<&Path>,
+    roots: &[PathBuf],
+    username: Option<&str>,
+) -> Result<Option<String>, DbErr> {
+    let location = entry.location.trim();
+    let is_url = location.contains("://") && !location.starts_with("file://");
+    if is_url {
+        if let Some(id) = stream_id(location) {
+            let found = find_song(
+                db,
+                sea_orm::Condition::all().add(child::Column::Id.eq(id)),
+                username,
+            )
+            .await?;
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+    } else {
+        for path in candidate_paths(location, base_dir, roots) {
+            let found = find_song(
+                db,
+                sea_orm::Condition::all().add(child::Column::Path.eq(path)),
+                username,
+            )
+            .await?;
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+    }
+
+    // Fall back to the title and artist the file lists for the entry.
+    let Some(title) = entry.title.as_deref().map(str::trim) else {
+        return Ok(None);
+    };
+    let mut condition = sea_orm::Condition::all().add(Expr::cust_with_values(
+        "children.title = ? COLLATE NOCASE",
+        [title.to_string()],
+    ));
+    if let Some(artist) = entry.artist.as_deref().map(str::trim) {
+        condition = condition.add(Expr::cust_with_values(
+            "EXISTS (SELECT 1 FROM song_artists sa JOIN artists a ON a.id = sa.artist_id \
+             WHERE sa.song_id = children.id AND a.name = ? COLLATE NOCASE)",
+            [artist.to_string()],
+        ));
+    }
+    find_song(db, condition, username).await
+}
+
+/// Matches `entries` against the library. When `username` is given only songs
+/// in their music folders match.
+pub async fn resolve<C: ConnectionTrait>(
+    db: &C,
+    entries: &[Entry],
+    base_dir: Option<&Path>,
+    username: Option<&str>,
+) -> Result<Resolved, DbErr> {
+    let roots: Vec<PathBuf> = music_folder::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|f| PathBuf::from(f.path))
+        .collect();
+    let mut resolved = Resolved::default();
+    for entry in entries {
+        match resolve_entry(db, entry, base_dir, &roots, username).await? {
+            Some(id) => resolved.song_ids.push(id),
+            None => resolved.unresolved.push(entry.location.clone()),
+        }
+    }
+    Ok(resolved)
+}
+
+async fn set_songs<C: ConnectionTrait>(
+    db: &C,
+    playlist_id: i32,
+    song_ids: Vec<String>,
+) -> Result<(), DbErr> {
+    playlist_song::Entity::delete_many()
+        .filter(playlist_song::Column::PlaylistId.eq(playlist_id))
+        .exec(db)
+        .await?;
+    if song_ids.is_empty() {
+        return Ok(());
+    }
+    let songs = song_ids
+        .into_iter()
+        .enumerate()
+        .map(|(i, song_id)| playlist_song::ActiveModel {
+            playlist_id: Set(playlist_id),
+            song_id: Set(song_id),
+            index: Set(i as i32),
+        });
+    playlist_song::Entity::insert_many(songs).exec(db).await?;
+    Ok(())
+}
+
+/// Playlist files found in the music folders.
+#[derive(Debug, Default)]
+struct FolderPlaylists {
+    /// `.m3u` and `.m3u8` files with their modification times.
+    files: Vec<(String, DateTime<Utc>)>,
+    /// Folders that were walked; playlists elsewhere are left alone.
+    walked: Vec<PathBuf>,
+    /// Directories that could not be read, so their playlists are unknown.
+    unreadable: Vec<PathBuf>,
+}
+
+impl FolderPlaylists {
+    /// Whether a playlist file at `path` would have been found if it existed.
+    fn covers(&self, path: &str) -> bool {
+        let path = Path::new(path);
+        self.walked.iter().any(|d| path.starts_with(d))
+            && !self.unreadable.iter().any(|d| path.starts_with(d))
+    }
+}
+
+fn find_playlist_files(folders: &[music_folder::Model]) -> FolderPlaylists {
+    let mut found = FolderPlaylists::default();
+    for folder in folders {
+        // An empty or missing folder is most likely an unmounted drive.
+        let readable = std::fs::read_dir(&folder.path).is_ok_and(|mut d| d.next().is_some());
+        if !readable {
+            log::warn!(
+                "Music folder {} is empty or unreadable, keeping its playlists",
+                folder.path
+            );
+            continue;
+        }
+        found.walked.push(PathBuf::from(&folder.path));
+        for entry in walkdir::WalkDir::new(&folder.path) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    log::warn!("Failed to read {}", e);
+                    found.unreadable.extend(e.path().map(Path::to_path_buf));
+                    continue;
+                }
+            };
+            let is_playlist = entry
+                .path()
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("m3u") || e.eq_ignore_ascii_case("m3u8"));
+            if !is_playlist || !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) else {
+                continue;
+            };
+            found.files.push((
+                entry.path().to_string_lossy().replace('\\', "/"),
+                DateTime::<Utc>::from(modified),
+            ));
+        }
+    }
+    found
+}
+
+/// Imports the `.m3u`/`.m3u8` files in the music folders as public playlists of
+/// the first admin. Playlists are re-imported when their file changed since
+/// and removed along with their file.
+pub async fn sync_folder_playlists<C: ConnectionTrait>(
+    db: &C,
+    folders: Vec<music_folder::Model>,
+) -> Result<(), anyhow::Error> {
+    let Some(owner) = user::Entity::find()
+        .filter(user::Column::AdminRole.eq(true))
+        .order_by_asc(user::Column::Username)
+        .one(db)
+        .await?
+    else {
+        return Ok(());
+    };
+    let found = tokio::task::spawn_blocking(move || find_playlist_files(&folders)).await?;
+
+    let mut existing: HashMap<String, playlist::Model> = playlist::Entity::find()
+        .filter(playlist::Column::SourcePath.is_not_null())
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|p| Some((p.source_path.clone()?, p)))
+        .collect();
+
+    for (path, modified) in found.files.iter().cloned() {
+        let current = existing.remove(&path);
+        if current.as_ref().is_some_and(|p| p.updated_at >= modified) {
+            continue;
+        }
+        let content = match tokio::fs::read(&path).await {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                log::warn!("Failed to read playlist {}: {}", path, e);
+                continue;
+            }
+        };
+        let file = parse_m3u(&content);
+        let base_dir = Path::new(&path).parent();
+        let resolved = resolve(db, &file.entries, base_dir, None).await?;
+        for location in &resolved.unresolved {
+            log::debug!("Playlist {}: no song found for {}", path, location);
+        }
+        if !resolved.unresolved.is_empty() {
+            log::warn!(
+                "Playlist {}: {} of {} entries not found in the library",
+                path,
+                resolved.unresolved.len(),
+                file.entries.len()
+            );
+        }
+
+        let name = file.name.unwrap_or_else(|| {
+            Path::new(&path)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone())
+        });
+        let now = Utc::now();
+        let id = match current {
+            Some(p) => {
+                let id = p.id;
+                let mut p: playlist::ActiveModel = p.into();
+                p.name = Set(name);
+                p.updated_at = Set(now);
+                p.update(db).await?;
+                id
+            }
+            None => {
+                playlist::ActiveModel {
+                    name: Set(name),
+                    owner: Set(owner.username.clone()),
+                    comment: Set(None),
+                    public: Set(true),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                    source_path: Set(Some(path.clone())),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?
+                .id
+            }
+        };
+        set_songs(db, id, resolved.song_ids).await?;
+        log::info!("Imported playlist {}", path);
+    }
+
+    // Whatever is left in a folder that was read in full lost its file.
+    for p in existing.into_values() {
+        if !p
+            .source_path
+            .as_deref()
+            .is_some_and(|path| found.covers(path))
+        {
+            continue;
+        }
+        log::info!(
+            "Removing playlist {} of deleted file {}",
+            p.name,
+            p.source_path.unwrap_or_default()
+        );
+        playlist::Entity::delete_by_id(p.id).exec(db).await?;
+    }
+    Ok(())
+}
+
+impl Service {
+    /// Renders playlist `id` if `username` may read it, returning its name and
+    /// the file content.
+    pub async fn export_playlist(
+        &self,
+        id: i32,
+        username: &str,
+        format: Format,
+        locations: &Locations,
+    ) -> Result<Option<(String, String)>, DbErr> {
+        let Some(PlaylistWithSongs { playlist, entry }) = self.get_playlist(id, username).await?
+        else {
+            return Ok(None);
+        };
+        let folders = music_folder::Entity::find().all(&self.db).await?;
+        let entries = entries(&entry, &folders, locations);
+        let content = render(format, &playlist.name, &entries);
+        Ok(Some((playlist.name, content)))
+    }
+
+    /// Creates a playlist of `username` from a parsed playlist file, using the
+    /// songs they can access.
+    pub async fn import_playlist(
+        &self,
+        username: &str,
+        name: String,
+        file: &PlaylistFile,
+    ) -> Result<(i32, Resolved), DbErr> {
+        let resolved = resolve(&self.db, &file.entries, None, Some(username)).await?;
+        let id = self
+            .create_playlist(name, username.to_string(), resolved.song_ids.clone())
+            .await?;
+        Ok((id, resolved))
+    }
+}
+
+#[cfg(test)]
+#[path = "playlist_files_tests.rs"]
+mod tests;
@@ -0,0 +1,336 @@
+use super::*;
+use crate::models::{artist, song_artist};
+use crate::test_support::{insert_folder, insert_user, memory_db, song, user as test_user};
+use sea_orm::DatabaseConnection;
+
+/// Songs s1 and s2 live in `root`; s3 lives in a second folder alice cannot see.
+async fn setup_db(root: &str) -> DatabaseConnection {
+    let db = memory_db().await;
+    user::ActiveModel {
+        settings_role: Set(true),
+        admin_role: Set(true),
+        ..test_user("admin")
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    insert_user(&db, "alice").await;
+    insert_folder(&db, 1, root, &["alice"]).await;
+    insert_folder(&db, 2, "/other", &[]).await;
+
+    for (id, name) in [("ar1", "Artist A"), ("ar2", "Artist B")] {
+        artist::ActiveModel {
+            id: Set(id.to_string()),
+            name: Set(name.to_string()),
+            artist_image_url: Set(None),
+            average_rating: Set(0.0),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+    for (id, title, path, folder_id, artist_id) in [
+        (
+            "s1",
+            "Song One",
+            format!("{}/A/01 Song One.mp3", root),
+            1,
+            "ar1",
+        ),
+        (
+            "s2",
+            "Song Two",
+            format!("{}/B/02 Song Two.mp3", root),
+            1,
+            "ar2",
+        ),
+        ("s3", "Song Two", "/other/s3.mp3".to_string(), 2, "ar2"),
+    ] {
+        child::ActiveModel {
+            title: Set(title.to_string()),
+            path: Set(path),
+            duration: Set(200),
+            ..song(id, folder_id)
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        song_artist::ActiveModel {
+            song_id: Set(id.to_string()),
+            artist_id: Set(artist_id.to_string()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+    db
+}
+
+fn entry(location: &str, artist: Option<&str>, title: Option<&str>) -> Entry {
+    Entry {
+        location: location.to_string(),
+        artist: artist.map(String::from),
+        title: title.map(String::from),
+        duration: None,
+    }
+}
+
+#[test]
+fn detects_formats() {
+    assert_eq!(
+        Format::detect("\u{feff}[Playlist]\nFile1=a.mp3"),
+        Format::Pls
+    );
+    assert_eq!(Format::detect("  <?xml version=\"1.0\"?>"), Format::Xspf);
+    assert_eq!(Format::detect("#EXTM3U\na.mp3"), Format::M3u8);
+    assert_eq!(Format::from_name("M3U"), Some(Format::M3u8));
+    assert_eq!(Format::from_name("wpl"), None);
+}
+
+#[test]
+fn parses_m3u_with_extinf() {
+    let file = parse(
+        Format::M3u8,
+        "\u{feff}#EXTM3U\r\n#PLAYLIST:Road Trip\r\n#EXTINF:215 tvg-id=\"x\",Artist A - Song One\r\nA/01 Song One.mp3\r\n# a comment\r\n\r\n/abs/song.flac\r\n#EXTINF:-1,Just A Title\r\nhttp://host/rest/stream?id=s2\r\n",
+    )
+    .unwrap();
+    assert_eq!(file.name.as_deref(), Some("Road Trip"));
+    assert_eq!(
+        file.entries,
+        vec![
+            Entry {
+                duration: Some(215),
+                ..entry("A/01 Song One.mp3", Some("Artist A"), Some("Song One"))
+            },
+            entry("/abs/song.flac", None, None),
+            entry("http://host/rest/stream?id=s2", None, Some("Just A Title")),
+        ]
+    );
+}
+
+#[test]
+fn parses_pls_in_entry_order() {
+    let file = parse(
+        Format::Pls,
+        "[playlist]\nFile2=b.mp3\nTitle2=Artist B - Song Two\nLength2=-1\nfile1=a.mp3\nLength1=200\nNumberOfEntries=2\nTitle3=No file\nVersion=2\n",
+    )
+    .unwrap();
+    assert_eq!(
+        file.entries,
+        vec![
+            Entry {
+                duration: Some(200),
+                ..entry("a.mp3", None, None)
+            },
+            entry("b.mp3", Some("Artist B"), Some("Song Two")),
+        ]
+    );
+}
+
+#[test]
+fn parses_xspf() {
+    let file = parse(
+        Format::Xspf,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <title>Mix &amp; Match</title>
+  <trackList>
+    <track>
+      <location>A/01%20Song%20One.mp3</location>
+      <title>Song One</title>
+      <creator>Artist A</creator>
+      <duration>200400</duration>
+    </track>
+    <track><location>file:///music/b.mp3</location></track>
+    <track><title>No location</title></track>
+  </trackList>
+</playlist>"#,
+    )
+    .unwrap();
+    assert_eq!(file.name.as_deref(), Some("Mix & Match"));
+    assert_eq!(
+        file.entries,
+        vec![
+            Entry {
+                duration: Some(200),
+                ..entry("A/01 Song One.mp3", Some("Artist A"), Some("Song One"))
+            },
+            entry("file:///music/b.mp3", None, None),
+        ]
+    );
+    assert!(parse(Format::Xspf, "<playlist><trackList>").is_err());
+}
+
+#[test]
+fn rendered_files_parse_back() {
+    let entries = vec![
+        Entry {
+            duration: Some(200),
+            ..entry(
+                "A/01 Song One.mp3",
+                Some("Artist A"),
+                Some("Song One & <More>"),
+            )
+        },
+        Entry {
+            duration: Some(5),
+            ..entry(
+                "http://host/rest/stream?id=s2&u=alice",
+                None,
+                Some("Song Two"),
+            )
+        },
+    ];
+    for format in [Format::M3u8, Format::Pls, Format::Xspf] {
+        let content = render(format, "Mix & Match", &entries);
+        assert_eq!(Format::detect(&content), format);
+        let file = parse(format, &content).unwrap();
+        assert_eq!(file.entries, entries, "{:?}", format);
+        if format != Format::Pls {
+            assert_eq!(file.name.as_deref(), Some("Mix & Match"));
+        }
+    }
+    let xspf = render(Format::Xspf, "x", &entries);
+    assert!(xspf.contains("<location>A/01%20Song%20One.mp3</location>"));
+    assert!(xspf.contains("<duration>200000</duration>"));
+}
+
+#[tokio::test]
+async fn resolves_paths_urls_and_metadata() {
+    let db = setup_db("/music").await;
+    let entries = vec![
+        entry("/music/A/01 Song One.mp3", None, None),
+        entry("../B/02 Song Two.mp3", None, None),
+        entry("A/01 Song One.mp3", None, None),
+        entry("file:///music/B/02%20Song%20Two.mp3", None, None),
+        entry("https://miko.example/rest/stream?id=s3&u=x", None, None),
+        entry("C:\\Music\\song.mp3", Some("artist a"), Some("SONG ONE")),
+        entry("missing.mp3", Some("Artist B"), Some("Song One")),
+        entry("also-missing.mp3", None, None),
+    ];
+
+    let resolved = resolve(&db, &entries, Some(Path::new("/music/A")), None)
+        .await
+        .unwrap();
+    // Repeated songs stay in the playlist.
+    assert_eq!(resolved.song_ids, vec!["s1", "s2", "s1", "s2", "s3", "s1"]);
+    assert_eq!(resolved.unresolved, vec!["missing.mp3", "also-missing.mp3"]);
+}
+
+#[tokio::test]
+async fn resolves_only_accessible_songs_for_a_user() {
+    let db = setup_db("/music").await;
+    let entries = vec![
+        entry("/other/s3.mp3", None, None),
+        entry("song.mp3", None, Some("Song Two")),
+    ];
+
+    let resolved = resolve(&db, &entries, None, Some("alice")).await.unwrap();
+    assert_eq!(resolved.song_ids, vec!["s2"]);
+    assert_eq!(resolved.unresolved, vec!["/other/s3.mp3"]);
+}
+
+#[tokio::test]
+async fn exports_relative_paths_and_stream_urls() {
+    let db = setup_db("/music").await;
+    let service = Service::new(db);
+    let file = PlaylistFile {
+        name: None,
+        entries: vec![entry("/music/B/02 Song Two.mp3", None, None)],
+    };
+    let (id, _) = service
+        .import_playlist("alice", "Mine".to_string(), &file)
+        .await
+        .unwrap();
+
+    let (name, content) = service
+        .export_playlist(id, "alice", Format::M3u8, &Locations::Relative)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(name, "Mine");
+    assert_eq!(
+        content,
+        "#EXTM3U\n#PLAYLIST:Mine\n#EXTINF:200,Artist B - Song Two\nB/02 Song Two.mp3\n"
+    );
+
+    let locations = Locations::Stream {
+        base_url: "https://miko.example/".to_string(),
+        query: "u=alice".to_string(),
+    };
+    let (_, content) = service
+        .export_playlist(id, "alice", Format::Pls, &locations)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(content.contains("File1=https://miko.example/rest/stream?id=s2&u=alice\n"));
+
+    // Private playlists are not exported for other users.
+    assert_eq!(
+        service
+            .export_playlist(id, "admin", Format::M3u8, &Locations::Relative)
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn syncs_playlist_files_in_music_folders() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_path_buf();
+    std::fs::create_dir_all(dir.join("lists")).unwrap();
+    let root = dir.to_string_lossy().replace('\\', "/");
+    let db = setup_db(&root).await;
+    let list = dir.join("lists/Favourites.m3u");
+    std::fs::write(&list, "../A/01 Song One.mp3\nnowhere.mp3\n").unwrap();
+    std::fs::write(dir.join("lists/notes.txt"), "not a playlist").unwrap();
+
+    let folders = music_folder::Entity::find().all(&db).await.unwrap();
+    sync_folder_playlists(&db, folders.clone()).await.unwrap();
+    let playlists = playlist::Entity::find().all(&db).await.unwrap();
+    assert_eq!(playlists.len(), 1);
+    let p = &playlists[0];
+    assert_eq!(p.name, "Favourites");
+    assert_eq!(p.owner, "admin");
+    assert!(p.public);
+    let songs = || async {
+        playlist_song::Entity::find()
+            .order_by_asc(playlist_song::Column::Index)
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.song_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(songs().await, vec!["s1"]);
+
+    // A changed file is imported again into the same playlist.
+    let mut stale: playlist::ActiveModel = p.clone().into();
+    stale.updated_at = Set(Utc::now() - chrono::Duration::days(1));
+    stale.update(&db).await.unwrap();
+    std::fs::write(
+        &list,
+        "#PLAYLIST:Best\n../B/02 Song Two.mp3\n../A/01 Song One.mp3\n../B/02 Song Two.mp3\n",
+    )
+    .unwrap();
+    sync_folder_playlists(&db, folders.clone()).await.unwrap();
+    let playlists = playlist::Entity::find().all(&db).await.unwrap();
+    assert_eq!(playlists.len(), 1);
+    assert_eq!(playlists[0].id, p.id);
+    assert_eq!(playlists[0].name, "Best");
+    assert_eq!(songs().await, vec!["s2", "s1", "s2"]);
+
+    // An unreadable folder, e.g. an unmounted drive, keeps its playlists.
+    let missing = vec![music_folder::Model {
+        path: dir.join("unmounted").to_string_lossy().into_owned(),
+        ..folders[0].clone()
+    }];
+    sync_folder_playlists(&db, missing).await.unwrap();
+    assert_eq!(playlist::Entity::find().all(&db).await.unwrap().len(), 1);
+
+    std::fs::remove_file(&list).unwrap();
+    sync_folder_playlists(&db, folders).await.unwrap();
+    assert!(playlist::Entity::find().all(&db).await.unwrap().is_empty());
+}
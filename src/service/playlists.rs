@@ -2,16 +2,18 @@ use crate::models::child::ChildWithMetadata;
 use crate::models::playlist::PlaylistWithStats;
 use crate::models::playlist_song::PlaylistWithSongs;
 use crate::models::queries::{self};
-use crate::models::{child, playlist, playlist_song};
+use crate::models::{child, playlist, playlist_song, playlist_user, user};
+use crate::service::smart_playlists::SmartRules;
 use crate::service::Service;
 use chrono::Utc;
-use sea_orm::sea_query::Expr;
+use sea_orm::sea_query::{Alias, Expr, Query, SimpleExpr};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, JoinType, QueryFilter, QueryOrder,
-    QuerySelect, Set, TransactionError, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, JoinType,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait, Select, Set,
+    TransactionError, TransactionTrait,
 };
 use serde::Deserialize;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
 #[derive(Debug, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -21,6 +23,88 @@ pub struct UpdatePlaylistOptions {
     pub public: Option<bool>,
     pub song_ids_to_add: Vec<String>,
     pub song_indices_to_remove: Vec<i32>,
+    /// New rules; only for smart playlists.
+    pub rules: Option<SmartRules>,
+}
+
+pub const PLAYLIST_NOT_FOUND: &str = "Playlist not found";
+pub const PERMISSION_DENIED: &str = "Permission denied";
+pub const READ_ONLY: &str = "Smart playlists are read-only";
+
+/// Playlists `username` may read: their own, public ones and ones they are
+/// allowed to edit.
+fn visible_to(username: &str) -> Condition {
+    Condition::any()
+        .add(playlist::Column::Owner.eq(username))
+        .add(playlist::Column::Public.eq(true))
+        .add(allowed(username))
+}
+
+fn allowed(username: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        "EXISTS (SELECT 1 FROM playlist_users pu WHERE pu.playlist_id = playlists.id AND pu.username = ?)",
+        [username.to_string()],
+    )
+}
+
+/// Rules of a smart playlist owned by `owner`. Rules were validated when
+/// saved, so ones that no longer parse select nothing.
+fn parse_rules(rules: &str, owner: &str) -> Option<SmartRules> {
+    SmartRules::parse(rules)
+        .inspect_err(|e| log::warn!("Ignoring rules of a smart playlist of {}: {}", owner, e))
+        .ok()
+}
+
+/// Playlists with their song count, duration and allowed users.
+fn with_stats() -> Select<playlist::Entity> {
+    playlist::Entity::find()
+        .column_as(playlist_song::Column::SongId.count(), "song_count")
+        .column_as(Expr::cust("COALESCE(SUM(duration), 0)"), "duration")
+        .column_as(
+            Expr::cust("(SELECT GROUP_CONCAT(pu.username) FROM playlist_users pu WHERE pu.playlist_id = playlists.id)"),
+            "allowed_users",
+        )
+        .join_rev(
+            JoinType::LeftJoin,
+            playlist_song::Entity::belongs_to(playlist::Entity)
+                .from(playlist_song::Column::PlaylistId)
+                .to(playlist::Column::Id)
+                .into(),
+        )
+        .join_rev(
+            JoinType::LeftJoin,
+            child::Entity::belongs_to(playlist_song::Entity)
+                .from(child::Column::Id)
+                .to(playlist_song::Column::SongId)
+                .into(),
+        )
+        .group_by(playlist::Column::Id)
+}
+
+/// Loads a playlist for a change by `username`: the owner may change
+/// anything, allowed users only the songs.
+async fn editable_playlist<C: ConnectionTrait>(
+    db: &C,
+    playlist_id: i32,
+    username: &str,
+    changes_details: bool,
+) -> Result<playlist::Model, DbErr> {
+    let p = playlist::Entity::find_by_id(playlist_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| DbErr::Custom(PLAYLIST_NOT_FOUND.to_string()))?;
+    if p.owner == username {
+        return Ok(p);
+    }
+    let is_allowed = playlist_user::Entity::find_by_id((playlist_id, username.to_string()))
+        .one(db)
+        .await?
+        .is_some();
+    if is_allowed && !changes_details {
+        Ok(p)
+    } else {
+        Err(DbErr::Custom(PERMISSION_DENIED.to_string()))
+    }
 }
 
 impl Service {
@@ -69,6 +153,109 @@ impl Service {
             })
     }
 
+    pub async fn create_smart_playlist(
+        &self,
+        name: String,
+        owner: String,
+        comment: Option<String>,
+        public: bool,
+        rules: &SmartRules,
+    ) -> Result<i32, DbErr> {
+        let now = Utc::now();
+        let p = playlist::ActiveModel {
+            name: Set(name),
+            owner: Set(owner),
+            created_at: Set(now),
+            updated_at: Set(now),
+            comment: Set(comment),
+            public: Set(public),
+            rules: Set(Some(
+                serde_json::to_string(rules).map_err(|e| DbErr::Custom(e.to_string()))?,
+            )),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+        Ok(p.id)
+    }
+
+    /// A playlist `username` may read: one of their own, a public one or one
+    /// they may edit.
+    pub async fn find_visible_playlist(
+        &self,
+        id: i32,
+        username: &str,
+    ) -> Result<Option<playlist::Model>, DbErr> {
+        playlist::Entity::find_by_id(id)
+            .filter(visible_to(username))
+            .one(&self.db)
+            .await
+    }
+
+    /// Users besides the owner who may edit playlist `id`.
+    pub async fn get_playlist_users(&self, id: i32, username: &str) -> Result<Vec<String>, DbErr> {
+        if self.find_visible_playlist(id, username).await?.is_none() {
+            return Err(DbErr::Custom(PLAYLIST_NOT_FOUND.to_string()));
+        }
+        playlist_user::Entity::find()
+            .select_only()
+            .column(playlist_user::Column::Username)
+            .filter(playlist_user::Column::PlaylistId.eq(id))
+            .order_by_asc(playlist_user::Column::Username)
+            .into_tuple::<String>()
+            .all(&self.db)
+            .await
+    }
+
+    /// Replaces the users allowed to edit playlist `id`; only its owner may.
+    pub async fn set_playlist_users(
+        &self,
+        id: i32,
+        username: &str,
+        usernames: Vec<String>,
+    ) -> Result<(), DbErr> {
+        let username = username.to_string();
+        self.db
+            .transaction::<_, (), DbErr>(|txn| {
+                Box::pin(async move {
+                    let p = editable_playlist(txn, id, &username, true).await?;
+                    if p.rules.is_some() {
+                        return Err(DbErr::Custom(READ_ONLY.to_string()));
+                    }
+                    let usernames: BTreeSet<String> =
+                        usernames.into_iter().filter(|u| *u != p.owner).collect();
+                    let known = user::Entity::find()
+                        .filter(user::Column::Username.is_in(usernames.iter().cloned()))
+                        .count(txn)
+                        .await?;
+                    if known as usize != usernames.len() {
+                        return Err(DbErr::Custom("Unknown user".to_string()));
+                    }
+
+                    playlist_user::Entity::delete_many()
+                        .filter(playlist_user::Column::PlaylistId.eq(id))
+                        .exec(txn)
+                        .await?;
+                    if !usernames.is_empty() {
+                        playlist_user::Entity::insert_many(usernames.into_iter().map(|u| {
+                            playlist_user::ActiveModel {
+                                playlist_id: Set(id),
+                                username: Set(u),
+                            }
+                        }))
+                        .exec(txn)
+                        .await?;
+                    }
+                    Ok(())
+                })
+            })
+            .await
+            .map_err(|e| match e {
+                TransactionError::Connection(e) => e,
+                TransactionError::Transaction(e) => e,
+            })
+    }
+
     pub async fn update_playlist(
         &self,
         playlist_id: i32,
@@ -79,14 +266,31 @@ impl Service {
         self.db
             .transaction::<_, (), DbErr>(|txn| {
                 Box::pin(async move {
-                    let mut p: playlist::ActiveModel = playlist::Entity::find_by_id(playlist_id)
-                        .one(txn)
-                        .await?
-                        .ok_or_else(|| DbErr::Custom("Playlist not found".to_string()))?
-                        .into();
-
-                    if p.owner.as_ref() != &username {
-                        return Err(DbErr::Custom("Permission denied".to_string()));
+                    let changes_details = opts.name.is_some()
+                        || opts.comment.is_some()
+                        || opts.public.is_some()
+                        || opts.rules.is_some();
+                    let mut p: playlist::ActiveModel =
+                        editable_playlist(txn, playlist_id, &username, changes_details)
+                            .await?
+                            .into();
+
+                    let changes_songs =
+                        !opts.song_ids_to_add.is_empty() || !opts.song_indices_to_remove.is_empty();
+                    match (p.rules.as_ref(), &opts.rules) {
+                        (Some(_), _) if changes_songs => {
+                            return Err(DbErr::Custom(READ_ONLY.to_string()))
+                        }
+                        (None, Some(_)) => {
+                            return Err(DbErr::Custom("Not a smart playlist".to_string()))
+                        }
+                        (Some(_), Some(rules)) => {
+                            p.rules = Set(Some(
+                                serde_json::to_string(rules)
+                                    .map_err(|e| DbErr::Custom(e.to_string()))?,
+                            ));
+                        }
+                        _ => {}
                     }
 
                     if let Some(name) = opts.name {
@@ -178,14 +382,12 @@ impl Service {
         self.db
             .transaction::<_, (), DbErr>(|txn| {
                 Box::pin(async move {
-                    let mut p: playlist::ActiveModel = playlist::Entity::find_by_id(playlist_id)
-                        .one(txn)
-                        .await?
-                        .ok_or_else(|| DbErr::Custom("Playlist not found".to_string()))?
-                        .into();
-
-                    if p.owner.as_ref() != &username {
-                        return Err(DbErr::Custom("Permission denied".to_string()));
+                    let mut p: playlist::ActiveModel =
+                        editable_playlist(txn, playlist_id, &username, opts.name.is_some())
+                            .await?
+                            .into();
+                    if p.rules.as_ref().is_some() {
+                        return Err(DbErr::Custom(READ_ONLY.to_string()));
                     }
 
                     if let Some(name) = opts.name {
@@ -230,46 +432,120 @@ impl Service {
         let p = playlist::Entity::find_by_id(id)
             .one(&self.db)
             .await?
-            .ok_or_else(|| DbErr::Custom("Playlist not found".to_string()))?;
+            .ok_or_else(|| DbErr::Custom(PLAYLIST_NOT_FOUND.to_string()))?;
 
         if p.owner != username {
-            return Err(DbErr::Custom("Permission denied".to_string()));
+            return Err(DbErr::Custom(PERMISSION_DENIED.to_string()));
         }
 
         playlist::Entity::delete_by_id(id).exec(&self.db).await?;
         Ok(())
     }
 
+    /// Playlists of `target_username` that `username` may read; their own
+    /// listing also includes public playlists and ones they may edit.
     pub async fn get_playlists(
         &self,
         username: &str,
         target_username: &str,
     ) -> Result<Vec<PlaylistWithStats>, DbErr> {
-        let mut query = playlist::Entity::find()
-            .column_as(playlist_song::Column::SongId.count(), "song_count")
-            .column_as(Expr::cust("COALESCE(SUM(duration), 0)"), "duration")
-            .join_rev(
-                JoinType::LeftJoin,
-                playlist_song::Entity::belongs_to(playlist::Entity)
-                    .from(playlist_song::Column::PlaylistId)
-                    .to(playlist::Column::Id)
-                    .into(),
-            )
-            .join_rev(
-                JoinType::LeftJoin,
-                child::Entity::belongs_to(playlist_song::Entity)
-                    .from(child::Column::Id)
-                    .to(playlist_song::Column::SongId)
-                    .into(),
-            )
-            .filter(playlist::Column::Owner.eq(target_username))
-            .group_by(playlist::Column::Id);
-
+        let mut query = with_stats().filter(visible_to(username));
         if username != target_username {
-            query = query.filter(playlist::Column::Public.eq(true));
+            query = query.filter(playlist::Column::Owner.eq(target_username));
         }
 
-        query.into_model::<PlaylistWithStats>().all(&self.db).await
+        let mut playlists = query
+            .into_model::<PlaylistWithStats>()
+            .all(&self.db)
+            .await?;
+        for p in playlists.iter_mut() {
+            if let Some(rules) = &p.rules {
+                (p.song_count, p.duration) = self
+                    .smart_playlist_totals(rules, &p.owner, username)
+                    .await?;
+            }
+        }
+        Ok(playlists)
+    }
+
+    /// Song count and total duration of a smart playlist, without loading
+    /// its songs.
+    async fn smart_playlist_totals(
+        &self,
+        rules: &str,
+        owner: &str,
+        username: &str,
+    ) -> Result<(i64, i64), DbErr> {
+        let Some(rules) = parse_rules(rules, owner) else {
+            return Ok((0, 0));
+        };
+        // The limit picks songs by the sort order, so it stays in the subquery.
+        let songs = rules.apply(
+            child::Entity::find()
+                .select_only()
+                .column(child::Column::Duration)
+                .filter(queries::song_access_filter(username)),
+            owner,
+        );
+        let totals = Query::select()
+            .expr_as(Expr::cust("COUNT(*)"), Alias::new("song_count"))
+            .expr_as(
+                Expr::cust("COALESCE(SUM(duration), 0)"),
+                Alias::new("duration"),
+            )
+            .from_subquery(songs.into_query(), Alias::new("songs"))
+            .to_owned();
+        let backend = self.db.get_database_backend();
+        match self.db.query_one(backend.build(&totals)).await? {
+            Some(row) => Ok((row.try_get("", "song_count")?, row.try_get("", "duration")?)),
+            None => Ok((0, 0)),
+        }
+    }
+
+    /// Whether the smart playlist with `rules`, owned by `owner`, holds
+    /// `song_id` for `username`.
+    pub async fn smart_playlist_contains(
+        &self,
+        rules: &str,
+        owner: &str,
+        username: &str,
+        song_id: &str,
+    ) -> Result<bool, DbErr> {
+        let Some(rules) = parse_rules(rules, owner) else {
+            return Ok(false);
+        };
+        // The limit picks songs by the sort order, so it stays in the subquery.
+        let songs = rules.apply(
+            child::Entity::find()
+                .select_only()
+                .column(child::Column::Id)
+                .filter(queries::song_access_filter(username)),
+            owner,
+        );
+        let count = child::Entity::find()
+            .filter(child::Column::Id.eq(song_id))
+            .filter(child::Column::Id.in_subquery(songs.into_query()))
+            .count(&self.db)
+            .await?;
+        Ok(count > 0)
+    }
+
+    /// Evaluates the stored rules of a smart playlist owned by `owner` for
+    /// `username`.
+    pub async fn smart_playlist_songs(
+        &self,
+        rules: &str,
+        owner: &str,
+        username: &str,
+    ) -> Result<Vec<ChildWithMetadata>, DbErr> {
+        let Some(rules) = parse_rules(rules, owner) else {
+            return Ok(Vec::new());
+        };
+        rules
+            .apply(queries::song_with_metadata_query(username), owner)
+            .into_model::<ChildWithMetadata>()
+            .all(&self.db)
+            .await
     }
 
     pub async fn get_playlist(
@@ -277,30 +553,26 @@ impl Service {
         id: i32,
         username: &str,
     ) -> Result<Option<PlaylistWithSongs>, DbErr> {
-        let playlist = playlist::Entity::find()
+        let playlist = with_stats()
             .filter(playlist::Column::Id.eq(id))
-            .column_as(playlist_song::Column::SongId.count(), "song_count")
-            .column_as(Expr::cust("COALESCE(SUM(duration), 0)"), "duration")
-            .join_rev(
-                JoinType::LeftJoin,
-                playlist_song::Entity::belongs_to(playlist::Entity)
-                    .from(playlist_song::Column::PlaylistId)
-                    .to(playlist::Column::Id)
-                    .into(),
-            )
-            .join_rev(
-                JoinType::LeftJoin,
-                child::Entity::belongs_to(playlist_song::Entity)
-                    .from(child::Column::Id)
-                    .to(playlist_song::Column::SongId)
-                    .into(),
-            )
-            .group_by(playlist::Column::Id)
+            .filter(visible_to(username))
             .into_model::<PlaylistWithStats>()
             .one(&self.db)
             .await?;
 
-        if let Some(playlist) = playlist {
+        if let Some(mut playlist) = playlist {
+            if let Some(rules) = &playlist.rules {
+                let songs = self
+                    .smart_playlist_songs(rules, &playlist.owner, username)
+                    .await?;
+                playlist.song_count = songs.len() as i64;
+                playlist.duration = songs.iter().map(|s| s.duration as i64).sum();
+                return Ok(Some(PlaylistWithSongs {
+                    playlist,
+                    entry: songs,
+                }));
+            }
+
             let songs = queries::song_with_metadata_query(username)
                 .join_rev(
                     JoinType::InnerJoin,
@@ -324,3 +596,7 @@ impl Service {
         }
     }
 }
+
+#[cfg(test)]
+#[path = "playlists_tests.rs"]
+mod tests;
@@ -0,0 +1,165 @@
+use super::*;
+use crate::test_support::{insert_folder, insert_user, memory_db, song};
+use sea_orm::DatabaseConnection;
+
+async fn setup_db() -> DatabaseConnection {
+    let db = memory_db().await;
+    for username in ["alice", "bob", "carol"] {
+        insert_user(&db, username).await;
+    }
+    insert_folder(&db, 1, "/music1", &["alice", "bob", "carol"]).await;
+    for id in ["s1", "s2", "s3"] {
+        child::ActiveModel {
+            duration: Set(100),
+            ..song(id, 1)
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+    db
+}
+
+fn denied<T: std::fmt::Debug>(result: Result<T, DbErr>) -> bool {
+    matches!(result, Err(DbErr::Custom(msg)) if msg == PERMISSION_DENIED)
+}
+
+fn names(playlists: Vec<PlaylistWithStats>) -> Vec<String> {
+    let mut names: Vec<_> = playlists.into_iter().map(|p| p.name).collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn lists_own_public_and_collaborative_playlists() {
+    let service = Service::new(setup_db().await);
+    let private = service
+        .create_playlist("Private".into(), "alice".into(), vec!["s1".into()])
+        .await
+        .unwrap();
+    let public = service
+        .create_playlist("Public".into(), "alice".into(), vec![])
+        .await
+        .unwrap();
+    let shared = service
+        .create_playlist("Shared".into(), "alice".into(), vec![])
+        .await
+        .unwrap();
+    let opts = UpdatePlaylistOptions {
+        public: Some(true),
+        ..Default::default()
+    };
+    service
+        .update_playlist(public, "alice", opts)
+        .await
+        .unwrap();
+    service
+        .set_playlist_users(shared, "alice", vec!["bob".into(), "alice".into()])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        names(service.get_playlists("alice", "alice").await.unwrap()),
+        vec!["Private", "Public", "Shared"]
+    );
+    assert_eq!(
+        names(service.get_playlists("bob", "bob").await.unwrap()),
+        vec!["Public", "Shared"]
+    );
+    assert_eq!(
+        names(service.get_playlists("carol", "alice").await.unwrap()),
+        vec!["Public"]
+    );
+    assert_eq!(
+        names(service.get_playlists("bob", "carol").await.unwrap()),
+        Vec::<String>::new()
+    );
+
+    assert!(service
+        .get_playlist(private, "bob")
+        .await
+        .unwrap()
+        .is_none());
+    let p = service.get_playlist(public, "bob").await.unwrap().unwrap();
+    assert!(!p.playlist.can_edit("bob"));
+    let p = service.get_playlist(shared, "bob").await.unwrap().unwrap();
+    assert_eq!(p.playlist.allowed_users, vec!["bob"]);
+    assert!(p.playlist.can_edit("bob"));
+    assert_eq!(
+        service
+            .get_playlist_users(shared, "carol")
+            .await
+            .unwrap_err(),
+        DbErr::Custom(PLAYLIST_NOT_FOUND.to_string())
+    );
+}
+
+#[tokio::test]
+async fn only_owners_and_allowed_users_edit_songs() {
+    let service = Service::new(setup_db().await);
+    let id = service
+        .create_playlist("Mix".into(), "alice".into(), vec!["s1".into()])
+        .await
+        .unwrap();
+    let opts = UpdatePlaylistOptions {
+        public: Some(true),
+        ..Default::default()
+    };
+    service.update_playlist(id, "alice", opts).await.unwrap();
+    service
+        .set_playlist_users(id, "alice", vec!["bob".into()])
+        .await
+        .unwrap();
+
+    let add = |song: &str| UpdatePlaylistOptions {
+        song_ids_to_add: vec![song.to_string()],
+        ..Default::default()
+    };
+    service.update_playlist(id, "bob", add("s2")).await.unwrap();
+    assert!(denied(
+        service.update_playlist(id, "carol", add("s3")).await
+    ));
+    assert!(denied(
+        service
+            .replace_playlist_songs(id, "carol", vec![], Default::default())
+            .await
+    ));
+    let rename = UpdatePlaylistOptions {
+        name: Some("Bob's".into()),
+        ..Default::default()
+    };
+    assert!(denied(service.update_playlist(id, "bob", rename).await));
+    assert!(denied(
+        service
+            .set_playlist_users(id, "bob", vec!["carol".into()])
+            .await
+    ));
+    assert!(denied(service.delete_playlist(id, "bob").await));
+    service
+        .replace_playlist_songs(
+            id,
+            "bob",
+            vec!["s3".into(), "s1".into()],
+            Default::default(),
+        )
+        .await
+        .unwrap();
+
+    let p = service.get_playlist(id, "alice").await.unwrap().unwrap();
+    let ids: Vec<_> = p.entry.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["s3", "s1"]);
+    assert_eq!(p.playlist.name, "Mix");
+
+    assert_eq!(
+        service
+            .set_playlist_users(id, "alice", vec!["nobody".into()])
+            .await
+            .unwrap_err(),
+        DbErr::Custom("Unknown user".to_string())
+    );
+    service
+        .set_playlist_users(id, "alice", vec![])
+        .await
+        .unwrap();
+    assert!(denied(service.update_playlist(id, "bob", add("s2")).await));
+}
@@ -0,0 +1,737 @@
+use crate::config::{Config, PodcastConfig};
+use crate::models::{podcast_channel, podcast_episode};
+use crate::scanner::utils::get_cover_cache_dir;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local, Utc};
+use once_cell::sync::Lazy;
+use path_clean::PathClean;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex::Regex;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+pub const STATUS_NEW: &str = "new";
+pub const STATUS_DOWNLOADING: &str = "downloading";
+pub const STATUS_COMPLETED: &str = "completed";
+pub const STATUS_ERROR: &str = "error";
+pub const STATUS_DELETED: &str = "deleted";
+
+const FEED_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest wait for more data from a server before a download is abandoned.
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+static HTML_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// A podcast feed reduced to what we store.
+#[derive(Debug, Default, PartialEq)]
+pub struct Feed {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub episodes: Vec<FeedEpisode>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct FeedEpisode {
+    pub guid: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub publish_date: Option<DateTime<Utc>>,
+    pub enclosure_url: String,
+    pub content_type: Option<String>,
+    pub size: Option<i64>,
+    pub duration: Option<i32>,
+}
+
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn set_once(field: &mut Option<String>, value: &str) {
+    if field.is_none() && !value.is_empty() {
+        *field = Some(value.to_string());
+    }
+}
+
+/// Reduces HTML show notes to plain text.
+fn plain_text(html: &str) -> String {
+    let text = HTML_TAG_RE.replace_all(html, " ");
+    let text = quick_xml::escape::unescape(&text)
+        .map(|t| t.into_owned())
+        .unwrap_or_else(|_| text.into_owned());
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parses `itunes:duration`, which is either seconds or `[HH:]MM:SS`.
+pub fn parse_duration(s: &str) -> Option<i32> {
+    let parts: Vec<&str> = s.trim().split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    let mut secs = 0f64;
+    for part in parts {
+        secs = secs * 60.0 + part.trim().parse::<f64>().ok()?;
+    }
+    Some(secs as i32)
+}
+
+/// RSS uses RFC 2822 dates and Atom RFC 3339; feeds in the wild mix both.
+pub fn parse_date(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    DateTime::parse_from_rfc2822(s)
+        .or_else(|_| DateTime::parse_from_rfc3339(s))
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+/// Parses an RSS 2.0 or Atom feed. Entries without an audio enclosure are dropped.
+pub fn parse_feed(xml: &str) -> Result<Feed> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut feed = Feed::default();
+    let mut stack: Vec<String> = Vec::new();
+    let mut text = String::new();
+    let mut episode: Option<FeedEpisode> = None;
+    let mut root_seen = false;
+
+    loop {
+        let event = reader.read_event()?;
+        let (e, empty) = match &event {
+            Event::Start(e) => (Some(e), false),
+            Event::Empty(e) => (Some(e), true),
+            _ => (None, false),
+        };
+        if let Some(e) = e {
+            let name = String::from_utf8_lossy(e.local_name().as_ref()).to_lowercase();
+            if !root_seen {
+                root_seen = true;
+                if name != "rss" && name != "feed" && name != "rdf" {
+                    bail!("Not an RSS or Atom feed");
+                }
+            }
+            match name.as_str() {
+                "item" | "entry" => episode = Some(FeedEpisode::default()),
+                // RSS <enclosure url type length/>
+                "enclosure" => {
+                    if let (Some(ep), Some(url)) = (episode.as_mut(), attr(e, b"url")) {
+                        ep.enclosure_url = url;
+                        ep.content_type = attr(e, b"type");
+                        ep.size = attr(e, b"length").and_then(|l| l.parse().ok());
+                    }
+                }
+                // Atom <link rel="enclosure" href type length/>
+                "link" if attr(e, b"rel").as_deref() == Some("enclosure") => {
+                    if let (Some(ep), Some(url)) = (episode.as_mut(), attr(e, b"href")) {
+                        ep.enclosure_url = url;
+                        ep.content_type = attr(e, b"type");
+                        ep.size = attr(e, b"length").and_then(|l| l.parse().ok());
+                    }
+                }
+                // <itunes:image href/>
+                "image" if episode.is_none() => {
+                    if let Some(href) = attr(e, b"href") {
+                        feed.image_url = Some(href);
+                    }
+                }
+                _ => {}
+            }
+            if !empty {
+                stack.push(name);
+                text.clear();
+            }
+            continue;
+        }
+
+        match event {
+            Event::Text(t) => text.push_str(&t.unescape()?),
+            Event::CData(c) => text.push_str(&String::from_utf8_lossy(&c.into_inner())),
+            Event::End(_) => {
+                let Some(name) = stack.pop() else { continue };
+                let parent = stack.last().map(String::as_str).unwrap_or_default();
+                let value = text.trim();
+                match (parent, name.as_str()) {
+                    (_, "item" | "entry") => {
+                        if let Some(mut ep) = episode.take() {
+                            if !ep.enclosure_url.is_empty() {
+                                if ep.guid.is_empty() {
+                                    ep.guid = ep.enclosure_url.clone();
+                                }
+                                if ep.title.is_empty() {
+                                    ep.title = ep.guid.clone();
+                                }
+                                feed.episodes.push(ep);
+                            }
+                        }
+                    }
+                    ("item" | "entry", field) => {
+                        if let Some(ep) = episode.as_mut() {
+                            match field {
+                                "title" if ep.title.is_empty() => ep.title = value.to_string(),
+                                "guid" | "id" => ep.guid = value.to_string(),
+                                "description" | "summary" | "encoded" | "content" => {
+                                    set_once(&mut ep.description, &plain_text(value))
+                                }
+                                "pubdate" | "published" => ep.publish_date = parse_date(value),
+                                "updated" if ep.publish_date.is_none() => {
+                                    ep.publish_date = parse_date(value)
+                                }
+                                "duration" => ep.duration = parse_duration(value),
+                                _ => {}
+                            }
+                        }
+                    }
+                    ("channel" | "feed", field) if episode.is_none() => match field {
+                        "title" => set_once(&mut feed.title, value),
+                        "description" | "subtitle" | "summary" => {
+                            set_once(&mut feed.description, &plain_text(value))
+                        }
+                        "logo" | "icon" => set_once(&mut feed.image_url, value),
+                        _ => {}
+                    },
+                    ("image", "url") if episode.is_none() => set_once(&mut feed.image_url, value),
+                    _ => {}
+                }
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !root_seen {
+        bail!("Empty feed");
+    }
+    Ok(feed)
+}
+
+/// Resolves a feed or enclosure URL. Absolute URLs are used as-is; anything
+/// else is taken relative to `base`.
+pub fn resolve_url(base: Option<&str>, url: &str) -> Result<String> {
+    let url = url.trim();
+    if url.contains("://") {
+        return Ok(url.to_string());
+    }
+    let base = base.ok_or_else(|| anyhow!("Not an absolute URL: {}", url))?;
+    Ok(format!(
+        "{}/{}",
+        base.trim_end_matches('/'),
+        url.trim_start_matches('/')
+    ))
+}
+
+/// File extension for a downloaded episode, from the enclosure URL or its MIME type.
+fn episode_suffix(url: &str, content_type: Option<&str>) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let from_url = path
+        .rsplit('/')
+        .next()
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_lowercase())
+        .filter(|ext| {
+            (1..=5).contains(&ext.len()) && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    from_url
+        .or_else(|| {
+            content_type
+                .and_then(mime_guess::get_mime_extensions_str)
+                .and_then(|exts| exts.first())
+                .map(|ext| ext.to_string())
+        })
+        .unwrap_or_else(|| "mp3".to_string())
+}
+
+/// Subscribes to podcast feeds, keeps them refreshed and downloads episodes
+/// into `SUBSONIC_DATA_DIR/podcasts/<channel>/`.
+pub struct PodcastService {
+    db: DatabaseConnection,
+    client: reqwest::Client,
+    config: PodcastConfig,
+    dir: PathBuf,
+    cover_dir: PathBuf,
+    refresh_lock: Mutex<()>,
+}
+
+impl PodcastService {
+    pub fn new(db: DatabaseConnection, config: &Config) -> Result<Self> {
+        Self::with_dirs(
+            db,
+            config.podcast.clone(),
+            Path::new(&config.subsonic.data_dir).join("podcasts"),
+            get_cover_cache_dir(config),
+        )
+    }
+
+    pub fn with_dirs(
+        db: DatabaseConnection,
+        config: PodcastConfig,
+        dir: PathBuf,
+        cover_dir: PathBuf,
+    ) -> Result<Self> {
+        Ok(Self {
+            db,
+            client: reqwest::Client::builder()
+                .user_agent(concat!(
+                    env!("CARGO_PKG_NAME"),
+                    "/",
+                    env!("CARGO_PKG_VERSION")
+                ))
+                .connect_timeout(Duration::from_secs(10))
+                .read_timeout(READ_TIMEOUT)
+                .build()?,
+            config,
+            dir,
+            cover_dir,
+            refresh_lock: Mutex::new(()),
+        })
+    }
+
+    /// Directory downloaded episodes live in; streamed files must be inside it.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Resets downloads interrupted by a restart and starts the refresh schedule.
+    pub fn start(self: &Arc<Self>) {
+        let schedule = self.config.refresh_schedule.clone();
+
+        let this = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = this.reset_interrupted_downloads().await {
+                log::error!("Failed to reset podcast downloads: {}", e);
+            }
+            let Some(schedule) = schedule else { return };
+            loop {
+                let Some(at) = schedule.after(&Local::now()).next() else {
+                    return;
+                };
+                tokio::time::sleep((at - Local::now()).to_std().unwrap_or_default()).await;
+                this.refresh_all().await;
+            }
+        });
+    }
+
+    async fn reset_interrupted_downloads(&self) -> Result<(), DbErr> {
+        podcast_episode::Entity::update_many()
+            .col_expr(podcast_episode::Column::Status, Expr::value(STATUS_NEW))
+            .filter(podcast_episode::Column::Status.eq(STATUS_DOWNLOADING))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    fn resolve(&self, url: &str) -> Result<String> {
+        resolve_url(self.config.feed_base_url.as_deref(), url)
+    }
+
+    /// Maps a `file://` URL to a local path. Only files under a `file://`
+    /// feed base are readable, so users cannot pull arbitrary server files.
+    fn local_path(&self, url: &str) -> Result<Option<PathBuf>> {
+        let Some(path) = url.strip_prefix("file://") else {
+            return Ok(None);
+        };
+        let root = self
+            .config
+            .feed_base_url
+            .as_deref()
+            .and_then(|b| b.strip_prefix("file://"))
+            .map(|b| Path::new(b).clean())
+            .ok_or_else(|| anyhow!("Local feeds are not enabled"))?;
+        let path = Path::new(path).clean();
+        if !path.starts_with(&root) {
+            bail!("Local path is outside the feed base: {}", url);
+        }
+        Ok(Some(path))
+    }
+
+    async fn fetch_text(&self, url: &str) -> Result<String> {
+        let url = self.resolve(url)?;
+        if let Some(path) = self.local_path(&url)? {
+            return Ok(tokio::fs::read_to_string(path).await?);
+        }
+        let res = self
+            .client
+            .get(&url)
+            .timeout(FEED_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(res.text().await?)
+    }
+
+    /// Downloads `url` to `dest` through a temporary file, so a failed
+    /// download never leaves a truncated episode behind.
+    async fn fetch_to_file(&self, url: &str, dest: &Path) -> Result<u64> {
+        let url = self.resolve(url)?;
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let part = dest.with_extension("part");
+        let written = async {
+            if let Some(path) = self.local_path(&url)? {
+                return Ok(tokio::fs::copy(path, &part).await?);
+            }
+            let mut res = self.client.get(&url).send().await?.error_for_status()?;
+            let mut file = tokio::fs::File::create(&part).await?;
+            let mut written = 0u64;
+            while let Some(chunk) = res.chunk().await? {
+                file.write_all(&chunk).await?;
+                written += chunk.len() as u64;
+            }
+            file.flush().await?;
+            Ok::<_, anyhow::Error>(written)
+        }
+        .await;
+        match written {
+            Ok(n) => {
+                tokio::fs::rename(&part, dest).await?;
+                Ok(n)
+            }
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&part).await;
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn get_channels(&self) -> Result<Vec<podcast_channel::Model>, DbErr> {
+        podcast_channel::Entity::find()
+            .order_by_asc(podcast_channel::Column::Title)
+            .order_by_asc(podcast_channel::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    pub async fn get_channel(&self, id: i32) -> Result<Option<podcast_channel::Model>, DbErr> {
+        podcast_channel::Entity::find_by_id(id).one(&self.db).await
+    }
+
+    /// Episodes of a channel, newest first.
+    pub async fn get_episodes(
+        &self,
+        channel_id: i32,
+    ) -> Result<Vec<podcast_episode::Model>, DbErr> {
+        podcast_episode::Entity::find()
+            .filter(podcast_episode::Column::ChannelId.eq(channel_id))
+            .order_by_desc(podcast_episode::Column::PublishDate)
+            .order_by_desc(podcast_episode::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    pub async fn get_episode(&self, id: i32) -> Result<Option<podcast_episode::Model>, DbErr> {
+        podcast_episode::Entity::find_by_id(id).one(&self.db).await
+    }
+
+    /// The most recently published episodes across all channels.
+    pub async fn get_newest_episodes(
+        &self,
+        count: u64,
+    ) -> Result<Vec<podcast_episode::Model>, DbErr> {
+        podcast_episode::Entity::find()
+            .filter(podcast_episode::Column::Status.ne(STATUS_DELETED))
+            .order_by_desc(podcast_episode::Column::PublishDate)
+            .order_by_desc(podcast_episode::Column::Id)
+            .limit(count)
+            .all(&self.db)
+            .await
+    }
+
+    /// Subscribes to `url`. The feed is fetched by a later `refresh_channel`.
+    pub async fn create_channel(&self, url: &str) -> Result<podcast_channel::Model> {
+        // Fail early on URLs we could never fetch.
+        let resolved = self.resolve(url)?;
+        if !resolved.starts_with("file://") {
+            reqwest::Url::parse(&resolved)?;
+        }
+        self.local_path(&resolved)?;
+
+        if let Some(existing) = podcast_channel::Entity::find()
+            .filter(podcast_channel::Column::Url.eq(url.trim()))
+            .one(&self.db)
+            .await?
+        {
+            return Ok(existing);
+        }
+        Ok(podcast_channel::ActiveModel {
+            url: Set(url.trim().to_string()),
+            status: Set(STATUS_NEW.to_string()),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?)
+    }
+
+    /// Unsubscribes and removes every downloaded episode of the channel.
+    pub async fn delete_channel(&self, id: i32) -> Result<bool> {
+        let res = podcast_channel::Entity::delete_by_id(id)
+            .exec(&self.db)
+            .await?;
+        let dir = self.dir.join(id.to_string());
+        if dir.exists() {
+            tokio::fs::remove_dir_all(&dir).await?;
+        }
+        let _ = tokio::fs::remove_file(self.cover_dir.join(format!("pc-{}", id))).await;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Removes the downloaded file. The episode is kept as `deleted` so the
+    /// next refresh does not bring it back.
+    pub async fn delete_episode(&self, id: i32) -> Result<bool> {
+        let Some(episode) = self.get_episode(id).await? else {
+            return Ok(false);
+        };
+        self.remove_episode_file(episode).await?;
+        Ok(true)
+    }
+
+    async fn remove_episode_file(&self, episode: podcast_episode::Model) -> Result<()> {
+        if let Some(path) = &episode.path {
+            match tokio::fs::remove_file(path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let mut episode: podcast_episode::ActiveModel = episode.into();
+        episode.status = Set(STATUS_DELETED.to_string());
+        episode.path = Set(None);
+        episode.downloaded_at = Set(None);
+        episode.error_message = Set(None);
+        episode.update(&self.db).await?;
+        Ok(())
+    }
+
+    pub async fn refresh_all(self: &Arc<Self>) {
+        let channels = match self.get_channels().await {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("Failed to load podcast channels: {}", e);
+                return;
+            }
+        };
+        for channel in channels {
+            if let Err(e) = self.refresh_channel(channel.id).await {
+                log::error!("Failed to refresh podcast channel {}: {}", channel.id, e);
+            }
+        }
+    }
+
+    /// Fetches the feed, records new episodes and, with auto-download on,
+    /// queues downloads of the newest of them. Feed errors are stored on the
+    /// channel.
+    pub async fn refresh_channel(self: &Arc<Self>, id: i32) -> Result<()> {
+        let mut added = self.update_channel(id).await?;
+        if self.config.auto_download && !added.is_empty() {
+            added.sort_by_key(|e| std::cmp::Reverse(e.publish_date));
+            let limit = match self.config.retention {
+                0 => added.len(),
+                n => n,
+            };
+            for episode in added.into_iter().take(limit) {
+                self.queue_download(episode.id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates channel `id` from its feed and returns the episodes it added.
+    /// Only one channel is updated at a time.
+    async fn update_channel(&self, id: i32) -> Result<Vec<podcast_episode::Model>> {
+        let _guard = self.refresh_lock.lock().await;
+        let Some(channel) = self.get_channel(id).await? else {
+            return Ok(Vec::new());
+        };
+
+        let feed = match self
+            .fetch_text(&channel.url)
+            .await
+            .and_then(|xml| parse_feed(&xml))
+        {
+            Ok(f) => f,
+            Err(e) => {
+                log::warn!("Failed to fetch podcast feed {}: {}", channel.url, e);
+                let mut channel: podcast_channel::ActiveModel = channel.into();
+                channel.status = Set(STATUS_ERROR.to_string());
+                channel.error_message = Set(Some(e.to_string()));
+                channel.last_refreshed_at = Set(Some(Utc::now()));
+                channel.update(&self.db).await?;
+                return Ok(Vec::new());
+            }
+        };
+
+        let image_changed = feed.image_url != channel.image_url;
+        let image_url = feed.image_url.clone();
+        let mut active: podcast_channel::ActiveModel = channel.into();
+        active.title = Set(feed.title.clone());
+        active.description = Set(feed.description.clone());
+        active.image_url = Set(feed.image_url.clone());
+        active.status = Set(STATUS_COMPLETED.to_string());
+        active.error_message = Set(None);
+        active.last_refreshed_at = Set(Some(Utc::now()));
+        active.update(&self.db).await?;
+
+        let cover = self.cover_dir.join(format!("pc-{}", id));
+        if let Some(url) = image_url.filter(|_| image_changed || !cover.exists()) {
+            if let Err(e) = self.fetch_to_file(&url, &cover).await {
+                log::warn!("Failed to fetch podcast image {}: {}", url, e);
+            }
+        }
+
+        let mut added = Vec::new();
+        for ep in feed.episodes {
+            let existing = podcast_episode::Entity::find()
+                .filter(podcast_episode::Column::ChannelId.eq(id))
+                .filter(podcast_episode::Column::Guid.eq(&ep.guid))
+                .one(&self.db)
+                .await?;
+            match existing {
+                Some(existing) => {
+                    let mut existing: podcast_episode::ActiveModel = existing.into();
+                    existing.title = Set(ep.title);
+                    existing.description = Set(ep.description);
+                    existing.publish_date = Set(ep.publish_date);
+                    existing.duration = Set(ep.duration);
+                    if existing.is_changed() {
+                        existing.update(&self.db).await?;
+                    }
+                }
+                None => {
+                    let inserted = podcast_episode::ActiveModel {
+                        channel_id: Set(id),
+                        guid: Set(ep.guid),
+                        title: Set(ep.title),
+                        description: Set(ep.description),
+                        publish_date: Set(ep.publish_date),
+                        enclosure_url: Set(ep.enclosure_url),
+                        content_type: Set(ep.content_type),
+                        size: Set(ep.size),
+                        duration: Set(ep.duration),
+                        status: Set(STATUS_NEW.to_string()),
+                        created_at: Set(Utc::now()),
+                        ..Default::default()
+                    }
+                    .insert(&self.db)
+                    .await?;
+                    added.push(inserted);
+                }
+            }
+        }
+
+        Ok(added)
+    }
+
+    /// Downloads an episode now. Returns `false` if it is unknown or already
+    /// being downloaded.
+    pub async fn download_episode(&self, id: i32) -> Result<bool> {
+        let claimed = podcast_episode::Entity::update_many()
+            .col_expr(
+                podcast_episode::Column::Status,
+                Expr::value(STATUS_DOWNLOADING),
+            )
+            .col_expr(
+                podcast_episode::Column::ErrorMessage,
+                Expr::value(Option::<String>::None),
+            )
+            .filter(podcast_episode::Column::Id.eq(id))
+            .filter(podcast_episode::Column::Status.ne(STATUS_DOWNLOADING))
+            .exec(&self.db)
+            .await?;
+        if claimed.rows_affected == 0 {
+            return Ok(false);
+        }
+        let Some(episode) = self.get_episode(id).await? else {
+            return Ok(false);
+        };
+
+        let suffix = episode_suffix(&episode.enclosure_url, episode.content_type.as_deref());
+        let dest = self
+            .dir
+            .join(episode.channel_id.to_string())
+            .join(format!("{}.{}", episode.id, suffix));
+        let result = self.fetch_to_file(&episode.enclosure_url, &dest).await;
+
+        let channel_id = episode.channel_id;
+        let mut active: podcast_episode::ActiveModel = episode.into();
+        match result {
+            Ok(size) => {
+                active.status = Set(STATUS_COMPLETED.to_string());
+                active.path = Set(Some(dest.to_string_lossy().into_owned()));
+                active.size = Set(Some(size as i64));
+                active.downloaded_at = Set(Some(Utc::now()));
+            }
+            Err(e) => {
+                log::warn!("Failed to download podcast episode {}: {}", id, e);
+                active.status = Set(STATUS_ERROR.to_string());
+                active.error_message = Set(Some(e.to_string()));
+            }
+        }
+        active.update(&self.db).await?;
+        self.apply_retention(channel_id).await?;
+        Ok(true)
+    }
+
+    /// Starts `download_episode` in the background.
+    pub fn queue_download(self: &Arc<Self>, id: i32) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = this.download_episode(id).await {
+                log::error!("Podcast download {} failed: {}", id, e);
+            }
+        });
+    }
+
+    /// Starts `refresh_channel` (or `refresh_all`) in the background.
+    pub fn queue_refresh(self: &Arc<Self>, id: Option<i32>) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            match id {
+                Some(id) => {
+                    if let Err(e) = this.refresh_channel(id).await {
+                        log::error!("Failed to refresh podcast channel {}: {}", id, e);
+                    }
+                }
+                None => this.refresh_all().await,
+            }
+        });
+    }
+
+    /// Deletes the least recently downloaded episodes beyond the configured
+    /// per-channel limit, so an older episode fetched on request is kept.
+    async fn apply_retention(&self, channel_id: i32) -> Result<()> {
+        if self.config.retention == 0 {
+            return Ok(());
+        }
+        let downloaded = podcast_episode::Entity::find()
+            .filter(podcast_episode::Column::ChannelId.eq(channel_id))
+            .filter(podcast_episode::Column::Status.eq(STATUS_COMPLETED))
+            .order_by_desc(podcast_episode::Column::DownloadedAt)
+            .order_by_desc(podcast_episode::Column::Id)
+            .all(&self.db)
+            .await?;
+        for episode in downloaded.into_iter().skip(self.config.retention) {
+            log::info!("Removing podcast episode {} past retention", episode.id);
+            self.remove_episode_file(episode).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "podcast_tests.rs"]
+mod tests;
@@ -0,0 +1,192 @@
+use super::*;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::Database;
+
+const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+  <channel>
+    <title>Test Cast</title>
+    <description><![CDATA[<p>All about <b>tests</b> &amp; more</p>]]></description>
+    <itunes:image href="cover.png"/>
+    <item>
+      <title>Episode 2</title>
+      <guid isPermaLink="false">ep-2</guid>
+      <pubDate>Tue, 02 Jan 2024 10:00:00 +0000</pubDate>
+      <itunes:duration>01:02:03</itunes:duration>
+      <enclosure url="ep2.mp3" type="audio/mpeg" length="5"/>
+    </item>
+    <item>
+      <title>Episode 1</title>
+      <pubDate>Mon, 01 Jan 2024 10:00:00 +0000</pubDate>
+      <itunes:duration>90</itunes:duration>
+      <enclosure url="ep1.mp3" type="audio/mpeg" length="5"/>
+    </item>
+    <item>
+      <title>Show notes only</title>
+    </item>
+  </channel>
+</rss>"#;
+
+#[test]
+fn parses_rss_feed() {
+    let feed = parse_feed(RSS).unwrap();
+    assert_eq!(feed.title.as_deref(), Some("Test Cast"));
+    assert_eq!(feed.description.as_deref(), Some("All about tests & more"));
+    assert_eq!(feed.image_url.as_deref(), Some("cover.png"));
+    assert_eq!(feed.episodes.len(), 2);
+
+    let ep = &feed.episodes[0];
+    assert_eq!(ep.guid, "ep-2");
+    assert_eq!(ep.title, "Episode 2");
+    assert_eq!(ep.duration, Some(3723));
+    assert_eq!(ep.size, Some(5));
+    assert_eq!(ep.content_type.as_deref(), Some("audio/mpeg"));
+    assert_eq!(ep.publish_date, parse_date("2024-01-02T10:00:00Z"));
+    // Without a guid the enclosure identifies the episode.
+    assert_eq!(feed.episodes[1].guid, "ep1.mp3");
+}
+
+#[test]
+fn parses_atom_feed() {
+    let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+      <title>Atom Cast</title>
+      <subtitle>Sub</subtitle>
+      <logo>https://example.com/logo.png</logo>
+      <entry>
+        <id>urn:uuid:1</id>
+        <title>First</title>
+        <link rel="alternate" href="https://example.com/1"/>
+        <updated>2024-03-01T00:00:00Z</updated>
+        <summary>Hello</summary>
+        <link rel="enclosure" href="https://example.com/1.ogg" type="audio/ogg" length="42"/>
+      </entry>
+    </feed>"#;
+    let feed = parse_feed(atom).unwrap();
+    assert_eq!(feed.title.as_deref(), Some("Atom Cast"));
+    assert_eq!(feed.description.as_deref(), Some("Sub"));
+    assert_eq!(
+        feed.image_url.as_deref(),
+        Some("https://example.com/logo.png")
+    );
+    assert_eq!(feed.episodes.len(), 1);
+    let ep = &feed.episodes[0];
+    assert_eq!(ep.guid, "urn:uuid:1");
+    assert_eq!(ep.enclosure_url, "https://example.com/1.ogg");
+    assert_eq!(ep.size, Some(42));
+    assert_eq!(ep.description.as_deref(), Some("Hello"));
+    assert!(ep.publish_date.is_some());
+}
+
+#[test]
+fn rejects_non_feeds() {
+    assert!(parse_feed("<html><body/></html>").is_err());
+    assert!(parse_feed("").is_err());
+}
+
+#[test]
+fn resolves_relative_urls_against_base() {
+    assert_eq!(
+        resolve_url(Some("file:///feeds/"), "/a.xml").unwrap(),
+        "file:///feeds/a.xml"
+    );
+    assert_eq!(
+        resolve_url(Some("file:///feeds"), "https://x/y.xml").unwrap(),
+        "https://x/y.xml"
+    );
+    assert!(resolve_url(None, "a.xml").is_err());
+    assert_eq!(episode_suffix("https://x/a.M4A?t=1", None), "m4a");
+    assert_eq!(episode_suffix("https://x/play", Some("audio/ogg")), "oga");
+}
+
+#[tokio::test]
+async fn refresh_downloads_and_applies_retention() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path().to_path_buf();
+    let feeds = root.join("feeds");
+    std::fs::create_dir_all(&feeds).unwrap();
+    std::fs::write(feeds.join("cast.xml"), RSS).unwrap();
+    std::fs::write(feeds.join("ep1.mp3"), b"one..").unwrap();
+    std::fs::write(feeds.join("ep2.mp3"), b"two..").unwrap();
+    std::fs::write(feeds.join("cover.png"), b"png").unwrap();
+
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let service = Arc::new(
+        PodcastService::with_dirs(
+            db,
+            PodcastConfig {
+                feed_base_url: Some(format!("file://{}", feeds.display())),
+                refresh_schedule: None,
+                retention: 1,
+                auto_download: true,
+            },
+            root.join("podcasts"),
+            root.join("covers"),
+        )
+        .unwrap(),
+    );
+
+    // Local files outside the feed base are off limits.
+    assert!(service.create_channel("file:///etc/passwd").await.is_err());
+
+    let channel = service.create_channel("cast.xml").await.unwrap();
+    service.refresh_channel(channel.id).await.unwrap();
+
+    let channel = service.get_channel(channel.id).await.unwrap().unwrap();
+    assert_eq!(channel.status, STATUS_COMPLETED);
+    assert_eq!(channel.title.as_deref(), Some("Test Cast"));
+    assert!(root
+        .join("covers")
+        .join(format!("pc-{}", channel.id))
+        .exists());
+
+    // Only the newest episode is fetched with a retention of one, in the
+    // background.
+    let mut episodes = service.get_episodes(channel.id).await.unwrap();
+    for _ in 0..100 {
+        if episodes[0].status != STATUS_NEW && episodes[0].status != STATUS_DOWNLOADING {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        episodes = service.get_episodes(channel.id).await.unwrap();
+    }
+    let statuses: Vec<(&str, &str)> = episodes
+        .iter()
+        .map(|e| (e.title.as_str(), e.status.as_str()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![("Episode 2", STATUS_COMPLETED), ("Episode 1", STATUS_NEW)]
+    );
+    let newest = episodes[0].path.clone().unwrap();
+    assert!(Path::new(&newest).starts_with(service.dir()));
+    assert_eq!(std::fs::read(&newest).unwrap(), b"two..");
+
+    // Asking for the older one keeps it as the latest download and pushes
+    // the newer one past the limit instead.
+    assert!(service.download_episode(episodes[1].id).await.unwrap());
+    let older = service.get_episode(episodes[1].id).await.unwrap().unwrap();
+    assert_eq!(older.status, STATUS_COMPLETED);
+    let newer = service.get_episode(episodes[0].id).await.unwrap().unwrap();
+    assert_eq!(newer.status, STATUS_DELETED);
+    assert!(!Path::new(&newest).exists());
+
+    // Deleted episodes are not re-added by a refresh.
+    service.refresh_channel(channel.id).await.unwrap();
+    assert_eq!(service.get_episodes(channel.id).await.unwrap().len(), 2);
+    assert_eq!(service.get_newest_episodes(10).await.unwrap().len(), 1);
+
+    let older_path = older.path.unwrap();
+    assert!(service.delete_episode(older.id).await.unwrap());
+    assert!(!Path::new(&older_path).exists());
+
+    // A broken feed is reported on the channel.
+    std::fs::write(feeds.join("cast.xml"), "not xml").unwrap();
+    service.refresh_channel(channel.id).await.unwrap();
+    let channel = service.get_channel(channel.id).await.unwrap().unwrap();
+    assert_eq!(channel.status, STATUS_ERROR);
+    assert!(channel.error_message.is_some());
+
+    assert!(service.delete_channel(channel.id).await.unwrap());
+    assert!(service.get_episodes(channel.id).await.unwrap().is_empty());
+}
@@ -0,0 +1,109 @@
+use crate::models::internet_radio_station;
+use crate::service::Service;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, DbErr, EntityTrait, QueryOrder, Set};
+
+/// Station fields as submitted by a client. `None` leaves a field unchanged on
+/// update; an empty home page URL clears it.
+#[derive(Debug, Default)]
+pub struct StationInput {
+    pub name: Option<String>,
+    pub stream_url: Option<String>,
+    pub home_page_url: Option<String>,
+}
+
+fn is_http_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https"))
+}
+
+impl StationInput {
+    /// Trims the fields and checks that the URLs are http(s). Creating a station
+    /// additionally requires a name and a stream URL.
+    pub fn validate(mut self, creating: bool) -> Result<Self, String> {
+        let trim = |v: Option<String>| v.map(|s| s.trim().to_string());
+        self.name = trim(self.name);
+        self.stream_url = trim(self.stream_url);
+        self.home_page_url = trim(self.home_page_url);
+
+        if self.name.as_deref() == Some("") || (creating && self.name.is_none()) {
+            return Err("Station name is required".into());
+        }
+        match self.stream_url.as_deref() {
+            Some(url) if !is_http_url(url) => return Err("Invalid stream URL".into()),
+            None if creating => return Err("Stream URL is required".into()),
+            _ => {}
+        }
+        if let Some(url) = self.home_page_url.as_deref() {
+            if !url.is_empty() && !is_http_url(url) {
+                return Err("Invalid home page URL".into());
+            }
+        }
+        Ok(self)
+    }
+}
+
+impl Service {
+    pub async fn get_radio_stations(&self) -> Result<Vec<internet_radio_station::Model>, DbErr> {
+        internet_radio_station::Entity::find()
+            .order_by_asc(internet_radio_station::Column::Name)
+            .order_by_asc(internet_radio_station::Column::Id)
+            .all(&self.db)
+            .await
+    }
+
+    /// Expects input that passed `StationInput::validate(true)`.
+    pub async fn create_radio_station(
+        &self,
+        input: StationInput,
+    ) -> Result<internet_radio_station::Model, DbErr> {
+        let now = Utc::now();
+        internet_radio_station::ActiveModel {
+            name: Set(input.name.unwrap_or_default()),
+            stream_url: Set(input.stream_url.unwrap_or_default()),
+            home_page_url: Set(input.home_page_url.filter(|u| !u.is_empty())),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await
+    }
+
+    /// Returns `None` if the station does not exist.
+    pub async fn update_radio_station(
+        &self,
+        id: i32,
+        input: StationInput,
+    ) -> Result<Option<internet_radio_station::Model>, DbErr> {
+        let Some(station) = internet_radio_station::Entity::find_by_id(id)
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let mut active: internet_radio_station::ActiveModel = station.into();
+        if let Some(name) = input.name {
+            active.name = Set(name);
+        }
+        if let Some(url) = input.stream_url {
+            active.stream_url = Set(url);
+        }
+        if let Some(url) = input.home_page_url {
+            active.home_page_url = Set(Some(url).filter(|u| !u.is_empty()));
+        }
+        active.updated_at = Set(Utc::now());
+        active.update(&self.db).await.map(Some)
+    }
+
+    pub async fn delete_radio_station(&self, id: i32) -> Result<bool, DbErr> {
+        let res = internet_radio_station::Entity::delete_by_id(id)
+            .exec(&self.db)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+}
+
+#[cfg(test)]
+#[path = "radio_tests.rs"]
+mod tests;
@@ -0,0 +1,78 @@
+use super::*;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::Database;
+
+fn input(name: Option<&str>, stream: Option<&str>, home: Option<&str>) -> StationInput {
+    StationInput {
+        name: name.map(Into::into),
+        stream_url: stream.map(Into::into),
+        home_page_url: home.map(Into::into),
+    }
+}
+
+#[test]
+fn validate_requires_name_and_http_urls() {
+    assert!(input(Some(" Jazz "), Some("https://radio/jazz"), None)
+        .validate(true)
+        .is_ok_and(|i| i.name.as_deref() == Some("Jazz")));
+    assert!(input(None, Some("https://radio/jazz"), None)
+        .validate(true)
+        .is_err());
+    assert!(input(Some("Jazz"), None, None).validate(true).is_err());
+    assert!(input(Some("Jazz"), Some("file:///etc/passwd"), None)
+        .validate(true)
+        .is_err());
+    assert!(input(Some("Jazz"), Some("http://radio/jazz"), Some("nope"))
+        .validate(true)
+        .is_err());
+    // Updates may leave fields out, but not blank the name.
+    assert!(input(None, None, Some("")).validate(false).is_ok());
+    assert!(input(Some(" "), None, None).validate(false).is_err());
+}
+
+#[tokio::test]
+async fn station_crud() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    let service = Service::new(db);
+
+    let jazz = service
+        .create_radio_station(input(
+            Some("Jazz"),
+            Some("https://radio/jazz"),
+            Some("https://radio"),
+        ))
+        .await
+        .unwrap();
+    service
+        .create_radio_station(input(Some("Ambient"), Some("https://radio/ambient"), None))
+        .await
+        .unwrap();
+
+    let names: Vec<String> = service
+        .get_radio_stations()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(names, vec!["Ambient", "Jazz"]);
+
+    let updated = service
+        .update_radio_station(jazz.id, input(None, Some("https://radio/jazz2"), Some("")))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.name, "Jazz");
+    assert_eq!(updated.stream_url, "https://radio/jazz2");
+    assert_eq!(updated.home_page_url, None);
+
+    assert!(service
+        .update_radio_station(999, StationInput::default())
+        .await
+        .unwrap()
+        .is_none());
+    assert!(service.delete_radio_station(jazz.id).await.unwrap());
+    assert!(!service.delete_radio_station(jazz.id).await.unwrap());
+    assert_eq!(service.get_radio_stations().await.unwrap().len(), 1);
+}
@@ -13,7 +13,7 @@ use crate::service::tag::SongTags;
 /// Escapes individual characters that form Lucene operators and special syntax:
 /// + - & | ! ( ) { } [ ] ^ " ~ * ? : \ /
 /// Note: Escaping & and | prevents formation of && and || operators
-fn escape_lucene(query: &str) -> String {
+pub(crate) fn escape_lucene(query: &str) -> String {
     let special_chars = r#"+-&|!(){}[]^"~*?:\/"#;
     let mut escaped = String::with_capacity(query.len() * 2);
     for c in query.chars() {
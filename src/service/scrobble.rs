@@ -0,0 +1,616 @@
+use crate::config::{Config, ScrobbleConfig};
+use crate::crypto;
+use crate::models::{scrobble_queue, user, user_connection};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+pub const LISTENBRAINZ: &str = "listenbrainz";
+pub const LASTFM: &str = "lastfm";
+pub const SERVICES: [&str; 2] = [LISTENBRAINZ, LASTFM];
+
+/// Submissions are dropped after this many failed deliveries.
+const MAX_ATTEMPTS: i32 = 10;
+
+/// Queue entries handled per pass of the worker.
+const BATCH_SIZE: u64 = 50;
+
+/// How often the worker wakes up to retry deferred entries when idle.
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrobbleKind {
+    NowPlaying,
+    Submission,
+}
+
+impl ScrobbleKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScrobbleKind::NowPlaying => "now_playing",
+            ScrobbleKind::Submission => "submission",
+        }
+    }
+
+    fn from_column(kind: &str) -> Self {
+        if kind == "now_playing" {
+            ScrobbleKind::NowPlaying
+        } else {
+            ScrobbleKind::Submission
+        }
+    }
+}
+
+/// Track metadata captured at enqueue time, so deliveries survive library rescans.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackInfo {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub duration: i32,
+    pub track: i32,
+}
+
+/// Delivery failures. Transient errors are retried with backoff, the rest are dropped.
+#[derive(Debug)]
+pub enum SendError {
+    Transient(String),
+    Permanent(String),
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Transient(msg) | SendError::Permanent(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl From<reqwest::Error> for SendError {
+    fn from(e: reqwest::Error) -> Self {
+        SendError::Transient(e.to_string())
+    }
+}
+
+fn classify_status(status: reqwest::StatusCode, body: String) -> SendError {
+    let msg = format!("HTTP {}: {}", status.as_u16(), body.trim());
+    if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        SendError::Transient(msg)
+    } else {
+        SendError::Permanent(msg)
+    }
+}
+
+/// Builds the body of a ListenBrainz `submit-listens` request.
+pub fn listenbrainz_payload(
+    kind: ScrobbleKind,
+    track: &TrackInfo,
+    played_at: DateTime<Utc>,
+) -> serde_json::Value {
+    let mut additional_info = json!({
+        "submission_client": env!("CARGO_PKG_NAME"),
+        "submission_client_version": env!("CARGO_PKG_VERSION"),
+    });
+    if track.duration > 0 {
+        additional_info["duration_ms"] = json!(track.duration as i64 * 1000);
+    }
+    if track.track > 0 {
+        additional_info["tracknumber"] = json!(track.track);
+    }
+
+    let mut metadata = json!({
+        "artist_name": track.artist,
+        "track_name": track.title,
+        "additional_info": additional_info,
+    });
+    if let Some(album) = &track.album {
+        metadata["release_name"] = json!(album);
+    }
+
+    let mut listen = json!({ "track_metadata": metadata });
+    let listen_type = match kind {
+        ScrobbleKind::NowPlaying => "playing_now",
+        ScrobbleKind::Submission => {
+            listen["listened_at"] = json!(played_at.timestamp());
+            "single"
+        }
+    };
+
+    json!({ "listen_type": listen_type, "payload": [listen] })
+}
+
+/// Signs a Last.fm request: every parameter except `format` and `callback`,
+/// sorted by name and concatenated as name+value, followed by the shared secret.
+pub fn lastfm_signature(params: &BTreeMap<&str, String>, secret: &str) -> String {
+    let mut raw = String::new();
+    for (k, v) in params {
+        if *k == "format" || *k == "callback" {
+            continue;
+        }
+        raw.push_str(k);
+        raw.push_str(v);
+    }
+    raw.push_str(secret);
+    format!("{:x}", md5::compute(raw))
+}
+
+#[derive(Deserialize)]
+struct LastFmError {
+    error: i32,
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LastFmSessionResponse {
+    session: LastFmSession,
+}
+
+#[derive(Deserialize)]
+struct LastFmSession {
+    name: String,
+    key: String,
+}
+
+#[derive(Deserialize)]
+struct ListenBrainzValidation {
+    valid: bool,
+    user_name: Option<String>,
+}
+
+/// Thin client for ListenBrainz and Last.fm-compatible scrobbling APIs.
+#[derive(Clone)]
+pub struct ScrobbleClient {
+    client: reqwest::Client,
+    config: ScrobbleConfig,
+}
+
+impl ScrobbleClient {
+    pub fn new(config: ScrobbleConfig) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(15))
+                .user_agent(concat!(
+                    env!("CARGO_PKG_NAME"),
+                    "/",
+                    env!("CARGO_PKG_VERSION")
+                ))
+                .build()?,
+            config,
+        })
+    }
+
+    fn listenbrainz_url(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.config.listenbrainz_url.trim_end_matches('/'),
+            path
+        )
+    }
+
+    pub fn lastfm_enabled(&self) -> bool {
+        !self.config.lastfm_api_key.is_empty() && !self.config.lastfm_api_secret.is_empty()
+    }
+
+    /// Checks a ListenBrainz user token and returns the account name it belongs to.
+    pub async fn validate_listenbrainz_token(&self, token: &str) -> Result<String> {
+        let resp: ListenBrainzValidation = self
+            .client
+            .get(self.listenbrainz_url("1/validate-token"))
+            .header("Authorization", format!("Token {}", token))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        match (resp.valid, resp.user_name) {
+            (true, Some(name)) => Ok(name),
+            _ => Err(anyhow!("Invalid ListenBrainz token")),
+        }
+    }
+
+    /// Exchanges Last.fm credentials for a session key via `auth.getMobileSession`.
+    /// Returns the account name and the session key.
+    pub async fn lastfm_session(&self, username: &str, password: &str) -> Result<(String, String)> {
+        if !self.lastfm_enabled() {
+            return Err(anyhow!("Last.fm API key is not configured"));
+        }
+        let mut params = BTreeMap::new();
+        params.insert("method", "auth.getMobileSession".to_string());
+        params.insert("username", username.to_string());
+        params.insert("password", password.to_string());
+        let body = self
+            .lastfm_call(params)
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+        let resp: LastFmSessionResponse = serde_json::from_str(&body)?;
+        Ok((resp.session.name, resp.session.key))
+    }
+
+    async fn lastfm_call(&self, mut params: BTreeMap<&str, String>) -> Result<String, SendError> {
+        params.insert("api_key", self.config.lastfm_api_key.clone());
+        let sig = lastfm_signature(&params, &self.config.lastfm_api_secret);
+        params.insert("api_sig", sig);
+        params.insert("format", "json".to_string());
+
+        let resp = self
+            .client
+            .post(&self.config.lastfm_url)
+            .form(&params)
+            .send()
+            .await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+
+        // Last.fm reports API errors in the body, sometimes with a 200 status.
+        if let Ok(err) = serde_json::from_str::<LastFmError>(&body) {
+            let msg = format!(
+                "Last.fm error {}: {}",
+                err.error,
+                err.message.unwrap_or_default()
+            );
+            // 11: service offline, 16: temporarily unavailable, 29: rate limited
+            return Err(if matches!(err.error, 11 | 16 | 29) {
+                SendError::Transient(msg)
+            } else {
+                SendError::Permanent(msg)
+            });
+        }
+        if !status.is_success() {
+            return Err(classify_status(status, body));
+        }
+        Ok(body)
+    }
+
+    /// Delivers one now-playing update or submission to `service`.
+    pub async fn send(
+        &self,
+        service: &str,
+        token: &str,
+        kind: ScrobbleKind,
+        track: &TrackInfo,
+        played_at: DateTime<Utc>,
+    ) -> Result<(), SendError> {
+        match service {
+            LISTENBRAINZ => {
+                let resp = self
+                    .client
+                    .post(self.listenbrainz_url("1/submit-listens"))
+                    .header("Authorization", format!("Token {}", token))
+                    .json(&listenbrainz_payload(kind, track, played_at))
+                    .send()
+                    .await?;
+                let status = resp.status();
+                if status.is_success() {
+                    Ok(())
+                } else {
+                    Err(classify_status(
+                        status,
+                        resp.text().await.unwrap_or_default(),
+                    ))
+                }
+            }
+            LASTFM => {
+                if !self.lastfm_enabled() {
+                    return Err(SendError::Transient(
+                        "Last.fm API key is not configured".to_string(),
+                    ));
+                }
+                let mut params = BTreeMap::new();
+                params.insert("sk", token.to_string());
+                params.insert("artist", track.artist.clone());
+                params.insert("track", track.title.clone());
+                if let Some(album) = &track.album {
+                    params.insert("album", album.clone());
+                }
+                if track.duration > 0 {
+                    params.insert("duration", track.duration.to_string());
+                }
+                if track.track > 0 {
+                    params.insert("trackNumber", track.track.to_string());
+                }
+                match kind {
+                    ScrobbleKind::NowPlaying => {
+                        params.insert("method", "track.updateNowPlaying".to_string());
+                    }
+                    ScrobbleKind::Submission => {
+                        params.insert("method", "track.scrobble".to_string());
+                        params.insert("timestamp", played_at.timestamp().to_string());
+                    }
+                }
+                self.lastfm_call(params).await.map(|_| ())
+            }
+            other => Err(SendError::Permanent(format!(
+                "Unknown scrobble service: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Persists outbound scrobbles in `scrobble_queue` and forwards them to the
+/// user's linked accounts from a background task, retrying with backoff.
+pub struct ScrobbleForwarder {
+    db: DatabaseConnection,
+    client: ScrobbleClient,
+    password_secret: String,
+    notify: Notify,
+}
+
+impl ScrobbleForwarder {
+    pub fn new(db: DatabaseConnection, config: &Config) -> Result<Self> {
+        Ok(Self::with_client(
+            db,
+            ScrobbleClient::new(config.scrobble.clone())?,
+            &config.server.password_secret,
+        ))
+    }
+
+    pub fn with_client(
+        db: DatabaseConnection,
+        client: ScrobbleClient,
+        password_secret: &str,
+    ) -> Self {
+        Self {
+            db,
+            client,
+            password_secret: password_secret.to_string(),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn client(&self) -> &ScrobbleClient {
+        &self.client
+    }
+
+    /// Starts the delivery loop. Pending entries from a previous run are sent first.
+    pub fn start(self: &Arc<Self>) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match this.process_due().await {
+                    // A full batch probably means more is due; go again right away.
+                    Ok(n) if n as u64 >= BATCH_SIZE => continue,
+                    Ok(_) => {}
+                    Err(e) => log::error!("Scrobble queue error: {}", e),
+                }
+                let _ = tokio::time::timeout(IDLE_INTERVAL, this.notify.notified()).await;
+            }
+        });
+    }
+
+    /// Queues `plays` for every account the user has linked, if scrobbling is enabled.
+    pub async fn enqueue(
+        &self,
+        user: &user::Model,
+        kind: ScrobbleKind,
+        plays: &[(String, DateTime<Utc>)],
+    ) -> Result<usize, DbErr> {
+        if !user.scrobbling_enabled || plays.is_empty() {
+            return Ok(0);
+        }
+        let services: Vec<String> = user_connection::Entity::find()
+            .select_only()
+            .column(user_connection::Column::Service)
+            .filter(user_connection::Column::Username.eq(&user.username))
+            .into_tuple::<String>()
+            .all(&self.db)
+            .await?;
+        if services.is_empty() {
+            return Ok(0);
+        }
+
+        let ids: Vec<String> = plays.iter().map(|(id, _)| id.clone()).collect();
+        let songs = crate::service::Service::new(self.db.clone())
+            .get_songs_by_ids(&ids, &user.username)
+            .await?;
+
+        let now = Utc::now();
+        let mut queued = 0;
+        for (song_id, played_at) in plays {
+            let Some(song) = songs.iter().find(|s| s.id == *song_id) else {
+                continue;
+            };
+            let Some(artist) = song
+                .artists
+                .first()
+                .map(|a| a.name.clone())
+                .or_else(|| song.album_artists.first().map(|a| a.name.clone()))
+            else {
+                // Both services reject listens without an artist.
+                continue;
+            };
+            let track = TrackInfo {
+                artist,
+                title: song.title.clone(),
+                album: song.album.clone(),
+                duration: song.duration,
+                track: song.track,
+            };
+            let payload =
+                serde_json::to_string(&track).map_err(|e| DbErr::Custom(e.to_string()))?;
+
+            for service in &services {
+                scrobble_queue::ActiveModel {
+                    username: Set(user.username.clone()),
+                    service: Set(service.clone()),
+                    kind: Set(kind.as_str().to_string()),
+                    payload: Set(payload.clone()),
+                    played_at: Set(*played_at),
+                    attempts: Set(0),
+                    next_attempt_at: Set(now),
+                    last_error: Set(None),
+                    created_at: Set(now),
+                    ..Default::default()
+                }
+                .insert(&self.db)
+                .await?;
+                queued += 1;
+            }
+        }
+
+        if queued > 0 {
+            self.notify.notify_one();
+        }
+        Ok(queued)
+    }
+
+    /// Attempts delivery of every entry that is due. Returns how many were handled.
+    pub async fn process_due(&self) -> Result<usize, DbErr> {
+        let now = Utc::now();
+        let due = scrobble_queue::Entity::find()
+            .filter(scrobble_queue::Column::NextAttemptAt.lte(now))
+            .order_by_asc(scrobble_queue::Column::PlayedAt)
+            .order_by_asc(scrobble_queue::Column::Id)
+            .limit(BATCH_SIZE)
+            .all(&self.db)
+            .await?;
+
+        for entry in &due {
+            let kind = ScrobbleKind::from_column(&entry.kind);
+            match self.deliver(entry, kind).await {
+                Ok(()) => {}
+                // Now-playing is only meaningful right away, so it is never retried.
+                Err(SendError::Transient(e))
+                    if kind == ScrobbleKind::Submission && entry.attempts + 1 < MAX_ATTEMPTS =>
+                {
+                    log::warn!(
+                        "Scrobble to {} for {} failed, will retry: {}",
+                        entry.service,
+                        entry.username,
+                        e
+                    );
+                    let attempts = entry.attempts + 1;
+                    let mut active: scrobble_queue::ActiveModel = entry.clone().into();
+                    active.attempts = Set(attempts);
+                    active.next_attempt_at = Set(now + retry_delay(attempts));
+                    active.last_error = Set(Some(e));
+                    active.update(&self.db).await?;
+                    continue;
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Dropping {} to {} for {}: {}",
+                        entry.kind,
+                        entry.service,
+                        entry.username,
+                        e
+                    );
+                }
+            }
+            scrobble_queue::Entity::delete_by_id(entry.id)
+                .exec(&self.db)
+                .await?;
+        }
+
+        Ok(due.len())
+    }
+
+    async fn deliver(
+        &self,
+        entry: &scrobble_queue::Model,
+        kind: ScrobbleKind,
+    ) -> Result<(), SendError> {
+        let connection =
+            user_connection::Entity::find_by_id((entry.username.clone(), entry.service.clone()))
+                .one(&self.db)
+                .await
+                .map_err(|e| SendError::Transient(e.to_string()))?
+                .ok_or_else(|| SendError::Permanent("Account is no longer linked".to_string()))?;
+
+        let token = crypto::decrypt(&connection.token, self.password_secret.as_bytes())
+            .map_err(|e| SendError::Permanent(format!("Failed to decrypt token: {}", e)))?;
+        let track: TrackInfo = serde_json::from_str(&entry.payload)
+            .map_err(|e| SendError::Permanent(format!("Invalid payload: {}", e)))?;
+        self.client
+            .send(&entry.service, &token, kind, &track, entry.played_at)
+            .await
+    }
+
+    /// Links (or relinks) an account, storing its token encrypted.
+    pub async fn link(
+        &self,
+        username: &str,
+        service: &str,
+        account_name: Option<String>,
+        token: &str,
+    ) -> Result<()> {
+        let token = crypto::encrypt(token, self.password_secret.as_bytes())?;
+        let model = user_connection::ActiveModel {
+            username: Set(username.to_string()),
+            service: Set(service.to_string()),
+            account_name: Set(account_name),
+            token: Set(token),
+            created_at: Set(Utc::now()),
+        };
+        user_connection::Entity::insert(model)
+            .on_conflict(
+                sea_orm::sea_query::OnConflict::columns([
+                    user_connection::Column::Username,
+                    user_connection::Column::Service,
+                ])
+                .update_columns([
+                    user_connection::Column::AccountName,
+                    user_connection::Column::Token,
+                    user_connection::Column::CreatedAt,
+                ])
+                .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Removes a linked account together with anything still queued for it.
+    pub async fn unlink(&self, username: &str, service: &str) -> Result<bool, DbErr> {
+        scrobble_queue::Entity::delete_many()
+            .filter(scrobble_queue::Column::Username.eq(username))
+            .filter(scrobble_queue::Column::Service.eq(service))
+            .exec(&self.db)
+            .await?;
+        let res = user_connection::Entity::delete_many()
+            .filter(user_connection::Column::Username.eq(username))
+            .filter(user_connection::Column::Service.eq(service))
+            .exec(&self.db)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Number of undelivered entries per service for the user.
+    pub async fn pending_counts(&self, username: &str) -> Result<Vec<(String, i64)>, DbErr> {
+        scrobble_queue::Entity::find()
+            .select_only()
+            .column(scrobble_queue::Column::Service)
+            .column_as(scrobble_queue::Column::Id.count(), "count")
+            .filter(scrobble_queue::Column::Username.eq(username))
+            .group_by(scrobble_queue::Column::Service)
+            .into_tuple::<(String, i64)>()
+            .all(&self.db)
+            .await
+    }
+
+    pub async fn connections(&self, username: &str) -> Result<Vec<user_connection::Model>, DbErr> {
+        user_connection::Entity::find()
+            .filter(user_connection::Column::Username.eq(username))
+            .all(&self.db)
+            .await
+    }
+}
+
+/// Exponential backoff starting at 30 seconds, capped at six hours.
+fn retry_delay(attempts: i32) -> ChronoDuration {
+    let secs = 30i64.saturating_mul(1i64 << attempts.clamp(0, 20));
+    ChronoDuration::seconds(secs.min(6 * 3600))
+}
+
+#[cfg(test)]
+#[path = "scrobble_tests.rs"]
+mod tests;
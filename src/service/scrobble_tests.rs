@@ -0,0 +1,252 @@
+use super::*;
+use crate::models::{artist, child, music_folder, song_artist, user_music_folder};
+use migration::{Migrator, MigratorTrait};
+use poem::{
+    handler, http::StatusCode, listener::Acceptor, listener::Listener, listener::TcpListener,
+    web::Json, EndpointExt, Request, Route, Server,
+};
+use sea_orm::Database;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Mutex;
+
+fn track() -> TrackInfo {
+    TrackInfo {
+        artist: "Radiohead".into(),
+        title: "Airbag".into(),
+        album: Some("OK Computer".into()),
+        duration: 284,
+        track: 1,
+    }
+}
+
+#[test]
+fn lastfm_signature_sorts_params_and_skips_format() {
+    let mut params = BTreeMap::new();
+    params.insert("method", "track.scrobble".to_string());
+    params.insert("api_key", "key".to_string());
+    params.insert("format", "json".to_string());
+
+    let expected = format!("{:x}", md5::compute("api_keykeymethodtrack.scrobblesecret"));
+    assert_eq!(lastfm_signature(&params, "secret"), expected);
+}
+
+#[test]
+fn listenbrainz_payload_omits_timestamp_for_now_playing() {
+    let played_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+    let single = listenbrainz_payload(ScrobbleKind::Submission, &track(), played_at);
+    assert_eq!(single["listen_type"], "single");
+    assert_eq!(single["payload"][0]["listened_at"], 1_700_000_000);
+    let meta = &single["payload"][0]["track_metadata"];
+    assert_eq!(meta["release_name"], "OK Computer");
+    assert_eq!(meta["additional_info"]["duration_ms"], 284_000);
+
+    let now = listenbrainz_payload(ScrobbleKind::NowPlaying, &track(), played_at);
+    assert_eq!(now["listen_type"], "playing_now");
+    assert!(now["payload"][0].get("listened_at").is_none());
+}
+
+#[derive(Default)]
+struct Stub {
+    status: AtomicU16,
+    requests: Mutex<Vec<(String, serde_json::Value)>>,
+}
+
+#[handler]
+async fn submit_listens(
+    req: &Request,
+    stub: poem::web::Data<&Arc<Stub>>,
+    Json(body): Json<serde_json::Value>,
+) -> StatusCode {
+    let auth = req.header("Authorization").unwrap_or_default().to_string();
+    stub.requests.lock().unwrap().push((auth, body));
+    StatusCode::from_u16(stub.status.load(Ordering::SeqCst)).unwrap()
+}
+
+/// Serves a fake ListenBrainz API on an ephemeral port and returns its base URL.
+async fn start_stub(stub: Arc<Stub>) -> String {
+    let acceptor = TcpListener::bind("127.0.0.1:0")
+        .into_acceptor()
+        .await
+        .unwrap();
+    let addr = acceptor.local_addr()[0].as_socket_addr().cloned().unwrap();
+    let app = Route::new()
+        .at("/1/submit-listens", poem::post(submit_listens))
+        .data(stub);
+    tokio::spawn(Server::new_with_acceptor(acceptor).run(app));
+    format!("http://{}", addr)
+}
+
+async fn setup(base_url: String) -> (DatabaseConnection, ScrobbleForwarder, user::Model) {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+
+    let now = Utc::now();
+    let user = user::ActiveModel {
+        username: Set("testuser".to_string()),
+        password: Set("pass".to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        scrobbling_enabled: Set(true),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    music_folder::ActiveModel {
+        id: Set(1),
+        path: Set("/music".to_string()),
+        name: Set(None),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    user_music_folder::ActiveModel {
+        username: Set("testuser".to_string()),
+        music_folder_id: Set(1),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    child::ActiveModel {
+        id: Set("song1".to_string()),
+        is_dir: Set(false),
+        title: Set("Airbag".to_string()),
+        path: Set("/music/airbag.mp3".to_string()),
+        music_folder_id: Set(1),
+        r#type: Set("music".to_string()),
+        track: Set(1),
+        year: Set(1997),
+        disc_number: Set(1),
+        duration: Set(284),
+        bit_rate: Set(320),
+        size: Set(0),
+        is_video: Set(false),
+        average_rating: Set(0.0),
+        play_count: Set(0),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    artist::ActiveModel {
+        id: Set("ar1".to_string()),
+        name: Set("Radiohead".to_string()),
+        artist_image_url: Set(None),
+        average_rating: Set(0.0),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    song_artist::ActiveModel {
+        song_id: Set("song1".to_string()),
+        artist_id: Set("ar1".to_string()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    let client = ScrobbleClient::new(ScrobbleConfig {
+        listenbrainz_url: base_url,
+        lastfm_url: "http://127.0.0.1:1".to_string(),
+        lastfm_api_key: String::new(),
+        lastfm_api_secret: String::new(),
+    })
+    .unwrap();
+    let forwarder = ScrobbleForwarder::with_client(db.clone(), client, "secret");
+    forwarder
+        .link("testuser", LISTENBRAINZ, Some("lb-user".into()), "lb-token")
+        .await
+        .unwrap();
+
+    (db, forwarder, user)
+}
+
+#[tokio::test]
+async fn forwards_queued_submissions_to_listenbrainz() {
+    let stub = Arc::new(Stub::default());
+    stub.status.store(200, Ordering::SeqCst);
+    let (db, forwarder, user) = setup(start_stub(stub.clone()).await).await;
+
+    let played_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+    let queued = forwarder
+        .enqueue(
+            &user,
+            ScrobbleKind::Submission,
+            &[("song1".into(), played_at)],
+        )
+        .await
+        .unwrap();
+    assert_eq!(queued, 1);
+
+    assert_eq!(forwarder.process_due().await.unwrap(), 1);
+
+    let requests = stub.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].0, "Token lb-token");
+    let listen = &requests[0].1["payload"][0];
+    assert_eq!(listen["listened_at"], 1_700_000_000);
+    assert_eq!(listen["track_metadata"]["artist_name"], "Radiohead");
+    assert_eq!(listen["track_metadata"]["track_name"], "Airbag");
+
+    let remaining = scrobble_queue::Entity::find().all(&db).await.unwrap();
+    assert!(remaining.is_empty());
+}
+
+#[tokio::test]
+async fn keeps_submissions_queued_while_service_is_down() {
+    let stub = Arc::new(Stub::default());
+    stub.status.store(503, Ordering::SeqCst);
+    let (db, forwarder, user) = setup(start_stub(stub.clone()).await).await;
+
+    forwarder
+        .enqueue(
+            &user,
+            ScrobbleKind::Submission,
+            &[("song1".into(), Utc::now())],
+        )
+        .await
+        .unwrap();
+    forwarder
+        .enqueue(
+            &user,
+            ScrobbleKind::NowPlaying,
+            &[("song1".into(), Utc::now())],
+        )
+        .await
+        .unwrap();
+    forwarder.process_due().await.unwrap();
+
+    // The submission is deferred, the stale now-playing update is dropped.
+    let remaining = scrobble_queue::Entity::find().all(&db).await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].kind, "submission");
+    assert_eq!(remaining[0].attempts, 1);
+    assert!(remaining[0].next_attempt_at > Utc::now());
+    assert!(remaining[0].last_error.as_deref().unwrap().contains("503"));
+
+    // Nothing is due until the backoff expires.
+    assert_eq!(forwarder.process_due().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn skips_users_with_scrobbling_disabled() {
+    let (db, forwarder, mut user) = setup("http://127.0.0.1:1".to_string()).await;
+    user.scrobbling_enabled = false;
+
+    let queued = forwarder
+        .enqueue(
+            &user,
+            ScrobbleKind::Submission,
+            &[("song1".into(), Utc::now())],
+        )
+        .await
+        .unwrap();
+    assert_eq!(queued, 0);
+    assert!(scrobble_queue::Entity::find()
+        .all(&db)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -3,9 +3,14 @@ use crate::models::artist::ArtistWithStats;
 use crate::models::child::ChildWithMetadata;
 use crate::models::queries::{self};
 use crate::models::{album, artist, child, song_artist};
+use crate::scanner::search_index::{self, Kind};
+use crate::scanner::tags::LOSSLESS_CODECS;
 use crate::service::Service;
-use sea_orm::sea_query::{Expr, Query};
-use sea_orm::{ColumnTrait, DbErr, JoinType, PaginatorTrait, QueryFilter, QuerySelect};
+use sea_orm::sea_query::{Expr, Query, SimpleExpr};
+use sea_orm::{
+    ColumnTrait, Condition, DbErr, EntityTrait, JoinType, Order, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Select, Value,
+};
 use serde::Deserialize;
 
 #[derive(Debug, Default, Deserialize)]
@@ -21,37 +26,564 @@ pub struct SearchOptions {
     pub music_folder_id: Option<i32>,
 }
 
+/// One page of each result kind, with the number of matches across all pages.
+#[derive(Debug, Default)]
+pub struct SearchResults {
+    pub artists: Vec<ArtistWithStats>,
+    pub albums: Vec<AlbumWithStats>,
+    pub songs: Vec<ChildWithMetadata>,
+    pub artist_total: u64,
+    pub album_total: u64,
+    pub song_total: u64,
+}
+
+/// Fields that can be filtered on with `field:value`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Title,
+    Artist,
+    Album,
+    Genre,
+    Format,
+    Year,
+    Rating,
+    Plays,
+    Duration,
+    BitRate,
+    SampleRate,
+    BitDepth,
+    Channels,
+    Codec,
+    Starred,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "title" => Field::Title,
+            "artist" => Field::Artist,
+            "album" => Field::Album,
+            "genre" => Field::Genre,
+            "format" | "suffix" => Field::Format,
+            "year" => Field::Year,
+            "rating" => Field::Rating,
+            "plays" | "playcount" => Field::Plays,
+            "duration" => Field::Duration,
+            "bitrate" => Field::BitRate,
+            "samplerate" => Field::SampleRate,
+            "bitdepth" => Field::BitDepth,
+            "channels" => Field::Channels,
+            "codec" => Field::Codec,
+            "starred" => Field::Starred,
+            _ => return None,
+        })
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            Field::Year
+                | Field::Rating
+                | Field::Plays
+                | Field::Duration
+                | Field::BitRate
+                | Field::SampleRate
+                | Field::BitDepth
+                | Field::Channels
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Words matched anywhere in the indexed text.
+    Text(String),
+    /// Words matched in a text field (title, artist, album, genre or format).
+    Field(Field, String),
+    /// A numeric field compared against a value.
+    Compare(Field, Comparison, i64),
+    /// A numeric field within an inclusive range, e.g. `year:1990..1999`.
+    Between(Field, i64, i64),
+    /// Whether the item is starred by the searching user.
+    Starred(bool),
+    /// Songs in a lossless codec.
+    Lossless,
+    /// Lossless songs above CD quality (more than 16 bits or 48 kHz).
+    HiRes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryNode {
+    Filter(Filter),
+    Not(Box<QueryNode>),
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Minus,
+    /// A word with its quotes removed. `quoted_from` is where the first quoted
+    /// part starts; quoted text is never an operator or a field name.
+    Word {
+        text: String,
+        quoted_from: Option<usize>,
+    },
+}
+
+fn tokenize(query: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '-' => {
+                chars.next();
+                match chars.peek() {
+                    Some(&next) if !next.is_whitespace() && next != ')' => {
+                        tokens.push(Token::Minus)
+                    }
+                    _ => tokens.push(Token::Word {
+                        text: "-".into(),
+                        quoted_from: None,
+                    }),
+                }
+            }
+            _ => {
+                let mut text = String::new();
+                let mut quoted_from = None;
+                let mut in_quotes = false;
+                while let Some(&c) = chars.peek() {
+                    if c == '"' {
+                        in_quotes = !in_quotes;
+                        quoted_from.get_or_insert(text.len());
+                    } else if !in_quotes && (c.is_whitespace() || c == '(' || c == ')') {
+                        break;
+                    } else {
+                        text.push(c);
+                    }
+                    chars.next();
+                }
+                tokens.push(Token::Word { text, quoted_from });
+            }
+        }
+    }
+    tokens
+}
+
+fn parse_number(value: &str) -> Result<i64, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Not a number: {}", value))
+}
+
+fn parse_filter(text: &str) -> Result<Filter, String> {
+    let Some((name, value)) = text.split_once(':') else {
+        return Ok(Filter::Text(text.to_string()));
+    };
+    if name.eq_ignore_ascii_case("is") {
+        match value.to_ascii_lowercase().as_str() {
+            "starred" => return Ok(Filter::Starred(true)),
+            "lossless" => return Ok(Filter::Lossless),
+            "hires" => return Ok(Filter::HiRes),
+            _ => {}
+        }
+    }
+    // Words like "AC:DC" stay text
+    let Some(field) = Field::from_name(name) else {
+        return Ok(Filter::Text(text.to_string()));
+    };
+    if value.is_empty() {
+        return Err(format!("Missing value for {}", name));
+    }
+
+    if field == Field::Starred {
+        return match value.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "1" => Ok(Filter::Starred(true)),
+            "false" | "no" | "0" => Ok(Filter::Starred(false)),
+            _ => Err(format!("Expected true or false for starred, got {}", value)),
+        };
+    }
+    if !field.is_numeric() {
+        return Ok(Filter::Field(field, value.to_string()));
+    }
+    if let Some((low, high)) = value.split_once("..") {
+        return Ok(Filter::Between(
+            field,
+            parse_number(low)?,
+            parse_number(high)?,
+        ));
+    }
+    let (comparison, number) = [
+        (">=", Comparison::Ge),
+        ("<=", Comparison::Le),
+        (">", Comparison::Gt),
+        ("<", Comparison::Lt),
+        ("=", Comparison::Eq),
+    ]
+    .into_iter()
+    .find_map(|(prefix, c)| value.strip_prefix(prefix).map(|rest| (c, rest)))
+    .unwrap_or((Comparison::Eq, value));
+    Ok(Filter::Compare(field, comparison, parse_number(number)?))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word { text, quoted_from: None }) if text == keyword)
+    }
+
+    fn parse_or(&mut self) -> Result<QueryNode, String> {
+        let mut nodes = vec![self.parse_and()?];
+        while self.is_keyword("OR") {
+            self.pos += 1;
+            nodes.push(self.parse_and()?);
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.remove(0)
+        } else {
+            QueryNode::Or(nodes)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<QueryNode, String> {
+        let mut nodes = Vec::new();
+        loop {
+            if self.is_keyword("AND") {
+                self.pos += 1;
+            }
+            match self.peek() {
+                None | Some(Token::Close) => break,
+                _ if self.is_keyword("OR") => break,
+                _ => nodes.push(self.parse_unary()?),
+            }
+        }
+        match nodes.len() {
+            0 => Err("Expected a search term".into()),
+            1 => Ok(nodes.remove(0)),
+            _ => Ok(QueryNode::And(nodes)),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<QueryNode, String> {
+        if self.is_keyword("NOT") {
+            self.pos += 1;
+            return Ok(QueryNode::Not(Box::new(self.parse_unary()?)));
+        }
+        let token = self.peek().cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Minus) => Ok(QueryNode::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open) => {
+                let node = self.parse_or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err("Missing closing parenthesis".into());
+                }
+                self.pos += 1;
+                Ok(node)
+            }
+            Some(Token::Word { text, quoted_from }) => match (text.find(':'), quoted_from) {
+                (Some(colon), Some(quote)) if quote <= colon => {
+                    Ok(QueryNode::Filter(Filter::Text(text)))
+                }
+                _ => parse_filter(&text).map(QueryNode::Filter),
+            },
+            Some(Token::Close) | None => Err("Expected a search term".into()),
+        }
+    }
+}
+
+impl QueryNode {
+    /// Parses the query language: `field:value` filters, numeric comparisons
+    /// (`year:>=2000`) and ranges (`year:1990..1999`), `-` or `NOT` negation,
+    /// `OR`, parentheses, and free text. Terms next to each other must all match.
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(query),
+            pos: 0,
+        };
+        let node = parser.parse_or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err("Unexpected closing parenthesis".into());
+        }
+        Ok(node)
+    }
+
+    /// True for free text only, which is searched as before.
+    fn is_plain(&self) -> bool {
+        match self {
+            QueryNode::Filter(Filter::Text(_)) => true,
+            QueryNode::And(nodes) => nodes.iter().all(|n| n.is_plain()),
+            _ => false,
+        }
+    }
+
+    /// Text that should make a row rank higher, i.e. text not under a negation.
+    fn ranked_text(&self, out: &mut Vec<String>) {
+        match self {
+            QueryNode::Filter(Filter::Text(text) | Filter::Field(_, text)) => {
+                out.push(text.clone())
+            }
+            QueryNode::And(nodes) | QueryNode::Or(nodes) => {
+                nodes.iter().for_each(|n| n.ranked_text(out))
+            }
+            QueryNode::Filter(_) | QueryNode::Not(_) => {}
+        }
+    }
+
+    fn condition(&self, kind: Kind, username: &str) -> Condition {
+        match self {
+            QueryNode::Filter(filter) => Condition::all().add(filter.condition(kind, username)),
+            QueryNode::Not(node) => node.condition(kind, username).not(),
+            QueryNode::And(nodes) => nodes
+                .iter()
+                .fold(Condition::all(), |c, n| c.add(n.condition(kind, username))),
+            QueryNode::Or(nodes) => nodes
+                .iter()
+                .fold(Condition::any(), |c, n| c.add(n.condition(kind, username))),
+        }
+    }
+}
+
+fn always(matches: bool) -> SimpleExpr {
+    Expr::cust(if matches { "1 = 1" } else { "1 = 0" })
+}
+
+impl Filter {
+    /// The SQL for this filter on `kind`'s table. Filters on fields the kind
+    /// doesn't have, like `bitrate` for artists, match nothing.
+    fn condition(&self, kind: Kind, username: &str) -> SimpleExpr {
+        let table = kind.table();
+        match self {
+            Filter::Text(text) => match search_index::match_expression(text) {
+                Some(m) => search_index::matches(kind, &m),
+                None => always(true),
+            },
+            Filter::Field(field @ (Field::Title | Field::Artist | Field::Album), text) => {
+                let column = match (kind, field) {
+                    (Kind::Song, Field::Title) => "title",
+                    (Kind::Song | Kind::Album, Field::Artist) => "artist",
+                    (Kind::Song, Field::Album) => "album",
+                    (Kind::Album, Field::Album) | (Kind::Artist, Field::Artist) => "title",
+                    _ => return always(false),
+                };
+                match search_index::match_column(column, text) {
+                    Some(m) => search_index::matches(kind, &m),
+                    None => always(true),
+                }
+            }
+            Filter::Field(Field::Genre, genre) => {
+                let sql = match kind {
+                    Kind::Song => "EXISTS (SELECT 1 FROM song_genres g WHERE g.song_id = children.id AND g.genre_name = ? COLLATE NOCASE)",
+                    Kind::Album => "EXISTS (SELECT 1 FROM album_genres g WHERE g.album_id = albums.id AND g.genre_name = ? COLLATE NOCASE)",
+                    Kind::Artist => "EXISTS (SELECT 1 FROM song_artists sa JOIN song_genres g ON g.song_id = sa.song_id WHERE sa.artist_id = artists.id AND g.genre_name = ? COLLATE NOCASE)",
+                };
+                Expr::cust_with_values(sql, [genre.clone()])
+            }
+            Filter::Field(Field::Format, format) if kind == Kind::Song => {
+                Expr::cust_with_values("children.suffix = ? COLLATE NOCASE", [format.clone()])
+            }
+            Filter::Field(Field::Codec, codec) if kind == Kind::Song => {
+                Expr::cust_with_values("children.codec = ? COLLATE NOCASE", [codec.clone()])
+            }
+            Filter::Field(..) => always(false),
+            Filter::Lossless | Filter::HiRes if kind == Kind::Song => {
+                let codecs = LOSSLESS_CODECS
+                    .iter()
+                    .map(|c| format!("'{}'", c))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let hires = if *self == Filter::HiRes {
+                    " AND (children.bit_depth > 16 OR children.sampling_rate > 48000)"
+                } else {
+                    ""
+                };
+                Expr::cust(format!("children.codec IN ({}){}", codecs, hires))
+            }
+            Filter::Lossless | Filter::HiRes => always(false),
+            Filter::Compare(field, comparison, value) => {
+                match numeric_column(*field, kind, username) {
+                    Some((sql, mut values)) => {
+                        values.push((*value).into());
+                        Expr::cust_with_values(
+                            format!("{} {} ?", sql, comparison.as_sql()),
+                            values,
+                        )
+                    }
+                    None => always(false),
+                }
+            }
+            Filter::Between(field, low, high) => match numeric_column(*field, kind, username) {
+                Some((sql, mut values)) => {
+                    values.push((*low).into());
+                    values.push((*high).into());
+                    Expr::cust_with_values(format!("{} BETWEEN ? AND ?", sql), values)
+                }
+                None => always(false),
+            },
+            Filter::Starred(starred) => Expr::cust_with_values(
+                format!(
+                    "{}EXISTS (SELECT 1 FROM user_stars s WHERE s.username = ? AND s.item_type = '{}' AND s.item_id = {}.id)",
+                    if *starred { "" } else { "NOT " },
+                    kind.as_str(),
+                    table
+                ),
+                [username.to_string()],
+            ),
+        }
+    }
+}
+
+/// SQL for a numeric field of `kind`'s table, with the values it binds.
+fn numeric_column(field: Field, kind: Kind, username: &str) -> Option<(String, Vec<Value>)> {
+    let user = || vec![Value::from(username.to_string())];
+    Some(match (field, kind) {
+        (Field::Year, Kind::Song) => ("children.year".into(), vec![]),
+        (Field::Year, Kind::Album) => ("albums.year".into(), vec![]),
+        (Field::Duration, Kind::Song) => ("children.duration".into(), vec![]),
+        (Field::BitRate, Kind::Song) => ("children.bit_rate".into(), vec![]),
+        (Field::SampleRate, Kind::Song) => ("children.sampling_rate".into(), vec![]),
+        (Field::BitDepth, Kind::Song) => ("children.bit_depth".into(), vec![]),
+        (Field::Channels, Kind::Song) => ("children.channel_count".into(), vec![]),
+        (Field::Rating, _) => (
+            format!(
+                "COALESCE((SELECT r.rating FROM user_ratings r WHERE r.username = ? AND r.item_type = '{}' AND r.item_id = {}.id), 0)",
+                kind.as_str(),
+                kind.table()
+            ),
+            user(),
+        ),
+        (Field::Plays, Kind::Song) => (
+            "(SELECT COUNT(*) FROM play_history ph WHERE ph.username = ? AND ph.song_id = children.id)".into(),
+            user(),
+        ),
+        (Field::Plays, Kind::Album) => (
+            "(SELECT COUNT(*) FROM play_history ph JOIN children c ON c.id = ph.song_id WHERE ph.username = ? AND c.album_id = albums.id)".into(),
+            user(),
+        ),
+        _ => return None,
+    })
+}
+
+/// A search query: plain text, or an expression in the query language.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchQuery {
+    /// The FTS expression for plain text, `None` for an empty query.
+    Text(Option<String>),
+    Advanced(QueryNode),
+}
+
+impl SearchQuery {
+    /// Queries without operators, and ones that don't parse, are plain text.
+    pub fn parse(query: &str) -> Self {
+        match QueryNode::parse(query) {
+            Ok(node) if !node.is_plain() => SearchQuery::Advanced(node),
+            _ => SearchQuery::Text(search_index::match_expression(query)),
+        }
+    }
+
+    /// Restricts `select` to matching rows. An empty query keeps every row,
+    /// which is how clients list the library.
+    fn filter<E: EntityTrait>(&self, select: Select<E>, kind: Kind, username: &str) -> Select<E> {
+        match self {
+            SearchQuery::Text(Some(m)) => {
+                search_index::join_ranked(select, JoinType::InnerJoin, kind, m)
+            }
+            SearchQuery::Text(None) => select,
+            SearchQuery::Advanced(node) => select.filter(node.condition(kind, username)),
+        }
+    }
+
+    /// Orders rows kept by [`Self::filter`] best matches first. Totals are
+    /// counted before ranking, so they don't pay for it.
+    fn rank<E: EntityTrait>(&self, select: Select<E>, kind: Kind) -> Select<E> {
+        match self {
+            SearchQuery::Text(Some(_)) => select.order_by(search_index::rank(), Order::Asc),
+            SearchQuery::Text(None) => select,
+            SearchQuery::Advanced(node) => {
+                let mut text = Vec::new();
+                node.ranked_text(&mut text);
+                let terms: Vec<String> = text
+                    .iter()
+                    .filter_map(|t| search_index::match_expression(t))
+                    .collect();
+                if terms.is_empty() {
+                    select
+                } else {
+                    let m = terms.join(" OR ");
+                    search_index::join_ranked(select, JoinType::LeftJoin, kind, &m)
+                        .order_by(search_index::rank(), Order::Asc)
+                }
+            }
+        }
+    }
+}
+
 impl Service {
     pub async fn search(
         &self,
         opts: SearchOptions,
         username: &str,
-    ) -> Result<
-        (
-            Vec<ArtistWithStats>,
-            Vec<AlbumWithStats>,
-            Vec<ChildWithMetadata>,
-        ),
-        DbErr,
-    > {
-        let clean_query = opts.query.trim().trim_matches('"');
-        let search_query = format!("%{}%", clean_query);
-
-        // Artists
-        let mut artist_query = queries::artist_with_stats_query(username)
-            .filter(artist::Column::Name.like(&search_query));
-
-        // Albums
-        let mut album_query = queries::album_with_stats_query(username)
-            .filter(album::Column::Name.like(&search_query));
-
-        let mut song_query = queries::song_with_metadata_query(username)
-            .filter(child::Column::IsDir.eq(false))
-            .filter(
-                child::Column::Title.like(&search_query)
-                    .or(Expr::cust_with_values("EXISTS (SELECT 1 FROM albums WHERE albums.id = children.album_id AND albums.name LIKE ?)", [search_query.clone()]))
-                    .or(Expr::cust_with_values("EXISTS (SELECT 1 FROM song_artists sa JOIN artists a ON sa.artist_id = a.id WHERE sa.song_id = children.id AND a.name LIKE ?)", [search_query.clone()]))
-            );
+    ) -> Result<SearchResults, DbErr> {
+        let search = SearchQuery::parse(&opts.query);
+
+        let mut artist_query = search.filter(
+            queries::artist_with_stats_query(username),
+            Kind::Artist,
+            username,
+        );
+        let mut album_query = search.filter(
+            queries::album_with_stats_query(username),
+            Kind::Album,
+            username,
+        );
+        let mut song_query = search.filter(
+            queries::song_with_metadata_query(username).filter(child::Column::IsDir.eq(false)),
+            Kind::Song,
+            username,
+        );
 
         if let Some(folder_id) = opts.music_folder_id {
             artist_query = artist_query.filter(
@@ -74,28 +606,46 @@ impl Service {
             song_query = song_query.filter(child::Column::MusicFolderId.eq(folder_id));
         }
 
-        let artists = artist_query
+        // Totals are counted unordered; names and titles keep equal ranks in a
+        // stable order so pages don't overlap
+        let artist_total = artist_query.clone().count(&self.db).await?;
+        let artists = search
+            .rank(artist_query, Kind::Artist)
+            .order_by_asc(artist::Column::Name)
             .limit(opts.artist_count)
             .offset(opts.artist_offset)
             .into_model::<ArtistWithStats>()
             .all(&self.db)
             .await?;
 
-        let albums = album_query
+        let album_total = album_query.clone().count(&self.db).await?;
+        let albums = search
+            .rank(album_query, Kind::Album)
+            .order_by_asc(album::Column::Name)
             .limit(opts.album_count)
             .offset(opts.album_offset)
             .into_model::<AlbumWithStats>()
             .all(&self.db)
             .await?;
 
-        let songs = song_query
+        let song_total = song_query.clone().count(&self.db).await?;
+        let songs = search
+            .rank(song_query, Kind::Song)
+            .order_by_asc(child::Column::Title)
             .limit(opts.song_count)
             .offset(opts.song_offset)
             .into_model::<ChildWithMetadata>()
             .all(&self.db)
             .await?;
 
-        Ok((artists, albums, songs))
+        Ok(SearchResults {
+            artists,
+            albums,
+            songs,
+            artist_total,
+            album_total,
+            song_total,
+        })
     }
 
     pub async fn search_songs(
@@ -105,19 +655,17 @@ impl Service {
         offset: u64,
         username: &str,
     ) -> Result<(Vec<ChildWithMetadata>, u64), DbErr> {
-        let clean_query = query.trim().trim_matches('"');
-        let search_query = format!("%{}%", clean_query);
-
-        let q = queries::song_with_metadata_query(username)
-            .filter(child::Column::IsDir.eq(false))
-            .filter(
-                child::Column::Title.like(&search_query)
-                    .or(Expr::cust_with_values("EXISTS (SELECT 1 FROM albums WHERE albums.id = children.album_id AND albums.name LIKE ?)", [search_query.clone()]))
-                    .or(Expr::cust_with_values("EXISTS (SELECT 1 FROM song_artists sa JOIN artists a ON sa.artist_id = a.id WHERE sa.song_id = children.id AND a.name LIKE ?)", [search_query.clone()]))
-            );
+        let search = SearchQuery::parse(query);
+        let q = search.filter(
+            queries::song_with_metadata_query(username).filter(child::Column::IsDir.eq(false)),
+            Kind::Song,
+            username,
+        );
 
         let total = q.clone().count(&self.db).await?;
-        let songs = q
+        let songs = search
+            .rank(q, Kind::Song)
+            .order_by_asc(child::Column::Title)
             .limit(count)
             .offset(offset)
             .into_model::<ChildWithMetadata>()
@@ -127,3 +675,7 @@ impl Service {
         Ok((songs, total))
     }
 }
+
+#[cfg(test)]
+#[path = "search_tests.rs"]
+mod tests;
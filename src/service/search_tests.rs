@@ -0,0 +1,287 @@
+use super::*;
+use crate::models::{genre, song_genre, user_rating, user_star};
+use crate::test_support::{insert_folder, insert_user, memory_db, song};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+
+fn text(t: &str) -> QueryNode {
+    QueryNode::Filter(Filter::Text(t.into()))
+}
+
+#[test]
+fn parses_fields_ranges_and_negation() {
+    assert_eq!(
+        QueryNode::parse("artist:Radiohead year:>=2000 genre:rock rating:>=4 -live").unwrap(),
+        QueryNode::And(vec![
+            QueryNode::Filter(Filter::Field(Field::Artist, "Radiohead".into())),
+            QueryNode::Filter(Filter::Compare(Field::Year, Comparison::Ge, 2000)),
+            QueryNode::Filter(Filter::Field(Field::Genre, "rock".into())),
+            QueryNode::Filter(Filter::Compare(Field::Rating, Comparison::Ge, 4)),
+            QueryNode::Not(Box::new(text("live"))),
+        ])
+    );
+    assert_eq!(
+        QueryNode::parse("year:1990..1999 is:starred").unwrap(),
+        QueryNode::And(vec![
+            QueryNode::Filter(Filter::Between(Field::Year, 1990, 1999)),
+            QueryNode::Filter(Filter::Starred(true)),
+        ])
+    );
+    assert_eq!(
+        QueryNode::parse("album:\"ok computer\" plays:3").unwrap(),
+        QueryNode::And(vec![
+            QueryNode::Filter(Filter::Field(Field::Album, "ok computer".into())),
+            QueryNode::Filter(Filter::Compare(Field::Plays, Comparison::Eq, 3)),
+        ])
+    );
+}
+
+#[test]
+fn parses_boolean_operators_and_groups() {
+    assert_eq!(
+        QueryNode::parse("(jazz OR blues) AND NOT live").unwrap(),
+        QueryNode::And(vec![
+            QueryNode::Or(vec![text("jazz"), text("blues")]),
+            QueryNode::Not(Box::new(text("live"))),
+        ])
+    );
+    assert_eq!(
+        QueryNode::parse("a b OR c").unwrap(),
+        QueryNode::Or(vec![QueryNode::And(vec![text("a"), text("b")]), text("c")])
+    );
+    assert!(QueryNode::parse("(jazz").is_err());
+    assert!(QueryNode::parse("jazz)").is_err());
+    assert!(QueryNode::parse("year:>=soon").is_err());
+    assert!(QueryNode::parse("starred:maybe").is_err());
+}
+
+#[test]
+fn plain_text_stays_plain() {
+    for query in [
+        "beatles abbey",
+        "Jay-Z",
+        "AC:DC",
+        "\"year:2000\"",
+        "Song (Live",
+        "",
+        "OR",
+    ] {
+        assert!(
+            matches!(SearchQuery::parse(query), SearchQuery::Text(_)),
+            "{} should be plain text",
+            query
+        );
+    }
+    assert_eq!(
+        SearchQuery::parse("Song (Live)"),
+        SearchQuery::Text(Some("\"Song\"* \"(Live)\"*".into()))
+    );
+    assert!(matches!(
+        SearchQuery::parse("rock -live"),
+        SearchQuery::Advanced(_)
+    ));
+}
+
+async fn setup_db() -> DatabaseConnection {
+    let db = memory_db().await;
+    insert_user(&db, "alice").await;
+    insert_folder(&db, 1, "/music1", &["alice"]).await;
+
+    let now = Utc::now();
+    artist::ActiveModel {
+        id: Set("ar1".to_string()),
+        name: Set("Radiohead".to_string()),
+        artist_image_url: Set(None),
+        average_rating: Set(0.0),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    for (id, name, year) in [("al1", "OK Computer", 1997), ("al2", "In Rainbows", 2007)] {
+        album::ActiveModel {
+            id: Set(id.to_string()),
+            name: Set(name.to_string()),
+            created: Set(now),
+            average_rating: Set(0.0),
+            year: Set(year),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    for name in ["Rock", "Alternative Rock", "Electronic"] {
+        genre::ActiveModel {
+            name: Set(name.to_string()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    for (id, title, album_id, year, genre, (codec, bit_depth, sampling_rate)) in [
+        (
+            "s1",
+            "Paranoid Android",
+            "al1",
+            1997,
+            "Rock",
+            ("flac", 24, 96000),
+        ),
+        (
+            "s2",
+            "Reckoner",
+            "al2",
+            2007,
+            "Alternative Rock",
+            ("flac", 16, 44100),
+        ),
+        (
+            "s3",
+            "Reckoner (Live)",
+            "al2",
+            2008,
+            "Rock",
+            ("mp3", 16, 44100),
+        ),
+        ("s4", "Nude", "al2", 2007, "Electronic", ("alac", 16, 48000)),
+    ] {
+        child::ActiveModel {
+            title: Set(title.to_string()),
+            album_id: Set(Some(album_id.to_string())),
+            year: Set(year),
+            codec: Set(Some(codec.to_string())),
+            bit_depth: Set(Some(bit_depth)),
+            sampling_rate: Set(Some(sampling_rate)),
+            ..song(id, 1)
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        song_artist::ActiveModel {
+            song_id: Set(id.to_string()),
+            artist_id: Set("ar1".to_string()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        song_genre::ActiveModel {
+            song_id: Set(id.to_string()),
+            genre_name: Set(genre.to_string()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    for (id, rating) in [("s1", 5), ("s2", 4), ("s3", 4), ("s4", 2)] {
+        user_rating::ActiveModel {
+            username: Set("alice".to_string()),
+            item_id: Set(id.to_string()),
+            item_type: Set("song".to_string()),
+            rating: Set(rating),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+    user_star::ActiveModel {
+        username: Set("alice".to_string()),
+        item_id: Set("s4".to_string()),
+        item_type: Set("song".to_string()),
+        starred_at: Set(now),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    search_index::rebuild(&db).await.unwrap();
+    db
+}
+
+async fn song_ids(service: &Service, query: &str) -> Vec<String> {
+    let (songs, total) = service.search_songs(query, 20, 0, "alice").await.unwrap();
+    assert_eq!(total as usize, songs.len());
+    let mut ids: Vec<String> = songs.into_iter().map(|s| s.id).collect();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn advanced_queries_filter_songs() {
+    let service = Service::new(setup_db().await);
+
+    assert_eq!(
+        song_ids(
+            &service,
+            "artist:Radiohead year:>=2000 genre:\"alternative rock\" rating:>=4 -live"
+        )
+        .await,
+        vec!["s2"]
+    );
+    // Genres match by whole name.
+    assert_eq!(song_ids(&service, "genre:rock").await, vec!["s1", "s3"]);
+    assert_eq!(song_ids(&service, "year:1990..2000").await, vec!["s1"]);
+    assert_eq!(song_ids(&service, "is:starred").await, vec!["s4"]);
+    assert_eq!(
+        song_ids(&service, "starred:false rating:<5").await,
+        vec!["s2", "s3"]
+    );
+    assert_eq!(
+        song_ids(&service, "album:\"ok comp\" OR title:nude").await,
+        vec!["s1", "s4"]
+    );
+    assert_eq!(
+        song_ids(&service, "title:radiohead").await,
+        Vec::<String>::new()
+    );
+    assert_eq!(song_ids(&service, "reckoner").await, vec!["s2", "s3"]);
+}
+
+#[tokio::test]
+async fn quality_filters_match_technical_properties() {
+    let service = Service::new(setup_db().await);
+
+    assert_eq!(
+        song_ids(&service, "is:lossless").await,
+        vec!["s1", "s2", "s4"]
+    );
+    assert_eq!(song_ids(&service, "is:hires").await, vec!["s1"]);
+    assert_eq!(song_ids(&service, "-is:lossless").await, vec!["s3"]);
+    assert_eq!(song_ids(&service, "codec:ALAC").await, vec!["s4"]);
+    assert_eq!(
+        song_ids(&service, "samplerate:>44100 bitdepth:16").await,
+        vec!["s4"]
+    );
+    assert_eq!(
+        QueryNode::parse("is:hires").unwrap(),
+        QueryNode::Filter(Filter::HiRes)
+    );
+}
+
+#[tokio::test]
+async fn advanced_queries_apply_to_albums_and_artists() {
+    let service = Service::new(setup_db().await);
+    let search = |query: &str| SearchOptions {
+        query: query.to_string(),
+        artist_count: 20,
+        album_count: 20,
+        song_count: 20,
+        ..Default::default()
+    };
+
+    let results = service.search(search("year:>2000"), "alice").await.unwrap();
+    let albums: Vec<_> = results.albums.iter().map(|a| a.id.as_str()).collect();
+    assert_eq!(albums, vec!["al2"]);
+    // Artists have no year
+    assert_eq!(results.artist_total, 0);
+    assert_eq!(results.song_total, 3);
+
+    let results = service
+        .search(search("artist:radio -nude"), "alice")
+        .await
+        .unwrap();
+    assert_eq!(results.artist_total, 1);
+    assert_eq!(results.song_total, 3);
+}
@@ -0,0 +1,315 @@
+use crate::models::child::ChildWithMetadata;
+use crate::models::queries;
+use crate::models::{album, child, playlist, playlist_song, share, share_entry};
+use crate::service::Service;
+use chrono::{DateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, JoinType, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionError, TransactionTrait,
+};
+
+pub const KIND_SONG: &str = "song";
+pub const KIND_ALBUM: &str = "album";
+pub const KIND_PLAYLIST: &str = "playlist";
+
+const TOKEN_LEN: usize = 22;
+
+fn new_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LEN)
+        .map(char::from)
+        .collect()
+}
+
+impl Service {
+    /// Works out what `id` refers to (a song, an album or a playlist) as seen by
+    /// `username`; `None` if it is none of them or not accessible.
+    pub async fn resolve_share_entry(
+        &self,
+        username: &str,
+        id: &str,
+    ) -> Result<Option<&'static str>, DbErr> {
+        let songs = child::Entity::find_by_id(id.to_string())
+            .filter(child::Column::IsDir.eq(false))
+            .filter(queries::song_access_filter(username))
+            .count(&self.db)
+            .await?;
+        if songs > 0 {
+            return Ok(Some(KIND_SONG));
+        }
+
+        let albums = album::Entity::find_by_id(id.to_string())
+            .filter(queries::album_access_filter(username))
+            .count(&self.db)
+            .await?;
+        if albums > 0 {
+            return Ok(Some(KIND_ALBUM));
+        }
+
+        if let Ok(playlist_id) = id.parse::<i32>() {
+            let playlist = playlist::Entity::find_by_id(playlist_id)
+                .one(&self.db)
+                .await?;
+            if playlist.is_some_and(|p| p.owner == username || p.public) {
+                return Ok(Some(KIND_PLAYLIST));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub async fn create_share(
+        &self,
+        username: &str,
+        entries: Vec<(&'static str, String)>,
+        description: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<share::Model, DbErr> {
+        let username = username.to_string();
+        self.db
+            .transaction::<_, share::Model, DbErr>(|txn| {
+                Box::pin(async move {
+                    let share = share::ActiveModel {
+                        token: Set(new_token()),
+                        username: Set(username),
+                        description: Set(description),
+                        created_at: Set(Utc::now()),
+                        expires_at: Set(expires_at),
+                        visit_count: Set(0),
+                        ..Default::default()
+                    }
+                    .insert(txn)
+                    .await?;
+
+                    let rows: Vec<share_entry::ActiveModel> = entries
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, (kind, id))| share_entry::ActiveModel {
+                            share_id: Set(share.id),
+                            position: Set(i as i32),
+                            kind: Set(kind.to_string()),
+                            entry_id: Set(id),
+                        })
+                        .collect();
+                    if !rows.is_empty() {
+                        share_entry::Entity::insert_many(rows).exec(txn).await?;
+                    }
+
+                    Ok(share)
+                })
+            })
+            .await
+            .map_err(|e| match e {
+                TransactionError::Connection(e) => e,
+                TransactionError::Transaction(e) => e,
+            })
+    }
+
+    /// Shares created by `username` that have not been revoked, newest first.
+    pub async fn get_shares(&self, username: &str) -> Result<Vec<share::Model>, DbErr> {
+        share::Entity::find()
+            .filter(share::Column::Username.eq(username))
+            .filter(share::Column::RevokedAt.is_null())
+            .order_by_desc(share::Column::CreatedAt)
+            .all(&self.db)
+            .await
+    }
+
+    /// A share that has not been revoked, by its numeric id.
+    pub async fn get_share(&self, id: i32) -> Result<Option<share::Model>, DbErr> {
+        share::Entity::find_by_id(id)
+            .filter(share::Column::RevokedAt.is_null())
+            .one(&self.db)
+            .await
+    }
+
+    pub async fn get_share_by_token(&self, token: &str) -> Result<Option<share::Model>, DbErr> {
+        share::Entity::find()
+            .filter(share::Column::Token.eq(token))
+            .one(&self.db)
+            .await
+    }
+
+    /// Updates the description and/or expiry; `Some(None)` clears the expiry.
+    pub async fn update_share(
+        &self,
+        id: i32,
+        description: Option<String>,
+        expires_at: Option<Option<DateTime<Utc>>>,
+    ) -> Result<(), DbErr> {
+        let mut share = share::ActiveModel {
+            id: Set(id),
+            ..Default::default()
+        };
+        if let Some(description) = description {
+            share.description = Set(Some(description));
+        }
+        if let Some(expires_at) = expires_at {
+            share.expires_at = Set(expires_at);
+        }
+        if share.is_changed() {
+            share.update(&self.db).await?;
+        }
+        Ok(())
+    }
+
+    /// Disables the public link; the row is kept so the token is never reused.
+    pub async fn revoke_share(&self, id: i32) -> Result<(), DbErr> {
+        share::Entity::update_many()
+            .col_expr(share::Column::RevokedAt, Expr::value(Utc::now()))
+            .filter(share::Column::Id.eq(id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn record_share_visit(&self, id: i32) -> Result<(), DbErr> {
+        share::Entity::update_many()
+            .col_expr(
+                share::Column::VisitCount,
+                Expr::col(share::Column::VisitCount).add(1),
+            )
+            .col_expr(share::Column::LastVisitedAt, Expr::value(Utc::now()))
+            .filter(share::Column::Id.eq(id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// The songs behind a share, in entry order, limited to what its creator can
+    /// still access.
+    pub async fn get_share_songs(
+        &self,
+        share: &share::Model,
+    ) -> Result<Vec<ChildWithMetadata>, DbErr> {
+        let owner = share.username.as_str();
+        let entries = share_entry::Entity::find()
+            .filter(share_entry::Column::ShareId.eq(share.id))
+            .order_by_asc(share_entry::Column::Position)
+            .all(&self.db)
+            .await?;
+
+        let mut songs = Vec::new();
+        for entry in entries {
+            let query = queries::song_with_metadata_query(owner)
+                .filter(queries::song_access_filter(owner))
+                .filter(child::Column::IsDir.eq(false));
+            let found = match entry.kind.as_str() {
+                KIND_SONG => {
+                    query
+                        .filter(child::Column::Id.eq(&entry.entry_id))
+                        .into_model::<ChildWithMetadata>()
+                        .all(&self.db)
+                        .await?
+                }
+                KIND_ALBUM => {
+                    query
+                        .filter(child::Column::AlbumId.eq(&entry.entry_id))
+                        .order_by_asc(child::Column::DiscNumber)
+                        .order_by_asc(child::Column::Track)
+                        .into_model::<ChildWithMetadata>()
+                        .all(&self.db)
+                        .await?
+                }
+                KIND_PLAYLIST => {
+                    let Ok(playlist_id) = entry.entry_id.parse::<i32>() else {
+                        continue;
+                    };
+                    let Some(p) = playlist::Entity::find_by_id(playlist_id)
+                        .one(&self.db)
+                        .await?
+                        .filter(|p| p.owner == owner || p.public)
+                    else {
+                        continue;
+                    };
+                    if let Some(rules) = &p.rules {
+                        songs.extend(self.smart_playlist_songs(rules, &p.owner, owner).await?);
+                        continue;
+                    }
+                    query
+                        .join_rev(
+                            JoinType::InnerJoin,
+                            playlist_song::Entity::belongs_to(child::Entity)
+                                .from(playlist_song::Column::SongId)
+                                .to(child::Column::Id)
+                                .into(),
+                        )
+                        .filter(playlist_song::Column::PlaylistId.eq(playlist_id))
+                        .order_by_asc(playlist_song::Column::Index)
+                        .into_model::<ChildWithMetadata>()
+                        .all(&self.db)
+                        .await?
+                }
+                _ => continue,
+            };
+            songs.extend(found);
+        }
+        Ok(songs)
+    }
+
+    /// Song `song_id` if `share` includes it and its creator can still
+    /// access it. Checks the entries one by one instead of loading the songs.
+    pub async fn get_share_song(
+        &self,
+        share: &share::Model,
+        song_id: &str,
+    ) -> Result<Option<child::Model>, DbErr> {
+        let owner = share.username.as_str();
+        let Some(song) = child::Entity::find_by_id(song_id)
+            .filter(child::Column::IsDir.eq(false))
+            .filter(queries::song_access_filter(owner))
+            .one(&self.db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let entries = share_entry::Entity::find()
+            .filter(share_entry::Column::ShareId.eq(share.id))
+            .all(&self.db)
+            .await?;
+        for entry in entries {
+            let included = match entry.kind.as_str() {
+                KIND_SONG => entry.entry_id == song.id,
+                KIND_ALBUM => song.album_id.as_deref() == Some(entry.entry_id.as_str()),
+                KIND_PLAYLIST => {
+                    let Ok(playlist_id) = entry.entry_id.parse::<i32>() else {
+                        continue;
+                    };
+                    let Some(p) = playlist::Entity::find_by_id(playlist_id)
+                        .one(&self.db)
+                        .await?
+                        .filter(|p| p.owner == owner || p.public)
+                    else {
+                        continue;
+                    };
+                    match &p.rules {
+                        Some(rules) => {
+                            self.smart_playlist_contains(rules, &p.owner, owner, &song.id)
+                                .await?
+                        }
+                        None => playlist_song::Entity::find()
+                            .filter(playlist_song::Column::PlaylistId.eq(playlist_id))
+                            .filter(playlist_song::Column::SongId.eq(&song.id))
+                            .one(&self.db)
+                            .await?
+                            .is_some(),
+                    }
+                }
+                _ => false,
+            };
+            if included {
+                return Ok(Some(song));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+#[path = "shares_tests.rs"]
+mod tests;
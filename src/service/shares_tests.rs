@@ -0,0 +1,168 @@
+use super::*;
+use crate::service::smart_playlists::SmartRules;
+use crate::test_support::{insert_folder, insert_user, memory_db, song};
+use chrono::Duration;
+use sea_orm::DatabaseConnection;
+
+async fn setup_db() -> DatabaseConnection {
+    let db = memory_db().await;
+    insert_user(&db, "alice").await;
+    insert_folder(&db, 1, "/music1", &["alice"]).await;
+    insert_folder(&db, 2, "/music2", &[]).await;
+
+    let now = Utc::now();
+    album::ActiveModel {
+        id: Set("al1".to_string()),
+        name: Set("Album".to_string()),
+        created: Set(now),
+        year: Set(2024),
+        average_rating: Set(0.0),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    // s2 comes before s1 on the album; s3 lives in a folder alice cannot see.
+    for (id, folder_id, track) in [("s1", 1, 2), ("s2", 1, 1), ("s3", 2, 1)] {
+        child::ActiveModel {
+            album_id: Set(Some("al1".to_string())),
+            track: Set(track),
+            ..song(id, folder_id)
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    db
+}
+
+#[tokio::test]
+async fn resolves_songs_albums_and_playlists() {
+    let service = Service::new(setup_db().await);
+    let playlist_id = service
+        .create_playlist("Mix".into(), "alice".into(), vec!["s1".into()])
+        .await
+        .unwrap();
+
+    let kind = |id: String| {
+        let service = &service;
+        async move { service.resolve_share_entry("alice", &id).await.unwrap() }
+    };
+    assert_eq!(kind("s1".into()).await, Some(KIND_SONG));
+    assert_eq!(kind("al1".into()).await, Some(KIND_ALBUM));
+    assert_eq!(kind(playlist_id.to_string()).await, Some(KIND_PLAYLIST));
+    assert_eq!(kind("s3".into()).await, None);
+    assert_eq!(kind("missing".into()).await, None);
+}
+
+#[tokio::test]
+async fn share_lifecycle() {
+    let service = Service::new(setup_db().await);
+    let playlist_id = service
+        .create_playlist("Mix".into(), "alice".into(), vec!["s1".into(), "s3".into()])
+        .await
+        .unwrap();
+
+    let share = service
+        .create_share(
+            "alice",
+            vec![
+                (KIND_ALBUM, "al1".into()),
+                (KIND_SONG, "s1".into()),
+                (KIND_PLAYLIST, playlist_id.to_string()),
+            ],
+            Some("For you".into()),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(share.token.len(), TOKEN_LEN);
+    assert!(share.is_active(Utc::now()));
+
+    // Album order, then the song, then the playlist minus the hidden song.
+    let songs = service.get_share_songs(&share).await.unwrap();
+    let ids: Vec<&str> = songs.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["s2", "s1", "s1", "s1"]);
+
+    service.record_share_visit(share.id).await.unwrap();
+    service.record_share_visit(share.id).await.unwrap();
+    let expired = Utc::now() - Duration::hours(1);
+    service
+        .update_share(share.id, Some("Updated".into()), Some(Some(expired)))
+        .await
+        .unwrap();
+    let share = service
+        .get_share_by_token(&share.token)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(share.visit_count, 2);
+    assert!(share.last_visited_at.is_some());
+    assert_eq!(share.description.as_deref(), Some("Updated"));
+    assert!(!share.is_active(Utc::now()));
+
+    service
+        .update_share(share.id, None, Some(None))
+        .await
+        .unwrap();
+    let share = service.get_share(share.id).await.unwrap().unwrap();
+    assert!(share.is_active(Utc::now()));
+    assert_eq!(service.get_shares("alice").await.unwrap().len(), 1);
+
+    service.revoke_share(share.id).await.unwrap();
+    assert!(service.get_shares("alice").await.unwrap().is_empty());
+    assert!(service.get_share(share.id).await.unwrap().is_none());
+    let share = service
+        .get_share_by_token(&share.token)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!share.is_active(Utc::now()));
+}
+
+#[tokio::test]
+async fn finds_single_shared_songs() {
+    let service = Service::new(setup_db().await);
+    let playlist_id = service
+        .create_playlist("Mix".into(), "alice".into(), vec!["s1".into(), "s3".into()])
+        .await
+        .unwrap();
+    let rules = SmartRules::parse(r#"{"sort":"title","limit":1}"#).unwrap();
+    let smart_id = service
+        .create_smart_playlist("First".into(), "alice".into(), None, false, &rules)
+        .await
+        .unwrap();
+
+    let included = |entries: Vec<(&'static str, String)>| {
+        let service = &service;
+        async move {
+            let share = service
+                .create_share("alice", entries, None, None)
+                .await
+                .unwrap();
+            let mut ids = Vec::new();
+            for id in ["s1", "s2", "s3", "missing"] {
+                if let Some(song) = service.get_share_song(&share, id).await.unwrap() {
+                    ids.push(song.id);
+                }
+            }
+            ids
+        }
+    };
+    // s3 is shared by the album and playlist, but alice can no longer see it.
+    assert_eq!(
+        included(vec![(KIND_ALBUM, "al1".into())]).await,
+        vec!["s1", "s2"]
+    );
+    assert_eq!(included(vec![(KIND_SONG, "s2".into())]).await, vec!["s2"]);
+    assert_eq!(
+        included(vec![(KIND_PLAYLIST, playlist_id.to_string())]).await,
+        vec!["s1"]
+    );
+    assert_eq!(
+        included(vec![(KIND_PLAYLIST, smart_id.to_string())]).await,
+        vec!["s1"]
+    );
+}
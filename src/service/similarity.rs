@@ -0,0 +1,245 @@
+use crate::models::child::ChildWithMetadata;
+use crate::models::{album, artist, child, queries, song_artist};
+use crate::service::Service;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, FromQueryResult, QueryFilter, QueryOrder,
+    QuerySelect, Statement,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Upper bound on the songs used to describe an artist, album or directory seed.
+const MAX_SEED_SONGS: u64 = 500;
+
+/// Candidates fetched per requested song, leaving room for deduplication.
+const CANDIDATE_FACTOR: u64 = 4;
+
+/// Scores a song against the seed. Every signal is normalised to 0..1 before
+/// weighting: shared genres (weighted by how common they are in the seed),
+/// shared artists, year proximity, co-occurrence in playlists the user can
+/// see, songs the user played within half an hour of a seed song, the user's
+/// own play count, plus a little jitter so repeated mixes vary.
+const SIMILAR_SONGS_SQL: &str = "
+    WITH seed AS (SELECT value AS song_id FROM json_each(?)),
+    seed_genres AS (
+        SELECT genre_name, COUNT(*) AS w FROM song_genres
+        WHERE song_id IN (SELECT song_id FROM seed) GROUP BY genre_name
+    ),
+    seed_artists AS (
+        SELECT DISTINCT artist_id FROM song_artists WHERE song_id IN (SELECT song_id FROM seed)
+    ),
+    seed_playlists AS (
+        SELECT DISTINCT ps.playlist_id FROM playlist_songs ps
+        JOIN playlists p ON p.id = ps.playlist_id
+        WHERE ps.song_id IN (SELECT song_id FROM seed) AND (p.public = 1 OR p.owner = ?)
+    ),
+    seed_plays AS (
+        SELECT played_at FROM play_history
+        WHERE username = ? AND song_id IN (SELECT song_id FROM seed)
+        ORDER BY played_at DESC LIMIT 100
+    ),
+    seed_year AS (
+        SELECT AVG(year) AS y FROM children WHERE id IN (SELECT song_id FROM seed) AND year > 0
+    ),
+    genre_match AS (
+        SELECT sg.song_id, SUM(g.w) * 1.0 / (SELECT SUM(w) FROM seed_genres) AS s
+        FROM song_genres sg JOIN seed_genres g ON g.genre_name = sg.genre_name
+        GROUP BY sg.song_id
+    ),
+    artist_match AS (
+        SELECT DISTINCT song_id FROM song_artists WHERE artist_id IN (SELECT artist_id FROM seed_artists)
+    ),
+    playlist_match AS (
+        SELECT song_id, MIN(1.0, COUNT(DISTINCT playlist_id) * 1.0 / (SELECT COUNT(*) FROM seed_playlists)) AS s
+        FROM playlist_songs WHERE playlist_id IN (SELECT playlist_id FROM seed_playlists)
+        GROUP BY song_id
+    ),
+    history_match AS (
+        SELECT ph.song_id, MIN(1.0, COUNT(*) / 5.0) AS s
+        FROM seed_plays sp JOIN play_history ph ON ph.username = ?
+            AND ph.played_at BETWEEN strftime('%Y-%m-%dT%H:%M:%S', sp.played_at, '-30 minutes')
+                                 AND strftime('%Y-%m-%dT%H:%M:%S', sp.played_at, '+30 minutes')
+        GROUP BY ph.song_id
+    )
+    SELECT
+        c.id AS id,
+        c.title AS title,
+        (SELECT MIN(artist_id) FROM song_artists WHERE song_id = c.id) AS artist_id,
+        0.35 * COALESCE(gm.s, 0)
+        + 0.20 * (am.song_id IS NOT NULL)
+        + 0.10 * (CASE WHEN c.year > 0 AND sy.y IS NOT NULL
+                       THEN MAX(0.0, 1.0 - ABS(c.year - sy.y) / 10.0) ELSE 0 END)
+        + 0.15 * COALESCE(pm.s, 0)
+        + 0.15 * COALESCE(hm.s, 0)
+        + 0.05 * MIN(1.0, (SELECT COUNT(*) FROM play_history WHERE username = ? AND song_id = c.id) / 20.0)
+        + 0.05 * (ABS(RANDOM()) % 1000) / 1000.0 AS score
+    FROM children c
+    CROSS JOIN seed_year sy
+    LEFT JOIN genre_match gm ON gm.song_id = c.id
+    LEFT JOIN artist_match am ON am.song_id = c.id
+    LEFT JOIN playlist_match pm ON pm.song_id = c.id
+    LEFT JOIN history_match hm ON hm.song_id = c.id
+    WHERE c.is_dir = 0
+      AND c.music_folder_id IN (SELECT music_folder_id FROM user_music_folders WHERE username = ?)
+      AND (gm.song_id IS NOT NULL OR am.song_id IS NOT NULL OR pm.song_id IS NOT NULL
+           OR hm.song_id IS NOT NULL OR (c.year > 0 AND ABS(c.year - sy.y) <= 2))
+      AND (? OR c.id NOT IN (SELECT song_id FROM seed))
+    ORDER BY score DESC
+    LIMIT ?
+";
+
+#[derive(Debug, Clone, FromQueryResult)]
+pub struct ScoredSong {
+    pub id: String,
+    pub title: String,
+    pub artist_id: Option<String>,
+    pub score: f64,
+}
+
+/// Picks up to `count` songs from score-ordered candidates, dropping duplicate
+/// recordings (same title and artist) and capping how many songs a single
+/// artist contributes. The cap is relaxed only if the mix would otherwise be short.
+pub fn pick_mix(candidates: Vec<ScoredSong>, count: usize) -> Vec<String> {
+    let per_artist = (count / 4).max(3);
+    let mut seen = HashSet::new();
+    let mut per_artist_count: HashMap<Option<String>, usize> = HashMap::new();
+    let mut picked = Vec::new();
+    let mut overflow = Vec::new();
+
+    for song in candidates {
+        if !seen.insert((song.title.trim().to_lowercase(), song.artist_id.clone())) {
+            continue;
+        }
+        let n = per_artist_count.entry(song.artist_id.clone()).or_default();
+        if *n < per_artist {
+            *n += 1;
+            picked.push(song.id);
+        } else {
+            overflow.push(song.id);
+        }
+    }
+
+    picked.extend(overflow);
+    picked.truncate(count);
+    picked
+}
+
+impl Service {
+    /// Songs describing `id`, which may be an artist, album, song or directory.
+    /// Returns `None` when the item does not exist or is not visible to the user.
+    async fn similarity_seed(
+        &self,
+        id: &str,
+        username: &str,
+    ) -> Result<Option<(Vec<String>, bool)>, DbErr> {
+        if artist::Entity::find_by_id(id.to_string())
+            .filter(queries::artist_access_filter(username))
+            .one(&self.db)
+            .await?
+            .is_some()
+        {
+            let songs = song_artist::Entity::find()
+                .select_only()
+                .column(song_artist::Column::SongId)
+                .filter(song_artist::Column::ArtistId.eq(id))
+                .limit(MAX_SEED_SONGS)
+                .into_tuple::<String>()
+                .all(&self.db)
+                .await?;
+            // Artist radio also plays the artist's own songs.
+            return Ok(Some((songs, true)));
+        }
+
+        let album_seed = album::Entity::find_by_id(id.to_string())
+            .filter(queries::album_access_filter(username))
+            .one(&self.db)
+            .await?
+            .map(|a| child::Column::AlbumId.eq(a.id));
+
+        let condition = match album_seed {
+            Some(cond) => cond,
+            None => {
+                let Some(item) = child::Entity::find_by_id(id.to_string())
+                    .filter(queries::song_access_filter(username))
+                    .one(&self.db)
+                    .await?
+                else {
+                    return Ok(None);
+                };
+                if !item.is_dir {
+                    return Ok(Some((vec![item.id], false)));
+                }
+                child::Column::Path.starts_with(format!("{}/", item.path))
+            }
+        };
+
+        let songs = child::Entity::find()
+            .select_only()
+            .column(child::Column::Id)
+            .filter(child::Column::IsDir.eq(false))
+            .filter(condition)
+            .filter(queries::song_access_filter(username))
+            .order_by_desc(child::Column::PlayCount)
+            .limit(MAX_SEED_SONGS)
+            .into_tuple::<String>()
+            .all(&self.db)
+            .await?;
+
+        Ok(Some((songs, false)))
+    }
+
+    /// Runs [`SIMILAR_SONGS_SQL`] for `seed`, best candidates first.
+    async fn similar_candidates(
+        &self,
+        seed: &[String],
+        include_seed: bool,
+        username: &str,
+        limit: u64,
+    ) -> Result<Vec<ScoredSong>, DbErr> {
+        let seed_json = serde_json::to_string(seed).map_err(|e| DbErr::Custom(e.to_string()))?;
+        ScoredSong::find_by_statement(Statement::from_sql_and_values(
+            self.db.get_database_backend(),
+            SIMILAR_SONGS_SQL,
+            [
+                seed_json.into(),
+                username.into(),
+                username.into(),
+                username.into(),
+                username.into(),
+                username.into(),
+                include_seed.into(),
+                (limit as i64).into(),
+            ],
+        ))
+        .all(&self.db)
+        .await
+    }
+
+    /// Builds an offline "instant mix" of up to `count` songs similar to `id`.
+    pub async fn get_similar_songs(
+        &self,
+        id: &str,
+        count: u64,
+        username: &str,
+    ) -> Result<Vec<ChildWithMetadata>, DbErr> {
+        let (seed, include_seed) = self
+            .similarity_seed(id, username)
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound(format!("Item {} not found", id)))?;
+        if seed.is_empty() || count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let candidates = self
+            .similar_candidates(&seed, include_seed, username, count * CANDIDATE_FACTOR)
+            .await?;
+
+        let ids = pick_mix(candidates, count as usize);
+        let mut songs = self.get_songs_by_ids(&ids, username).await?;
+        songs.sort_by_key(|s| ids.iter().position(|id| *id == s.id));
+        Ok(songs)
+    }
+}
+
+#[cfg(test)]
+#[path = "similarity_tests.rs"]
+mod tests;
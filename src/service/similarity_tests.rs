@@ -0,0 +1,137 @@
+use super::*;
+use crate::models::{genre, song_genre};
+use crate::test_support::{insert_folder, insert_user, memory_db, song};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+
+fn scored(id: &str, title: &str, artist: &str, score: f64) -> ScoredSong {
+    ScoredSong {
+        id: id.to_string(),
+        title: title.to_string(),
+        artist_id: Some(artist.to_string()),
+        score,
+    }
+}
+
+#[test]
+fn pick_mix_drops_duplicate_recordings() {
+    let mix = pick_mix(
+        vec![
+            scored("1", "Airbag", "rh", 0.9),
+            scored("2", "airbag ", "rh", 0.8),
+            scored("3", "Airbag", "other", 0.7),
+        ],
+        10,
+    );
+    assert_eq!(mix, vec!["1", "3"]);
+}
+
+#[test]
+fn pick_mix_caps_songs_per_artist_until_short() {
+    let mut candidates: Vec<_> = (0..5)
+        .map(|i| scored(&format!("a{}", i), &format!("A{}", i), "a", 1.0))
+        .collect();
+    candidates.push(scored("b0", "B0", "b", 0.5));
+
+    // Three songs per artist, then the other artist, then the overflow.
+    let mix = pick_mix(candidates.clone(), 5);
+    assert_eq!(mix, vec!["a0", "a1", "a2", "b0", "a3"]);
+
+    let mix = pick_mix(candidates, 2);
+    assert_eq!(mix, vec!["a0", "a1"]);
+}
+
+/// The seed is a 2000 rock song by ar1 on one of alice's playlists. Each
+/// candidate shares a different mix of those traits; the score gaps between
+/// them are wider than the random jitter, so the order is stable.
+async fn setup_db() -> DatabaseConnection {
+    let db = memory_db().await;
+    insert_user(&db, "alice").await;
+    insert_folder(&db, 1, "/music1", &["alice"]).await;
+    insert_folder(&db, 2, "/music2", &[]).await;
+    genre::ActiveModel {
+        name: Set("Rock".to_string()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    artist::ActiveModel {
+        id: Set("ar1".to_string()),
+        name: Set("Artist".to_string()),
+        artist_image_url: Set(None),
+        average_rating: Set(0.0),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    // (id, folder, year, rock, by ar1)
+    for (id, folder, year, rock, by_artist) in [
+        ("seed", 1, 2000, true, true),
+        ("everything", 1, 2000, true, true),
+        ("genre_artist", 1, 0, true, true),
+        ("genre", 1, 0, true, false),
+        ("playlist_year", 1, 2000, false, false),
+        ("near_year", 1, 2001, false, false),
+        ("unrelated", 1, 1980, false, false),
+        ("hidden", 2, 2000, true, true),
+    ] {
+        child::ActiveModel {
+            year: Set(year),
+            ..song(id, folder)
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        if rock {
+            song_genre::ActiveModel {
+                song_id: Set(id.to_string()),
+                genre_name: Set("Rock".to_string()),
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+        if by_artist {
+            song_artist::ActiveModel {
+                song_id: Set(id.to_string()),
+                artist_id: Set("ar1".to_string()),
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+    }
+    db
+}
+
+#[tokio::test]
+async fn similar_songs_rank_by_shared_traits_within_visible_folders() {
+    let service = Service::new(setup_db().await);
+    service
+        .create_playlist(
+            "Mix".into(),
+            "alice".into(),
+            vec!["seed".into(), "everything".into(), "playlist_year".into()],
+        )
+        .await
+        .unwrap();
+
+    let candidates = service
+        .similar_candidates(&["seed".to_string()], false, "alice", 100)
+        .await
+        .unwrap();
+    let ids: Vec<_> = candidates.iter().map(|c| c.id.as_str()).collect();
+    // Genre 0.35, artist 0.20, year 0.10 and co-playlist 0.15 add up; the
+    // seed itself, unrelated songs and the ungranted folder are left out.
+    assert_eq!(
+        ids,
+        vec![
+            "everything",
+            "genre_artist",
+            "genre",
+            "playlist_year",
+            "near_year"
+        ]
+    );
+    assert_eq!(candidates[1].artist_id.as_deref(), Some("ar1"));
+}
@@ -0,0 +1,224 @@
+//! Smart playlists: a JSON rule set stored on the playlist instead of a song
+//! list, evaluated each time the playlist is read. Per-user criteria (rating,
+//! starred, plays) use the playlist owner's data.
+
+use crate::models::child;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::{Expr, SimpleExpr};
+use sea_orm::{ColumnTrait, Condition, Order, QueryFilter, QueryOrder, QuerySelect, Select, Value};
+use serde::{Deserialize, Serialize};
+
+/// Most songs a smart playlist can hold.
+pub const MAX_LIMIT: u64 = 5000;
+
+/// Inclusive bounds; either end may be left open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Range {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+}
+
+impl Range {
+    fn condition(&self, sql: &str, values: Vec<Value>) -> Condition {
+        let mut condition = Condition::all();
+        for (bound, op) in [(self.min, ">="), (self.max, "<=")] {
+            if let Some(bound) = bound {
+                let mut values = values.clone();
+                values.push(bound.into());
+                condition =
+                    condition.add(Expr::cust_with_values(format!("{} {} ?", sql, op), values));
+            }
+        }
+        condition
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortField {
+    #[default]
+    Title,
+    Artist,
+    Album,
+    Year,
+    Rating,
+    PlayCount,
+    LastPlayed,
+    Added,
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SmartRules {
+    /// Songs with any of these genres.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub genres: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<Range>,
+    /// The owner's rating, 0 when unrated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<Range>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub starred: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub play_count: Option<Range>,
+    /// Songs last played before this time; songs never played match too.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_played_before: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_played_after: Option<DateTime<Utc>>,
+    /// Songs first scanned at most this many days ago.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_within_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub music_folder_id: Option<i32>,
+    #[serde(default)]
+    pub sort: SortField,
+    #[serde(default)]
+    pub order: SortOrder,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+const RATING: &str = "COALESCE((SELECT r.rating FROM user_ratings r WHERE r.username = ? AND r.item_type = 'song' AND r.item_id = children.id), 0)";
+const PLAY_COUNT: &str =
+    "(SELECT COUNT(*) FROM play_history ph WHERE ph.username = ? AND ph.song_id = children.id)";
+const LAST_PLAYED: &str =
+    "(SELECT MAX(ph.played_at) FROM play_history ph WHERE ph.username = ? AND ph.song_id = children.id)";
+
+impl SmartRules {
+    /// Parses and validates a rule set.
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let rules: Self =
+            serde_json::from_str(json).map_err(|e| format!("Invalid rules: {}", e))?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, range) in [
+            ("year", self.year),
+            ("rating", self.rating),
+            ("playCount", self.play_count),
+        ] {
+            if let Some(Range {
+                min: Some(min),
+                max: Some(max),
+            }) = range
+            {
+                if min > max {
+                    return Err(format!("{}: min is greater than max", name));
+                }
+            }
+        }
+        match self.limit {
+            Some(0) => Err("limit must be at least 1".into()),
+            Some(limit) if limit > MAX_LIMIT => Err(format!("limit must be at most {}", MAX_LIMIT)),
+            _ => Ok(()),
+        }
+    }
+
+    fn condition(&self, owner: &str) -> Condition {
+        let user = || vec![Value::from(owner.to_string())];
+        let mut condition = Condition::all().add(child::Column::IsDir.eq(false));
+
+        if !self.genres.is_empty() {
+            condition = condition.add(Expr::cust_with_values(
+                format!(
+                    "EXISTS (SELECT 1 FROM song_genres g WHERE g.song_id = children.id AND g.genre_name COLLATE NOCASE IN ({}))",
+                    vec!["?"; self.genres.len()].join(", ")
+                ),
+                self.genres.iter().map(|g| Value::from(g.clone())),
+            ));
+        }
+        if let Some(year) = self.year {
+            condition = condition.add(year.condition("children.year", vec![]));
+        }
+        if let Some(rating) = self.rating {
+            condition = condition.add(rating.condition(RATING, user()));
+        }
+        if let Some(starred) = self.starred {
+            condition = condition.add(Expr::cust_with_values(
+                format!(
+                    "{}EXISTS (SELECT 1 FROM user_stars s WHERE s.username = ? AND s.item_type = 'song' AND s.item_id = children.id)",
+                    if starred { "" } else { "NOT " }
+                ),
+                user(),
+            ));
+        }
+        if let Some(plays) = self.play_count {
+            condition = condition.add(plays.condition(PLAY_COUNT, user()));
+        }
+        if let Some(before) = self.last_played_before {
+            condition = condition.add(Expr::cust_with_values(
+                format!("COALESCE({} < ?, 1)", LAST_PLAYED),
+                [Value::from(owner.to_string()), before.into()],
+            ));
+        }
+        if let Some(after) = self.last_played_after {
+            condition = condition.add(Expr::cust_with_values(
+                format!("{} >= ?", LAST_PLAYED),
+                [Value::from(owner.to_string()), after.into()],
+            ));
+        }
+        if let Some(days) = self.added_within_days {
+            let since = Utc::now() - Duration::days(days.into());
+            condition = condition.add(child::Column::Created.gte(since));
+        }
+        if let Some(folder_id) = self.music_folder_id {
+            condition = condition.add(child::Column::MusicFolderId.eq(folder_id));
+        }
+        condition
+    }
+
+    fn sort_key(&self, owner: &str) -> SimpleExpr {
+        let user = || [owner.to_string()];
+        match self.sort {
+            SortField::Title => Expr::col((child::Entity, child::Column::Title)).into(),
+            SortField::Artist => Expr::cust("(SELECT MIN(a.name) FROM song_artists sa JOIN artists a ON a.id = sa.artist_id WHERE sa.song_id = children.id)"),
+            SortField::Album => Expr::cust("(SELECT name FROM albums WHERE id = children.album_id)"),
+            SortField::Year => Expr::col((child::Entity, child::Column::Year)).into(),
+            SortField::Rating => Expr::cust_with_values(RATING, user()),
+            SortField::PlayCount => Expr::cust_with_values(PLAY_COUNT, user()),
+            SortField::LastPlayed => Expr::cust_with_values(LAST_PLAYED, user()),
+            SortField::Added => Expr::col((child::Entity, child::Column::Created)).into(),
+            SortField::Random => Expr::cust("RANDOM()"),
+        }
+    }
+
+    /// Restricts a song query to the playlist's songs, in playlist order.
+    pub fn apply(&self, select: Select<child::Entity>, owner: &str) -> Select<child::Entity> {
+        let order = match self.order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        };
+        let mut select = select
+            .filter(self.condition(owner))
+            .order_by(self.sort_key(owner), order);
+        // Keep albums in track order when sorting by album, and ties stable otherwise
+        if self.sort == SortField::Album {
+            select = select
+                .order_by_asc(child::Column::DiscNumber)
+                .order_by_asc(child::Column::Track);
+        }
+        select
+            .order_by_asc(child::Column::Title)
+            .limit(self.limit.unwrap_or(MAX_LIMIT))
+    }
+}
+
+#[cfg(test)]
+#[path = "smart_playlists_tests.rs"]
+mod tests;
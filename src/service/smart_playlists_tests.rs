@@ -0,0 +1,283 @@
+use super::*;
+use crate::models::playlist::PlaylistWithStats;
+use crate::models::{genre, play_history, song_genre};
+use crate::models::{user_rating, user_star};
+use crate::service::playlists::UpdatePlaylistOptions;
+use crate::service::Service;
+use crate::test_support::{insert_folder, insert_user, memory_db, song};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+
+#[test]
+fn parses_and_validates_rules() {
+    let rules = SmartRules::parse(
+        r#"{"genres":["Rock"],"year":{"min":1990,"max":1999},"starred":true,
+            "lastPlayedBefore":"2024-01-01T00:00:00Z","sort":"playCount","order":"desc","limit":25}"#,
+    )
+    .unwrap();
+    assert_eq!(rules.genres, vec!["Rock"]);
+    assert_eq!(
+        rules.year,
+        Some(Range {
+            min: Some(1990),
+            max: Some(1999)
+        })
+    );
+    assert_eq!(rules.sort, SortField::PlayCount);
+    assert_eq!(rules.order, SortOrder::Desc);
+    assert_eq!(rules.limit, Some(25));
+
+    assert_eq!(SmartRules::parse("{}").unwrap(), SmartRules::default());
+    assert!(SmartRules::parse(r#"{"genre":["Rock"]}"#).is_err());
+    assert!(SmartRules::parse(r#"{"year":{"min":2000,"max":1990}}"#).is_err());
+    assert!(SmartRules::parse(r#"{"limit":0}"#).is_err());
+    assert!(SmartRules::parse(r#"{"sort":"loudness"}"#).is_err());
+}
+
+async fn setup_db() -> DatabaseConnection {
+    let db = memory_db().await;
+    insert_user(&db, "alice").await;
+    insert_user(&db, "bob").await;
+    // bob cannot see folder 2
+    insert_folder(&db, 1, "/music1", &["alice", "bob"]).await;
+    insert_folder(&db, 2, "/music2", &["alice"]).await;
+
+    let now = Utc::now();
+    for name in ["Rock", "Jazz"] {
+        genre::ActiveModel {
+            name: Set(name.to_string()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    for (id, folder_id, year, genre, days_old) in [
+        ("s1", 1, 1994, "Rock", 400),
+        ("s2", 1, 1997, "rock", 3),
+        ("s3", 2, 1995, "Rock", 3),
+        ("s4", 1, 2010, "Jazz", 3),
+    ] {
+        child::ActiveModel {
+            year: Set(year),
+            duration: Set(100),
+            created: Set(Some(now - Duration::days(days_old))),
+            ..song(id, folder_id)
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        song_genre::ActiveModel {
+            song_id: Set(id.to_string()),
+            genre_name: Set(if genre == "rock" { "Rock" } else { genre }.to_string()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    for (id, rating) in [("s1", 5), ("s2", 3), ("s3", 5)] {
+        user_rating::ActiveModel {
+            username: Set("alice".to_string()),
+            item_id: Set(id.to_string()),
+            item_type: Set("song".to_string()),
+            rating: Set(rating),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+    user_star::ActiveModel {
+        username: Set("alice".to_string()),
+        item_id: Set("s2".to_string()),
+        item_type: Set("song".to_string()),
+        starred_at: Set(now),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    // s1 played twice long ago, s2 once yesterday
+    for (id, days_ago) in [("s1", 100), ("s1", 90), ("s2", 1)] {
+        play_history::ActiveModel {
+            username: Set("alice".to_string()),
+            song_id: Set(id.to_string()),
+            played_at: Set(now - Duration::days(days_ago)),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    db
+}
+
+async fn evaluate(service: &Service, rules: &str, username: &str) -> Vec<String> {
+    service
+        .smart_playlist_songs(rules, "alice", username)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.id)
+        .collect()
+}
+
+#[tokio::test]
+async fn rules_select_and_order_songs() {
+    let service = Service::new(setup_db().await);
+
+    let nineties = r#"{"genres":["rock"],"year":{"min":1990,"max":1999}}"#;
+    assert_eq!(
+        evaluate(&service, nineties, "alice").await,
+        vec!["s1", "s2", "s3"]
+    );
+    // Evaluated with the reader's folder access
+    assert_eq!(evaluate(&service, nineties, "bob").await, vec!["s1", "s2"]);
+
+    assert_eq!(
+        evaluate(
+            &service,
+            r#"{"rating":{"min":4},"sort":"year","order":"desc"}"#,
+            "alice"
+        )
+        .await,
+        vec!["s3", "s1"]
+    );
+    assert_eq!(
+        evaluate(&service, r#"{"starred":true}"#, "alice").await,
+        vec!["s2"]
+    );
+    assert_eq!(
+        evaluate(
+            &service,
+            r#"{"playCount":{"min":1},"sort":"playCount","order":"desc","limit":1}"#,
+            "alice"
+        )
+        .await,
+        vec!["s1"]
+    );
+    let month_ago = (Utc::now() - Duration::days(30)).to_rfc3339();
+    assert_eq!(
+        evaluate(
+            &service,
+            &format!(r#"{{"lastPlayedBefore":"{}"}}"#, month_ago),
+            "alice"
+        )
+        .await,
+        vec!["s1", "s3", "s4"]
+    );
+    assert_eq!(
+        evaluate(
+            &service,
+            &format!(r#"{{"lastPlayedAfter":"{}"}}"#, month_ago),
+            "alice"
+        )
+        .await,
+        vec!["s2"]
+    );
+    assert_eq!(
+        evaluate(
+            &service,
+            r#"{"addedWithinDays":30,"musicFolderId":1}"#,
+            "alice"
+        )
+        .await,
+        vec!["s2", "s4"]
+    );
+    assert_eq!(
+        evaluate(&service, "not json", "alice").await,
+        Vec::<String>::new()
+    );
+}
+
+#[tokio::test]
+async fn smart_playlist_totals_respect_limit_and_folders() {
+    let service = Service::new(setup_db().await);
+    let top = SmartRules::parse(r#"{"genres":["Rock"],"limit":2}"#).unwrap();
+    let nineties = SmartRules::parse(r#"{"year":{"min":1990,"max":1999}}"#).unwrap();
+    for (name, rules) in [("Top", &top), ("Nineties", &nineties)] {
+        service
+            .create_smart_playlist(name.into(), "alice".into(), None, true, rules)
+            .await
+            .unwrap();
+    }
+
+    let totals = |playlists: Vec<PlaylistWithStats>| {
+        let mut totals: Vec<_> = playlists
+            .into_iter()
+            .map(|p| (p.name, p.song_count, p.duration))
+            .collect();
+        totals.sort();
+        totals
+    };
+    assert_eq!(
+        totals(service.get_playlists("alice", "alice").await.unwrap()),
+        vec![("Nineties".into(), 3, 300), ("Top".into(), 2, 200)]
+    );
+    // s3 is in a folder bob cannot see
+    assert_eq!(
+        totals(service.get_playlists("bob", "alice").await.unwrap()),
+        vec![("Nineties".into(), 2, 200), ("Top".into(), 2, 200)]
+    );
+}
+
+#[tokio::test]
+async fn smart_playlists_read_like_regular_ones() {
+    let service = Service::new(setup_db().await);
+    let rules = SmartRules::parse(r#"{"genres":["Rock"],"sort":"year"}"#).unwrap();
+    let id = service
+        .create_smart_playlist("Rock".into(), "alice".into(), None, false, &rules)
+        .await
+        .unwrap();
+
+    let playlist = service.get_playlist(id, "alice").await.unwrap().unwrap();
+    let ids: Vec<_> = playlist.entry.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["s1", "s3", "s2"]);
+    assert_eq!(playlist.playlist.song_count, 3);
+    assert_eq!(playlist.playlist.duration, 300);
+
+    let listed = service.get_playlists("alice", "alice").await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].song_count, 3);
+    assert_eq!(listed[0].duration, 300);
+
+    // Songs can't be edited, but the rules and details can
+    let add = UpdatePlaylistOptions {
+        song_ids_to_add: vec!["s4".into()],
+        ..Default::default()
+    };
+    assert!(service.update_playlist(id, "alice", add).await.is_err());
+    assert!(service
+        .replace_playlist_songs(id, "alice", vec!["s4".into()], Default::default())
+        .await
+        .is_err());
+    let retarget = UpdatePlaylistOptions {
+        name: Some("Jazz".into()),
+        rules: Some(SmartRules {
+            genres: vec!["Jazz".into()],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    service
+        .update_playlist(id, "alice", retarget)
+        .await
+        .unwrap();
+    let playlist = service.get_playlist(id, "alice").await.unwrap().unwrap();
+    assert_eq!(playlist.playlist.name, "Jazz");
+    let ids: Vec<_> = playlist.entry.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["s4"]);
+
+    // Rules can't be put on a regular playlist
+    let regular = service
+        .create_playlist("Mix".into(), "alice".into(), vec!["s1".into()])
+        .await
+        .unwrap();
+    let opts = UpdatePlaylistOptions {
+        rules: Some(SmartRules::default()),
+        ..Default::default()
+    };
+    assert!(service
+        .update_playlist(regular, "alice", opts)
+        .await
+        .is_err());
+}
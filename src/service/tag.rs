@@ -25,6 +25,10 @@ pub struct SongTags {
     pub duration: u32,
     pub bit_rate: u32,
     pub format: String,
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub bit_depth: Option<u8>,
+    pub channels: Option<u8>,
     pub front_cover: Option<String>,
     // Extended tags
     pub composer: Option<String>,
@@ -81,6 +85,10 @@ impl SongTags {
                 .and_then(|s| s.to_str())
                 .unwrap_or("unknown")
                 .to_string(),
+            codec: crate::scanner::tags::codec(&tagged_file, path),
+            sample_rate: properties.sample_rate(),
+            bit_depth: properties.bit_depth(),
+            channels: properties.channels(),
             ..Default::default()
         };
 
@@ -0,0 +1,215 @@
+use crate::config::{ReplayGainMode, TranscodeConfig};
+use crate::models::child::ReplayGain;
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader, ReadBuf};
+use tokio::process::{Child, ChildStdout, Command};
+
+/// Bit rate used when a client asks for an explicit format that has no profile.
+const DEFAULT_BIT_RATE: u32 = 192;
+
+/// A resolved decision to transcode a single stream request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscodePlan {
+    pub suffix: String,
+    pub content_type: &'static str,
+    pub bit_rate: u32,
+    pub time_offset: u32,
+    /// Volume adjustment in dB applied by the encoder.
+    pub gain: Option<f64>,
+}
+
+/// Returns the MIME type produced when encoding to `suffix`, or `None` if the
+/// target format is not supported by the encoder pipeline.
+pub fn target_content_type(suffix: &str) -> Option<&'static str> {
+    match suffix {
+        "mp3" => Some("audio/mpeg"),
+        "opus" | "ogg" => Some("audio/ogg"),
+        "aac" => Some("audio/aac"),
+        _ => None,
+    }
+}
+
+fn encoder_args(suffix: &str) -> &'static [&'static str] {
+    match suffix {
+        "mp3" => &["-c:a", "libmp3lame", "-f", "mp3"],
+        "opus" => &["-c:a", "libopus", "-f", "ogg"],
+        "ogg" => &["-c:a", "libvorbis", "-f", "ogg"],
+        "aac" => &["-c:a", "aac", "-f", "adts"],
+        _ => &[],
+    }
+}
+
+/// Combines the request's `maxBitRate` with the user's `max_bit_rate` limit.
+/// `0` (or a missing value) means unlimited.
+pub fn effective_max_bit_rate(requested: Option<u32>, user_limit: Option<i32>) -> u32 {
+    let user_limit = user_limit.filter(|v| *v > 0).map(|v| v as u32);
+    match (requested.filter(|v| *v > 0), user_limit) {
+        (Some(a), Some(b)) => a.min(b),
+        (Some(a), None) => a,
+        (None, Some(b)) => b,
+        (None, None) => 0,
+    }
+}
+
+/// Decides whether a stream request needs transcoding.
+///
+/// - `format=raw` always serves the original file.
+/// - An explicit, supported `format` that differs from the source forces transcoding.
+/// - Otherwise the source is transcoded by its profile when it exceeds `max_bit_rate`.
+/// - A non-zero `time_offset` requires the encoder, so it also picks the profile.
+pub fn plan(
+    cfg: &TranscodeConfig,
+    source_suffix: &str,
+    source_bit_rate: i32,
+    format: Option<&str>,
+    max_bit_rate: u32,
+    time_offset: u32,
+) -> Option<TranscodePlan> {
+    let source_suffix = source_suffix.to_lowercase();
+    let format = format.map(|f| f.trim().to_lowercase());
+
+    if format.as_deref() == Some("raw") {
+        return None;
+    }
+
+    let cap = |bit_rate: u32| {
+        if max_bit_rate > 0 {
+            bit_rate.min(max_bit_rate)
+        } else {
+            bit_rate
+        }
+    };
+
+    if let Some(f) = format.as_deref().filter(|f| *f != source_suffix) {
+        if let Some(content_type) = target_content_type(f) {
+            let bit_rate = cfg
+                .profiles
+                .iter()
+                .find(|p| p.target == f)
+                .map(|p| p.bit_rate)
+                .unwrap_or(DEFAULT_BIT_RATE);
+            return Some(TranscodePlan {
+                suffix: f.to_string(),
+                content_type,
+                bit_rate: cap(bit_rate),
+                time_offset,
+                gain: None,
+            });
+        }
+    }
+
+    let exceeds_cap =
+        max_bit_rate > 0 && (source_bit_rate <= 0 || source_bit_rate as u32 > max_bit_rate);
+    if !exceeds_cap && time_offset == 0 {
+        return None;
+    }
+
+    let profile = cfg.profile_for(&source_suffix)?;
+    let content_type = target_content_type(&profile.target)?;
+    Some(TranscodePlan {
+        suffix: profile.target.clone(),
+        content_type,
+        bit_rate: cap(profile.bit_rate),
+        time_offset,
+        gain: None,
+    })
+}
+
+/// Returns the `(suffix, content_type)` a plain stream of a song is
+/// transcoded to for a user limited to `max_bit_rate`, used to populate
+/// `transcodedSuffix`/`transcodedContentType`.
+pub fn stream_target(
+    cfg: &TranscodeConfig,
+    suffix: &str,
+    bit_rate: i32,
+    max_bit_rate: u32,
+) -> Option<(String, &'static str)> {
+    plan(cfg, suffix, bit_rate, None, max_bit_rate, 0).map(|p| (p.suffix, p.content_type))
+}
+
+/// Picks the gain to apply to a transcoded song, lowered where needed so the
+/// tagged peak does not clip.
+pub fn replay_gain(mode: ReplayGainMode, rg: &ReplayGain) -> Option<f64> {
+    let track = rg.track_gain.map(|g| (g, rg.track_peak));
+    let album = rg.album_gain.map(|g| (g, rg.album_peak));
+    let (gain, peak) = match mode {
+        ReplayGainMode::Off => None,
+        ReplayGainMode::Track => track.or(album),
+        ReplayGainMode::Album => album.or(track),
+    }?;
+    match peak.filter(|p| *p > 0.0) {
+        Some(peak) => Some(gain.min(-20.0 * peak.log10())),
+        None => Some(gain),
+    }
+}
+
+/// Encoder stdout that keeps the child process alive for as long as the
+/// response body is being read; the encoder is killed when the stream is dropped.
+pub struct TranscodeStream {
+    stdout: ChildStdout,
+    _child: Child,
+}
+
+impl AsyncRead for TranscodeStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stdout).poll_read(cx, buf)
+    }
+}
+
+/// Spawns the encoder for `path` according to `plan`, streaming the result on stdout.
+pub fn spawn(
+    cfg: &TranscodeConfig,
+    path: &Path,
+    plan: &TranscodePlan,
+) -> io::Result<TranscodeStream> {
+    let mut cmd = Command::new(&cfg.ffmpeg_path);
+    cmd.args(["-v", "error", "-nostdin"]);
+    if plan.time_offset > 0 {
+        cmd.args(["-ss", &plan.time_offset.to_string()]);
+    }
+    cmd.arg("-i")
+        .arg(path)
+        .args(["-map", "0:a:0", "-vn", "-map_metadata", "-1"]);
+    if let Some(gain) = plan.gain {
+        cmd.args(["-af", &format!("volume={:.2}dB", gain)]);
+    }
+    cmd.args(encoder_args(&plan.suffix))
+        .args(["-b:a", &format!("{}k", plan.bit_rate)])
+        .arg("pipe:1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = cmd.spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| io::Error::other("Encoder stdout is not available"))?;
+    if let Some(stderr) = child.stderr.take() {
+        let path = path.display().to_string();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                log::warn!("Encoder error for {}: {}", path, line);
+            }
+        });
+    }
+
+    Ok(TranscodeStream {
+        stdout,
+        _child: child,
+    })
+}
+
+#[cfg(test)]
+#[path = "transcode_tests.rs"]
+mod tests;
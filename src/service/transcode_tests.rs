@@ -0,0 +1,117 @@
+use super::*;
+use crate::config::{parse_transcode_profiles, ReplayGainMode, TranscodeConfig};
+use crate::models::child::ReplayGain;
+
+fn test_config() -> TranscodeConfig {
+    TranscodeConfig {
+        ffmpeg_path: "ffmpeg".to_string(),
+        profiles: parse_transcode_profiles("flac>opus:128,*>mp3:320").unwrap(),
+        replay_gain: ReplayGainMode::Off,
+    }
+}
+
+#[test]
+fn parse_profiles_reads_table() {
+    let cfg = test_config();
+    assert_eq!(cfg.profiles.len(), 2);
+    assert_eq!(cfg.profiles[0].source, "flac");
+    assert_eq!(cfg.profiles[0].target, "opus");
+    assert_eq!(cfg.profiles[0].bit_rate, 128);
+    assert_eq!(cfg.profiles[1].source, "*");
+    assert!(parse_transcode_profiles("flac-opus").is_err());
+}
+
+#[test]
+fn effective_max_bit_rate_takes_lowest_limit() {
+    assert_eq!(effective_max_bit_rate(None, None), 0);
+    assert_eq!(effective_max_bit_rate(Some(0), Some(0)), 0);
+    assert_eq!(effective_max_bit_rate(Some(192), None), 192);
+    assert_eq!(effective_max_bit_rate(None, Some(128)), 128);
+    assert_eq!(effective_max_bit_rate(Some(320), Some(128)), 128);
+}
+
+#[test]
+fn plan_serves_raw_without_limits() {
+    let cfg = test_config();
+    assert_eq!(plan(&cfg, "flac", 1000, None, 0, 0), None);
+    assert_eq!(plan(&cfg, "flac", 1000, Some("raw"), 128, 0), None);
+}
+
+#[test]
+fn plan_transcodes_when_over_cap() {
+    let cfg = test_config();
+    let p = plan(&cfg, "flac", 1000, None, 96, 0).unwrap();
+    assert_eq!(p.suffix, "opus");
+    assert_eq!(p.content_type, "audio/ogg");
+    assert_eq!(p.bit_rate, 96);
+
+    // Under the cap: served as-is
+    assert_eq!(plan(&cfg, "mp3", 128, None, 192, 0), None);
+
+    // Wildcard profile never exceeds the cap
+    let p = plan(&cfg, "m4a", 256, None, 192, 0).unwrap();
+    assert_eq!(p.suffix, "mp3");
+    assert_eq!(p.bit_rate, 192);
+}
+
+#[test]
+fn plan_honors_explicit_format() {
+    let cfg = test_config();
+    let p = plan(&cfg, "flac", 1000, Some("mp3"), 0, 0).unwrap();
+    assert_eq!(p.suffix, "mp3");
+    assert_eq!(p.content_type, "audio/mpeg");
+    assert_eq!(p.bit_rate, 320);
+
+    let p = plan(&cfg, "flac", 1000, Some("aac"), 0, 30).unwrap();
+    assert_eq!(p.bit_rate, 192);
+    assert_eq!(p.time_offset, 30);
+}
+
+#[test]
+fn stream_target_follows_the_bit_rate_limit() {
+    let cfg = test_config();
+    assert_eq!(stream_target(&cfg, "flac", 1000, 0), None);
+    assert_eq!(
+        stream_target(&cfg, "flac", 1000, 192),
+        Some(("opus".to_string(), "audio/ogg"))
+    );
+    assert_eq!(stream_target(&cfg, "mp3", 128, 192), None);
+    assert_eq!(
+        stream_target(&cfg, "mp3", 320, 192),
+        Some(("mp3".to_string(), "audio/mpeg"))
+    );
+}
+
+#[test]
+fn replay_gain_picks_mode_and_prevents_clipping() {
+    let gain = ReplayGain {
+        track_gain: Some(-7.5),
+        track_peak: Some(0.9),
+        album_gain: Some(-6.0),
+        album_peak: Some(1.0),
+        base_gain: None,
+    };
+    assert_eq!(replay_gain(ReplayGainMode::Off, &gain), None);
+    assert_eq!(replay_gain(ReplayGainMode::Track, &gain), Some(-7.5));
+    assert_eq!(replay_gain(ReplayGainMode::Album, &gain), Some(-6.0));
+
+    // Falls back to the other gain
+    let track_only = ReplayGain {
+        track_gain: Some(-3.0),
+        ..Default::default()
+    };
+    assert_eq!(replay_gain(ReplayGainMode::Album, &track_only), Some(-3.0));
+    assert_eq!(
+        replay_gain(ReplayGainMode::Track, &ReplayGain::default()),
+        None
+    );
+
+    // A boost is limited so the peak stays below full scale
+    let quiet = ReplayGain {
+        track_gain: Some(10.0),
+        track_peak: Some(0.5),
+        ..Default::default()
+    };
+    let applied = replay_gain(ReplayGainMode::Track, &quiet).unwrap();
+    assert!((applied - 6.02).abs() < 0.01);
+}
@@ -1,7 +1,11 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-static LYRICS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[(\d+):(\d+)\.(\d+)\](.*)$").unwrap());
+static LYRICS_TIME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[(\d+):(\d{1,2})(?:[.:](\d{1,3}))?\]").unwrap());
+static LYRICS_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[([A-Za-z#]+):(.*)\]$").unwrap());
+static LYRICS_WORD_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<\d+:\d{1,2}(?:[.:]\d{1,3})?>").unwrap());
 
 pub fn strip_articles(name: &str, articles: &[&str]) -> String {
     let upper_name = name.to_uppercase();
@@ -51,9 +55,15 @@ where
     result
 }
 
+/// Parses LRC lyrics into `(synced, lines)`, where each line carries its start
+/// time in milliseconds. Lines may have several timestamps, enhanced LRC word
+/// timings (`<mm:ss.xx>`) are stripped from the text, and an `[offset:]` tag
+/// shifts every start time (positive values make lines appear sooner). Any
+/// untimed text makes the whole set unsynced.
 pub fn parse_lrc(content: &str) -> (bool, Vec<(Option<i32>, String)>) {
     let mut lines = Vec::new();
     let mut synced = true;
+    let mut offset = 0;
 
     for row in content.lines() {
         let row = row.trim();
@@ -61,28 +71,50 @@ pub fn parse_lrc(content: &str) -> (bool, Vec<(Option<i32>, String)>) {
             continue;
         }
 
-        let parsed_line = (|| {
-            let caps = LYRICS_RE.captures(row)?;
-            let min: i32 = caps[1].parse().ok()?;
-            let sec: i32 = caps[2].parse().ok()?;
-            let ms_str = &caps[3];
-            let mut ms: i32 = ms_str.parse().ok()?;
-            if ms_str.len() == 2 {
-                ms *= 10;
-            }
-            let text = caps[4].trim().to_string();
-            let start_time = (min * 60 + sec) * 1000 + ms;
-            Some((Some(start_time), text))
-        })();
-
-        match parsed_line {
-            Some(line) => lines.push(line),
-            None => {
-                synced = false;
-                lines.push((None, row.to_string()));
+        if let Some(caps) = LYRICS_TAG_RE.captures(row) {
+            if caps[1].eq_ignore_ascii_case("offset") {
+                offset = caps[2].trim().trim_start_matches('+').parse().unwrap_or(0);
             }
+            continue;
+        }
+
+        let mut starts = Vec::new();
+        let mut rest = row;
+        while let Some(caps) = LYRICS_TIME_RE.captures(rest) {
+            let (Ok(min), Ok(sec)) = (caps[1].parse::<i32>(), caps[2].parse::<i32>()) else {
+                break;
+            };
+            let ms = caps.get(3).map_or(0, |m| {
+                let value: i32 = m.as_str().parse().unwrap_or(0);
+                value * 10_i32.pow(3 - m.as_str().len() as u32)
+            });
+            starts.push((min * 60 + sec) * 1000 + ms);
+            rest = &rest[caps[0].len()..];
+        }
+
+        if starts.is_empty() {
+            synced = false;
+            lines.push((None, row.to_string()));
+            continue;
         }
+
+        let text = LYRICS_WORD_RE.replace_all(rest, "");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        for start in starts {
+            lines.push((Some(start), text.clone()));
+        }
+    }
+
+    if synced {
+        for (start, _) in &mut lines {
+            *start = start.map(|s| (s - offset).max(0));
+        }
+        lines.sort_by_key(|(start, _)| *start);
     }
 
-    (synced, lines)
+    (synced && !lines.is_empty(), lines)
 }
+
+#[cfg(test)]
+#[path = "utils_tests.rs"]
+mod tests;
@@ -0,0 +1,52 @@
+use super::*;
+
+#[test]
+fn parse_lrc_reads_timestamps_and_skips_tags() {
+    let (synced, lines) =
+        parse_lrc("[ar:Someone]\n[ti:Song]\n[00:01.5]One\n[00:02.25] Two\n[01:03.123]Three\n");
+    assert!(synced);
+    assert_eq!(
+        lines,
+        vec![
+            (Some(1500), "One".to_string()),
+            (Some(2250), "Two".to_string()),
+            (Some(63123), "Three".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn parse_lrc_handles_repeats_word_timings_and_offset() {
+    let content = "[offset:+500]\n[00:10.00][00:30.00]Chorus\n[00:20.00]<00:20.00>Hello <00:20.50>world\n[00:00.20]Intro";
+    let (synced, lines) = parse_lrc(content);
+    assert!(synced);
+    assert_eq!(
+        lines,
+        vec![
+            (Some(0), "Intro".to_string()),
+            (Some(9500), "Chorus".to_string()),
+            (Some(19500), "Hello world".to_string()),
+            (Some(29500), "Chorus".to_string()),
+        ]
+    );
+
+    let (_, lines) = parse_lrc("[offset:-250]\n[00:01.00]Late");
+    assert_eq!(lines, vec![(Some(1250), "Late".to_string())]);
+}
+
+#[test]
+fn parse_lrc_plain_text_is_unsynced() {
+    let (synced, lines) = parse_lrc("First line\n\nSecond line\n");
+    assert!(!synced);
+    assert_eq!(
+        lines,
+        vec![
+            (None, "First line".to_string()),
+            (None, "Second line".to_string())
+        ]
+    );
+
+    let (synced, lines) = parse_lrc("");
+    assert!(!synced);
+    assert!(lines.is_empty());
+}
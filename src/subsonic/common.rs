@@ -1,6 +1,10 @@
-use crate::subsonic::models::SubsonicResponse;
-use poem::Response;
+use crate::config::Config;
+use crate::subsonic::models::{SubsonicResponse, SubsonicResponseBody};
+use poem::error::ParseQueryError;
+use poem::{FromRequest, Request, RequestBody, Response};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Deserializer};
+use std::ops::Deref;
 
 pub fn deserialize_vec<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
 where
@@ -69,6 +73,9 @@ pub struct SubsonicParams {
     pub s: Option<String>,
     pub c: Option<String>,
     pub f: Option<String>,
+    /// OpenSubsonic `apiKeyAuthentication`, used instead of `u` with `p` or `t`/`s`.
+    #[serde(rename = "apiKey")]
+    pub api_key: Option<String>,
 }
 
 impl Default for SubsonicParams {
@@ -80,10 +87,46 @@ impl Default for SubsonicParams {
             s: None,
             c: Some("miko-api".to_string()),
             f: Some("json".to_string()),
+            api_key: None,
         }
     }
 }
 
+/// The request's parameters as a query string: the URI query, plus the body of
+/// an OpenSubsonic `formPost` request once
+/// [`FormPost`](crate::subsonic::middleware::FormPost) has merged it in.
+#[derive(Debug, Clone, Default)]
+pub struct RequestParams(pub String);
+
+impl RequestParams {
+    pub fn of(req: &Request) -> &str {
+        match req.data::<RequestParams>() {
+            Some(params) => &params.0,
+            None => req.uri().query().unwrap_or_default(),
+        }
+    }
+}
+
+/// Like `poem::web::Query`, but reads [`RequestParams`] so form posts are
+/// handled the same as GET parameters.
+pub struct Params<T>(pub T);
+
+impl<T> Deref for Params<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, T: DeserializeOwned> FromRequest<'a> for Params<T> {
+    async fn from_request(req: &'a Request, _body: &mut RequestBody) -> poem::Result<Self> {
+        serde_urlencoded::from_str(RequestParams::of(req))
+            .map(Self)
+            .map_err(|e| ParseQueryError(e).into())
+    }
+}
+
 pub fn send_response(resp: SubsonicResponse, format: &Option<String>) -> Response {
     let is_json = format.as_deref() == Some("json");
 
@@ -123,6 +166,31 @@ pub fn send_response(resp: SubsonicResponse, format: &Option<String>) -> Respons
     }
 }
 
+/// An empty `ok` response.
+pub fn ok(params: &SubsonicParams) -> Response {
+    send_response(
+        SubsonicResponse::new_ok(SubsonicResponseBody::None),
+        &params.f,
+    )
+}
+
+/// Error 70 for a missing `what`, e.g. "Podcast channel".
+pub fn not_found(what: &str, params: &SubsonicParams) -> Response {
+    send_response(
+        SubsonicResponse::new_error(70, format!("{} not found", what)),
+        &params.f,
+    )
+}
+
+/// Logs `e` and reports a generic server error.
+pub fn db_error(e: impl std::fmt::Display, params: &SubsonicParams) -> Response {
+    log::error!("Database error: {}", e);
+    send_response(
+        SubsonicResponse::new_error(0, "Database error".into()),
+        &params.f,
+    )
+}
+
 pub fn clean_json_attributes(value: &mut serde_json::Value) {
     match value {
         serde_json::Value::Object(map) => {
@@ -164,3 +232,24 @@ pub fn clean_json_attributes(value: &mut serde_json::Value) {
         _ => {}
     }
 }
+
+/// The externally visible root URL of the server, without a trailing slash:
+/// `BASE_URL` if configured, otherwise rebuilt from the (possibly proxied) request.
+pub fn public_base_url(config: &Config, req: &Request) -> String {
+    if let Some(base) = &config.server.base_url {
+        return base.clone();
+    }
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let scheme = header("x-forwarded-proto").unwrap_or_else(|| req.scheme().to_string());
+    let host = header("x-forwarded-host")
+        .or_else(|| header("host"))
+        .unwrap_or_else(|| format!("localhost:{}", config.server.port));
+    format!("{}://{}", scheme, host)
+}
@@ -1,15 +1,15 @@
+use crate::models::user;
+use crate::service::scrobble::{ScrobbleForwarder, ScrobbleKind};
 use crate::subsonic::{
-    common::{deserialize_optional_bool, deserialize_vec, send_response, SubsonicParams},
+    common::{deserialize_vec, send_response, Params, RequestParams, SubsonicParams},
     models::{SubsonicResponse, SubsonicResponseBody},
 };
-use poem::{
-    handler,
-    web::{Data, Query},
-    IntoResponse,
-};
+use chrono::{DateTime, Utc};
+use poem::{handler, web::Data, IntoResponse, Request};
 use sea_orm::sea_query::Expr;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
 use serde::Deserialize;
+use std::sync::Arc;
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -29,14 +29,89 @@ pub struct SetRatingQuery {
     pub rating: i32,
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// Scrobble parameters. `id` and `time` may be repeated so offline clients can
+/// submit several plays at once; `time` is in milliseconds since the epoch.
+#[derive(Debug, Default, PartialEq)]
 pub struct ScrobbleQuery {
-    pub id: String,
-    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub id: Vec<String>,
+    pub time: Vec<i64>,
     pub submission: Option<bool>,
 }
 
+impl ScrobbleQuery {
+    pub fn parse(query: &str) -> Self {
+        let pairs = serde_urlencoded::from_str::<Vec<(String, String)>>(query).unwrap_or_default();
+        let mut parsed = Self::default();
+        for (key, value) in pairs {
+            match key.as_str() {
+                "id" => parsed.id.push(value),
+                "time" => parsed.time.extend(value.parse::<i64>().ok()),
+                "submission" => {
+                    parsed.submission = Some(matches!(
+                        value.to_lowercase().as_str(),
+                        "true" | "t" | "yes" | "y" | "1"
+                    ))
+                }
+                _ => {}
+            }
+        }
+        parsed
+    }
+}
+
+/// Drops ids the user cannot see because they live outside their granted music folders.
+async fn retain_accessible(
+    db: &DatabaseConnection,
+    username: &str,
+    query: StarQuery,
+) -> Result<StarQuery, sea_orm::DbErr> {
+    use crate::models::{album, artist, child, queries};
+    use sea_orm::QuerySelect;
+
+    let id = if query.id.is_empty() {
+        query.id
+    } else {
+        child::Entity::find()
+            .select_only()
+            .column(child::Column::Id)
+            .filter(child::Column::Id.is_in(query.id))
+            .filter(queries::song_access_filter(username))
+            .into_tuple::<String>()
+            .all(db)
+            .await?
+    };
+    let album_id = if query.album_id.is_empty() {
+        query.album_id
+    } else {
+        album::Entity::find()
+            .select_only()
+            .column(album::Column::Id)
+            .filter(album::Column::Id.is_in(query.album_id))
+            .filter(queries::album_access_filter(username))
+            .into_tuple::<String>()
+            .all(db)
+            .await?
+    };
+    let artist_id = if query.artist_id.is_empty() {
+        query.artist_id
+    } else {
+        artist::Entity::find()
+            .select_only()
+            .column(artist::Column::Id)
+            .filter(artist::Column::Id.is_in(query.artist_id))
+            .filter(queries::artist_access_filter(username))
+            .into_tuple::<String>()
+            .all(db)
+            .await?
+    };
+
+    Ok(StarQuery {
+        id,
+        album_id,
+        artist_id,
+    })
+}
+
 async fn insert_stars(
     db: &DatabaseConnection,
     username: &str,
@@ -151,9 +226,13 @@ pub async fn star(
     db: Data<&DatabaseConnection>,
     user: Data<&std::sync::Arc<crate::models::user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<StarQuery>,
+    query: Params<StarQuery>,
 ) -> impl IntoResponse {
-    match insert_stars(db.0, &user.username, query.0).await {
+    let result = match retain_accessible(db.0, &user.username, query.0).await {
+        Ok(q) => insert_stars(db.0, &user.username, q).await,
+        Err(e) => Err(e),
+    };
+    match result {
         Ok(_) => send_response(
             SubsonicResponse::new_ok(SubsonicResponseBody::None),
             &params.f,
@@ -173,7 +252,7 @@ pub async fn unstar(
     db: Data<&DatabaseConnection>,
     user: Data<&std::sync::Arc<crate::models::user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<StarQuery>,
+    query: Params<StarQuery>,
 ) -> impl IntoResponse {
     match remove_stars(db.0, &user.username, query.0).await {
         Ok(_) => send_response(
@@ -195,9 +274,9 @@ pub async fn set_rating(
     db: Data<&DatabaseConnection>,
     user: Data<&std::sync::Arc<crate::models::user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<SetRatingQuery>,
+    query: Params<SetRatingQuery>,
 ) -> impl IntoResponse {
-    use crate::models::{album, artist, child, user_rating};
+    use crate::models::{album, artist, child, queries, user_rating};
     use sea_orm::QuerySelect;
 
     if query.rating < 0 || query.rating > 5 {
@@ -212,16 +291,19 @@ pub async fn set_rating(
     // Determine item_type by checking all entities concurrently
     let (song_check, album_check, artist_check) = tokio::join!(
         child::Entity::find_by_id(&query.id)
+            .filter(queries::song_access_filter(username))
             .select_only()
             .column(child::Column::Id)
             .into_tuple::<String>()
             .one(db.0),
         album::Entity::find_by_id(&query.id)
+            .filter(queries::album_access_filter(username))
             .select_only()
             .column(album::Column::Id)
             .into_tuple::<String>()
             .one(db.0),
         artist::Entity::find_by_id(&query.id)
+            .filter(queries::artist_access_filter(username))
             .select_only()
             .column(artist::Column::Id)
             .into_tuple::<String>()
@@ -298,37 +380,108 @@ pub async fn set_rating(
     }
 }
 
+/// Appends plays to the user's history, skipping songs outside their folders and
+/// plays already recorded with the same timestamp, and bumps the library-wide counters.
+/// Returns the plays that were actually recorded.
+async fn record_plays(
+    db: &DatabaseConnection,
+    username: &str,
+    client: Option<String>,
+    plays: Vec<(String, DateTime<Utc>)>,
+) -> Result<Vec<(String, DateTime<Utc>)>, sea_orm::DbErr> {
+    use crate::models::{child, play_history, queries};
+    use sea_orm::{PaginatorTrait, QuerySelect, TransactionTrait};
+
+    let ids: Vec<String> = plays.iter().map(|(id, _)| id.clone()).collect();
+    let accessible: Vec<String> = child::Entity::find()
+        .select_only()
+        .column(child::Column::Id)
+        .filter(child::Column::Id.is_in(ids))
+        .filter(queries::song_access_filter(username))
+        .into_tuple::<String>()
+        .all(db)
+        .await?;
+
+    let txn = db.begin().await?;
+    let mut recorded = Vec::new();
+    for (song_id, played_at) in plays {
+        if !accessible.contains(&song_id) {
+            continue;
+        }
+        let duplicate = play_history::Entity::find()
+            .filter(play_history::Column::Username.eq(username))
+            .filter(play_history::Column::SongId.eq(&song_id))
+            .filter(play_history::Column::PlayedAt.eq(played_at))
+            .count(&txn)
+            .await?;
+        if duplicate > 0 {
+            continue;
+        }
+
+        play_history::ActiveModel {
+            username: Set(username.to_string()),
+            song_id: Set(song_id.clone()),
+            played_at: Set(played_at),
+            client: Set(client.clone()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+
+        child::Entity::update_many()
+            .filter(child::Column::Id.eq(&song_id))
+            .col_expr(
+                child::Column::PlayCount,
+                Expr::col(child::Column::PlayCount).add(1),
+            )
+            .col_expr(
+                child::Column::LastPlayed,
+                Expr::cust_with_values(
+                    "CASE WHEN last_played IS NULL OR last_played < ? THEN ? ELSE last_played END",
+                    [played_at, played_at],
+                ),
+            )
+            .exec(&txn)
+            .await?;
+        recorded.push((song_id, played_at));
+    }
+    txn.commit().await?;
+
+    Ok(recorded)
+}
+
 #[handler]
 pub async fn scrobble(
     db: Data<&DatabaseConnection>,
+    user: Data<&Arc<user::Model>>,
+    forwarder: Data<&Arc<ScrobbleForwarder>>,
     params: Data<&SubsonicParams>,
-    query: Query<ScrobbleQuery>,
+    req: &Request,
 ) -> impl IntoResponse {
-    use crate::models::{child, now_playing};
-    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, Set};
-
-    let ScrobbleQuery {
-        id: song_id,
-        submission,
-    } = query.0;
-    let submission = submission.unwrap_or(false);
-    let username = params
-        .u
-        .as_deref()
-        .map(ammonia::clean)
-        .unwrap_or_else(|| "guest".to_string());
+    use crate::models::now_playing;
+
+    let query = ScrobbleQuery::parse(RequestParams::of(req));
+    let submission = query.submission.unwrap_or(false);
+    let username = user.username.clone();
     let player_name = params
         .c
         .as_deref()
         .map(ammonia::clean)
         .unwrap_or_else(|| "unknown".to_string());
 
+    let Some(song_id) = query.id.first().cloned() else {
+        return send_response(
+            SubsonicResponse::new_error(10, "Required parameter is missing: id".into()),
+            &params.f,
+        );
+    };
+
     if !submission {
-        let now = chrono::Utc::now();
+        let now = Utc::now();
         let np = now_playing::ActiveModel {
             username: Set(username),
             player_name: Set(player_name),
-            song_id: Set(song_id),
+            song_id: Set(song_id.clone()),
             updated_at: Set(now),
         };
 
@@ -352,6 +505,13 @@ pub async fn scrobble(
             );
         }
 
+        if let Err(e) = forwarder
+            .enqueue(&user, ScrobbleKind::NowPlaying, &[(song_id, now)])
+            .await
+        {
+            log::error!("Failed to queue now playing for forwarding: {}", e);
+        }
+
         return send_response(
             SubsonicResponse::new_ok(SubsonicResponseBody::None),
             &params.f,
@@ -365,22 +525,36 @@ pub async fn scrobble(
         .exec(db.0)
         .await;
 
-    let now = chrono::Utc::now();
-    let res = child::Entity::update_many()
-        .filter(child::Column::Id.eq(&song_id))
-        .col_expr(
-            child::Column::PlayCount,
-            Expr::col(child::Column::PlayCount).add(1),
-        )
-        .col_expr(child::Column::LastPlayed, Expr::value(Some(now)))
-        .exec(db.0)
-        .await;
+    // Backfilled plays keep their client-supplied time; anything missing or in the future is "now".
+    let now = Utc::now();
+    let plays = query
+        .id
+        .into_iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let played_at = query
+                .time
+                .get(i)
+                .and_then(|t| DateTime::from_timestamp_millis(*t))
+                .filter(|t| *t <= now)
+                .unwrap_or(now);
+            (id, played_at)
+        })
+        .collect();
 
-    match res {
-        Ok(_) => send_response(
-            SubsonicResponse::new_ok(SubsonicResponseBody::None),
-            &params.f,
-        ),
+    match record_plays(db.0, &username, params.c.clone(), plays).await {
+        Ok(recorded) => {
+            if let Err(e) = forwarder
+                .enqueue(&user, ScrobbleKind::Submission, &recorded)
+                .await
+            {
+                log::error!("Failed to queue scrobbles for forwarding: {}", e);
+            }
+            send_response(
+                SubsonicResponse::new_ok(SubsonicResponseBody::None),
+                &params.f,
+            )
+        }
         Err(e) => {
             log::error!("Database error in scrobble: {}", e);
             send_response(
@@ -361,3 +361,123 @@ async fn rating_does_not_affect_other_users() {
     assert_eq!(ratings[0].username, "otheruser");
     assert_eq!(ratings[0].rating, 5);
 }
+
+// ─── retain_accessible ─────────────────────────────────────────
+
+async fn insert_folder_with_song(db: &DatabaseConnection, folder_id: i32, song_id: &str) {
+    use crate::models::{child, music_folder};
+
+    music_folder::ActiveModel {
+        id: Set(folder_id),
+        path: Set(format!("/music{}", folder_id)),
+        name: Set(None),
+    }
+    .insert(db)
+    .await
+    .unwrap();
+
+    child::ActiveModel {
+        id: Set(song_id.to_string()),
+        parent: Set(None),
+        is_dir: Set(false),
+        title: Set(song_id.to_string()),
+        path: Set(format!("/music{}/{}.mp3", folder_id, song_id)),
+        music_folder_id: Set(folder_id),
+        r#type: Set("music".to_string()),
+        track: Set(0),
+        year: Set(0),
+        disc_number: Set(0),
+        duration: Set(0),
+        bit_rate: Set(0),
+        size: Set(0),
+        is_video: Set(false),
+        average_rating: Set(0.0),
+        play_count: Set(0),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn retain_accessible_drops_songs_outside_granted_folders() {
+    use crate::models::user_music_folder;
+
+    let db = setup_db().await;
+    insert_folder_with_song(&db, 1, "song1").await;
+    insert_folder_with_song(&db, 2, "song2").await;
+
+    user_music_folder::ActiveModel {
+        username: Set("testuser".to_string()),
+        music_folder_id: Set(1),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    let query = StarQuery {
+        id: vec!["song1".into(), "song2".into(), "missing".into()],
+        album_id: vec![],
+        artist_id: vec![],
+    };
+    let retained = retain_accessible(&db, "testuser", query).await.unwrap();
+    assert_eq!(retained.id, vec!["song1".to_string()]);
+}
+
+// ─── scrobble ──────────────────────────────────────────────────
+
+#[test]
+fn scrobble_query_pairs_repeated_ids_and_times() {
+    let query = ScrobbleQuery::parse("u=x&id=a&time=1000&id=b&time=2000&submission=true");
+    assert_eq!(query.id, vec!["a", "b"]);
+    assert_eq!(query.time, vec![1000, 2000]);
+    assert_eq!(query.submission, Some(true));
+}
+
+#[tokio::test]
+async fn record_plays_writes_per_user_history() {
+    use crate::models::{child, play_history, queries, user_music_folder};
+    use sea_orm::QueryFilter;
+
+    let db = setup_db().await;
+    insert_folder_with_song(&db, 1, "song1").await;
+    insert_folder_with_song(&db, 2, "song2").await;
+    user_music_folder::ActiveModel {
+        username: Set("testuser".to_string()),
+        music_folder_id: Set(1),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    let played_at = chrono::DateTime::from_timestamp_millis(1_700_000_000_000).unwrap();
+    let plays = vec![
+        ("song1".to_string(), played_at),
+        ("song1".to_string(), played_at),
+        ("song2".to_string(), played_at),
+    ];
+    let recorded = record_plays(&db, "testuser", Some("client".into()), plays)
+        .await
+        .unwrap();
+    assert_eq!(
+        recorded.len(),
+        1,
+        "duplicates and inaccessible songs are skipped"
+    );
+
+    let history = play_history::Entity::find().all(&db).await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].played_at, played_at);
+    assert_eq!(history[0].client.as_deref(), Some("client"));
+
+    let song = queries::song_with_metadata_query("testuser")
+        .filter(child::Column::Id.eq("song1"))
+        .into_model::<child::ChildWithMetadata>()
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(song.play_count, 1);
+    assert_eq!(song.last_played, Some(played_at));
+}
@@ -1,14 +1,11 @@
+use crate::config::Config;
 use crate::models::user;
 use crate::service::Service;
 use crate::subsonic::{
-    common::{deserialize_vec, send_response, SubsonicParams},
+    common::{deserialize_vec, send_response, Params, SubsonicParams},
     models::{Bookmark, Bookmarks, Child, PlayQueue, SubsonicResponse, SubsonicResponseBody},
 };
-use poem::{
-    handler,
-    web::{Data, Query},
-    IntoResponse,
-};
+use poem::{handler, web::Data, IntoResponse};
 use serde::Deserialize;
 use std::sync::Arc;
 
@@ -35,6 +32,7 @@ pub struct SavePlayQueueQuery {
 #[handler]
 pub async fn get_bookmarks(
     service: Data<&Arc<Service>>,
+    config: Data<&Arc<Config>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
 ) -> impl IntoResponse {
@@ -45,7 +43,7 @@ pub async fn get_bookmarks(
             let bookmarks = res
                 .into_iter()
                 .map(|(bm, s)| Bookmark {
-                    entry: s.into(),
+                    entry: Child::from(s).transcoded_for(&config.transcode, &user),
                     position: bm.position,
                     comment: bm.comment,
                     created: bm.created_at,
@@ -76,7 +74,7 @@ pub async fn create_bookmark(
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<CreateBookmarkQuery>,
+    query: Params<CreateBookmarkQuery>,
 ) -> impl IntoResponse {
     let username = &user.username;
     let position = query.position.unwrap_or(0);
@@ -104,7 +102,7 @@ pub async fn delete_bookmark(
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<DeleteBookmarkQuery>,
+    query: Params<DeleteBookmarkQuery>,
 ) -> impl IntoResponse {
     let username = &user.username;
 
@@ -125,6 +123,7 @@ pub async fn delete_bookmark(
 
 #[handler]
 pub async fn get_play_queue(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
@@ -139,7 +138,10 @@ pub async fn get_play_queue(
                 changed: Some(pq.changed),
                 changed_by: Some(pq.changed_by),
                 username: pq.username,
-                entry: songs.into_iter().map(Child::from).collect(),
+                entry: songs
+                    .into_iter()
+                    .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+                    .collect(),
             };
 
             send_response(
@@ -177,7 +179,7 @@ pub async fn save_play_queue(
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<SavePlayQueueQuery>,
+    query: Params<SavePlayQueueQuery>,
 ) -> impl IntoResponse {
     let username = &user.username;
     let client_name = params.c.as_deref().unwrap_or("Default");
@@ -1,22 +1,21 @@
 use crate::config::Config;
-use crate::models::{music_folder, user};
+use crate::models::{music_folder, queries, user};
 use crate::scanner::Scanner;
+use crate::service::metadata::{ArtistInfoResult, ArtistMetadata, MetadataService};
 use crate::service::Service;
 use crate::subsonic::{
-    common::{send_response, SubsonicParams},
+    common::{send_response, Params, SubsonicParams},
     models::{
         AlbumID3, AlbumInfo, AlbumWithSongsID3, Artist, ArtistID3, ArtistInfo, ArtistInfo2,
-        ArtistWithAlbumsID3, ArtistsID3, Child, Directory, Genre, Genres, Index, IndexID3, Indexes,
-        MusicFolder, MusicFolders, SimilarSongs, SimilarSongs2, SubsonicResponse,
-        SubsonicResponseBody, TopSongs,
+        ArtistInfoBase, ArtistWithAlbumsID3, ArtistsID3, Child, Directory, Genre, Genres, Index,
+        IndexID3, Indexes, MusicFolder, MusicFolders, SimilarSongs, SimilarSongs2,
+        SubsonicResponse, SubsonicResponseBody, TopSongs,
     },
 };
-use poem::{
-    handler,
-    web::{Data, Query},
-    IntoResponse,
+use poem::{handler, web::Data, IntoResponse};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, EntityTrait, FromQueryResult, QueryFilter, Statement,
 };
-use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait, FromQueryResult, Statement};
 use serde::Deserialize;
 use std::sync::Arc;
 
@@ -54,9 +53,14 @@ struct MusicFolderStats {
 #[handler]
 pub async fn get_music_folders(
     db: Data<&DatabaseConnection>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
 ) -> impl IntoResponse {
-    let folders = match music_folder::Entity::find().all(*db).await {
+    let folders = match music_folder::Entity::find()
+        .filter(queries::folder_access_filter(&user.username))
+        .all(*db)
+        .await
+    {
         Ok(f) => f,
         Err(_) => {
             return send_response(
@@ -128,14 +132,19 @@ pub async fn get_music_folders(
 pub async fn get_indexes(
     service: Data<&Arc<Service>>,
     config: Data<&Arc<Config>>,
+    user: Data<&Arc<user::Model>>,
     scanner: Data<&Arc<Scanner>>,
     params: Data<&SubsonicParams>,
-    query: Query<GetIndexesQuery>,
+    query: Params<GetIndexesQuery>,
 ) -> impl IntoResponse {
     let music_folder_id = query.music_folder_id;
 
     match service
-        .get_indexes(music_folder_id, &config.subsonic.ignored_articles)
+        .get_indexes(
+            music_folder_id,
+            &config.subsonic.ignored_articles,
+            &user.username,
+        )
         .await
     {
         Ok(indexes) => {
@@ -169,10 +178,11 @@ pub async fn get_indexes(
 
 #[handler]
 pub async fn get_music_directory(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<GetMusicDirectoryQuery>,
+    query: Params<GetMusicDirectoryQuery>,
 ) -> impl IntoResponse {
     let id = &query.id;
     let offset = query.offset.unwrap_or(0);
@@ -193,7 +203,11 @@ pub async fn get_music_directory(
                 play_count: Some(data.dir.play_count),
                 total_count: Some(data.total_count),
                 path: Some(data.dir.path),
-                child: data.children.into_iter().map(Child::from).collect(),
+                child: data
+                    .children
+                    .into_iter()
+                    .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+                    .collect(),
             }));
 
             send_response(resp, &params.f)
@@ -211,9 +225,10 @@ pub async fn get_music_directory(
 #[handler]
 pub async fn get_genres(
     service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
 ) -> impl IntoResponse {
-    let genres = match service.get_genres().await {
+    let genres = match service.get_genres(&user.username).await {
         Ok(g) => g,
         Err(e) => {
             log::error!("Failed to query genres: {:?}", e);
@@ -273,7 +288,7 @@ pub async fn get_artist(
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<IdQuery>,
+    query: Params<IdQuery>,
 ) -> impl IntoResponse {
     let id = &query.id;
 
@@ -299,10 +314,11 @@ pub async fn get_artist(
 
 #[handler]
 pub async fn get_album(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<IdQuery>,
+    query: Params<IdQuery>,
 ) -> impl IntoResponse {
     let id = &query.id;
 
@@ -310,7 +326,10 @@ pub async fn get_album(
         Ok((album, songs)) => {
             let resp = SubsonicResponse::new_ok(SubsonicResponseBody::Album(AlbumWithSongsID3 {
                 album: AlbumID3::from(album),
-                song: songs.into_iter().map(Child::from).collect(),
+                song: songs
+                    .into_iter()
+                    .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+                    .collect(),
             }));
 
             send_response(resp, &params.f)
@@ -327,16 +346,19 @@ pub async fn get_album(
 
 #[handler]
 pub async fn get_song(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<IdQuery>,
+    query: Params<IdQuery>,
 ) -> impl IntoResponse {
     let id = &query.id;
 
     match service.get_song(id, &user.username).await {
         Ok(song) => {
-            let resp = SubsonicResponse::new_ok(SubsonicResponseBody::Song(Child::from(song)));
+            let resp = SubsonicResponse::new_ok(SubsonicResponseBody::Song(
+                Child::from(song).transcoded_for(&config.transcode, &user),
+            ));
             send_response(resp, &params.f)
         }
         Err(e) => {
@@ -349,68 +371,214 @@ pub async fn get_song(
     }
 }
 
+#[derive(Deserialize)]
+pub struct ArtistInfoQuery {
+    pub id: String,
+    pub count: Option<usize>,
+}
+
+async fn artist_info(
+    metadata: &MetadataService,
+    user: &user::Model,
+    params: &SubsonicParams,
+    query: &ArtistInfoQuery,
+) -> Result<ArtistInfoResult, poem::Response> {
+    match metadata
+        .get_artist_info(&query.id, &user.username, query.count.unwrap_or(20))
+        .await
+    {
+        Ok(Some(result)) => Ok(result),
+        Ok(None) => Err(send_response(
+            SubsonicResponse::new_error(70, "Artist not found".into()),
+            &params.f,
+        )),
+        Err(e) => {
+            log::error!("Failed to get artist info {}: {:?}", query.id, e);
+            Err(send_response(
+                SubsonicResponse::new_error(0, "Failed to get artist info".into()),
+                &params.f,
+            ))
+        }
+    }
+}
+
+async fn album_info(
+    metadata: &MetadataService,
+    user: &user::Model,
+    params: &SubsonicParams,
+    id: &str,
+) -> poem::Response {
+    match metadata.get_album_info(id, &user.username).await {
+        Ok(Some(info)) => {
+            let resp = SubsonicResponse::new_ok(SubsonicResponseBody::AlbumInfo(AlbumInfo {
+                notes: info.notes,
+                music_brainz_id: info.music_brainz_id,
+                last_fm_url: info.last_fm_url,
+                small_image_url: info.small_image_url,
+                medium_image_url: info.medium_image_url,
+                large_image_url: info.large_image_url,
+            }));
+            send_response(resp, &params.f)
+        }
+        Ok(None) => send_response(
+            SubsonicResponse::new_error(70, "Album not found".into()),
+            &params.f,
+        ),
+        Err(e) => {
+            log::error!("Failed to get album info {}: {:?}", id, e);
+            send_response(
+                SubsonicResponse::new_error(0, "Failed to get album info".into()),
+                &params.f,
+            )
+        }
+    }
+}
+
+fn artist_info_base(info: ArtistMetadata) -> ArtistInfoBase {
+    ArtistInfoBase {
+        biography: info.biography,
+        music_brainz_id: info.music_brainz_id,
+        last_fm_url: info.last_fm_url,
+        small_image_url: info.small_image_url,
+        medium_image_url: info.medium_image_url,
+        large_image_url: info.large_image_url,
+    }
+}
+
 #[handler]
 pub async fn get_artist_info(
+    metadata: Data<&Arc<MetadataService>>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    _query: Query<IdQuery>,
+    query: Params<ArtistInfoQuery>,
 ) -> impl IntoResponse {
-    let resp = SubsonicResponse::new_ok(SubsonicResponseBody::ArtistInfo(ArtistInfo::default()));
-    send_response(resp, &params.f)
+    match artist_info(&metadata, &user, &params, &query).await {
+        Ok(result) => {
+            let resp = SubsonicResponse::new_ok(SubsonicResponseBody::ArtistInfo(ArtistInfo {
+                base: artist_info_base(result.info),
+                similar_artist: result.similar.into_iter().map(Artist::from).collect(),
+            }));
+            send_response(resp, &params.f)
+        }
+        Err(resp) => resp,
+    }
 }
 
 #[handler]
 pub async fn get_artist_info2(
+    metadata: Data<&Arc<MetadataService>>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    _query: Query<IdQuery>,
+    query: Params<ArtistInfoQuery>,
 ) -> impl IntoResponse {
-    let resp = SubsonicResponse::new_ok(SubsonicResponseBody::ArtistInfo2(ArtistInfo2::default()));
-    send_response(resp, &params.f)
+    match artist_info(&metadata, &user, &params, &query).await {
+        Ok(result) => {
+            let resp = SubsonicResponse::new_ok(SubsonicResponseBody::ArtistInfo2(ArtistInfo2 {
+                base: artist_info_base(result.info),
+                similar_artist: result.similar.into_iter().map(ArtistID3::from).collect(),
+            }));
+            send_response(resp, &params.f)
+        }
+        Err(resp) => resp,
+    }
 }
 
 #[handler]
 pub async fn get_album_info(
+    metadata: Data<&Arc<MetadataService>>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    _query: Query<IdQuery>,
+    query: Params<IdQuery>,
 ) -> impl IntoResponse {
-    let resp = SubsonicResponse::new_ok(SubsonicResponseBody::AlbumInfo(AlbumInfo::default()));
-    send_response(resp, &params.f)
+    album_info(&metadata, &user, &params, &query.id).await
 }
 
 #[handler]
 pub async fn get_album_info2(
+    metadata: Data<&Arc<MetadataService>>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    _query: Query<IdQuery>,
+    query: Params<IdQuery>,
 ) -> impl IntoResponse {
-    let resp = SubsonicResponse::new_ok(SubsonicResponseBody::AlbumInfo(AlbumInfo::default()));
-    send_response(resp, &params.f)
+    album_info(&metadata, &user, &params, &query.id).await
+}
+
+#[derive(Deserialize)]
+pub struct SimilarSongsQuery {
+    pub id: String,
+    pub count: Option<u64>,
+}
+
+async fn similar_songs(
+    service: &Service,
+    config: &Config,
+    user: &user::Model,
+    params: &SubsonicParams,
+    query: &SimilarSongsQuery,
+) -> Result<Vec<Child>, poem::Response> {
+    match service
+        .get_similar_songs(&query.id, query.count.unwrap_or(50), &user.username)
+        .await
+    {
+        Ok(songs) => Ok(songs
+            .into_iter()
+            .map(|s| Child::from(s).transcoded_for(&config.transcode, user))
+            .collect()),
+        Err(sea_orm::DbErr::RecordNotFound(_)) => Err(send_response(
+            SubsonicResponse::new_error(70, "Item not found".into()),
+            &params.f,
+        )),
+        Err(e) => {
+            log::error!("Failed to get similar songs for {}: {:?}", query.id, e);
+            Err(send_response(
+                SubsonicResponse::new_error(0, "Failed to fetch similar songs".into()),
+                &params.f,
+            ))
+        }
+    }
 }
 
 #[handler]
 pub async fn get_similar_songs(
+    service: Data<&Arc<Service>>,
+    config: Data<&Arc<Config>>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    _query: Query<IdQuery>,
+    query: Params<SimilarSongsQuery>,
 ) -> impl IntoResponse {
-    let resp =
-        SubsonicResponse::new_ok(SubsonicResponseBody::SimilarSongs(SimilarSongs::default()));
-    send_response(resp, &params.f)
+    match similar_songs(&service, &config, &user, &params, &query).await {
+        Ok(song) => send_response(
+            SubsonicResponse::new_ok(SubsonicResponseBody::SimilarSongs(SimilarSongs { song })),
+            &params.f,
+        ),
+        Err(resp) => resp,
+    }
 }
 
 #[handler]
 pub async fn get_similar_songs2(
+    service: Data<&Arc<Service>>,
+    config: Data<&Arc<Config>>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    _query: Query<IdQuery>,
+    query: Params<SimilarSongsQuery>,
 ) -> impl IntoResponse {
-    let resp =
-        SubsonicResponse::new_ok(SubsonicResponseBody::SimilarSongs2(SimilarSongs2::default()));
-    send_response(resp, &params.f)
+    match similar_songs(&service, &config, &user, &params, &query).await {
+        Ok(song) => send_response(
+            SubsonicResponse::new_ok(SubsonicResponseBody::SimilarSongs2(SimilarSongs2 { song })),
+            &params.f,
+        ),
+        Err(resp) => resp,
+    }
 }
 
 #[handler]
 pub async fn get_top_songs(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<ArtistQuery>,
+    query: Params<ArtistQuery>,
 ) -> impl IntoResponse {
     let count = query.count.unwrap_or(50);
     let songs = match service
@@ -427,7 +595,10 @@ pub async fn get_top_songs(
     };
 
     let resp = SubsonicResponse::new_ok(SubsonicResponseBody::TopSongs(TopSongs {
-        song: songs.into_iter().map(Child::from).collect(),
+        song: songs
+            .into_iter()
+            .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+            .collect(),
     }));
     send_response(resp, &params.f)
 }
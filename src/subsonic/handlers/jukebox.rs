@@ -0,0 +1,146 @@
+use crate::config::Config;
+use crate::models::user;
+use crate::service::jukebox::{JukeboxService, Status};
+use crate::subsonic::{
+    common::{db_error, send_response, RequestParams, SubsonicParams},
+    models::{Child, JukeboxPlaylist, JukeboxStatus, SubsonicResponse, SubsonicResponseBody},
+};
+use poem::{handler, web::Data, IntoResponse, Request, Response};
+use std::sync::Arc;
+
+/// Stands in for a queued song the caller can't see, so entry positions still
+/// match the playlist index.
+fn unavailable(id: String) -> Child {
+    Child {
+        id,
+        title: "Unavailable".to_string(),
+        name: "Unavailable".to_string(),
+        is_dir: false,
+        ..Default::default()
+    }
+}
+
+/// jukeboxControl parameters. `id` may be repeated for `add` and `set`.
+#[derive(Debug, Default, PartialEq)]
+pub struct JukeboxQuery {
+    pub action: Option<String>,
+    pub index: Option<usize>,
+    pub offset: Option<u32>,
+    pub id: Vec<String>,
+    pub gain: Option<f64>,
+}
+
+impl JukeboxQuery {
+    pub fn parse(query: &str) -> Self {
+        let pairs = serde_urlencoded::from_str::<Vec<(String, String)>>(query).unwrap_or_default();
+        let mut parsed = Self::default();
+        for (key, value) in pairs {
+            match key.as_str() {
+                "action" => parsed.action = Some(value),
+                "index" => parsed.index = value.parse().ok(),
+                "offset" => parsed.offset = value.parse().ok(),
+                "id" => parsed.id.push(value),
+                "gain" => parsed.gain = value.parse().ok(),
+                _ => {}
+            }
+        }
+        parsed
+    }
+}
+
+fn error(code: i32, message: &str, params: &SubsonicParams) -> Response {
+    send_response(SubsonicResponse::new_error(code, message.into()), &params.f)
+}
+
+fn status(status: Status, params: &SubsonicParams) -> Response {
+    send_response(
+        SubsonicResponse::new_ok(SubsonicResponseBody::JukeboxStatus(status.into())),
+        &params.f,
+    )
+}
+
+#[handler]
+pub async fn jukebox_control(
+    config: Data<&Arc<Config>>,
+    jukebox: Data<&Arc<JukeboxService>>,
+    user: Data<&Arc<user::Model>>,
+    params: Data<&SubsonicParams>,
+    req: &Request,
+) -> impl IntoResponse {
+    if !jukebox.enabled() {
+        return error(0, "Jukebox is not enabled", &params);
+    }
+    let query = JukeboxQuery::parse(RequestParams::of(req));
+    let Some(action) = query.action.as_deref() else {
+        return error(10, "Missing required parameter: action", &params);
+    };
+
+    let updated = match action {
+        "get" => {
+            return match jukebox.get_entries(&user.username).await {
+                Ok(songs) => send_response(
+                    SubsonicResponse::new_ok(SubsonicResponseBody::JukeboxPlaylist(
+                        JukeboxPlaylist {
+                            status: JukeboxStatus::from(jukebox.status()),
+                            entry: songs
+                                .into_iter()
+                                .map(|(id, song)| {
+                                    song.map(|s| {
+                                        Child::from(s).transcoded_for(&config.transcode, &user)
+                                    })
+                                    .unwrap_or_else(|| unavailable(id))
+                                })
+                                .collect(),
+                        },
+                    )),
+                    &params.f,
+                ),
+                Err(e) => db_error(e, &params),
+            }
+        }
+        "status" => Some(jukebox.status()),
+        "start" => Some(jukebox.start()),
+        "stop" => Some(jukebox.stop()),
+        "set" | "add" => {
+            // Check every song before the playlist is touched.
+            let tracks = match jukebox.resolve_tracks(&query.id, &user.username).await {
+                Ok(Some(t)) => t,
+                Ok(None) => return error(70, "Song not found", &params),
+                Err(e) => return db_error(e, &params),
+            };
+            if action == "set" {
+                jukebox.update(|q| Some(q.set(tracks)))
+            } else {
+                jukebox.update(|q| Some(q.add(tracks)))
+            }
+        }
+        "clear" => jukebox.update(|q| Some(q.clear())),
+        "shuffle" => jukebox.update(|q| Some(q.shuffle(&mut rand::thread_rng()))),
+        "skip" | "remove" => {
+            let Some(index) = query.index else {
+                return error(10, "Missing required parameter: index", &params);
+            };
+            if action == "skip" {
+                let offset = query.offset.unwrap_or(0);
+                jukebox.update(|q| q.skip(index, offset))
+            } else {
+                jukebox.update(|q| q.remove(index))
+            }
+        }
+        "setGain" => match query.gain {
+            Some(gain) if (0.0..=1.0).contains(&gain) => Some(jukebox.set_gain(gain)),
+            Some(_) => return error(10, "Gain must be between 0.0 and 1.0", &params),
+            None => return error(10, "Missing required parameter: gain", &params),
+        },
+        _ => return error(10, &format!("Unknown jukebox action: {}", action), &params),
+    };
+
+    match updated {
+        Some(s) => status(s, &params),
+        None => error(10, "Invalid index", &params),
+    }
+}
+
+#[cfg(test)]
+#[path = "jukebox_tests.rs"]
+mod tests;
@@ -0,0 +1,11 @@
+use super::*;
+
+#[test]
+fn jukebox_query_collects_repeated_ids() {
+    let query = JukeboxQuery::parse("u=x&action=add&id=a&id=b&gain=0.5&index=2&offset=10");
+    assert_eq!(query.action.as_deref(), Some("add"));
+    assert_eq!(query.id, vec!["a", "b"]);
+    assert_eq!(query.gain, Some(0.5));
+    assert_eq!((query.index, query.offset), (Some(2), Some(10)));
+    assert_eq!(JukeboxQuery::parse("action=skip&index=-1").index, None);
+}
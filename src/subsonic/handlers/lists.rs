@@ -1,19 +1,16 @@
+use crate::config::Config;
 use crate::models::user;
 use crate::service::library::AlbumListOptions;
 use crate::service::Service;
 use crate::subsonic::{
-    common::{send_response, SubsonicParams},
+    common::{send_response, Params, SubsonicParams},
     models::{
         AlbumID3, AlbumList, AlbumList2, Artist, ArtistID3, Child, NowPlaying, NowPlayingEntry,
         RandomSongs, SongsByGenre, Starred, Starred2, SubsonicResponse, SubsonicResponseBody,
     },
 };
 use chrono::Utc;
-use poem::{
-    handler,
-    web::{Data, Query},
-    IntoResponse,
-};
+use poem::{handler, web::Data, IntoResponse};
 use sea_orm::DatabaseConnection;
 use serde::Deserialize;
 use std::sync::Arc;
@@ -44,7 +41,7 @@ pub async fn get_album_list2(
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    list_params: Query<AlbumListOptions>,
+    list_params: Params<AlbumListOptions>,
 ) -> impl IntoResponse {
     let albums = match service.get_albums(list_params.0, &user.username).await {
         Ok(a) => a,
@@ -69,7 +66,7 @@ pub async fn get_album_list(
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    list_params: Query<AlbumListOptions>,
+    list_params: Params<AlbumListOptions>,
 ) -> impl IntoResponse {
     let albums = match service.get_albums(list_params.0, &user.username).await {
         Ok(a) => a,
@@ -91,10 +88,11 @@ pub async fn get_album_list(
 
 #[handler]
 pub async fn get_random_songs(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    list_params: Query<AlbumListOptions>,
+    list_params: Params<AlbumListOptions>,
 ) -> impl IntoResponse {
     let songs = match service
         .get_random_songs(list_params.0, &user.username)
@@ -111,7 +109,10 @@ pub async fn get_random_songs(
     };
 
     let resp = SubsonicResponse::new_ok(SubsonicResponseBody::RandomSongs(RandomSongs {
-        song: songs.into_iter().map(Child::from).collect(),
+        song: songs
+            .into_iter()
+            .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+            .collect(),
     }));
 
     send_response(resp, &params.f)
@@ -119,10 +120,11 @@ pub async fn get_random_songs(
 
 #[handler]
 pub async fn get_songs_by_genre(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<SongsByGenreQuery>,
+    query: Params<SongsByGenreQuery>,
 ) -> impl IntoResponse {
     let songs = match service
         .get_songs_by_genre(
@@ -144,7 +146,10 @@ pub async fn get_songs_by_genre(
     };
 
     let resp = SubsonicResponse::new_ok(SubsonicResponseBody::SongsByGenre(SongsByGenre {
-        song: songs.into_iter().map(Child::from).collect(),
+        song: songs
+            .into_iter()
+            .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+            .collect(),
     }));
 
     send_response(resp, &params.f)
@@ -152,10 +157,11 @@ pub async fn get_songs_by_genre(
 
 #[handler]
 pub async fn get_starred(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<MusicFolderQuery>,
+    query: Params<MusicFolderQuery>,
 ) -> impl IntoResponse {
     let music_folder_id = query.music_folder_id;
 
@@ -176,7 +182,10 @@ pub async fn get_starred(
     let resp = SubsonicResponse::new_ok(SubsonicResponseBody::Starred(Starred {
         artist: artists.into_iter().map(Artist::from).collect(),
         album: albums.into_iter().map(Child::from_album_stats).collect(),
-        song: songs.into_iter().map(Child::from).collect(),
+        song: songs
+            .into_iter()
+            .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+            .collect(),
     }));
 
     send_response(resp, &params.f)
@@ -184,10 +193,11 @@ pub async fn get_starred(
 
 #[handler]
 pub async fn get_starred2(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<MusicFolderQuery>,
+    query: Params<MusicFolderQuery>,
 ) -> impl IntoResponse {
     let music_folder_id = query.music_folder_id;
 
@@ -208,7 +218,10 @@ pub async fn get_starred2(
     let resp = SubsonicResponse::new_ok(SubsonicResponseBody::Starred2(Starred2 {
         artist: artists.into_iter().map(ArtistID3::from).collect(),
         album: albums.into_iter().map(AlbumID3::from).collect(),
-        song: songs.into_iter().map(Child::from).collect(),
+        song: songs
+            .into_iter()
+            .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+            .collect(),
     }));
 
     send_response(resp, &params.f)
@@ -219,6 +232,7 @@ const NOW_PLAYING_EXPIRATION_MINUTES: i64 = 10;
 #[handler]
 pub async fn get_now_playing(
     db: Data<&DatabaseConnection>,
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
@@ -286,7 +300,7 @@ pub async fn get_now_playing(
         if let Some(song_metadata) = song_map.get(&record.song_id) {
             let minutes_ago = (now - record.updated_at).num_minutes() as i32;
             entries.push(NowPlayingEntry {
-                child: song_metadata.clone().into(),
+                child: Child::from(song_metadata.clone()).transcoded_for(&config.transcode, &user),
                 username: record.username,
                 minutes_ago,
                 player_name: record.player_name,
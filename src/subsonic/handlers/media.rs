@@ -1,9 +1,11 @@
 use crate::config::Config;
 use crate::models::queries::{self, FolderPathInfo};
-use crate::models::{artist, child, music_folder};
+use crate::models::{album, artist, child, music_folder, user};
 use crate::scanner::utils::get_cover_cache_dir;
+use crate::service::podcast::PodcastService;
 use crate::service::utils::parse_lrc;
-use crate::subsonic::common::{send_response, SubsonicParams};
+use crate::service::{cover, transcode};
+use crate::subsonic::common::{send_response, Params, SubsonicParams};
 use crate::subsonic::models::{
     Lyrics, LyricsLine, LyricsList, StructuredLyrics, SubsonicResponse, SubsonicResponseBody,
 };
@@ -12,10 +14,12 @@ use path_clean::PathClean;
 use poem::{
     handler,
     http::StatusCode,
-    web::{Data, Query, StaticFileRequest},
-    IntoResponse,
+    web::{Data, StaticFileRequest},
+    Body, IntoResponse, Response,
+};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect,
 };
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect};
 use serde::Deserialize;
 use std::path::Path;
 use std::sync::Arc;
@@ -25,6 +29,21 @@ pub struct IdQuery {
     pub id: String,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamQuery {
+    pub id: String,
+    pub max_bit_rate: Option<u32>,
+    pub format: Option<String>,
+    pub time_offset: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct CoverArtQuery {
+    pub id: String,
+    pub size: Option<u32>,
+}
+
 #[derive(Deserialize)]
 pub struct LyricsQuery {
     pub artist: String,
@@ -39,10 +58,12 @@ pub struct UsernameQuery {
 async fn get_song_path_or_error(
     db: &DatabaseConnection,
     id: &str,
+    username: &str,
     params: &SubsonicParams,
-) -> Result<String, poem::Response> {
+) -> Result<queries::SongPathInfo, poem::Response> {
     let res = queries::song_path_info_query()
         .filter(child::Column::Id.eq(id))
+        .filter(queries::song_access_filter(username))
         .into_model::<queries::SongPathInfo>()
         .one(db)
         .await;
@@ -85,7 +106,7 @@ async fn get_song_path_or_error(
                 ));
             }
 
-            Ok(s.path)
+            Ok(s)
         }
         Ok(None) => Err(send_response(
             SubsonicResponse::new_error(70, "Audio file not found".into()),
@@ -101,21 +122,83 @@ async fn get_song_path_or_error(
     }
 }
 
+/// Resolves a downloaded podcast episode (`pe-<id>`) like a song.
+async fn get_episode_path_or_error(
+    podcasts: &PodcastService,
+    id: &str,
+    params: &SubsonicParams,
+) -> Result<queries::SongPathInfo, poem::Response> {
+    let episode = match id.parse::<i32>() {
+        Ok(id) => podcasts.get_episode(id).await,
+        Err(_) => Ok(None),
+    };
+    let path = match episode {
+        Ok(Some(e)) => e.path,
+        Ok(None) => None,
+        Err(e) => {
+            log::error!("Database error: {}", e);
+            return Err(send_response(
+                SubsonicResponse::new_error(0, "Database error".into()),
+                &params.f,
+            ));
+        }
+    };
+    let Some(path) = path else {
+        return Err(send_response(
+            SubsonicResponse::new_error(70, "Podcast episode not downloaded".into()),
+            &params.f,
+        ));
+    };
+
+    let path = Path::new(&path).clean();
+    if !path.starts_with(podcasts.dir().clean()) {
+        log::error!(
+            "Security: Blocked podcast episode outside the podcast directory. ID: {}, Path: {:?}",
+            id,
+            path
+        );
+        return Err(send_response(
+            SubsonicResponse::new_error(70, "Access denied".into()),
+            &params.f,
+        ));
+    }
+
+    Ok(queries::SongPathInfo {
+        suffix: path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase()),
+        path: path.to_string_lossy().into_owned(),
+        music_folder_id: 0,
+        bit_rate: 0,
+        replay_gain_track_gain: None,
+        replay_gain_track_peak: None,
+        replay_gain_album_gain: None,
+        replay_gain_album_peak: None,
+    })
+}
+
 #[handler]
 pub async fn stream(
     db: Data<&DatabaseConnection>,
+    config: Data<&Arc<Config>>,
+    podcasts: Data<&Arc<PodcastService>>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<IdQuery>,
+    query: Params<StreamQuery>,
     file_req: StaticFileRequest,
 ) -> impl IntoResponse {
     let id = &query.id;
 
-    let path_str = match get_song_path_or_error(*db, id, &params).await {
-        Ok(p) => p,
+    let song = match id.strip_prefix("pe-") {
+        Some(episode_id) => get_episode_path_or_error(&podcasts, episode_id, &params).await,
+        None => get_song_path_or_error(*db, id, &user.username, &params).await,
+    };
+    let song = match song {
+        Ok(s) => s,
         Err(r) => return r,
     };
 
-    let path = Path::new(&path_str);
+    let path = Path::new(&song.path);
     if !path.exists() {
         return send_response(
             SubsonicResponse::new_error(70, "File not found on disk".into()),
@@ -123,6 +206,44 @@ pub async fn stream(
         );
     }
 
+    let max_bit_rate = transcode::effective_max_bit_rate(query.max_bit_rate, user.max_bit_rate);
+    let plan = transcode::plan(
+        &config.transcode,
+        song.suffix.as_deref().unwrap_or_default(),
+        song.bit_rate,
+        query.format.as_deref(),
+        max_bit_rate,
+        query.time_offset.unwrap_or(0),
+    );
+
+    if let Some(mut plan) = plan {
+        plan.gain = transcode::replay_gain(config.transcode.replay_gain, &song.replay_gain());
+        log::debug!(
+            "Transcoding {} to {} at {}kbps (offset {}s, gain {:?}dB)",
+            id,
+            plan.suffix,
+            plan.bit_rate,
+            plan.time_offset,
+            plan.gain
+        );
+        return match transcode::spawn(&config.transcode, path, &plan) {
+            Ok(output) => Response::builder()
+                .content_type(plan.content_type)
+                .body(Body::from_async_read(output)),
+            Err(e) => {
+                log::error!(
+                    "Failed to start encoder '{}': {}",
+                    config.transcode.ffmpeg_path,
+                    e
+                );
+                send_response(
+                    SubsonicResponse::new_error(0, "Failed to transcode".into()),
+                    &params.f,
+                )
+            }
+        };
+    }
+
     match file_req.create_response(path, false, false) {
         Ok(resp) => resp.into_response(),
         Err(e) => {
@@ -135,18 +256,24 @@ pub async fn stream(
 #[handler]
 pub async fn download(
     db: Data<&DatabaseConnection>,
+    podcasts: Data<&Arc<PodcastService>>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<IdQuery>,
+    query: Params<IdQuery>,
     file_req: StaticFileRequest,
 ) -> impl IntoResponse {
     let id = &query.id;
 
-    let path_str = match get_song_path_or_error(*db, id, &params).await {
-        Ok(p) => p,
+    let song = match id.strip_prefix("pe-") {
+        Some(episode_id) => get_episode_path_or_error(&podcasts, episode_id, &params).await,
+        None => get_song_path_or_error(*db, id, &user.username, &params).await,
+    };
+    let song = match song {
+        Ok(s) => s,
         Err(r) => return r,
     };
 
-    let path = Path::new(&path_str);
+    let path = Path::new(&song.path);
     if !path.exists() {
         return send_response(
             SubsonicResponse::new_error(70, "File not found on disk".into()),
@@ -186,16 +313,85 @@ pub async fn download(
 pub async fn get_cover_art(
     db: Data<&DatabaseConnection>,
     config: Data<&Arc<Config>>,
+    podcasts: Data<&Arc<PodcastService>>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<IdQuery>,
+    query: Params<CoverArtQuery>,
     file_req: StaticFileRequest,
 ) -> impl IntoResponse {
     let id = &query.id;
 
-    let cover_art = if id.starts_with("al-") || id.starts_with("ar-") {
+    let cover_art = if let Some(album_id) = id.strip_prefix("al-") {
+        match album::Entity::find_by_id(album_id.to_string())
+            .filter(queries::album_access_filter(&user.username))
+            .count(*db)
+            .await
+        {
+            Ok(0) => {
+                return send_response(
+                    SubsonicResponse::new_error(70, "Cover art not found".into()),
+                    &params.f,
+                )
+            }
+            Ok(_) => id.to_string(),
+            Err(e) => {
+                log::error!("Database error: {}", e);
+                return send_response(
+                    SubsonicResponse::new_error(0, "Database error".into()),
+                    &params.f,
+                );
+            }
+        }
+    } else if let Some(artist_id) = id.strip_prefix("ar-") {
+        match artist::Entity::find_by_id(artist_id.to_string())
+            .filter(queries::artist_access_filter(&user.username))
+            .count(*db)
+            .await
+        {
+            Ok(0) => {
+                return send_response(
+                    SubsonicResponse::new_error(70, "Cover art not found".into()),
+                    &params.f,
+                )
+            }
+            Ok(_) => id.to_string(),
+            Err(e) => {
+                log::error!("Database error: {}", e);
+                return send_response(
+                    SubsonicResponse::new_error(0, "Database error".into()),
+                    &params.f,
+                );
+            }
+        }
+    } else if id.starts_with("pc-") {
         id.to_string()
+    } else if let Some(episode_id) = id.strip_prefix("pe-") {
+        let episode = match episode_id.parse::<i32>() {
+            Ok(episode_id) => podcasts.get_episode(episode_id).await,
+            Err(_) => Ok(None),
+        };
+        match episode {
+            Ok(Some(e)) => format!("pc-{}", e.channel_id),
+            Ok(None) => {
+                return send_response(
+                    SubsonicResponse::new_error(70, "Cover art not found".into()),
+                    &params.f,
+                )
+            }
+            Err(e) => {
+                log::error!("Database error: {}", e);
+                return send_response(
+                    SubsonicResponse::new_error(0, "Database error".into()),
+                    &params.f,
+                );
+            }
+        }
     } else {
-        match child::Entity::find_by_id(id.to_string()).one(*db).await {
+        match child::Entity::find_by_id(id.to_string())
+            .filter(queries::song_access_filter(&user.username))
+            .one(*db)
+            .await
+        {
             Ok(Some(s)) => {
                 if let Some(aid) = s.album_id {
                     format!("al-{}", aid)
@@ -238,7 +434,29 @@ pub async fn get_cover_art(
         return StatusCode::BAD_REQUEST.into_response();
     }
 
-    let cache_path = cache_dir.join(safe_cover_art);
+    let mut cache_path = cache_dir.join(safe_cover_art);
+
+    if let Some(size) = query
+        .size
+        .filter(|s| *s > 0)
+        .and_then(cover::thumbnail_size)
+    {
+        let thumb_path = cover::thumbnail_path(&cache_dir, safe_cover_art, size);
+        if thumb_path.exists() {
+            cache_path = thumb_path;
+        } else if cache_path.exists() {
+            let src = cache_path.clone();
+            let dst = thumb_path.clone();
+            match tokio::task::spawn_blocking(move || cover::create_thumbnail(&src, &dst, size))
+                .await
+            {
+                Ok(Ok(true)) => cache_path = thumb_path,
+                Ok(Ok(false)) => {}
+                Ok(Err(e)) => log::warn!("Failed to resize cover art {}: {}", cover_art, e),
+                Err(e) => log::error!("Cover art resize task failed: {}", e),
+            }
+        }
+    }
 
     if cache_path.exists() {
         return match file_req.create_response(&cache_path, false, false) {
@@ -256,8 +474,9 @@ pub async fn get_cover_art(
 #[handler]
 pub async fn get_lyrics(
     db: Data<&DatabaseConnection>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<LyricsQuery>,
+    query: Params<LyricsQuery>,
 ) -> impl IntoResponse {
     let artist_name = &query.artist;
     let title = &query.title;
@@ -265,6 +484,7 @@ pub async fn get_lyrics(
     let res = queries::lyrics_with_metadata_query()
         .filter(artist::Column::Name.eq(artist_name))
         .filter(child::Column::Title.eq(title))
+        .filter(queries::song_access_filter(&user.username))
         .into_model::<queries::LyricsWithMetadata>()
         .one(*db)
         .await;
@@ -289,7 +509,13 @@ pub async fn get_lyrics(
     let resp = SubsonicResponse::new_ok(SubsonicResponseBody::Lyrics(Lyrics {
         artist: some_artist_name_or_default(&song.artist),
         title: Some(song.title),
-        value: song.content,
+        // getLyrics only carries plain text, so drop any LRC timestamps.
+        value: parse_lrc(&song.content)
+            .1
+            .into_iter()
+            .map(|(_, line)| line)
+            .collect::<Vec<_>>()
+            .join("\n"),
     }));
 
     send_response(resp, &params.f)
@@ -302,20 +528,22 @@ fn some_artist_name_or_default(artist: &Option<String>) -> Option<String> {
 #[handler]
 pub async fn get_lyrics_by_song_id(
     db: Data<&DatabaseConnection>,
+    user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<IdQuery>,
+    query: Params<IdQuery>,
 ) -> impl IntoResponse {
     let id = &query.id;
 
     let res = queries::lyrics_with_metadata_query()
         .filter(child::Column::Id.eq(id))
+        .filter(queries::song_access_filter(&user.username))
         .into_model::<queries::LyricsWithMetadata>()
-        .one(*db)
+        .all(*db)
         .await;
 
-    let song = match res {
-        Ok(Some(s)) => s,
-        Ok(None) => {
+    let sets = match res {
+        Ok(s) if !s.is_empty() => s,
+        Ok(_) => {
             return send_response(
                 SubsonicResponse::new_error(70, "Lyrics not found".into()),
                 &params.f,
@@ -330,21 +558,30 @@ pub async fn get_lyrics_by_song_id(
         }
     };
 
-    let (synced, parsed_lines) = parse_lrc(&song.content);
-
-    let lines = parsed_lines
+    let structured_lyrics = sets
         .into_iter()
-        .map(|(start, value)| LyricsLine { start, value })
+        .map(|set| {
+            let (_, parsed_lines) = parse_lrc(&set.content);
+            let lines = parsed_lines
+                .into_iter()
+                // Unsynced sets never carry start times, even for stray timed lines.
+                .map(|(start, value)| LyricsLine {
+                    start: start.filter(|_| set.synced),
+                    value,
+                })
+                .collect();
+            StructuredLyrics {
+                synced: set.synced,
+                lang: Some(set.lang),
+                display_artist: some_artist_name_or_default(&set.artist),
+                display_title: Some(set.title),
+                lines,
+            }
+        })
         .collect();
 
     let resp = SubsonicResponse::new_ok(SubsonicResponseBody::LyricsList(LyricsList {
-        structured_lyrics: vec![StructuredLyrics {
-            synced,
-            lang: Some("xxx".to_string()),
-            display_artist: some_artist_name_or_default(&song.artist),
-            display_title: Some(song.title),
-            lines,
-        }],
+        structured_lyrics,
     }));
 
     send_response(resp, &params.f)
@@ -354,7 +591,7 @@ pub async fn get_lyrics_by_song_id(
 pub async fn get_avatar(
     config: Data<&Arc<Config>>,
     _params: Data<&SubsonicParams>,
-    query: Query<UsernameQuery>,
+    query: Params<UsernameQuery>,
     file_req: StaticFileRequest,
 ) -> impl IntoResponse {
     let username = &query.username;
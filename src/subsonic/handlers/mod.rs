@@ -1,11 +1,15 @@
 pub mod annotation;
 pub mod bookmarks;
 pub mod browsing;
+pub mod jukebox;
 pub mod lists;
 pub mod media;
 pub mod playlists;
+pub mod podcasts;
+pub mod radio;
 pub mod scan;
 pub mod search;
 pub mod shared;
+pub mod shares;
 pub mod system;
 pub mod user;
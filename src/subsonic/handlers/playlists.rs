@@ -1,15 +1,59 @@
+use crate::config::Config;
+use crate::models::playlist_song::PlaylistWithSongs;
 use crate::models::user;
-use crate::service::playlists::UpdatePlaylistOptions;
+use crate::service::playlists::{
+    UpdatePlaylistOptions, PERMISSION_DENIED, PLAYLIST_NOT_FOUND, READ_ONLY,
+};
 use crate::service::Service;
 use crate::subsonic::common::{
-    deserialize_optional_bool, deserialize_vec, send_response, SubsonicParams,
+    deserialize_optional_bool, deserialize_vec, send_response, Params, SubsonicParams,
 };
 use crate::subsonic::models::{Playlist, Playlists, SubsonicResponse, SubsonicResponseBody};
-use poem::web::{Data, Query};
-use poem::{handler, IntoResponse};
+use poem::web::Data;
+use poem::{handler, IntoResponse, Response};
+use sea_orm::DbErr;
 use serde::Deserialize;
 use std::sync::Arc;
 
+/// Marks a playlist read-only for a user who can't edit its songs.
+fn with_access(mut playlist: Playlist, can_edit: bool) -> Playlist {
+    playlist.readonly = (!can_edit).then_some(true);
+    playlist
+}
+
+/// A playlist with its songs, as returned to `user`.
+fn playlist_with_songs(
+    playlist: PlaylistWithSongs,
+    config: &Config,
+    user: &user::Model,
+) -> Playlist {
+    let can_edit = playlist.playlist.can_edit(&user.username);
+    let mut playlist = with_access(Playlist::from(playlist), can_edit);
+    playlist.entry = playlist
+        .entry
+        .into_iter()
+        .map(|c| c.transcoded_for(&config.transcode, user))
+        .collect();
+    playlist
+}
+
+/// Reports a failed playlist change, with error 50 when the user may not make it.
+fn change_error(action: &str, e: DbErr, params: &SubsonicParams) -> Response {
+    let resp = match e {
+        DbErr::Custom(msg) if msg == PLAYLIST_NOT_FOUND => SubsonicResponse::new_error(70, msg),
+        DbErr::Custom(msg) if msg == PERMISSION_DENIED => SubsonicResponse::new_error(
+            50,
+            "User is not authorized to change this playlist".to_string(),
+        ),
+        DbErr::Custom(msg) if msg == READ_ONLY => SubsonicResponse::new_error(50, msg),
+        e => {
+            log::error!("Failed to {} playlist: {}", action, e);
+            SubsonicResponse::new_error(0, format!("Failed to {} playlist: {}", action, e))
+        }
+    };
+    send_response(resp, &params.f)
+}
+
 #[derive(Deserialize)]
 pub struct GetPlaylistsParams {
     pub username: Option<String>,
@@ -20,14 +64,20 @@ pub async fn get_playlists(
     service: Data<&Arc<Service>>,
     current_user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<GetPlaylistsParams>,
+    query: Params<GetPlaylistsParams>,
 ) -> impl IntoResponse {
     let username = &current_user.username;
     let target_username = query.username.as_deref().unwrap_or(username);
 
     match service.get_playlists(username, target_username).await {
         Ok(playlists) => {
-            let playlists: Vec<_> = playlists.into_iter().map(Playlist::from).collect();
+            let playlists: Vec<_> = playlists
+                .into_iter()
+                .map(|p| {
+                    let can_edit = p.can_edit(username);
+                    with_access(Playlist::from(p), can_edit)
+                })
+                .collect();
             let resp = SubsonicResponse::new_ok(SubsonicResponseBody::Playlists(Playlists {
                 playlist: playlists,
             }));
@@ -49,13 +99,14 @@ pub struct GetPlaylistParams {
 #[handler]
 pub async fn get_playlist(
     service: Data<&Arc<Service>>,
+    config: Data<&Arc<Config>>,
     current_user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<GetPlaylistParams>,
+    query: Params<GetPlaylistParams>,
 ) -> impl IntoResponse {
     match service.get_playlist(query.id, &current_user.username).await {
         Ok(Some(playlist)) => {
-            let subsonic_playlist = Playlist::from(playlist);
+            let subsonic_playlist = playlist_with_songs(playlist, &config, &current_user);
             let resp = SubsonicResponse::new_ok(SubsonicResponseBody::Playlist(subsonic_playlist));
             send_response(resp, &params.f)
         }
@@ -83,9 +134,10 @@ pub struct CreatePlaylistParams {
 #[handler]
 pub async fn create_playlist(
     service: Data<&Arc<Service>>,
+    config: Data<&Arc<Config>>,
     current_user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<CreatePlaylistParams>,
+    query: Params<CreatePlaylistParams>,
 ) -> impl IntoResponse {
     let username = &current_user.username;
     let song_ids = query.song_id.clone();
@@ -98,16 +150,13 @@ pub async fn create_playlist(
             song_indices_to_remove: vec![],
             comment: None,
             public: None,
+            rules: None,
         };
         if let Err(e) = service
             .replace_playlist_songs(pid, username, song_ids, opts)
             .await
         {
-            log::error!("Failed to update playlist: {}", e);
-            return send_response(
-                SubsonicResponse::new_error(0, format!("Failed to update playlist: {}", e)),
-                &params.f,
-            );
+            return change_error("update", e, &params);
         }
         pid
     } else {
@@ -138,8 +187,9 @@ pub async fn create_playlist(
     // Return the created/updated playlist per Subsonic API 1.14.0+
     match service.get_playlist(playlist_id, username).await {
         Ok(Some(playlist)) => {
-            let resp =
-                SubsonicResponse::new_ok(SubsonicResponseBody::Playlist(Playlist::from(playlist)));
+            let resp = SubsonicResponse::new_ok(SubsonicResponseBody::Playlist(
+                playlist_with_songs(playlist, &config, &current_user),
+            ));
             send_response(resp, &params.f)
         }
         Ok(None) => {
@@ -176,7 +226,7 @@ pub async fn update_playlist(
     service: Data<&Arc<Service>>,
     current_user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<UpdatePlaylistParams>,
+    query: Params<UpdatePlaylistParams>,
 ) -> impl IntoResponse {
     let username = &current_user.username;
     let opts = UpdatePlaylistOptions {
@@ -185,6 +235,7 @@ pub async fn update_playlist(
         public: query.public,
         song_ids_to_add: query.song_id_to_add.clone(),
         song_indices_to_remove: query.song_index_to_remove.clone(),
+        rules: None,
     };
 
     match service
@@ -195,11 +246,7 @@ pub async fn update_playlist(
             let resp = SubsonicResponse::new_ok(SubsonicResponseBody::None);
             send_response(resp, &params.f)
         }
-        Err(e) => {
-            log::error!("Failed to update playlist: {}", e);
-            let resp = SubsonicResponse::new_error(0, format!("Failed to update playlist: {}", e));
-            send_response(resp, &params.f)
-        }
+        Err(e) => change_error("update", e, &params),
     }
 }
 
@@ -213,7 +260,7 @@ pub async fn delete_playlist(
     service: Data<&Arc<Service>>,
     current_user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<DeletePlaylistParams>,
+    query: Params<DeletePlaylistParams>,
 ) -> impl IntoResponse {
     let username = &current_user.username;
     match service.delete_playlist(query.id, username).await {
@@ -221,10 +268,6 @@ pub async fn delete_playlist(
             let resp = SubsonicResponse::new_ok(SubsonicResponseBody::None);
             send_response(resp, &params.f)
         }
-        Err(e) => {
-            log::error!("Failed to delete playlist: {}", e);
-            let resp = SubsonicResponse::new_error(0, "Failed to delete playlist".to_string());
-            send_response(resp, &params.f)
-        }
+        Err(e) => change_error("delete", e, &params),
     }
 }
@@ -0,0 +1,201 @@
+use crate::models::podcast_channel;
+use crate::service::podcast::PodcastService;
+use crate::subsonic::{
+    common::{db_error, not_found, ok, send_response, Params, SubsonicParams},
+    models::{
+        NewestPodcasts, PodcastChannel, PodcastEpisode, Podcasts, SubsonicResponse,
+        SubsonicResponseBody,
+    },
+};
+use poem::{handler, web::Data, IntoResponse};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPodcastsQuery {
+    pub include_episodes: Option<bool>,
+    pub id: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NewestPodcastsQuery {
+    pub count: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct UrlQuery {
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+pub struct IdQuery {
+    pub id: String,
+}
+
+/// Accepts both the prefixed ids we hand out (`pc-1`, `pe-2`) and bare numbers.
+fn parse_id(id: &str, prefix: &str) -> Option<i32> {
+    id.strip_prefix(prefix).unwrap_or(id).parse().ok()
+}
+
+#[handler]
+pub async fn get_podcasts(
+    podcasts: Data<&Arc<PodcastService>>,
+    params: Data<&SubsonicParams>,
+    query: Params<GetPodcastsQuery>,
+) -> impl IntoResponse {
+    let channels = match &query.id {
+        Some(id) => {
+            let channel = match parse_id(id, "pc-") {
+                Some(id) => podcasts.get_channel(id).await,
+                None => Ok(None),
+            };
+            match channel {
+                Ok(Some(c)) => vec![c],
+                Ok(None) => return not_found("Podcast channel", &params),
+                Err(e) => return db_error(e, &params),
+            }
+        }
+        None => match podcasts.get_channels().await {
+            Ok(c) => c,
+            Err(e) => return db_error(e, &params),
+        },
+    };
+
+    let mut channel = Vec::with_capacity(channels.len());
+    for c in channels {
+        let mut item = PodcastChannel::from(&c);
+        if query.include_episodes.unwrap_or(true) {
+            match podcasts.get_episodes(c.id).await {
+                Ok(episodes) => {
+                    item.episode = episodes
+                        .into_iter()
+                        .map(|e| PodcastEpisode::new(e, &c))
+                        .collect()
+                }
+                Err(e) => return db_error(e, &params),
+            }
+        }
+        channel.push(item);
+    }
+
+    send_response(
+        SubsonicResponse::new_ok(SubsonicResponseBody::Podcasts(Podcasts { channel })),
+        &params.f,
+    )
+}
+
+#[handler]
+pub async fn get_newest_podcasts(
+    podcasts: Data<&Arc<PodcastService>>,
+    params: Data<&SubsonicParams>,
+    query: Params<NewestPodcastsQuery>,
+) -> impl IntoResponse {
+    let episodes = match podcasts
+        .get_newest_episodes(query.count.unwrap_or(20))
+        .await
+    {
+        Ok(e) => e,
+        Err(e) => return db_error(e, &params),
+    };
+    let channels: HashMap<i32, podcast_channel::Model> = match podcasts.get_channels().await {
+        Ok(c) => c.into_iter().map(|c| (c.id, c)).collect(),
+        Err(e) => return db_error(e, &params),
+    };
+
+    let episode = episodes
+        .into_iter()
+        .filter_map(|e| {
+            let channel = channels.get(&e.channel_id)?;
+            Some(PodcastEpisode::new(e, channel))
+        })
+        .collect();
+    send_response(
+        SubsonicResponse::new_ok(SubsonicResponseBody::NewestPodcasts(NewestPodcasts {
+            episode,
+        })),
+        &params.f,
+    )
+}
+
+#[handler]
+pub async fn refresh_podcasts(
+    podcasts: Data<&Arc<PodcastService>>,
+    params: Data<&SubsonicParams>,
+) -> impl IntoResponse {
+    podcasts.queue_refresh(None);
+    ok(&params)
+}
+
+#[handler]
+pub async fn create_podcast_channel(
+    podcasts: Data<&Arc<PodcastService>>,
+    params: Data<&SubsonicParams>,
+    query: Params<UrlQuery>,
+) -> impl IntoResponse {
+    match podcasts.create_channel(&query.url).await {
+        Ok(channel) => {
+            podcasts.queue_refresh(Some(channel.id));
+            ok(&params)
+        }
+        Err(e) => {
+            log::warn!("Failed to add podcast channel {}: {}", query.url, e);
+            send_response(
+                SubsonicResponse::new_error(0, format!("Failed to add podcast channel: {}", e)),
+                &params.f,
+            )
+        }
+    }
+}
+
+#[handler]
+pub async fn delete_podcast_channel(
+    podcasts: Data<&Arc<PodcastService>>,
+    params: Data<&SubsonicParams>,
+    query: Params<IdQuery>,
+) -> impl IntoResponse {
+    let Some(id) = parse_id(&query.id, "pc-") else {
+        return not_found("Podcast channel", &params);
+    };
+    match podcasts.delete_channel(id).await {
+        Ok(true) => ok(&params),
+        Ok(false) => not_found("Podcast channel", &params),
+        Err(e) => db_error(e, &params),
+    }
+}
+
+#[handler]
+pub async fn download_podcast_episode(
+    podcasts: Data<&Arc<PodcastService>>,
+    params: Data<&SubsonicParams>,
+    query: Params<IdQuery>,
+) -> impl IntoResponse {
+    let Some(id) = parse_id(&query.id, "pe-") else {
+        return not_found("Podcast episode", &params);
+    };
+    match podcasts.get_episode(id).await {
+        Ok(Some(_)) => {
+            podcasts.queue_download(id);
+            ok(&params)
+        }
+        Ok(None) => not_found("Podcast episode", &params),
+        Err(e) => db_error(e, &params),
+    }
+}
+
+#[handler]
+pub async fn delete_podcast_episode(
+    podcasts: Data<&Arc<PodcastService>>,
+    params: Data<&SubsonicParams>,
+    query: Params<IdQuery>,
+) -> impl IntoResponse {
+    let Some(id) = parse_id(&query.id, "pe-") else {
+        return not_found("Podcast episode", &params);
+    };
+    match podcasts.delete_episode(id).await {
+        Ok(true) => ok(&params),
+        Ok(false) => not_found("Podcast episode", &params),
+        Err(e) => db_error(e, &params),
+    }
+}
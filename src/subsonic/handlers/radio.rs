@@ -0,0 +1,108 @@
+use crate::service::radio::StationInput;
+use crate::service::Service;
+use crate::subsonic::{
+    common::{db_error, not_found, ok, send_response, Params, SubsonicParams},
+    models::{InternetRadioStations, SubsonicResponse, SubsonicResponseBody},
+};
+use poem::{handler, web::Data, IntoResponse, Response};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationQuery {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub stream_url: Option<String>,
+    pub homepage_url: Option<String>,
+}
+
+impl StationQuery {
+    fn input(&self) -> StationInput {
+        StationInput {
+            name: self.name.clone(),
+            stream_url: self.stream_url.clone(),
+            home_page_url: self.homepage_url.clone(),
+        }
+    }
+}
+
+fn invalid(message: String, params: &SubsonicParams) -> Response {
+    send_response(SubsonicResponse::new_error(10, message), &params.f)
+}
+
+#[handler]
+pub async fn get_internet_radio_stations(
+    service: Data<&Arc<Service>>,
+    params: Data<&SubsonicParams>,
+) -> impl IntoResponse {
+    match service.get_radio_stations().await {
+        Ok(stations) => send_response(
+            SubsonicResponse::new_ok(SubsonicResponseBody::InternetRadioStations(
+                InternetRadioStations {
+                    internet_radio_station: stations.into_iter().map(Into::into).collect(),
+                },
+            )),
+            &params.f,
+        ),
+        Err(e) => db_error(e, &params),
+    }
+}
+
+#[handler]
+pub async fn create_internet_radio_station(
+    service: Data<&Arc<Service>>,
+    params: Data<&SubsonicParams>,
+    query: Params<StationQuery>,
+) -> impl IntoResponse {
+    let input = match query.input().validate(true) {
+        Ok(i) => i,
+        Err(msg) => return invalid(msg, &params),
+    };
+    match service.create_radio_station(input).await {
+        Ok(_) => ok(&params),
+        Err(e) => db_error(e, &params),
+    }
+}
+
+#[handler]
+pub async fn update_internet_radio_station(
+    service: Data<&Arc<Service>>,
+    params: Data<&SubsonicParams>,
+    query: Params<StationQuery>,
+) -> impl IntoResponse {
+    let Some(id) = query.id.as_deref() else {
+        return invalid("Missing required parameter: id".into(), &params);
+    };
+    let Ok(id) = id.parse() else {
+        return not_found("Internet radio station", &params);
+    };
+    let input = match query.input().validate(false) {
+        Ok(i) => i,
+        Err(msg) => return invalid(msg, &params),
+    };
+    match service.update_radio_station(id, input).await {
+        Ok(Some(_)) => ok(&params),
+        Ok(None) => not_found("Internet radio station", &params),
+        Err(e) => db_error(e, &params),
+    }
+}
+
+#[handler]
+pub async fn delete_internet_radio_station(
+    service: Data<&Arc<Service>>,
+    params: Data<&SubsonicParams>,
+    query: Params<StationQuery>,
+) -> impl IntoResponse {
+    let Some(id) = query.id.as_deref() else {
+        return invalid("Missing required parameter: id".into(), &params);
+    };
+    let Ok(id) = id.parse() else {
+        return not_found("Internet radio station", &params);
+    };
+    match service.delete_radio_station(id).await {
+        Ok(true) => ok(&params),
+        Ok(false) => not_found("Internet radio station", &params),
+        Err(e) => db_error(e, &params),
+    }
+}
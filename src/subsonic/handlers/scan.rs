@@ -1,11 +1,7 @@
 use crate::scanner::Scanner;
-use crate::subsonic::common::{deserialize_optional_bool, send_response, SubsonicParams};
+use crate::subsonic::common::{deserialize_optional_bool, send_response, Params, SubsonicParams};
 use crate::subsonic::models::{ScanStatus, SubsonicResponse, SubsonicResponseBody};
-use poem::{
-    handler,
-    web::{Data, Query},
-    IntoResponse,
-};
+use poem::{handler, web::Data, IntoResponse};
 use serde::Deserialize;
 use std::sync::Arc;
 
@@ -16,20 +12,25 @@ pub struct StartScanQuery {
     pub full_scan: Option<bool>,
 }
 
+fn scan_status(scanner: &Scanner, scanning: bool) -> ScanStatus {
+    ScanStatus {
+        scanning,
+        count: Some(scanner.scan_count()),
+        total: Some(scanner.total_count()),
+        last_scan: scanner.last_scan_at(),
+        next_scan: scanner.next_scan_at(),
+    }
+}
+
 #[handler]
 pub async fn get_scan_status(
     scanner: Data<&Arc<Scanner>>,
     params: Data<&SubsonicParams>,
 ) -> impl IntoResponse {
-    let scanning = scanner.is_scanning();
-    let count = scanner.scan_count();
-    let total = scanner.total_count();
-
-    let resp = SubsonicResponse::new_ok(SubsonicResponseBody::ScanStatus(ScanStatus {
-        scanning,
-        count: Some(count),
-        total: Some(total),
-    }));
+    let resp = SubsonicResponse::new_ok(SubsonicResponseBody::ScanStatus(scan_status(
+        &scanner,
+        scanner.is_scanning(),
+    )));
 
     send_response(resp, &params.f)
 }
@@ -38,7 +39,7 @@ pub async fn get_scan_status(
 pub async fn start_scan(
     scanner: Data<&Arc<Scanner>>,
     params: Data<&SubsonicParams>,
-    query: Query<StartScanQuery>,
+    query: Params<StartScanQuery>,
 ) -> impl IntoResponse {
     let incremental = !query.full_scan.unwrap_or(false);
 
@@ -49,14 +50,9 @@ pub async fn start_scan(
         }
     });
 
-    let count = scanner.scan_count();
-    let total = scanner.total_count();
-
-    let resp = SubsonicResponse::new_ok(SubsonicResponseBody::ScanStatus(ScanStatus {
-        scanning: true,
-        count: Some(count),
-        total: Some(total),
-    }));
+    let resp = SubsonicResponse::new_ok(SubsonicResponseBody::ScanStatus(scan_status(
+        &scanner, true,
+    )));
 
     send_response(resp, &params.f)
 }
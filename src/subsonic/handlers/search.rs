@@ -1,18 +1,15 @@
+use crate::config::Config;
 use crate::models::user;
 use crate::service::search::SearchOptions;
 use crate::service::Service;
 use crate::subsonic::{
-    common::{send_response, SubsonicParams},
+    common::{send_response, Params, SubsonicParams},
     models::{
         AlbumID3, Artist, ArtistID3, Child, SearchResult, SearchResult2, SearchResult3,
         SubsonicResponse, SubsonicResponseBody,
     },
 };
-use poem::{
-    handler,
-    web::{Data, Query},
-    IntoResponse,
-};
+use poem::{handler, web::Data, IntoResponse};
 use serde::Deserialize;
 use std::sync::Arc;
 
@@ -53,20 +50,28 @@ pub struct SearchQuery {
 
 #[handler]
 pub async fn search3(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<Search23Query>,
+    query: Params<Search23Query>,
 ) -> impl IntoResponse {
     let opts = SearchOptions::from(query.0);
 
     match service.search(opts, &user.username).await {
-        Ok((artists, albums, songs)) => {
+        Ok(results) => {
             let resp =
                 SubsonicResponse::new_ok(SubsonicResponseBody::SearchResult3(SearchResult3 {
-                    artist: artists.into_iter().map(ArtistID3::from).collect(),
-                    album: albums.into_iter().map(AlbumID3::from).collect(),
-                    song: songs.into_iter().map(Child::from).collect(),
+                    artist_total: Some(results.artist_total),
+                    album_total: Some(results.album_total),
+                    song_total: Some(results.song_total),
+                    artist: results.artists.into_iter().map(ArtistID3::from).collect(),
+                    album: results.albums.into_iter().map(AlbumID3::from).collect(),
+                    song: results
+                        .songs
+                        .into_iter()
+                        .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+                        .collect(),
                 }));
             send_response(resp, &params.f)
         }
@@ -82,24 +87,30 @@ pub async fn search3(
 
 #[handler]
 pub async fn search2(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<Search23Query>,
+    query: Params<Search23Query>,
 ) -> impl IntoResponse {
     let opts = SearchOptions::from(query.0);
 
     match service.search(opts, &user.username).await {
-        Ok((artists, albums, songs)) => {
+        Ok(results) => {
             // Search2 uses Artist and Child (for albums)
             let resp =
                 SubsonicResponse::new_ok(SubsonicResponseBody::SearchResult2(SearchResult2 {
-                    artist: artists.into_iter().map(Artist::from).collect(),
-                    album: albums
+                    artist: results.artists.into_iter().map(Artist::from).collect(),
+                    album: results
+                        .albums
                         .into_iter()
                         .map(|a| Child::from_album_stats(a))
                         .collect(),
-                    song: songs.into_iter().map(Child::from).collect(),
+                    song: results
+                        .songs
+                        .into_iter()
+                        .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+                        .collect(),
                 }));
             send_response(resp, &params.f)
         }
@@ -115,10 +126,11 @@ pub async fn search2(
 
 #[handler]
 pub async fn search(
+    config: Data<&Arc<Config>>,
     service: Data<&Arc<Service>>,
     user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<SearchQuery>,
+    query: Params<SearchQuery>,
 ) -> impl IntoResponse {
     let q = &query.query;
     let count = query.count.unwrap_or(20);
@@ -129,7 +141,10 @@ pub async fn search(
             let resp = SubsonicResponse::new_ok(SubsonicResponseBody::SearchResult(SearchResult {
                 offset,
                 total_hits,
-                match_vec: songs.into_iter().map(Child::from).collect(),
+                match_vec: songs
+                    .into_iter()
+                    .map(|s| Child::from(s).transcoded_for(&config.transcode, &user))
+                    .collect(),
             }));
             send_response(resp, &params.f)
         }
@@ -0,0 +1,231 @@
+use crate::config::Config;
+use crate::models::{share, user};
+use crate::service::Service;
+use crate::subsonic::{
+    common::{
+        db_error, not_found, ok, public_base_url, send_response, Params, RequestParams,
+        SubsonicParams,
+    },
+    models::{Share, Shares, SubsonicResponse, SubsonicResponseBody},
+};
+use chrono::{DateTime, Utc};
+use poem::{handler, web::Data, IntoResponse, Request, Response};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// createShare parameters. `id` may be repeated to share several items at once.
+#[derive(Debug, Default, PartialEq)]
+pub struct CreateShareQuery {
+    pub id: Vec<String>,
+    pub description: Option<String>,
+    /// Milliseconds since the epoch.
+    pub expires: Option<i64>,
+}
+
+impl CreateShareQuery {
+    pub fn parse(query: &str) -> Self {
+        let pairs = serde_urlencoded::from_str::<Vec<(String, String)>>(query).unwrap_or_default();
+        let mut parsed = Self::default();
+        for (key, value) in pairs {
+            match key.as_str() {
+                "id" => parsed.id.push(value),
+                "description" => parsed.description = Some(value),
+                "expires" => parsed.expires = value.parse::<i64>().ok(),
+                _ => {}
+            }
+        }
+        parsed
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdateShareQuery {
+    pub id: String,
+    pub description: Option<String>,
+    /// Milliseconds since the epoch; `0` removes the expiry.
+    pub expires: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteShareQuery {
+    pub id: String,
+}
+
+fn expiry_from_millis(ms: i64) -> Option<DateTime<Utc>> {
+    (ms > 0)
+        .then(|| DateTime::from_timestamp_millis(ms))
+        .flatten()
+}
+
+async fn to_share(
+    service: &Service,
+    base_url: &str,
+    share: share::Model,
+) -> Result<Share, sea_orm::DbErr> {
+    let entry = service
+        .get_share_songs(&share)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    Ok(Share {
+        id: share.id.to_string(),
+        url: format!("{}/share/{}", base_url, share.token),
+        description: share.description,
+        username: share.username,
+        created: share.created_at,
+        expires: share.expires_at,
+        last_visited: share.last_visited_at,
+        visit_count: share.visit_count,
+        entry,
+    })
+}
+
+async fn shares_response(
+    service: &Service,
+    base_url: &str,
+    shares: Vec<share::Model>,
+    params: &SubsonicParams,
+) -> Response {
+    let mut share = Vec::with_capacity(shares.len());
+    for s in shares {
+        match to_share(service, base_url, s).await {
+            Ok(s) => share.push(s),
+            Err(e) => return db_error(e, params),
+        }
+    }
+    send_response(
+        SubsonicResponse::new_ok(SubsonicResponseBody::Shares(Shares { share })),
+        &params.f,
+    )
+}
+
+/// Loads a share the current user may modify, or the error response to send.
+async fn owned_share(
+    service: &Service,
+    user: &user::Model,
+    id: &str,
+    params: &SubsonicParams,
+) -> Result<share::Model, Response> {
+    let share = match id.parse::<i32>() {
+        Ok(id) => service.get_share(id).await,
+        Err(_) => Ok(None),
+    };
+    match share {
+        Ok(Some(s)) if s.username == user.username || user.admin_role => Ok(s),
+        Ok(Some(_)) => Err(send_response(
+            SubsonicResponse::new_error(50, "Not allowed to modify this share".into()),
+            &params.f,
+        )),
+        Ok(None) => Err(not_found("Share", params)),
+        Err(e) => Err(db_error(e, params)),
+    }
+}
+
+#[handler]
+pub async fn get_shares(
+    req: &Request,
+    service: Data<&Arc<Service>>,
+    config: Data<&Arc<Config>>,
+    user: Data<&Arc<user::Model>>,
+    params: Data<&SubsonicParams>,
+) -> impl IntoResponse {
+    match service.get_shares(&user.username).await {
+        Ok(shares) => {
+            shares_response(&service, &public_base_url(&config, req), shares, &params).await
+        }
+        Err(e) => db_error(e, &params),
+    }
+}
+
+#[handler]
+pub async fn create_share(
+    req: &Request,
+    service: Data<&Arc<Service>>,
+    config: Data<&Arc<Config>>,
+    user: Data<&Arc<user::Model>>,
+    params: Data<&SubsonicParams>,
+) -> impl IntoResponse {
+    let query = CreateShareQuery::parse(RequestParams::of(req));
+    if query.id.is_empty() {
+        return send_response(
+            SubsonicResponse::new_error(10, "Missing required parameter: id".into()),
+            &params.f,
+        );
+    }
+
+    let mut entries = Vec::with_capacity(query.id.len());
+    for id in &query.id {
+        match service.resolve_share_entry(&user.username, id).await {
+            Ok(Some(kind)) => entries.push((kind, id.clone())),
+            Ok(None) => {
+                return send_response(
+                    SubsonicResponse::new_error(70, format!("Item not found: {}", id)),
+                    &params.f,
+                )
+            }
+            Err(e) => return db_error(e, &params),
+        }
+    }
+
+    let expires_at = query.expires.and_then(expiry_from_millis);
+    match service
+        .create_share(&user.username, entries, query.description, expires_at)
+        .await
+    {
+        Ok(share) => {
+            shares_response(
+                &service,
+                &public_base_url(&config, req),
+                vec![share],
+                &params,
+            )
+            .await
+        }
+        Err(e) => db_error(e, &params),
+    }
+}
+
+#[handler]
+pub async fn update_share(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    params: Data<&SubsonicParams>,
+    query: Params<UpdateShareQuery>,
+) -> impl IntoResponse {
+    let share = match owned_share(&service, &user, &query.id, &params).await {
+        Ok(s) => s,
+        Err(r) => return r,
+    };
+
+    let expires_at = query.expires.map(expiry_from_millis);
+    match service
+        .update_share(share.id, query.description.clone(), expires_at)
+        .await
+    {
+        Ok(()) => ok(&params),
+        Err(e) => db_error(e, &params),
+    }
+}
+
+#[handler]
+pub async fn delete_share(
+    service: Data<&Arc<Service>>,
+    user: Data<&Arc<user::Model>>,
+    params: Data<&SubsonicParams>,
+    query: Params<DeleteShareQuery>,
+) -> impl IntoResponse {
+    let share = match owned_share(&service, &user, &query.id, &params).await {
+        Ok(s) => s,
+        Err(r) => return r,
+    };
+
+    match service.revoke_share(share.id).await {
+        Ok(()) => ok(&params),
+        Err(e) => db_error(e, &params),
+    }
+}
+
+#[cfg(test)]
+#[path = "shares_tests.rs"]
+mod tests;
@@ -0,0 +1,16 @@
+use super::*;
+
+#[test]
+fn create_share_query_collects_repeated_ids() {
+    let query =
+        CreateShareQuery::parse("u=x&id=a&id=b&description=Road%20trip&expires=1700000000000");
+    assert_eq!(query.id, vec!["a", "b"]);
+    assert_eq!(query.description.as_deref(), Some("Road trip"));
+    assert_eq!(query.expires, Some(1_700_000_000_000));
+}
+
+#[test]
+fn zero_expiry_means_never() {
+    assert_eq!(expiry_from_millis(0), None);
+    assert!(expiry_from_millis(1_700_000_000_000).is_some());
+}
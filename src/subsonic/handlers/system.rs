@@ -1,8 +1,11 @@
+use crate::models::user;
 use crate::subsonic::common::{send_response, SubsonicParams};
 use crate::subsonic::models::{
     License, OpenSubsonicExtension, OpenSubsonicExtensions, SubsonicResponse, SubsonicResponseBody,
+    TokenInfo,
 };
 use poem::{handler, web::Data, IntoResponse};
+use std::sync::Arc;
 
 #[handler]
 pub async fn ping(params: Data<&SubsonicParams>) -> impl IntoResponse {
@@ -30,12 +33,34 @@ pub async fn get_license(params: Data<&SubsonicParams>) -> impl IntoResponse {
 pub async fn get_open_subsonic_extensions(params: Data<&SubsonicParams>) -> impl IntoResponse {
     let resp = SubsonicResponse::new_ok(SubsonicResponseBody::OpenSubsonicExtensions(
         OpenSubsonicExtensions {
-            extension: vec![OpenSubsonicExtension {
-                name: "songLyrics".to_string(),
-                versions: vec![1],
-            }],
+            extension: vec![
+                OpenSubsonicExtension {
+                    name: "songLyrics".to_string(),
+                    versions: vec![1],
+                },
+                OpenSubsonicExtension {
+                    name: "formPost".to_string(),
+                    versions: vec![1],
+                },
+                OpenSubsonicExtension {
+                    name: "apiKeyAuthentication".to_string(),
+                    versions: vec![1],
+                },
+            ],
         },
     ));
 
     send_response(resp, &params.f)
 }
+
+#[handler]
+pub async fn token_info(
+    user: Data<&Arc<user::Model>>,
+    params: Data<&SubsonicParams>,
+) -> impl IntoResponse {
+    let resp = SubsonicResponse::new_ok(SubsonicResponseBody::TokenInfo(TokenInfo {
+        username: user.username.clone(),
+    }));
+
+    send_response(resp, &params.f)
+}
@@ -1,14 +1,15 @@
 use crate::config::Config;
 use crate::crypto::encrypt;
-use crate::models::{music_folder, user};
-use crate::subsonic::common::{deserialize_optional_bool, send_response, SubsonicParams};
+use crate::models::{music_folder, user, user_music_folder};
+use crate::subsonic::common::{
+    deserialize_optional_bool, deserialize_vec, send_response, Params, SubsonicParams,
+};
 use crate::subsonic::models::{SubsonicResponse, SubsonicResponseBody, User, Users};
-use poem::{
-    handler,
-    web::{Data, Query},
-    IntoResponse,
+use poem::{handler, web::Data, IntoResponse};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+    IntoActiveModel, QueryFilter, QuerySelect, Set, TransactionTrait,
 };
-use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Set};
 use serde::Deserialize;
 use std::sync::Arc;
 
@@ -25,6 +26,8 @@ pub struct CreateUserQuery {
     pub email: Option<String>,
     #[serde(default, deserialize_with = "deserialize_optional_bool")]
     pub admin_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_vec")]
+    pub music_folder_id: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -35,6 +38,65 @@ pub struct UpdateUserQuery {
     pub email: Option<String>,
     #[serde(default, deserialize_with = "deserialize_optional_bool")]
     pub admin_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_vec")]
+    pub music_folder_id: Vec<String>,
+}
+
+/// Role flags and limits shared by `createUser` and `updateUser`.
+/// Fields left unset keep their current (or default) value.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSettingsQuery {
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub settings_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub stream_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub jukebox_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub download_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub upload_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub playlist_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub cover_art_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub comment_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub podcast_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub share_role: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub video_conversion_role: Option<bool>,
+    pub max_bit_rate: Option<i32>,
+}
+
+impl UserSettingsQuery {
+    fn apply(&self, user: &mut user::ActiveModel) {
+        let roles = [
+            (self.settings_role, &mut user.settings_role),
+            (self.stream_role, &mut user.stream_role),
+            (self.jukebox_role, &mut user.jukebox_role),
+            (self.download_role, &mut user.download_role),
+            (self.upload_role, &mut user.upload_role),
+            (self.playlist_role, &mut user.playlist_role),
+            (self.cover_art_role, &mut user.cover_art_role),
+            (self.comment_role, &mut user.comment_role),
+            (self.podcast_role, &mut user.podcast_role),
+            (self.share_role, &mut user.share_role),
+            (self.video_conversion_role, &mut user.video_conversion_role),
+        ];
+        for (value, field) in roles {
+            if let Some(v) = value {
+                *field = Set(v);
+            }
+        }
+        if let Some(max_bit_rate) = self.max_bit_rate {
+            // 0 means no limit
+            user.max_bit_rate = Set((max_bit_rate > 0).then_some(max_bit_rate));
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -42,6 +104,57 @@ pub struct DeleteUserQuery {
     pub username: String,
 }
 
+fn parse_folder_ids(ids: &[String]) -> Result<Vec<i32>, std::num::ParseIntError> {
+    ids.iter().map(|id| id.trim().parse()).collect()
+}
+
+/// Replaces the music folders granted to `username`.
+/// An empty `folder_ids` grants every configured folder.
+async fn grant_music_folders<C: ConnectionTrait>(
+    db: &C,
+    username: &str,
+    folder_ids: Vec<i32>,
+) -> Result<(), sea_orm::DbErr> {
+    let folder_ids = if folder_ids.is_empty() {
+        music_folder::Entity::find()
+            .select_only()
+            .column(music_folder::Column::Id)
+            .into_tuple::<i32>()
+            .all(db)
+            .await?
+    } else {
+        folder_ids
+    };
+
+    user_music_folder::Entity::delete_many()
+        .filter(user_music_folder::Column::Username.eq(username))
+        .exec(db)
+        .await?;
+
+    if folder_ids.is_empty() {
+        return Ok(());
+    }
+
+    user_music_folder::Entity::insert_many(folder_ids.into_iter().map(|id| {
+        user_music_folder::ActiveModel {
+            username: Set(username.to_string()),
+            music_folder_id: Set(id),
+        }
+    }))
+    .on_conflict(
+        sea_orm::sea_query::OnConflict::columns([
+            user_music_folder::Column::Username,
+            user_music_folder::Column::MusicFolderId,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+
+    Ok(())
+}
+
 #[handler]
 pub async fn get_users(
     db: Data<&DatabaseConnection>,
@@ -91,7 +204,7 @@ pub async fn get_user(
     db: Data<&DatabaseConnection>,
     current_user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<GetUserQuery>,
+    query: Params<GetUserQuery>,
 ) -> impl IntoResponse {
     let username = &query.username;
 
@@ -141,7 +254,8 @@ pub async fn create_user(
     config: Data<&Arc<Config>>,
     current_user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<CreateUserQuery>,
+    query: Params<CreateUserQuery>,
+    settings: Params<UserSettingsQuery>,
 ) -> impl IntoResponse {
     if !current_user.admin_role {
         return send_response(
@@ -153,6 +267,16 @@ pub async fn create_user(
         );
     }
 
+    let folder_ids = match parse_folder_ids(&query.music_folder_id) {
+        Ok(ids) => ids,
+        Err(_) => {
+            return send_response(
+                SubsonicResponse::new_error(0, "Invalid musicFolderId".into()),
+                &params.f,
+            );
+        }
+    };
+
     let encrypted_password =
         match encrypt(&query.password, config.server.password_secret.as_bytes()) {
             Ok(p) => p,
@@ -165,12 +289,13 @@ pub async fn create_user(
             }
         };
 
-    let user = user::ActiveModel {
+    let mut user = user::ActiveModel {
         username: Set(query.username.clone()),
         password: Set(encrypted_password),
         email: Set(query.email.clone()),
         admin_role: Set(query.admin_role.unwrap_or(false)),
-        // "let the rest role always be true"
+        // roles not sent by the client default to enabled, except jukebox
+        // and video conversion
         settings_role: Set(true),
         download_role: Set(true),
         upload_role: Set(true),
@@ -183,10 +308,23 @@ pub async fn create_user(
         scrobbling_enabled: Set(true),
         created_at: Set(chrono::Utc::now()),
         updated_at: Set(chrono::Utc::now()),
+        jukebox_role: Set(false),
+        video_conversion_role: Set(false),
         ..Default::default()
     };
+    settings.apply(&mut user);
 
-    match user.insert(*db).await {
+    let result = db
+        .transaction::<_, (), sea_orm::DbErr>(|txn| {
+            let username = query.username.clone();
+            Box::pin(async move {
+                user.insert(txn).await?;
+                grant_music_folders(txn, &username, folder_ids).await
+            })
+        })
+        .await;
+
+    match result {
         Ok(_) => send_response(
             SubsonicResponse::new_ok(SubsonicResponseBody::None),
             &params.f,
@@ -207,7 +345,8 @@ pub async fn update_user(
     config: Data<&Arc<Config>>,
     current_user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<UpdateUserQuery>,
+    query: Params<UpdateUserQuery>,
+    settings: Params<UserSettingsQuery>,
 ) -> impl IntoResponse {
     if !current_user.admin_role {
         return send_response(
@@ -239,6 +378,16 @@ pub async fn update_user(
         }
     };
 
+    let folder_ids = match parse_folder_ids(&query.music_folder_id) {
+        Ok(ids) => ids,
+        Err(_) => {
+            return send_response(
+                SubsonicResponse::new_error(0, "Invalid musicFolderId".into()),
+                &params.f,
+            );
+        }
+    };
+
     let mut user_active = user.into_active_model();
 
     if let Some(password) = &query.password {
@@ -267,10 +416,25 @@ pub async fn update_user(
     if let Some(admin_role) = query.admin_role {
         user_active.admin_role = Set(admin_role);
     }
+    settings.apply(&mut user_active);
 
     user_active.updated_at = Set(chrono::Utc::now());
 
-    match user_active.update(*db).await {
+    let result = db
+        .transaction::<_, (), sea_orm::DbErr>(|txn| {
+            let username = query.username.clone();
+            Box::pin(async move {
+                user_active.update(txn).await?;
+                // Only touch the grants when the client sent musicFolderId
+                if !folder_ids.is_empty() {
+                    grant_music_folders(txn, &username, folder_ids).await?;
+                }
+                Ok(())
+            })
+        })
+        .await;
+
+    match result {
         Ok(_) => send_response(
             SubsonicResponse::new_ok(SubsonicResponseBody::None),
             &params.f,
@@ -290,7 +454,7 @@ pub async fn delete_user(
     db: Data<&DatabaseConnection>,
     current_user: Data<&Arc<user::Model>>,
     params: Data<&SubsonicParams>,
-    query: Query<DeleteUserQuery>,
+    query: Params<DeleteUserQuery>,
 ) -> impl IntoResponse {
     if !current_user.admin_role {
         return send_response(
@@ -1,8 +1,10 @@
 use crate::config::Config;
 use crate::models::user;
+use crate::service::api_keys;
 use crate::subsonic::auth::{verify_password, verify_token};
-use crate::subsonic::common::{send_response, SubsonicParams};
+use crate::subsonic::common::{send_response, RequestParams, SubsonicParams};
 use crate::subsonic::models::SubsonicResponse;
+use poem::http::Method;
 use poem::{Endpoint, IntoResponse, Middleware, Request, Response, Result};
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use std::sync::Arc;
@@ -11,6 +13,47 @@ pub struct SubsonicAuth;
 
 pub struct SubsonicParamsMiddleware;
 
+/// OpenSubsonic `formPost`: merges the parameters of an
+/// `application/x-www-form-urlencoded` POST body with the query string into
+/// [`RequestParams`], so the other middlewares and the handlers read them like
+/// GET parameters.
+pub struct FormPost;
+
+/// Subsonic role flags that gate individual endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Stream,
+    Download,
+    Playlist,
+    CoverArt,
+    Settings,
+    Share,
+    Comment,
+    Podcast,
+    Jukebox,
+    Admin,
+}
+
+impl Role {
+    pub fn granted(self, user: &user::Model) -> bool {
+        match self {
+            Role::Stream => user.stream_role,
+            Role::Download => user.download_role,
+            Role::Playlist => user.playlist_role,
+            Role::CoverArt => user.cover_art_role,
+            Role::Settings => user.settings_role,
+            Role::Share => user.share_role,
+            Role::Comment => user.comment_role,
+            Role::Podcast => user.podcast_role,
+            Role::Jukebox => user.jukebox_role,
+            Role::Admin => user.admin_role,
+        }
+    }
+}
+
+/// Rejects the request with Subsonic error 50 unless the authenticated user has `Role`.
+pub struct RequireRole(pub Role);
+
 impl<E: Endpoint> Middleware<E> for SubsonicAuth {
     type Output = SubsonicAuthEndpoint<E>;
 
@@ -27,6 +70,99 @@ impl<E: Endpoint> Middleware<E> for SubsonicParamsMiddleware {
     }
 }
 
+impl<E: Endpoint> Middleware<E> for FormPost {
+    type Output = FormPostEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        FormPostEndpoint { ep }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for RequireRole {
+    type Output = RequireRoleEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RequireRoleEndpoint { ep, role: self.0 }
+    }
+}
+
+pub struct RequireRoleEndpoint<E> {
+    ep: E,
+    role: Role,
+}
+
+impl<E: Endpoint> Endpoint for RequireRoleEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let user = req.data::<Arc<user::Model>>().ok_or_else(|| {
+            poem::Error::from_status(poem::http::StatusCode::INTERNAL_SERVER_ERROR)
+        })?;
+
+        if !self.role.granted(user) {
+            log::debug!(
+                "User '{}' lacks the {:?} role for {}",
+                user.username,
+                self.role,
+                req.uri().path()
+            );
+            let format = req.data::<SubsonicParams>().and_then(|p| p.f.clone());
+            let resp = SubsonicResponse::new_error(
+                50,
+                "User is not authorized for the given operation.".to_string(),
+            );
+            return Ok(send_response(resp, &format));
+        }
+
+        self.ep.call(req).await.map(IntoResponse::into_response)
+    }
+}
+
+pub struct FormPostEndpoint<E> {
+    ep: E,
+}
+
+fn is_form(content_type: Option<&str>) -> bool {
+    content_type
+        .and_then(|c| c.split(';').next())
+        .is_some_and(|c| {
+            c.trim()
+                .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+        })
+}
+
+/// Largest form body accepted, enough for play queues and playlists with tens
+/// of thousands of songs.
+const MAX_FORM_BODY: usize = 4 * 1024 * 1024;
+
+/// Appends the parameters of a form body to a query string, re-encoded so the
+/// result always parses as a query.
+pub fn merge_form_body(query: Option<&str>, body: &str) -> String {
+    let pairs =
+        serde_urlencoded::from_str::<Vec<(String, String)>>(body.trim()).unwrap_or_default();
+    let form = serde_urlencoded::to_string(pairs).unwrap_or_default();
+    match query.filter(|q| !q.is_empty()) {
+        Some(query) if !form.is_empty() => format!("{}&{}", query, form),
+        Some(query) => query.to_string(),
+        None => form,
+    }
+}
+
+impl<E: Endpoint> Endpoint for FormPostEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        if req.method() == Method::POST && is_form(req.content_type()) {
+            let body = req.take_body().into_bytes_limit(MAX_FORM_BODY).await?;
+            let body = String::from_utf8_lossy(&body);
+            let params = merge_form_body(req.uri().query(), &body);
+            req.set_data(RequestParams(params));
+        }
+
+        self.ep.call(req).await.map(IntoResponse::into_response)
+    }
+}
+
 pub struct SubsonicParamsEndpoint<E> {
     ep: E,
 }
@@ -35,9 +171,8 @@ impl<E: Endpoint> Endpoint for SubsonicParamsEndpoint<E> {
     type Output = Response;
 
     async fn call(&self, mut req: Request) -> Result<Self::Output> {
-        let params =
-            serde_urlencoded::from_str::<SubsonicParams>(req.uri().query().unwrap_or_default())
-                .unwrap_or_default();
+        let params = serde_urlencoded::from_str::<SubsonicParams>(RequestParams::of(&req))
+            .unwrap_or_default();
         req.set_data(params);
 
         self.ep.call(req).await.map(IntoResponse::into_response)
@@ -55,46 +190,66 @@ impl<E: Endpoint> Endpoint for SubsonicAuthEndpoint<E> {
         let query = req.data::<SubsonicParams>().ok_or_else(|| {
             poem::Error::from_status(poem::http::StatusCode::INTERNAL_SERVER_ERROR)
         })?;
-        let mut authenticated_user = None;
         let db = req.data::<DatabaseConnection>().ok_or_else(|| {
             poem::Error::from_status(poem::http::StatusCode::INTERNAL_SERVER_ERROR)
         })?;
         let config = req.data::<Arc<Config>>().ok_or_else(|| {
             poem::Error::from_status(poem::http::StatusCode::INTERNAL_SERVER_ERROR)
         })?;
-        let username = match &query.u {
-            Some(u) => u,
-            None => {
-                let resp = SubsonicResponse::new_error(10, "User not found".to_string());
+        let user = if let Some(api_key) = &query.api_key {
+            if query.u.is_some() || query.p.is_some() || query.t.is_some() || query.s.is_some() {
+                let resp = SubsonicResponse::new_error(
+                    43,
+                    "Multiple conflicting authentication mechanisms provided".to_string(),
+                );
                 return Ok(send_response(resp, &query.f));
             }
-        };
-
-        let user = user::Entity::find()
-            .filter(user::Column::Username.eq(username))
-            .one(db)
-            .await
-            .map_err(poem::error::InternalServerError)?;
-
-        if let Some(user) = user {
-            let secret_bytes = config.server.password_secret.as_bytes();
-            if let Some(password) = &query.p {
-                if verify_password(&user.password, password, secret_bytes) {
-                    authenticated_user = Some(user);
+            match api_keys::authenticate(db, api_key, query.c.as_deref())
+                .await
+                .map_err(poem::error::InternalServerError)?
+            {
+                Some(user) => user,
+                None => {
+                    let resp = SubsonicResponse::new_error(44, "Invalid API key".to_string());
+                    return Ok(send_response(resp, &query.f));
                 }
-            } else if let (Some(token), Some(salt)) = (&query.t, &query.s) {
-                if verify_token(&user.password, token, salt, secret_bytes) {
-                    authenticated_user = Some(user);
+            }
+        } else {
+            let username = match &query.u {
+                Some(u) => u,
+                None => {
+                    let resp = SubsonicResponse::new_error(10, "User not found".to_string());
+                    return Ok(send_response(resp, &query.f));
+                }
+            };
+
+            let user = user::Entity::find()
+                .filter(user::Column::Username.eq(username))
+                .one(db)
+                .await
+                .map_err(poem::error::InternalServerError)?;
+
+            let mut authenticated_user = None;
+            if let Some(user) = user {
+                let secret_bytes = config.server.password_secret.as_bytes();
+                if let Some(password) = &query.p {
+                    if verify_password(&user.password, password, secret_bytes) {
+                        authenticated_user = Some(user);
+                    }
+                } else if let (Some(token), Some(salt)) = (&query.t, &query.s) {
+                    if verify_token(&user.password, token, salt, secret_bytes) {
+                        authenticated_user = Some(user);
+                    }
                 }
             }
-        }
 
-        let user = match authenticated_user {
-            Some(u) => u,
-            None => {
-                let resp =
-                    SubsonicResponse::new_error(40, "Wrong username or password".to_string());
-                return Ok(send_response(resp, &query.f));
+            match authenticated_user {
+                Some(u) => u,
+                None => {
+                    let resp =
+                        SubsonicResponse::new_error(40, "Wrong username or password".to_string());
+                    return Ok(send_response(resp, &query.f));
+                }
             }
         };
 
@@ -111,3 +266,7 @@ impl<E: Endpoint> Endpoint for SubsonicAuthEndpoint<E> {
         self.ep.call(req).await.map(IntoResponse::into_response)
     }
 }
+
+#[cfg(test)]
+#[path = "middleware_tests.rs"]
+mod tests;
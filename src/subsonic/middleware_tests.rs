@@ -0,0 +1,78 @@
+use super::*;
+use poem::{handler, EndpointExt};
+
+#[handler]
+fn echo_query(req: &Request) -> String {
+    RequestParams::of(req).to_string()
+}
+
+async fn call(req: Request) -> String {
+    echo_query
+        .with(FormPost)
+        .call(req)
+        .await
+        .unwrap()
+        .into_body()
+        .into_string()
+        .await
+        .unwrap()
+}
+
+fn form_post(body: String) -> Request {
+    Request::builder()
+        .method(Method::POST)
+        .uri_str("/savePlayQueue")
+        .content_type("application/x-www-form-urlencoded")
+        .body(body)
+}
+
+#[test]
+fn merges_form_bodies_into_queries() {
+    assert_eq!(
+        merge_form_body(None, "u=alice&id=1&id=2\n"),
+        "u=alice&id=1&id=2"
+    );
+    assert_eq!(
+        merge_form_body(Some("f=json"), "name=Caf%C3%A9+Mix"),
+        "f=json&name=Caf%C3%A9+Mix"
+    );
+    assert_eq!(merge_form_body(Some("f=json"), ""), "f=json");
+    // Raw characters a client failed to encode still end up in a valid query.
+    assert_eq!(
+        merge_form_body(Some(""), "name=a b&c=ü"),
+        "name=a+b&c=%C3%BC"
+    );
+}
+
+#[tokio::test]
+async fn merges_form_posts_with_the_query() {
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri_str("/savePlayQueue?f=json")
+        .content_type("application/x-www-form-urlencoded; charset=UTF-8")
+        .body("u=alice&p=secret&id=1&id=2");
+    assert_eq!(call(req).await, "f=json&u=alice&p=secret&id=1&id=2");
+
+    // Other bodies and methods are left alone.
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri_str("/ping?f=json")
+        .content_type("application/json")
+        .body("u=alice");
+    assert_eq!(call(req).await, "f=json");
+    let req = Request::builder().uri_str("/ping?u=bob").finish();
+    assert_eq!(call(req).await, "u=bob");
+}
+
+#[tokio::test]
+async fn accepts_form_posts_longer_than_a_uri() {
+    let body = vec!["id=0123456789abcdef0123456789abcdef"; 5000].join("&");
+    assert_eq!(call(form_post(body.clone())).await, body);
+
+    let err = echo_query
+        .with(FormPost)
+        .call(form_post("a".repeat(MAX_FORM_BODY + 1)))
+        .await
+        .unwrap_err();
+    assert_eq!(err.status(), poem::http::StatusCode::PAYLOAD_TOO_LARGE);
+}
@@ -20,9 +20,10 @@ macro_rules! subsonic_routes {
 use crate::subsonic::{
     common::SubsonicParams,
     handlers::{
-        annotation, bookmarks, browsing, lists, media, playlists, scan, search, shared, system,
-        user,
+        annotation, bookmarks, browsing, jukebox, lists, media, playlists, podcasts, radio, scan,
+        search, shared, shares, system, user,
     },
+    middleware::{RequireRole, Role},
 };
 
 fn base_routes() -> Route {
@@ -35,6 +36,7 @@ fn base_routes() -> Route {
             "/getOpenSubsonicExtensions",
             system::get_open_subsonic_extensions
         ),
+        ("/tokenInfo", system::token_info),
         // browsing
         ("/getMusicFolders", browsing::get_music_folders),
         ("/getIndexes", browsing::get_indexes),
@@ -54,8 +56,11 @@ fn base_routes() -> Route {
         ("/getSimilarSongs2", browsing::get_similar_songs2),
         ("/getTopSongs", browsing::get_top_songs),
         // media retrieval
-        ("/stream", media::stream),
-        ("/download", media::download),
+        ("/stream", media::stream.with(RequireRole(Role::Stream))),
+        (
+            "/download",
+            media::download.with(RequireRole(Role::Download))
+        ),
         ("/hls.m3u8", shared::not_supported),
         ("/getCaptions", shared::not_supported),
         ("/getCoverArt", media::get_cover_art),
@@ -65,9 +70,18 @@ fn base_routes() -> Route {
         // playlists
         ("/getPlaylists", playlists::get_playlists),
         ("/getPlaylist", playlists::get_playlist),
-        ("/createPlaylist", playlists::create_playlist),
-        ("/updatePlaylist", playlists::update_playlist),
-        ("/deletePlaylist", playlists::delete_playlist),
+        (
+            "/createPlaylist",
+            playlists::create_playlist.with(RequireRole(Role::Playlist))
+        ),
+        (
+            "/updatePlaylist",
+            playlists::update_playlist.with(RequireRole(Role::Playlist))
+        ),
+        (
+            "/deletePlaylist",
+            playlists::delete_playlist.with(RequireRole(Role::Playlist))
+        ),
         // scan
         ("/getScanStatus", scan::get_scan_status),
         ("/startScan", scan::start_scan),
@@ -83,7 +97,10 @@ fn base_routes() -> Route {
         ("/createUser", user::create_user),
         ("/updateUser", user::update_user),
         ("/deleteUser", user::delete_user),
-        ("/changePassword", shared::not_supported),
+        (
+            "/changePassword",
+            shared::not_supported.with(RequireRole(Role::Settings))
+        ),
         // list
         ("/getAlbumList", lists::get_album_list),
         ("/getAlbumList2", lists::get_album_list2),
@@ -103,11 +120,75 @@ fn base_routes() -> Route {
         ("/deleteBookmark", bookmarks::delete_bookmark),
         ("/getPlayQueue", bookmarks::get_play_queue),
         ("/savePlayQueue", bookmarks::save_play_queue),
+        // sharing
+        ("/getShares", shares::get_shares),
+        (
+            "/createShare",
+            shares::create_share.with(RequireRole(Role::Share))
+        ),
+        (
+            "/updateShare",
+            shares::update_share.with(RequireRole(Role::Share))
+        ),
+        (
+            "/deleteShare",
+            shares::delete_share.with(RequireRole(Role::Share))
+        ),
+        // podcasts
+        ("/getPodcasts", podcasts::get_podcasts),
+        ("/getNewestPodcasts", podcasts::get_newest_podcasts),
+        (
+            "/refreshPodcasts",
+            podcasts::refresh_podcasts.with(RequireRole(Role::Podcast))
+        ),
+        (
+            "/createPodcastChannel",
+            podcasts::create_podcast_channel.with(RequireRole(Role::Podcast))
+        ),
+        (
+            "/deletePodcastChannel",
+            podcasts::delete_podcast_channel.with(RequireRole(Role::Podcast))
+        ),
+        (
+            "/downloadPodcastEpisode",
+            podcasts::download_podcast_episode.with(RequireRole(Role::Podcast))
+        ),
+        (
+            "/deletePodcastEpisode",
+            podcasts::delete_podcast_episode.with(RequireRole(Role::Podcast))
+        ),
+        // internet radio
+        (
+            "/getInternetRadioStations",
+            radio::get_internet_radio_stations
+        ),
+        (
+            "/createInternetRadioStation",
+            radio::create_internet_radio_station.with(RequireRole(Role::Admin))
+        ),
+        (
+            "/updateInternetRadioStation",
+            radio::update_internet_radio_station.with(RequireRole(Role::Admin))
+        ),
+        (
+            "/deleteInternetRadioStation",
+            radio::delete_internet_radio_station.with(RequireRole(Role::Admin))
+        ),
+        // jukebox
+        (
+            "/jukeboxControl",
+            jukebox::jukebox_control.with(RequireRole(Role::Jukebox))
+        ),
     )
 }
 
 pub fn create_api_route() -> Route {
-    Route::new().nest("/", base_routes().data(SubsonicParams::default()))
+    Route::new().nest(
+        "/",
+        base_routes()
+            .data(SubsonicParams::default())
+            .with(middleware::FormPost),
+    )
 }
 
 pub fn create_route() -> Route {
@@ -115,6 +196,7 @@ pub fn create_route() -> Route {
         "/",
         base_routes()
             .with(middleware::SubsonicAuth)
-            .with(middleware::SubsonicParamsMiddleware),
+            .with(middleware::SubsonicParamsMiddleware)
+            .with(middleware::FormPost),
     )
 }
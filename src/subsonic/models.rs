@@ -1,10 +1,12 @@
+use crate::config::TranscodeConfig;
 use crate::models::album::AlbumWithStats;
 use crate::models::artist::{ArtistIdName, ArtistWithStats};
-use crate::models::child::ChildWithMetadata;
+use crate::models::child::{self, ChildWithMetadata};
 use crate::models::genre::{self, GenreName, GenreWithStats};
 use crate::models::playlist::PlaylistWithStats;
 use crate::models::playlist_song::PlaylistWithSongs;
-use crate::models::{artist, user};
+use crate::models::{artist, internet_radio_station, podcast_channel, podcast_episode, user};
+use crate::service::{jukebox, transcode};
 use serde::{Deserialize, Serialize};
 
 fn join_artist_names(artists: &[ArtistIdName]) -> Option<String> {
@@ -56,6 +58,8 @@ pub enum SubsonicResponseBody {
     MusicFolders(MusicFolders),
     #[serde(rename = "openSubsonicExtensions")]
     OpenSubsonicExtensions(OpenSubsonicExtensions),
+    #[serde(rename = "tokenInfo")]
+    TokenInfo(TokenInfo),
     #[serde(rename = "indexes")]
     Indexes(Indexes),
     #[serde(rename = "directory")]
@@ -120,6 +124,18 @@ pub enum SubsonicResponseBody {
     Bookmarks(Bookmarks),
     #[serde(rename = "playQueue")]
     PlayQueue(PlayQueue),
+    #[serde(rename = "shares")]
+    Shares(Shares),
+    #[serde(rename = "podcasts")]
+    Podcasts(Podcasts),
+    #[serde(rename = "newestPodcasts")]
+    NewestPodcasts(NewestPodcasts),
+    #[serde(rename = "internetRadioStations")]
+    InternetRadioStations(InternetRadioStations),
+    #[serde(rename = "jukeboxStatus")]
+    JukeboxStatus(JukeboxStatus),
+    #[serde(rename = "jukeboxPlaylist")]
+    JukeboxPlaylist(JukeboxPlaylist),
     #[serde(other)]
     None,
 }
@@ -137,6 +153,13 @@ pub struct OpenSubsonicExtension {
     pub versions: Vec<i32>,
 }
 
+/// OpenSubsonic `tokenInfo`: who the API key of the request belongs to.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenInfo {
+    #[serde(rename = "@username")]
+    pub username: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SubsonicError {
     #[serde(rename = "@code")]
@@ -291,7 +314,7 @@ pub struct Directory {
     pub child: Vec<Child>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Child {
     #[serde(rename = "@id")]
@@ -373,11 +396,110 @@ pub struct Child {
     pub display_album_artist: Option<String>,
     #[serde(rename = "@bookmarkPosition", skip_serializing_if = "Option::is_none")]
     pub bookmark_position: Option<i64>,
+    /// OpenSubsonic name of `lastPlayed`.
+    #[serde(rename = "@played", skip_serializing_if = "Option::is_none")]
+    pub played: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "@bpm", skip_serializing_if = "Option::is_none")]
+    pub bpm: Option<i32>,
+    #[serde(rename = "@comment", skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(rename = "@sortName", skip_serializing_if = "Option::is_none")]
+    pub sort_name: Option<String>,
+    #[serde(rename = "@mediaType", skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(rename = "@musicBrainzId", skip_serializing_if = "Option::is_none")]
+    pub music_brainz_id: Option<String>,
+    #[serde(rename = "@channelCount", skip_serializing_if = "Option::is_none")]
+    pub channel_count: Option<i32>,
+    #[serde(rename = "@samplingRate", skip_serializing_if = "Option::is_none")]
+    pub sampling_rate: Option<i32>,
+    #[serde(rename = "@bitDepth", skip_serializing_if = "Option::is_none")]
+    pub bit_depth: Option<i32>,
+    /// Not part of OpenSubsonic; tells e.g. AAC from ALAC in `.m4a` files.
+    #[serde(rename = "@codec", skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+    #[serde(rename = "@explicitStatus", skip_serializing_if = "Option::is_none")]
+    pub explicit_status: Option<String>,
+    #[serde(rename = "replayGain", skip_serializing_if = "Option::is_none")]
+    pub replay_gain: Option<ReplayGain>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub contributors: Vec<Contributor>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub moods: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayGain {
+    #[serde(rename = "@trackGain", skip_serializing_if = "Option::is_none")]
+    pub track_gain: Option<f64>,
+    #[serde(rename = "@albumGain", skip_serializing_if = "Option::is_none")]
+    pub album_gain: Option<f64>,
+    #[serde(rename = "@trackPeak", skip_serializing_if = "Option::is_none")]
+    pub track_peak: Option<f64>,
+    #[serde(rename = "@albumPeak", skip_serializing_if = "Option::is_none")]
+    pub album_peak: Option<f64>,
+    #[serde(rename = "@baseGain", skip_serializing_if = "Option::is_none")]
+    pub base_gain: Option<f64>,
+}
+
+impl From<child::ReplayGain> for ReplayGain {
+    fn from(r: child::ReplayGain) -> Self {
+        Self {
+            track_gain: r.track_gain,
+            album_gain: r.album_gain,
+            track_peak: r.track_peak,
+            album_peak: r.album_peak,
+            base_gain: r.base_gain,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Contributor {
+    #[serde(rename = "@role")]
+    pub role: String,
+    #[serde(rename = "@subRole", skip_serializing_if = "Option::is_none")]
+    pub sub_role: Option<String>,
+    pub artist: ArtistIdName,
+}
+
+impl From<child::Contributor> for Contributor {
+    fn from(c: child::Contributor) -> Self {
+        Self {
+            role: c.role,
+            sub_role: c.sub_role,
+            artist: c.artist,
+        }
+    }
 }
 
 impl Child {
+    /// Fills `transcodedSuffix`/`transcodedContentType` for a song that
+    /// `user`'s streams are transcoded from.
+    pub fn transcoded_for(mut self, cfg: &TranscodeConfig, user: &user::Model) -> Self {
+        if self.is_dir {
+            return self;
+        }
+        let max_bit_rate = transcode::effective_max_bit_rate(None, user.max_bit_rate);
+        if let Some((suffix, content_type)) = transcode::stream_target(
+            cfg,
+            self.suffix.as_deref().unwrap_or_default(),
+            self.bit_rate.unwrap_or_default(),
+            max_bit_rate,
+        ) {
+            self.transcoded_suffix = Some(suffix);
+            self.transcoded_content_type = Some(content_type.to_string());
+        }
+        self
+    }
+
     pub fn from_album_stats(a: AlbumWithStats) -> Self {
-        let display_artist = join_artist_names(&a.artists);
+        let display_artist = a
+            .display_artist
+            .clone()
+            .or_else(|| join_artist_names(&a.artists));
         let genres = genre::split_genres(a.genre.as_ref());
         let name = a.name;
         let id = a.id;
@@ -418,6 +540,20 @@ impl Child {
             album_artists: a.artists,
             display_album_artist: display_artist,
             bookmark_position: None,
+            played: a.last_played,
+            bpm: None,
+            comment: None,
+            sort_name: a.sort_name,
+            media_type: Some("album".to_string()),
+            music_brainz_id: a.music_brainz_id,
+            channel_count: None,
+            sampling_rate: None,
+            bit_depth: None,
+            codec: None,
+            explicit_status: a.explicit_status,
+            replay_gain: None,
+            contributors: Vec::new(),
+            moods: a.moods,
         }
     }
 }
@@ -429,8 +565,9 @@ impl From<ChildWithMetadata> for Child {
         } else {
             Some(c.id.clone())
         };
-        let display_artist = join_artist_names(&c.artists);
+        let display_artist = c.display_artist.or_else(|| join_artist_names(&c.artists));
         let display_album_artist = join_artist_names(&c.album_artists);
+        let media_type = (!c.is_dir).then(|| "song".to_string());
         let title = c.title;
         Self {
             id: c.id,
@@ -447,8 +584,8 @@ impl From<ChildWithMetadata> for Child {
             size: Some(c.size),
             content_type: c.content_type,
             suffix: c.suffix,
-            transcoded_content_type: c.transcoded_content_type,
-            transcoded_suffix: c.transcoded_suffix,
+            transcoded_content_type: None,
+            transcoded_suffix: None,
             duration: Some(c.duration),
             bit_rate: Some(c.bit_rate),
             path: (!c.path.is_empty()).then_some(c.path),
@@ -457,6 +594,7 @@ impl From<ChildWithMetadata> for Child {
             average_rating: Some(c.average_rating),
             play_count: Some(c.play_count),
             last_played: c.last_played,
+            played: c.last_played,
             disc_number: Some(c.disc_number),
             created: c.created,
             starred: c.starred,
@@ -469,6 +607,19 @@ impl From<ChildWithMetadata> for Child {
             display_artist,
             display_album_artist,
             bookmark_position: None,
+            bpm: c.bpm,
+            comment: c.comment,
+            sort_name: c.sort_name,
+            media_type,
+            music_brainz_id: c.music_brainz_id,
+            channel_count: c.channel_count,
+            sampling_rate: c.sampling_rate,
+            bit_depth: c.bit_depth,
+            codec: c.codec,
+            explicit_status: c.explicit_status,
+            replay_gain: (!c.replay_gain.is_empty()).then(|| c.replay_gain.into()),
+            contributors: c.contributors.into_iter().map(Into::into).collect(),
+            moods: c.moods,
         }
     }
 }
@@ -588,14 +739,81 @@ pub struct AlbumID3 {
     pub year: Option<i32>,
     #[serde(rename = "@genre", skip_serializing_if = "Option::is_none")]
     pub genre: Option<String>,
+    #[serde(rename = "@played", skip_serializing_if = "Option::is_none")]
+    pub played: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "@sortName", skip_serializing_if = "Option::is_none")]
+    pub sort_name: Option<String>,
+    #[serde(rename = "@musicBrainzId", skip_serializing_if = "Option::is_none")]
+    pub music_brainz_id: Option<String>,
+    #[serde(rename = "@displayArtist", skip_serializing_if = "Option::is_none")]
+    pub display_artist: Option<String>,
+    #[serde(rename = "@isCompilation")]
+    pub is_compilation: bool,
+    #[serde(rename = "@explicitStatus", skip_serializing_if = "Option::is_none")]
+    pub explicit_status: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub artists: Vec<ArtistIdName>,
     #[serde(rename = "genres", skip_serializing_if = "Vec::is_empty")]
     pub genres: Vec<GenreName>,
+    #[serde(rename = "recordLabels", skip_serializing_if = "Vec::is_empty")]
+    pub record_labels: Vec<RecordLabel>,
+    #[serde(rename = "releaseTypes", skip_serializing_if = "Vec::is_empty")]
+    pub release_types: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub moods: Vec<String>,
+    #[serde(
+        rename = "originalReleaseDate",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub original_release_date: Option<ItemDate>,
+    #[serde(rename = "discTitles", skip_serializing_if = "Vec::is_empty")]
+    pub disc_titles: Vec<DiscTitle>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordLabel {
+    #[serde(rename = "@name")]
+    pub name: String,
+}
+
+/// A possibly partial date, as in "1969" or "1969-09".
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ItemDate {
+    #[serde(rename = "@year", skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+    #[serde(rename = "@month", skip_serializing_if = "Option::is_none")]
+    pub month: Option<u32>,
+    #[serde(rename = "@day", skip_serializing_if = "Option::is_none")]
+    pub day: Option<u32>,
+}
+
+impl ItemDate {
+    /// Parses "YYYY", "YYYY-MM" or "YYYY-MM-DD".
+    pub fn parse(date: &str) -> Option<Self> {
+        let mut parts = date.splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        Some(Self {
+            year: Some(year),
+            month: parts.next().and_then(|m| m.parse().ok()),
+            day: parts.next().and_then(|d| d.parse().ok()),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiscTitle {
+    #[serde(rename = "@disc")]
+    pub disc: i32,
+    #[serde(rename = "@title")]
+    pub title: String,
 }
 
 impl From<AlbumWithStats> for AlbumID3 {
     fn from(a: AlbumWithStats) -> Self {
+        let display_artist = a
+            .display_artist
+            .clone()
+            .or_else(|| join_artist_names(&a.artists));
         Self {
             id: a.id.clone(),
             name: a.name,
@@ -611,8 +829,27 @@ impl From<AlbumWithStats> for AlbumID3 {
             average_rating: Some(a.average_rating),
             year: Some(a.year),
             genre: a.genre.clone(),
+            played: a.last_played,
+            sort_name: a.sort_name,
+            music_brainz_id: a.music_brainz_id,
+            display_artist,
+            is_compilation: a.is_compilation,
+            explicit_status: a.explicit_status,
             artists: a.artists,
             genres: genre::split_genres(a.genre.as_ref()),
+            record_labels: a
+                .record_labels
+                .into_iter()
+                .map(|name| RecordLabel { name })
+                .collect(),
+            release_types: a.release_types,
+            moods: a.moods,
+            original_release_date: a.original_release_date.as_deref().and_then(ItemDate::parse),
+            disc_titles: a
+                .disc_titles
+                .into_iter()
+                .map(|(disc, title)| DiscTitle { disc, title })
+                .collect(),
         }
     }
 }
@@ -706,6 +943,229 @@ pub struct Bookmark {
     pub username: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Shares {
+    #[serde(rename = "share", default)]
+    pub share: Vec<Share>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Share {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "@url")]
+    pub url: String,
+    #[serde(rename = "@description", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "@username")]
+    pub username: String,
+    #[serde(rename = "@created")]
+    pub created: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "@expires", skip_serializing_if = "Option::is_none")]
+    pub expires: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "@lastVisited", skip_serializing_if = "Option::is_none")]
+    pub last_visited: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "@visitCount")]
+    pub visit_count: i32,
+    #[serde(rename = "entry", skip_serializing_if = "Vec::is_empty", default)]
+    pub entry: Vec<Child>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Podcasts {
+    #[serde(rename = "channel", default)]
+    pub channel: Vec<PodcastChannel>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewestPodcasts {
+    #[serde(rename = "episode", default)]
+    pub episode: Vec<PodcastEpisode>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PodcastChannel {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "@url")]
+    pub url: String,
+    #[serde(rename = "@title", skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(rename = "@description", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "@coverArt", skip_serializing_if = "Option::is_none")]
+    pub cover_art: Option<String>,
+    #[serde(rename = "@originalImageUrl", skip_serializing_if = "Option::is_none")]
+    pub original_image_url: Option<String>,
+    #[serde(rename = "@status")]
+    pub status: String,
+    #[serde(rename = "@errorMessage", skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+    #[serde(rename = "episode", skip_serializing_if = "Vec::is_empty", default)]
+    pub episode: Vec<PodcastEpisode>,
+}
+
+impl From<&podcast_channel::Model> for PodcastChannel {
+    fn from(c: &podcast_channel::Model) -> Self {
+        Self {
+            id: format!("pc-{}", c.id),
+            url: c.url.clone(),
+            title: c.title.clone(),
+            description: c.description.clone(),
+            cover_art: c.image_url.as_ref().map(|_| format!("pc-{}", c.id)),
+            original_image_url: c.image_url.clone(),
+            status: c.status.clone(),
+            error_message: c.error_message.clone(),
+            episode: Vec::new(),
+        }
+    }
+}
+
+/// A podcast episode; the `Child` part lets clients play it like a song via `streamId`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PodcastEpisode {
+    #[serde(flatten)]
+    pub child: Child,
+    #[serde(rename = "@streamId", skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<String>,
+    #[serde(rename = "@channelId")]
+    pub channel_id: String,
+    #[serde(rename = "@description", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "@status")]
+    pub status: String,
+    #[serde(rename = "@publishDate", skip_serializing_if = "Option::is_none")]
+    pub publish_date: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl PodcastEpisode {
+    pub fn new(e: podcast_episode::Model, channel: &podcast_channel::Model) -> Self {
+        let id = format!("pe-{}", e.id);
+        let suffix = e
+            .path
+            .as_deref()
+            .and_then(|p| std::path::Path::new(p).extension())
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        let content_type = e.content_type.clone().or_else(|| {
+            suffix
+                .as_deref()
+                .and_then(|s| mime_guess::from_ext(s).first_raw())
+                .map(str::to_string)
+        });
+        let downloaded = e.path.is_some();
+        let child = Child {
+            id: id.clone(),
+            parent: Some(format!("pc-{}", channel.id)),
+            is_dir: false,
+            title: e.title.clone(),
+            name: e.title,
+            album: channel.title.clone(),
+            artist: channel.title.clone(),
+            track: None,
+            year: e.publish_date.map(|d| chrono::Datelike::year(&d)),
+            genre: Some("Podcast".to_string()),
+            cover_art: channel
+                .image_url
+                .as_ref()
+                .map(|_| format!("pc-{}", channel.id)),
+            size: e.size,
+            content_type,
+            suffix,
+            transcoded_content_type: None,
+            transcoded_suffix: None,
+            duration: e.duration,
+            bit_rate: None,
+            path: None,
+            is_video: Some(false),
+            user_rating: None,
+            average_rating: None,
+            play_count: None,
+            last_played: None,
+            disc_number: None,
+            created: Some(e.publish_date.unwrap_or(e.created_at)),
+            starred: None,
+            album_id: None,
+            artist_id: None,
+            r#type: Some("podcast".to_string()),
+            artists: Vec::new(),
+            genres: Vec::new(),
+            album_artists: Vec::new(),
+            display_artist: channel.title.clone(),
+            display_album_artist: None,
+            bookmark_position: None,
+            ..Default::default()
+        };
+        Self {
+            child,
+            stream_id: downloaded.then_some(id),
+            channel_id: format!("pc-{}", channel.id),
+            description: e.description,
+            status: e.status,
+            publish_date: e.publish_date,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InternetRadioStations {
+    #[serde(rename = "internetRadioStation", default)]
+    pub internet_radio_station: Vec<InternetRadioStation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InternetRadioStation {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@streamUrl")]
+    pub stream_url: String,
+    #[serde(rename = "@homePageUrl", skip_serializing_if = "Option::is_none")]
+    pub home_page_url: Option<String>,
+}
+
+impl From<internet_radio_station::Model> for InternetRadioStation {
+    fn from(s: internet_radio_station::Model) -> Self {
+        Self {
+            id: s.id.to_string(),
+            name: s.name,
+            stream_url: s.stream_url,
+            home_page_url: s.home_page_url,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JukeboxStatus {
+    #[serde(rename = "@currentIndex")]
+    pub current_index: i32,
+    #[serde(rename = "@playing")]
+    pub playing: bool,
+    #[serde(rename = "@gain")]
+    pub gain: f64,
+    #[serde(rename = "@position")]
+    pub position: u32,
+}
+
+impl From<jukebox::Status> for JukeboxStatus {
+    fn from(s: jukebox::Status) -> Self {
+        Self {
+            current_index: s.current_index,
+            playing: s.playing,
+            gain: s.gain,
+            position: s.position,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JukeboxPlaylist {
+    #[serde(flatten)]
+    pub status: JukeboxStatus,
+    #[serde(rename = "entry", default)]
+    pub entry: Vec<Child>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PlayQueue {
@@ -731,6 +1191,10 @@ pub struct ScanStatus {
     pub count: Option<i64>,
     #[serde(rename = "@total", skip_serializing_if = "Option::is_none")]
     pub total: Option<i64>,
+    #[serde(rename = "@lastScan", skip_serializing_if = "Option::is_none")]
+    pub last_scan: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "@nextScan", skip_serializing_if = "Option::is_none")]
+    pub next_scan: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -756,6 +1220,14 @@ pub struct SearchResult2 {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchResult3 {
+    /// Matches across all pages; not part of the Subsonic spec, so clients
+    /// that don't know them ignore them.
+    #[serde(rename = "@artistTotal", skip_serializing_if = "Option::is_none")]
+    pub artist_total: Option<u64>,
+    #[serde(rename = "@albumTotal", skip_serializing_if = "Option::is_none")]
+    pub album_total: Option<u64>,
+    #[serde(rename = "@songTotal", skip_serializing_if = "Option::is_none")]
+    pub song_total: Option<u64>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub artist: Vec<ArtistID3>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -790,6 +1262,13 @@ pub struct Playlist {
     pub created: chrono::DateTime<chrono::Utc>,
     #[serde(rename = "@changed")]
     pub changed: chrono::DateTime<chrono::Utc>,
+    /// OpenSubsonic: set when the songs can't be edited, on smart playlists
+    /// and on other users' playlists one isn't allowed to edit.
+    #[serde(rename = "@readonly", skip_serializing_if = "Option::is_none")]
+    pub readonly: Option<bool>,
+    /// Users besides the owner who may edit the songs.
+    #[serde(rename = "allowedUser", skip_serializing_if = "Vec::is_empty", default)]
+    pub allowed_user: Vec<String>,
     #[serde(rename = "entry", skip_serializing_if = "Vec::is_empty", default)]
     pub entry: Vec<Child>,
 }
@@ -806,6 +1285,8 @@ impl From<PlaylistWithStats> for Playlist {
             duration: p.duration as i32,
             created: p.created_at,
             changed: p.updated_at,
+            readonly: p.rules.is_some().then_some(true),
+            allowed_user: p.allowed_users,
             entry: vec![],
         }
     }
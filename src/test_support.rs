@@ -0,0 +1,89 @@
+//! Fixtures shared by the database-backed unit tests.
+
+use crate::models::{child, music_folder, user, user_music_folder};
+use chrono::Utc;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ActiveModelTrait, Database, DatabaseConnection, Set};
+
+/// A migrated in-memory database.
+pub async fn memory_db() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    db
+}
+
+/// A non-admin user with the usual listening roles.
+pub fn user(username: &str) -> user::ActiveModel {
+    let now = Utc::now();
+    user::ActiveModel {
+        username: Set(username.to_string()),
+        password: Set("pass".to_string()),
+        email: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+        scrobbling_enabled: Set(true),
+        settings_role: Set(false),
+        download_role: Set(true),
+        upload_role: Set(false),
+        admin_role: Set(false),
+        playlist_role: Set(true),
+        cover_art_role: Set(true),
+        comment_role: Set(true),
+        podcast_role: Set(false),
+        stream_role: Set(true),
+        jukebox_role: Set(false),
+        share_role: Set(true),
+        video_conversion_role: Set(false),
+        ..Default::default()
+    }
+}
+
+/// Inserts [`user`] `username`.
+pub async fn insert_user(db: &DatabaseConnection, username: &str) {
+    user(username).insert(db).await.unwrap();
+}
+
+/// Inserts music folder `id` at `path` and grants it to `usernames`.
+pub async fn insert_folder(db: &DatabaseConnection, id: i32, path: &str, usernames: &[&str]) {
+    music_folder::ActiveModel {
+        id: Set(id),
+        path: Set(path.to_string()),
+        name: Set(None),
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    for username in usernames {
+        user_music_folder::ActiveModel {
+            username: Set(username.to_string()),
+            music_folder_id: Set(id),
+        }
+        .insert(db)
+        .await
+        .unwrap();
+    }
+}
+
+/// A song titled `id` in music folder `folder_id`, to be customised with
+/// struct update syntax before inserting.
+pub fn song(id: &str, folder_id: i32) -> child::ActiveModel {
+    child::ActiveModel {
+        id: Set(id.to_string()),
+        parent: Set(None),
+        is_dir: Set(false),
+        title: Set(id.to_string()),
+        path: Set(format!("/music{}/{}.mp3", folder_id, id)),
+        music_folder_id: Set(folder_id),
+        r#type: Set("music".to_string()),
+        track: Set(1),
+        year: Set(0),
+        disc_number: Set(1),
+        duration: Set(0),
+        bit_rate: Set(0),
+        size: Set(0),
+        is_video: Set(false),
+        average_rating: Set(0.0),
+        play_count: Set(0),
+        ..Default::default()
+    }
+}
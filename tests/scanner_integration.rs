@@ -9,6 +9,7 @@ use miko::models::{
     album, album_artist, album_genre, artist, child, genre, lyrics, song_artist, song_genre,
 };
 use miko::scanner::flusher;
+use miko::scanner::lyrics::{LyricsSet, UNKNOWN_LANG};
 use miko::scanner::seen;
 use miko::scanner::types::{AlbumRelations, SongRelations, UpsertMessage};
 use miko::scanner::utils;
@@ -63,8 +64,6 @@ fn generate_virtual_library(folder_id: i32, folder_path: &str) -> Vec<UpsertMess
             music_folder_id: Set(folder_id),
             content_type: Set(None),
             suffix: Set(None),
-            transcoded_content_type: Set(None),
-            transcoded_suffix: Set(None),
             album_id: Set(None),
             r#type: Set("directory".into()),
             track: Set(0),
@@ -99,8 +98,6 @@ fn generate_virtual_library(folder_id: i32, folder_path: &str) -> Vec<UpsertMess
                 music_folder_id: Set(folder_id),
                 content_type: Set(None),
                 suffix: Set(None),
-                transcoded_content_type: Set(None),
-                transcoded_suffix: Set(None),
                 album_id: Set(None),
                 r#type: Set("directory".into()),
                 track: Set(0),
@@ -137,8 +134,6 @@ fn generate_virtual_library(folder_id: i32, folder_path: &str) -> Vec<UpsertMess
                     music_folder_id: Set(folder_id),
                     content_type: Set(None),
                     suffix: Set(None),
-                    transcoded_content_type: Set(None),
-                    transcoded_suffix: Set(None),
                     album_id: Set(None),
                     r#type: Set("directory".into()),
                     track: Set(0),
@@ -218,8 +213,6 @@ fn generate_virtual_library(folder_id: i32, folder_path: &str) -> Vec<UpsertMess
                     content_type: Set(Some("audio/mp3".into())),
                     created: Set(Some(now)),
                     music_folder_id: Set(folder_id),
-                    transcoded_content_type: Set(None),
-                    transcoded_suffix: Set(None),
                     album_id: Set(Some(al_id.clone())),
                     r#type: Set("music".into()),
                     track: Set(track as i32 + 1),
@@ -239,10 +232,15 @@ fn generate_virtual_library(folder_id: i32, folder_path: &str) -> Vec<UpsertMess
                     artists: vec![a_id.clone()],
                     genres: vec![g_name.clone()],
                     lyrics: if has_lyrics {
-                        Some(format!("Lyrics for {}", title))
+                        vec![LyricsSet::new(
+                            format!("Lyrics for {}", title),
+                            UNKNOWN_LANG.into(),
+                            None,
+                        )]
                     } else {
-                        None
+                        Vec::new()
                     },
+                    contributors: Vec::new(),
                 })));
 
                 all_batches.push(UpsertMessage::Batch(batch));
@@ -442,8 +440,6 @@ async fn flusher_handles_2000_songs_then_prune_removes_missing() {
             music_folder_id: Set(folder_id),
             content_type: Set(None),
             suffix: Set(None),
-            transcoded_content_type: Set(None),
-            transcoded_suffix: Set(None),
             album_id: Set(None),
             r#type: Set("directory".into()),
             track: Set(0),
@@ -479,8 +475,6 @@ async fn flusher_handles_2000_songs_then_prune_removes_missing() {
                 music_folder_id: Set(folder_id),
                 content_type: Set(None),
                 suffix: Set(None),
-                transcoded_content_type: Set(None),
-                transcoded_suffix: Set(None),
                 album_id: Set(None),
                 r#type: Set("directory".into()),
                 track: Set(0),
@@ -519,8 +513,6 @@ async fn flusher_handles_2000_songs_then_prune_removes_missing() {
                     music_folder_id: Set(folder_id),
                     content_type: Set(None),
                     suffix: Set(None),
-                    transcoded_content_type: Set(None),
-                    transcoded_suffix: Set(None),
                     album_id: Set(None),
                     r#type: Set("directory".into()),
                     track: Set(0),
@@ -591,8 +583,6 @@ async fn flusher_handles_2000_songs_then_prune_removes_missing() {
                     content_type: Set(Some("audio/mp3".into())),
                     created: Set(Some(now)),
                     music_folder_id: Set(folder_id),
-                    transcoded_content_type: Set(None),
-                    transcoded_suffix: Set(None),
                     album_id: Set(Some(al_id.clone())),
                     r#type: Set("music".into()),
                     track: Set(track as i32 + 1),
@@ -610,10 +600,15 @@ async fn flusher_handles_2000_songs_then_prune_removes_missing() {
                     artists: vec![a_id.clone()],
                     genres: vec![g_name.clone()],
                     lyrics: if has_lyrics {
-                        Some(format!("Lyrics for {}", title))
+                        vec![LyricsSet::new(
+                            format!("Lyrics for {}", title),
+                            UNKNOWN_LANG.into(),
+                            None,
+                        )]
                     } else {
-                        None
+                        Vec::new()
                     },
+                    contributors: Vec::new(),
                 })));
                 tx2.send(UpsertMessage::Batch(batch)).await.unwrap();
             }
@@ -771,8 +766,6 @@ async fn flusher_song_album_fk_same_flush_window() {
                 music_folder_id: Set(1),
                 content_type: Set(Some("audio/mp3".into())),
                 suffix: Set(Some("mp3".into())),
-                transcoded_content_type: Set(None),
-                transcoded_suffix: Set(None),
                 album_id: Set(Some(al_id.clone())),
                 r#type: Set("music".into()),
                 track: Set(1),
@@ -790,7 +783,8 @@ async fn flusher_song_album_fk_same_flush_window() {
                 song_id: s_id.clone(),
                 artists: vec![a_id.clone()],
                 genres: vec![g_name.clone()],
-                lyrics: None,
+                lyrics: Vec::new(),
+                contributors: Vec::new(),
             })),
             UpsertMessage::AlbumRelations(Box::new(AlbumRelations {
                 album_id: al_id,
@@ -851,8 +845,6 @@ async fn flusher_parent_child_self_referencing_fk() {
                 music_folder_id: Set(1),
                 content_type: Set(None),
                 suffix: Set(None),
-                transcoded_content_type: Set(None),
-                transcoded_suffix: Set(None),
                 album_id: Set(None),
                 r#type: Set("directory".into()),
                 track: Set(0),
@@ -883,8 +875,6 @@ async fn flusher_parent_child_self_referencing_fk() {
             music_folder_id: Set(1),
             content_type: Set(Some("audio/mp3".into())),
             suffix: Set(Some("mp3".into())),
-            transcoded_content_type: Set(None),
-            transcoded_suffix: Set(None),
             album_id: Set(None),
             r#type: Set("music".into()),
             track: Set(1),
@@ -982,8 +972,6 @@ async fn flusher_lyrics_and_relations_same_flush_window() {
                 music_folder_id: Set(1),
                 content_type: Set(Some("audio/mp3".into())),
                 suffix: Set(Some("mp3".into())),
-                transcoded_content_type: Set(None),
-                transcoded_suffix: Set(None),
                 album_id: Set(None),
                 r#type: Set("music".into()),
                 track: Set(i as i32 + 1),
@@ -1001,7 +989,12 @@ async fn flusher_lyrics_and_relations_same_flush_window() {
                 song_id: s_id.clone(),
                 artists: vec![a_id],
                 genres: vec![g_name],
-                lyrics: Some(format!("Lyrics for song {}", i)),
+                lyrics: vec![LyricsSet::new(
+                    format!("Lyrics for song {}", i),
+                    UNKNOWN_LANG.into(),
+                    None,
+                )],
+                contributors: Vec::new(),
             })),
         ]))
         .await
@@ -1071,8 +1064,6 @@ async fn flusher_rescan_updates_metadata() {
             music_folder_id: Set(1),
             content_type: Set(Some("audio/mp3".into())),
             suffix: Set(Some("mp3".into())),
-            transcoded_content_type: Set(None),
-            transcoded_suffix: Set(None),
             album_id: Set(Some("al1".into())),
             r#type: Set("music".into()),
             track: Set(1),
@@ -1090,7 +1081,12 @@ async fn flusher_rescan_updates_metadata() {
             song_id: "s1".into(),
             artists: vec!["a1".into()],
             genres: vec!["Rock".into()],
-            lyrics: Some("Old lyrics".into()),
+            lyrics: vec![LyricsSet::new(
+                "Old lyrics".into(),
+                UNKNOWN_LANG.into(),
+                None,
+            )],
+            contributors: Vec::new(),
         })),
     ]))
     .await
@@ -1136,8 +1132,6 @@ async fn flusher_rescan_updates_metadata() {
             music_folder_id: Set(1),
             content_type: Set(Some("audio/mp3".into())),
             suffix: Set(Some("mp3".into())),
-            transcoded_content_type: Set(None),
-            transcoded_suffix: Set(None),
             album_id: Set(Some("al1".into())),
             r#type: Set("music".into()),
             track: Set(1),
@@ -1155,7 +1149,12 @@ async fn flusher_rescan_updates_metadata() {
             song_id: "s1".into(),
             artists: vec!["a1".into()],
             genres: vec!["Rock".into()],
-            lyrics: Some("New lyrics".into()),
+            lyrics: vec![LyricsSet::new(
+                "New lyrics".into(),
+                UNKNOWN_LANG.into(),
+                None,
+            )],
+            contributors: Vec::new(),
         })),
     ]))
     .await
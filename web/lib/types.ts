@@ -3,6 +3,8 @@ export interface Stats {
     albums?: number;
     artists?: number;
     genres?: number;
+    last_scan?: string;
+    next_scan?: string;
 }
 
 export interface SystemInfo {
@@ -18,12 +20,35 @@ export interface FolderInfo {
     song_count: number;
 }
 
+export interface RadioStation {
+    id: number;
+    name: string;
+    stream_url: string;
+    home_page_url?: string | null;
+}
+
+export interface ApiKey {
+    id: number;
+    name: string;
+    created_at: string;
+    last_used_at?: string | null;
+    last_client?: string | null;
+}
+
 export interface ScanStatus {
     scanning: boolean;
     count: number;
     total: number;
 }
 
+export interface AccountConnection {
+    service: 'listenbrainz' | 'lastfm';
+    connected: boolean;
+    account_name?: string;
+    pending: number;
+    available: boolean;
+}
+
 export interface UserProfile {
     username: string;
     email?: string;
@@ -46,6 +71,10 @@ export interface Song {
     suffix?: string;
     duration?: number;
     bitRate?: number;
+    codec?: string;
+    samplingRate?: number;
+    bitDepth?: number;
+    channelCount?: number;
     path?: string;
     playCount?: number;
     created?: string;
@@ -113,6 +142,9 @@ export interface UsersResponse {
     user: SubsonicUser[];
 }
 export interface SearchResult3 {
+    songTotal?: number;
+    albumTotal?: number;
+    artistTotal?: number;
     song?: Song[];
     album?: AlbumReference[];
     artist?: ArtistReference[];
@@ -143,6 +175,10 @@ export interface SongTags {
     duration: number;
     bitRate?: number;
     format: string;
+    codec?: string;
+    sampleRate?: number;
+    bitDepth?: number;
+    channels?: number;
     frontCover?: string;
     // Additional tags
     composer?: string;
@@ -12,6 +12,8 @@ import SettingsProfile from './routes/settings/Profile.svelte';
 import SettingsFolders from './routes/settings/Folders.svelte';
 import SettingsConnections from './routes/settings/Connections.svelte';
 import SettingsUsers from './routes/settings/Users.svelte';
+import SettingsRadio from './routes/settings/Radio.svelte';
+import SettingsApiKeys from './routes/settings/ApiKeys.svelte';
 import NotFound from './routes/NotFound.svelte';
 import MainLayout from './components/MainLayout.svelte';
 
@@ -34,6 +36,8 @@ export const { p, navigate, route, isActive } = createRouter({
         '/profile': SettingsProfile,
         '/folders': SettingsFolders,
         '/connections': SettingsConnections,
+        '/radio': SettingsRadio,
+        '/api-keys': SettingsApiKeys,
         '/users': SettingsUsers,
         layout: SettingsLayout,
     },